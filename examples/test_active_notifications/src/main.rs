@@ -34,6 +34,7 @@ fn create_test_categories() -> Vec<NotificationCategory> {
                     title: "Detail".to_string(),
                 },
             ],
+            ..Default::default()
         },
     ]
 }
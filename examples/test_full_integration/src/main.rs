@@ -35,6 +35,7 @@ fn create_test_categories() -> Vec<NotificationCategory> {
                     title: "Detail".to_string(),
                 },
             ],
+            ..Default::default()
         },
         NotificationCategory {
             identifier: TEXT_INPUT_CATEGORY_ID.to_string(),
@@ -43,7 +44,9 @@ fn create_test_categories() -> Vec<NotificationCategory> {
                 title: "Reply".to_string(),
                 input_button_title: "Send".to_string(),
                 input_placeholder: "Type your message here...".to_string(),
+                default_text: Some("On my way!".to_string()),
             }],
+            ..Default::default()
         },
     ]
 }
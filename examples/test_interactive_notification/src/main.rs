@@ -31,6 +31,7 @@ fn create_test_categories() -> Vec<NotificationCategory> {
                     title: "Cancel".to_string(),
                 },
             ],
+            ..Default::default()
         },
         NotificationCategory {
             identifier: TEXT_INPUT_CATEGORY_ID.to_string(),
@@ -39,7 +40,9 @@ fn create_test_categories() -> Vec<NotificationCategory> {
                 title: "Reply".to_string(),
                 input_button_title: "Send".to_string(),
                 input_placeholder: "Type your message here...".to_string(),
+                default_text: None,
             }],
+            ..Default::default()
         },
     ]
 }
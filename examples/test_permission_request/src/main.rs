@@ -29,6 +29,7 @@ fn create_test_categories() -> Vec<NotificationCategory> {
                     title: "Cancel".to_string(),
                 },
             ],
+            ..Default::default()
         },
     ]
 }
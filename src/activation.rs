@@ -0,0 +1,143 @@
+//! Clears the badge, and optionally the delivered notifications, when the user comes back to the app
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), user_notify::Error> {
+//! use user_notify::activation::ClearOnActivateManager;
+//!
+//! let manager = ClearOnActivateManager::new(user_notify::get_notification_manager(
+//!     "com.example.app".to_owned(),
+//!     None,
+//! ))
+//! .set_clear_delivered(true);
+//!
+//! // on Windows and Linux, from the focus event of the main window
+//! manager.app_activated().await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! On macOS this happens by itself when the app becomes active,
+//! the other platforms do not tell the library when the app is focused,
+//! so the app calls [ClearOnActivateManager::app_activated] from the focus event of its window.
+
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+
+use crate::{Error, NotificationManager};
+
+#[derive(Debug)]
+struct State {
+    inner: Arc<dyn NotificationManager>,
+    clear_delivered: AtomicBool,
+}
+
+impl State {
+    async fn clear(&self) -> Result<(), Error> {
+        if self.clear_delivered.load(Ordering::Relaxed) {
+            self.inner.remove_all_delivered_notifications()?;
+        }
+        self.inner.clear_badge().await
+    }
+}
+
+/// Wraps a [NotificationManager] and clears the badge when the app is activated,
+/// see the [module documentation](self)
+#[derive(Debug, Clone)]
+pub struct ClearOnActivateManager {
+    state: Arc<State>,
+}
+
+impl ClearOnActivateManager {
+    /// On macOS it starts to observe the activation of the app,
+    /// the observer does nothing anymore once the manager is dropped
+    pub fn new(inner: Arc<dyn NotificationManager>) -> Self {
+        let state = Arc::new(State {
+            inner,
+            clear_delivered: AtomicBool::new(false),
+        });
+        #[cfg(all(target_os = "macos", feature = "macos"))]
+        observe_activation(Arc::downgrade(&state));
+        Self { state }
+    }
+
+    /// Also remove the delivered notifications from the notification center, off by default
+    pub fn set_clear_delivered(self, clear_delivered: bool) -> Self {
+        self.state
+            .clear_delivered
+            .store(clear_delivered, Ordering::Relaxed);
+        self
+    }
+
+    /// The wrapped manager
+    pub fn as_inner(&self) -> &Arc<dyn NotificationManager> {
+        &self.state.inner
+    }
+
+    /// Clears the badge, and the delivered notifications if [Self::set_clear_delivered] is on.
+    /// Call it when the app window gets the focus, on macOS this is not needed.
+    pub async fn app_activated(&self) -> Result<(), Error> {
+        self.state.clear().await
+    }
+}
+
+/// Observes [NSApplicationDidBecomeActiveNotification](https://developer.apple.com/documentation/appkit/nsapplication/didbecomeactivenotification)
+#[cfg(all(target_os = "macos", feature = "macos"))]
+fn observe_activation(state: std::sync::Weak<State>) {
+    use objc2_foundation::{NSNotification, NSNotificationCenter, NSString};
+
+    let block = block2::RcBlock::new(move |_: std::ptr::NonNull<NSNotification>| {
+        let Some(state) = state.upgrade() else {
+            return;
+        };
+        // the notification is posted on the main thread, which should not wait for the notification center
+        std::thread::spawn(move || {
+            if let Err(err) = futures_lite::future::block_on(state.clear()) {
+                log::error!("failed to clear the badge on activation: {err:?}");
+            }
+        });
+    });
+    // the notification center keeps the observer, so the returned token is not needed
+    let _ = unsafe {
+        NSNotificationCenter::defaultCenter().addObserverForName_object_queue_usingBlock(
+            Some(&NSString::from_str(
+                "NSApplicationDidBecomeActiveNotification",
+            )),
+            None,
+            None,
+            &block,
+        )
+    };
+}
+
+impl crate::delegate::DelegatingManager for ClearOnActivateManager {
+    fn inner(&self) -> &Arc<dyn NotificationManager> {
+        &self.state.inner
+    }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use super::*;
+    use crate::{NotificationBuilder, mock::NotificationManagerMock};
+
+    #[tokio::test]
+    async fn clear_on_activate() {
+        let mock = NotificationManagerMock::new();
+        let manager = ClearOnActivateManager::new(Arc::new(mock.clone()));
+        manager.set_badge_count(3).await.unwrap();
+        manager
+            .send_notification(NotificationBuilder::new())
+            .await
+            .unwrap();
+
+        manager.app_activated().await.unwrap();
+        assert_eq!(manager.get_badge_count().await.unwrap(), 0);
+        assert_eq!(manager.get_active_notifications().await.unwrap().len(), 1);
+
+        let manager = manager.set_clear_delivered(true);
+        manager.app_activated().await.unwrap();
+        assert!(manager.get_active_notifications().await.unwrap().is_empty());
+    }
+}
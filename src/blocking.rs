@@ -0,0 +1,290 @@
+//! Synchronous wrapper around [crate::NotificationManager], for apps and scripts without an async runtime
+//!
+//! ```no_run
+//! let manager = user_notify::blocking::NotificationManager::new("com.example.app".to_owned(), None);
+//! manager.send_notification(user_notify::NotificationBuilder::new().title("Hello"))?;
+//! # Ok::<(), user_notify::Error>(())
+//! ```
+//!
+//! With the `tokio` feature a small tokio runtime is started for the scheduled notifications,
+//! when the calls are not made from within a tokio runtime already.
+
+use std::{future::Future, sync::Arc};
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    CalendarTrigger, Error, LocationTrigger, NotificationBuilder, NotificationCapabilities,
+    NotificationCategory, NotificationEvent, NotificationHandle, NotificationResponse,
+    ProgressState, RepeatInterval,
+};
+
+/// Blocks the current thread on every call until the platform answered,
+/// see [crate::NotificationManager] for the documentation of the methods
+#[derive(Debug, Clone)]
+pub struct NotificationManager {
+    manager: Arc<dyn crate::NotificationManager>,
+}
+
+impl NotificationManager {
+    /// Wraps the manager returned by [crate::get_notification_manager]
+    pub fn new(app_id: String, notification_protocol: Option<String>) -> Self {
+        Self::from_async(crate::get_notification_manager(
+            app_id,
+            notification_protocol,
+        ))
+    }
+
+    pub fn from_async(manager: Arc<dyn crate::NotificationManager>) -> Self {
+        Self { manager }
+    }
+
+    /// The wrapped async manager
+    pub fn as_async(&self) -> &Arc<dyn crate::NotificationManager> {
+        &self.manager
+    }
+
+    pub fn get_notification_permission_state(&self) -> Result<bool, Error> {
+        block_on(self.manager.get_notification_permission_state())
+    }
+
+    pub fn first_time_ask_for_notification_permission(&self) -> Result<bool, Error> {
+        block_on(self.manager.first_time_ask_for_notification_permission())
+    }
+
+    pub fn request_provisional_permission(&self) -> Result<bool, Error> {
+        block_on(self.manager.request_provisional_permission())
+    }
+
+    pub fn capabilities(&self) -> Result<NotificationCapabilities, Error> {
+        block_on(self.manager.capabilities())
+    }
+
+    pub fn is_do_not_disturb_active(&self) -> Result<bool, Error> {
+        block_on(self.manager.is_do_not_disturb_active())
+    }
+
+    pub fn subscribe_do_not_disturb(
+        &self,
+    ) -> Result<tokio::sync::broadcast::Receiver<bool>, Error> {
+        self.manager.subscribe_do_not_disturb()
+    }
+
+    pub fn register(
+        &self,
+        handler_callback: Box<dyn Fn(NotificationResponse) + Send + Sync + 'static>,
+        categories: Vec<NotificationCategory>,
+    ) -> Result<(), Error> {
+        self.manager.register(handler_callback, categories)
+    }
+
+    pub fn set_response_handler(
+        &self,
+        handler_callback: Box<dyn Fn(NotificationResponse) + Send + Sync + 'static>,
+    ) -> Result<(), Error> {
+        self.manager.set_response_handler(handler_callback)
+    }
+
+    pub fn clear_response_handler(&self) -> Result<(), Error> {
+        self.manager.clear_response_handler()
+    }
+
+    /// The receiver can be read without a runtime with [tokio::sync::mpsc::UnboundedReceiver::blocking_recv]
+    pub fn responses(
+        &self,
+        categories: Vec<NotificationCategory>,
+    ) -> Result<tokio::sync::mpsc::UnboundedReceiver<NotificationResponse>, Error> {
+        self.manager.responses(categories)
+    }
+
+    pub fn take_launch_response(&self) -> Option<NotificationResponse> {
+        self.manager.take_launch_response()
+    }
+
+    pub fn subscribe_events(&self) -> tokio::sync::broadcast::Receiver<NotificationEvent> {
+        self.manager.subscribe_events()
+    }
+
+    pub fn add_categories(&self, categories: Vec<NotificationCategory>) -> Result<(), Error> {
+        self.manager.add_categories(categories)
+    }
+
+    pub fn set_categories(&self, categories: Vec<NotificationCategory>) -> Result<(), Error> {
+        self.manager.set_categories(categories)
+    }
+
+    pub fn set_categories_for_locale(
+        &self,
+        categories: Vec<NotificationCategory>,
+        locale: &str,
+    ) -> Result<(), Error> {
+        self.manager.set_categories_for_locale(categories, locale)
+    }
+
+    pub fn remove_category(&self, identifier: &str) -> Result<(), Error> {
+        self.manager.remove_category(identifier)
+    }
+
+    pub fn remove_all_delivered_notifications(&self) -> Result<(), Error> {
+        self.manager.remove_all_delivered_notifications()
+    }
+
+    pub fn remove_delivered_notifications(&self, ids: Vec<&str>) -> Result<(), Error> {
+        self.manager.remove_delivered_notifications(ids)
+    }
+
+    pub fn dismiss_thread(&self, thread_id: &str) -> Result<(), Error> {
+        block_on(self.manager.dismiss_thread(thread_id))
+    }
+
+    pub fn active_count_for_thread(&self, thread_id: &str) -> Result<usize, Error> {
+        block_on(self.manager.active_count_for_thread(thread_id))
+    }
+
+    pub fn active_thread_ids(&self) -> Result<Vec<String>, Error> {
+        block_on(self.manager.active_thread_ids())
+    }
+
+    pub fn get_active_notifications(&self) -> Result<Vec<Box<dyn NotificationHandle>>, Error> {
+        block_on(self.manager.get_active_notifications())
+    }
+
+    pub fn send_notification(
+        &self,
+        builder: NotificationBuilder,
+    ) -> Result<Box<dyn NotificationHandle>, Error> {
+        block_on(self.manager.send_notification(builder))
+    }
+
+    pub fn update_notification(
+        &self,
+        id: &str,
+        builder: NotificationBuilder,
+    ) -> Result<Box<dyn NotificationHandle>, Error> {
+        block_on(self.manager.update_notification(id, builder))
+    }
+
+    pub fn update_progress(
+        &self,
+        id: &str,
+        state: ProgressState,
+        status: &str,
+    ) -> Result<(), Error> {
+        block_on(self.manager.update_progress(id, state, status))
+    }
+
+    pub fn set_badge_count(&self, count: u32) -> Result<(), Error> {
+        block_on(self.manager.set_badge_count(count))
+    }
+
+    pub fn get_badge_count(&self) -> Result<u32, Error> {
+        block_on(self.manager.get_badge_count())
+    }
+
+    pub fn clear_badge(&self) -> Result<(), Error> {
+        block_on(self.manager.clear_badge())
+    }
+
+    pub fn schedule_notification(
+        &self,
+        builder: NotificationBuilder,
+        at: DateTime<Utc>,
+    ) -> Result<String, Error> {
+        block_on(self.manager.schedule_notification(builder, at))
+    }
+
+    pub fn schedule_repeating_notification(
+        &self,
+        builder: NotificationBuilder,
+        interval: RepeatInterval,
+    ) -> Result<String, Error> {
+        block_on(
+            self.manager
+                .schedule_repeating_notification(builder, interval),
+        )
+    }
+
+    pub fn schedule_calendar_notification(
+        &self,
+        builder: NotificationBuilder,
+        trigger: CalendarTrigger,
+    ) -> Result<String, Error> {
+        block_on(
+            self.manager
+                .schedule_calendar_notification(builder, trigger),
+        )
+    }
+
+    pub fn schedule_location_notification(
+        &self,
+        builder: NotificationBuilder,
+        trigger: LocationTrigger,
+    ) -> Result<String, Error> {
+        block_on(
+            self.manager
+                .schedule_location_notification(builder, trigger),
+        )
+    }
+
+    pub fn cancel_scheduled_notification(&self, id: &str) -> Result<(), Error> {
+        self.manager.cancel_scheduled_notification(id)
+    }
+}
+
+#[cfg(feature = "tokio")]
+fn block_on<F: Future>(future: F) -> F::Output {
+    use std::sync::OnceLock;
+
+    /// Runs the scheduled notifications of callers without a tokio runtime
+    static RUNTIME: OnceLock<Option<tokio::runtime::Runtime>> = OnceLock::new();
+
+    let _guard = match tokio::runtime::Handle::try_current() {
+        Ok(_) => None,
+        Err(_) => RUNTIME
+            .get_or_init(|| {
+                tokio::runtime::Builder::new_multi_thread()
+                    .worker_threads(1)
+                    .thread_name("user-notify")
+                    .enable_time()
+                    .build()
+                    .inspect_err(|err| log::error!("failed to start tokio runtime: {err}"))
+                    .ok()
+            })
+            .as_ref()
+            .map(|runtime| runtime.enter()),
+    };
+    futures_lite::future::block_on(future)
+}
+
+#[cfg(not(feature = "tokio"))]
+fn block_on<F: Future>(future: F) -> F::Output {
+    futures_lite::future::block_on(future)
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::mock::NotificationManagerMock;
+
+    #[test]
+    fn without_runtime() {
+        let mock = NotificationManagerMock::new();
+        let manager = NotificationManager::from_async(Arc::new(mock.clone()));
+
+        let handle = manager
+            .send_notification(NotificationBuilder::new().title("title"))
+            .unwrap();
+        assert_eq!(manager.get_active_notifications().unwrap().len(), 1);
+        assert_eq!(handle.get_content().title.as_deref(), Some("title"));
+
+        manager
+            .schedule_notification(NotificationBuilder::new().title("scheduled"), Utc::now())
+            .unwrap();
+        std::thread::sleep(Duration::from_millis(200));
+        let sent = futures_lite::future::block_on(mock.sent_notifications());
+        assert_eq!(sent.len(), 2);
+        assert_eq!(sent[1].get_title(), Some("scheduled"));
+    }
+}
@@ -0,0 +1,393 @@
+//! Forwarding of the [NotificationManager] methods for the managers that wrap another manager,
+//! so a wrapper only implements the methods it changes.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use crate::{
+    CalendarTrigger, Error, LocationTrigger, NotificationBuilder, NotificationCapabilities,
+    NotificationCategory, NotificationEvent, NotificationHandle, NotificationManager,
+    NotificationResponse, NotificationSettings, ProgressState, RepeatInterval,
+};
+
+/// Every method forwards to [DelegatingManager::inner] unless the wrapper overrides it,
+/// the wrapper gets [NotificationManager] through the blanket implementation below.
+///
+/// Do not import this trait next to [NotificationManager], the method names are the same.
+#[async_trait]
+pub(crate) trait DelegatingManager
+where
+    Self: Send + Sync + std::fmt::Debug,
+{
+    /// The wrapped manager
+    fn inner(&self) -> &Arc<dyn NotificationManager>;
+
+    async fn get_notification_permission_state(&self) -> Result<bool, Error> {
+        self.inner().get_notification_permission_state().await
+    }
+
+    async fn first_time_ask_for_notification_permission(&self) -> Result<bool, Error> {
+        self.inner()
+            .first_time_ask_for_notification_permission()
+            .await
+    }
+
+    async fn get_notification_settings(&self) -> Result<NotificationSettings, Error> {
+        self.inner().get_notification_settings().await
+    }
+
+    async fn request_provisional_permission(&self) -> Result<bool, Error> {
+        self.inner().request_provisional_permission().await
+    }
+
+    async fn capabilities(&self) -> Result<NotificationCapabilities, Error> {
+        self.inner().capabilities().await
+    }
+
+    async fn is_do_not_disturb_active(&self) -> Result<bool, Error> {
+        self.inner().is_do_not_disturb_active().await
+    }
+
+    fn subscribe_do_not_disturb(&self) -> Result<tokio::sync::broadcast::Receiver<bool>, Error> {
+        self.inner().subscribe_do_not_disturb()
+    }
+
+    fn register(
+        &self,
+        handler_callback: Box<dyn Fn(NotificationResponse) + Send + Sync + 'static>,
+        categories: Vec<NotificationCategory>,
+    ) -> Result<(), Error> {
+        self.inner().register(handler_callback, categories)
+    }
+
+    fn set_response_handler(
+        &self,
+        handler_callback: Box<dyn Fn(NotificationResponse) + Send + Sync + 'static>,
+    ) -> Result<(), Error> {
+        self.inner().set_response_handler(handler_callback)
+    }
+
+    fn clear_response_handler(&self) -> Result<(), Error> {
+        self.inner().clear_response_handler()
+    }
+
+    fn take_launch_response(&self) -> Option<NotificationResponse> {
+        self.inner().take_launch_response()
+    }
+
+    fn subscribe_events(&self) -> tokio::sync::broadcast::Receiver<NotificationEvent> {
+        self.inner().subscribe_events()
+    }
+
+    fn add_categories(&self, categories: Vec<NotificationCategory>) -> Result<(), Error> {
+        self.inner().add_categories(categories)
+    }
+
+    fn set_categories(&self, categories: Vec<NotificationCategory>) -> Result<(), Error> {
+        self.inner().set_categories(categories)
+    }
+
+    fn set_categories_for_locale(
+        &self,
+        categories: Vec<NotificationCategory>,
+        locale: &str,
+    ) -> Result<(), Error> {
+        self.inner().set_categories_for_locale(categories, locale)
+    }
+
+    fn remove_category(&self, identifier: &str) -> Result<(), Error> {
+        self.inner().remove_category(identifier)
+    }
+
+    fn responses(
+        &self,
+        categories: Vec<NotificationCategory>,
+    ) -> Result<tokio::sync::mpsc::UnboundedReceiver<NotificationResponse>, Error> {
+        self.inner().responses(categories)
+    }
+
+    fn remove_all_delivered_notifications(&self) -> Result<(), Error> {
+        self.inner().remove_all_delivered_notifications()
+    }
+
+    fn remove_delivered_notifications(&self, ids: Vec<&str>) -> Result<(), Error> {
+        self.inner().remove_delivered_notifications(ids)
+    }
+
+    async fn dismiss_thread(&self, thread_id: &str) -> Result<(), Error> {
+        self.inner().dismiss_thread(thread_id).await
+    }
+
+    async fn active_count_for_thread(&self, thread_id: &str) -> Result<usize, Error> {
+        self.inner().active_count_for_thread(thread_id).await
+    }
+
+    async fn active_thread_ids(&self) -> Result<Vec<String>, Error> {
+        self.inner().active_thread_ids().await
+    }
+
+    async fn get_active_notifications(&self) -> Result<Vec<Box<dyn NotificationHandle>>, Error> {
+        self.inner().get_active_notifications().await
+    }
+
+    async fn send_notification(
+        &self,
+        builder: NotificationBuilder,
+    ) -> Result<Box<dyn NotificationHandle>, Error> {
+        self.inner().send_notification(builder).await
+    }
+
+    async fn update_notification(
+        &self,
+        id: &str,
+        builder: NotificationBuilder,
+    ) -> Result<Box<dyn NotificationHandle>, Error> {
+        self.inner().update_notification(id, builder).await
+    }
+
+    async fn update_progress(
+        &self,
+        id: &str,
+        state: ProgressState,
+        status: &str,
+    ) -> Result<(), Error> {
+        self.inner().update_progress(id, state, status).await
+    }
+
+    async fn set_badge_count(&self, count: u32) -> Result<(), Error> {
+        self.inner().set_badge_count(count).await
+    }
+
+    async fn get_badge_count(&self) -> Result<u32, Error> {
+        self.inner().get_badge_count().await
+    }
+
+    async fn clear_badge(&self) -> Result<(), Error> {
+        self.inner().clear_badge().await
+    }
+
+    async fn schedule_notification(
+        &self,
+        builder: NotificationBuilder,
+        at: DateTime<Utc>,
+    ) -> Result<String, Error> {
+        self.inner().schedule_notification(builder, at).await
+    }
+
+    async fn schedule_repeating_notification(
+        &self,
+        builder: NotificationBuilder,
+        interval: RepeatInterval,
+    ) -> Result<String, Error> {
+        self.inner()
+            .schedule_repeating_notification(builder, interval)
+            .await
+    }
+
+    async fn schedule_calendar_notification(
+        &self,
+        builder: NotificationBuilder,
+        trigger: CalendarTrigger,
+    ) -> Result<String, Error> {
+        self.inner()
+            .schedule_calendar_notification(builder, trigger)
+            .await
+    }
+
+    async fn schedule_location_notification(
+        &self,
+        builder: NotificationBuilder,
+        trigger: LocationTrigger,
+    ) -> Result<String, Error> {
+        self.inner()
+            .schedule_location_notification(builder, trigger)
+            .await
+    }
+
+    fn cancel_scheduled_notification(&self, id: &str) -> Result<(), Error> {
+        self.inner().cancel_scheduled_notification(id)
+    }
+}
+
+#[async_trait]
+impl<T: DelegatingManager> NotificationManager for T {
+    async fn get_notification_permission_state(&self) -> Result<bool, Error> {
+        DelegatingManager::get_notification_permission_state(self).await
+    }
+
+    async fn first_time_ask_for_notification_permission(&self) -> Result<bool, Error> {
+        DelegatingManager::first_time_ask_for_notification_permission(self).await
+    }
+
+    async fn get_notification_settings(&self) -> Result<NotificationSettings, Error> {
+        DelegatingManager::get_notification_settings(self).await
+    }
+
+    async fn request_provisional_permission(&self) -> Result<bool, Error> {
+        DelegatingManager::request_provisional_permission(self).await
+    }
+
+    async fn capabilities(&self) -> Result<NotificationCapabilities, Error> {
+        DelegatingManager::capabilities(self).await
+    }
+
+    async fn is_do_not_disturb_active(&self) -> Result<bool, Error> {
+        DelegatingManager::is_do_not_disturb_active(self).await
+    }
+
+    fn subscribe_do_not_disturb(&self) -> Result<tokio::sync::broadcast::Receiver<bool>, Error> {
+        DelegatingManager::subscribe_do_not_disturb(self)
+    }
+
+    fn register(
+        &self,
+        handler_callback: Box<dyn Fn(NotificationResponse) + Send + Sync + 'static>,
+        categories: Vec<NotificationCategory>,
+    ) -> Result<(), Error> {
+        DelegatingManager::register(self, handler_callback, categories)
+    }
+
+    fn set_response_handler(
+        &self,
+        handler_callback: Box<dyn Fn(NotificationResponse) + Send + Sync + 'static>,
+    ) -> Result<(), Error> {
+        DelegatingManager::set_response_handler(self, handler_callback)
+    }
+
+    fn clear_response_handler(&self) -> Result<(), Error> {
+        DelegatingManager::clear_response_handler(self)
+    }
+
+    fn take_launch_response(&self) -> Option<NotificationResponse> {
+        DelegatingManager::take_launch_response(self)
+    }
+
+    fn subscribe_events(&self) -> tokio::sync::broadcast::Receiver<NotificationEvent> {
+        DelegatingManager::subscribe_events(self)
+    }
+
+    fn add_categories(&self, categories: Vec<NotificationCategory>) -> Result<(), Error> {
+        DelegatingManager::add_categories(self, categories)
+    }
+
+    fn set_categories(&self, categories: Vec<NotificationCategory>) -> Result<(), Error> {
+        DelegatingManager::set_categories(self, categories)
+    }
+
+    fn set_categories_for_locale(
+        &self,
+        categories: Vec<NotificationCategory>,
+        locale: &str,
+    ) -> Result<(), Error> {
+        DelegatingManager::set_categories_for_locale(self, categories, locale)
+    }
+
+    fn remove_category(&self, identifier: &str) -> Result<(), Error> {
+        DelegatingManager::remove_category(self, identifier)
+    }
+
+    fn responses(
+        &self,
+        categories: Vec<NotificationCategory>,
+    ) -> Result<tokio::sync::mpsc::UnboundedReceiver<NotificationResponse>, Error> {
+        DelegatingManager::responses(self, categories)
+    }
+
+    fn remove_all_delivered_notifications(&self) -> Result<(), Error> {
+        DelegatingManager::remove_all_delivered_notifications(self)
+    }
+
+    fn remove_delivered_notifications(&self, ids: Vec<&str>) -> Result<(), Error> {
+        DelegatingManager::remove_delivered_notifications(self, ids)
+    }
+
+    async fn dismiss_thread(&self, thread_id: &str) -> Result<(), Error> {
+        DelegatingManager::dismiss_thread(self, thread_id).await
+    }
+
+    async fn active_count_for_thread(&self, thread_id: &str) -> Result<usize, Error> {
+        DelegatingManager::active_count_for_thread(self, thread_id).await
+    }
+
+    async fn active_thread_ids(&self) -> Result<Vec<String>, Error> {
+        DelegatingManager::active_thread_ids(self).await
+    }
+
+    async fn get_active_notifications(&self) -> Result<Vec<Box<dyn NotificationHandle>>, Error> {
+        DelegatingManager::get_active_notifications(self).await
+    }
+
+    async fn send_notification(
+        &self,
+        builder: NotificationBuilder,
+    ) -> Result<Box<dyn NotificationHandle>, Error> {
+        DelegatingManager::send_notification(self, builder).await
+    }
+
+    async fn update_notification(
+        &self,
+        id: &str,
+        builder: NotificationBuilder,
+    ) -> Result<Box<dyn NotificationHandle>, Error> {
+        DelegatingManager::update_notification(self, id, builder).await
+    }
+
+    async fn update_progress(
+        &self,
+        id: &str,
+        state: ProgressState,
+        status: &str,
+    ) -> Result<(), Error> {
+        DelegatingManager::update_progress(self, id, state, status).await
+    }
+
+    async fn set_badge_count(&self, count: u32) -> Result<(), Error> {
+        DelegatingManager::set_badge_count(self, count).await
+    }
+
+    async fn get_badge_count(&self) -> Result<u32, Error> {
+        DelegatingManager::get_badge_count(self).await
+    }
+
+    async fn clear_badge(&self) -> Result<(), Error> {
+        DelegatingManager::clear_badge(self).await
+    }
+
+    async fn schedule_notification(
+        &self,
+        builder: NotificationBuilder,
+        at: DateTime<Utc>,
+    ) -> Result<String, Error> {
+        DelegatingManager::schedule_notification(self, builder, at).await
+    }
+
+    async fn schedule_repeating_notification(
+        &self,
+        builder: NotificationBuilder,
+        interval: RepeatInterval,
+    ) -> Result<String, Error> {
+        DelegatingManager::schedule_repeating_notification(self, builder, interval).await
+    }
+
+    async fn schedule_calendar_notification(
+        &self,
+        builder: NotificationBuilder,
+        trigger: CalendarTrigger,
+    ) -> Result<String, Error> {
+        DelegatingManager::schedule_calendar_notification(self, builder, trigger).await
+    }
+
+    async fn schedule_location_notification(
+        &self,
+        builder: NotificationBuilder,
+        trigger: LocationTrigger,
+    ) -> Result<String, Error> {
+        DelegatingManager::schedule_location_notification(self, builder, trigger).await
+    }
+
+    fn cancel_scheduled_notification(&self, id: &str) -> Result<(), Error> {
+        DelegatingManager::cancel_scheduled_notification(self, id)
+    }
+}
@@ -0,0 +1,233 @@
+//! Passes the responses to several independent subscribers, each for the notifications it cares about
+//!
+//! ```no_run
+//! # fn example() -> Result<(), user_notify::Error> {
+//! use user_notify::dispatch::{ResponseDispatcher, ResponseFilter};
+//!
+//! let manager = user_notify::get_notification_manager("com.example.app".to_owned(), None);
+//! let dispatcher = ResponseDispatcher::new();
+//! manager.register(dispatcher.handler(), vec![])?;
+//!
+//! let chat = dispatcher.subscribe(ResponseFilter::new().set_category_id("chat"), |response| {
+//!     println!("chat: {response:?}");
+//! });
+//! dispatcher.subscribe(ResponseFilter::new().set_thread_id("downloads"), |response| {
+//!     println!("download: {response:?}");
+//! });
+//! dispatcher.unsubscribe(chat);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [NotificationManager::register] takes a single handler, [ResponseDispatcher::handler] is that handler.
+//! The filters use the category and thread that are stored in the user info by
+//! [NotificationBuilder::set_category_id] and [NotificationBuilder::set_thread_id].
+//!
+//! [NotificationManager::register]: crate::NotificationManager::register
+//! [NotificationBuilder::set_category_id]: crate::NotificationBuilder::set_category_id
+//! [NotificationBuilder::set_thread_id]: crate::NotificationBuilder::set_thread_id
+
+use std::sync::{
+    Arc, Mutex,
+    atomic::{AtomicU64, Ordering},
+};
+
+use crate::NotificationResponse;
+
+type Callback = Arc<dyn Fn(NotificationResponse) + Send + Sync + 'static>;
+
+/// Selects the responses of a subscription, the default matches all
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResponseFilter {
+    category_id: Option<String>,
+    thread_id: Option<String>,
+}
+
+impl ResponseFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only responses to notifications with this category
+    pub fn set_category_id(mut self, category_id: &str) -> Self {
+        self.category_id = Some(category_id.to_owned());
+        self
+    }
+
+    /// Only responses to notifications in this thread
+    pub fn set_thread_id(mut self, thread_id: &str) -> Self {
+        self.thread_id = Some(thread_id.to_owned());
+        self
+    }
+
+    pub fn matches(&self, response: &NotificationResponse) -> bool {
+        self.category_id
+            .as_deref()
+            .is_none_or(|category_id| response.get_category_id() == Some(category_id))
+            && self
+                .thread_id
+                .as_deref()
+                .is_none_or(|thread_id| response.get_thread_id() == Some(thread_id))
+    }
+}
+
+/// Returned by [ResponseDispatcher::subscribe], to unsubscribe again
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(u64);
+
+#[derive(Default)]
+struct Subscriptions {
+    next_id: AtomicU64,
+    subscribers: Mutex<Vec<(SubscriptionId, ResponseFilter, Callback)>>,
+}
+
+/// Fans out the responses to the subscribers whose filter matches,
+/// see the [module documentation](self)
+#[derive(Clone, Default)]
+pub struct ResponseDispatcher {
+    subscriptions: Arc<Subscriptions>,
+}
+
+impl std::fmt::Debug for ResponseDispatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let filters: Vec<_> = self
+            .subscriptions
+            .subscribers
+            .lock()
+            .map(|subscribers| {
+                subscribers
+                    .iter()
+                    .map(|(_, filter, _)| filter.clone())
+                    .collect()
+            })
+            .unwrap_or_default();
+        f.debug_struct("ResponseDispatcher")
+            .field("subscribers", &filters)
+            .finish()
+    }
+}
+
+impl ResponseDispatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The handler for [crate::NotificationManager::register]
+    pub fn handler(&self) -> Box<dyn Fn(NotificationResponse) + Send + Sync + 'static> {
+        let dispatcher = self.clone();
+        Box::new(move |response| dispatcher.dispatch(response))
+    }
+
+    /// Calls `callback` with every response that matches the filter, in the order of subscription
+    pub fn subscribe(
+        &self,
+        filter: ResponseFilter,
+        callback: impl Fn(NotificationResponse) + Send + Sync + 'static,
+    ) -> SubscriptionId {
+        let id = SubscriptionId(self.subscriptions.next_id.fetch_add(1, Ordering::Relaxed));
+        match self.subscriptions.subscribers.lock() {
+            Ok(mut subscribers) => subscribers.push((id, filter, Arc::new(callback))),
+            Err(_) => log::error!("response subscribers lock is poisoned"),
+        }
+        id
+    }
+
+    /// Returns false if there was no subscription with this id
+    pub fn unsubscribe(&self, id: SubscriptionId) -> bool {
+        let Ok(mut subscribers) = self.subscriptions.subscribers.lock() else {
+            return false;
+        };
+        let count = subscribers.len();
+        subscribers.retain(|(subscription, _, _)| *subscription != id);
+        subscribers.len() != count
+    }
+
+    /// Passes the response to the matching subscribers, it is only logged when none matches
+    pub fn dispatch(&self, response: NotificationResponse) {
+        // collected first, so a callback can subscribe or unsubscribe without a deadlock
+        let callbacks: Vec<Callback> = match self.subscriptions.subscribers.lock() {
+            Ok(subscribers) => subscribers
+                .iter()
+                .filter(|(_, filter, _)| filter.matches(&response))
+                .map(|(_, _, callback)| callback.clone())
+                .collect(),
+            Err(_) => {
+                log::error!("response subscribers lock is poisoned");
+                return;
+            }
+        };
+        if callbacks.is_empty() {
+            log::debug!("no subscriber for response {response:?}");
+        }
+        for callback in callbacks {
+            callback(response.clone());
+        }
+    }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use super::*;
+    use crate::{
+        NotificationBuilder, NotificationManager, NotificationResponseAction,
+        mock::NotificationManagerMock,
+    };
+
+    #[tokio::test]
+    async fn fan_out() {
+        let manager = NotificationManagerMock::new();
+        let dispatcher = ResponseDispatcher::new();
+        manager.register(dispatcher.handler(), vec![]).unwrap();
+
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let subscribe = |name: &'static str, filter| {
+            let received = received.clone();
+            dispatcher.subscribe(filter, move |response: NotificationResponse| {
+                received
+                    .lock()
+                    .unwrap()
+                    .push((name, response.notification_id));
+            })
+        };
+        subscribe("all", ResponseFilter::new());
+        let chat = subscribe("chat", ResponseFilter::new().set_category_id("chat"));
+        subscribe(
+            "downloads",
+            ResponseFilter::new().set_thread_id("downloads"),
+        );
+
+        let chat_id = manager
+            .send_notification(NotificationBuilder::new().set_category_id("chat"))
+            .await
+            .unwrap()
+            .get_id();
+        let download_id = manager
+            .send_notification(NotificationBuilder::new().set_thread_id("downloads"))
+            .await
+            .unwrap()
+            .get_id();
+        for id in [&chat_id, &download_id] {
+            manager
+                .simulate_response(id, NotificationResponseAction::Default, None)
+                .await
+                .unwrap();
+        }
+        assert!(dispatcher.unsubscribe(chat));
+        assert!(!dispatcher.unsubscribe(chat));
+        manager
+            .simulate_response(&chat_id, NotificationResponseAction::Dismiss, None)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            *received.lock().unwrap(),
+            vec![
+                ("all", chat_id.clone()),
+                ("chat", chat_id.clone()),
+                ("all", download_id.clone()),
+                ("downloads", download_id),
+                ("all", chat_id),
+            ]
+        );
+    }
+}
@@ -6,15 +6,23 @@ pub enum Error {
     // Tauri(#[from] tauri::Error),
     // #[error("window label not found in HtmlEmailInstancesState")]
     // WindowNotFoundInState,
-    #[cfg(any(target_os = "macos", target_os = "ios"))]
+    #[cfg(all(any(target_os = "macos", target_os = "ios"), feature = "macos"))]
     #[error("bundle id is not set, this is required to send notifications")]
     NoBundleId,
-    #[cfg(any(target_os = "macos", target_os = "ios"))]
+    #[cfg(all(any(target_os = "macos", target_os = "ios"), feature = "macos"))]
     #[error("macOS apis need to be called from the main thread, but this is not the main thread")]
     NotMainThread,
-    #[cfg(any(target_os = "macos", target_os = "ios"))]
-    #[error("NSError: {0}")]
-    NSError(String),
+    #[cfg(all(any(target_os = "macos", target_os = "ios"), feature = "macos"))]
+    #[error("NSError {domain} {code}: {description}")]
+    NSError {
+        domain: String,
+        code: isize,
+        description: String,
+    },
+    #[error("the user or the system does not allow notifications for this app")]
+    PermissionDenied,
+    #[error("notifications are not available: {0}")]
+    PlatformUnavailable(String),
     #[error("Infallible error, something went really wrong: {0}")]
     Infallible(#[from] std::convert::Infallible),
     #[error(transparent)]
@@ -23,31 +31,296 @@ pub enum Error {
     TokioTryLock(#[from] tokio::sync::TryLockError),
     #[error("Url from path parse error {0:?}")]
     ParseUrlFromPath(PathBuf),
-    #[cfg(target_os = "windows")]
+    #[error("Sound file {0:?} does not exist or has an unsupported format")]
+    UnsupportedSoundFile(PathBuf),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("failed to download {url}: {reason}")]
+    ImageDownload { url: String, reason: String },
+    #[error("No notification with id {0}")]
+    NotificationNotFound(String),
+    #[error("the notification was dropped because of the rate limit")]
+    RateLimited,
+    #[error("{0} is not supported on this platform")]
+    Unsupported(String),
+    #[error("Invalid notification: {0}")]
+    InvalidNotification(String),
+    #[error("Invalid notification template: {0}")]
+    InvalidTemplate(String),
+    #[error("Notification template parameter {0} is missing")]
+    MissingTemplateParameter(String),
+    #[cfg(all(target_os = "windows", feature = "windows"))]
     #[error(transparent)]
     Windows(#[from] windows::core::Error),
-    #[cfg(target_os = "windows")]
     #[error("Failed to parse user info {0:?}")]
     FailedToParseUserInfo(serde_json::Error),
-    #[cfg(target_os = "windows")]
     #[error("Error Setting Handler Callback")]
     SettingHandler,
-    #[cfg(target_os = "windows")]
+    #[cfg(all(target_os = "windows", feature = "windows"))]
     #[error(transparent)]
     XmlEscape(#[from] quick_xml::escape::EscapeError),
-    #[cfg(target_os = "windows")]
+    #[cfg(all(target_os = "windows", feature = "windows"))]
     #[error(transparent)]
     UrlParse(#[from] url::ParseError),
-    #[cfg(target_os = "windows")]
+    #[cfg(all(target_os = "windows", feature = "windows"))]
     #[error(transparent)]
     Base64Decode(#[from] base64::DecodeError),
-    #[cfg(any(
+    #[cfg(all(
+        any(
+            target_os = "linux",
+            target_os = "dragonfly",
+            target_os = "freebsd",
+            target_os = "openbsd",
+            target_os = "netbsd"
+        ),
+        feature = "linux-dbus"
+    ))]
+    #[error(transparent)]
+    RustNotifyError(#[from] notify_rust::error::Error),
+    #[cfg(all(
+        any(
+            target_os = "linux",
+            target_os = "dragonfly",
+            target_os = "freebsd",
+            target_os = "openbsd",
+            target_os = "netbsd"
+        ),
+        any(feature = "linux-dbus", feature = "linux-portal")
+    ))]
+    #[error(transparent)]
+    Zbus(#[from] zbus::Error),
+    #[cfg(all(target_arch = "wasm32", feature = "web"))]
+    #[error("JavaScript error: {0}")]
+    Js(String),
+}
+
+/// Category of an [Error], to react to errors without matching the platform specific variants
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// Notifications are turned off for the app, asking again does not help,
+    /// the user needs to enable them in the system settings
+    PermissionDenied,
+    /// The notification system can not be reached, like a missing bundle id on macOS or no notification daemon on Linux
+    PlatformUnavailable,
+    /// The notification or its attachments were rejected
+    InvalidContent,
+    /// There is no notification with this id
+    NotFound,
+    /// The call to the platform failed, see [Error::os_error_code]
+    BackendIo,
+    /// The platform did not answer in time
+    Timeout,
+    /// The notification was not shown because of [crate::rate_limit]
+    RateLimited,
+    /// The platform or the enabled features can not do this, see [crate::NotificationCapabilities]
+    Unsupported,
+    Other,
+}
+
+impl Error {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            #[cfg(all(any(target_os = "macos", target_os = "ios"), feature = "macos"))]
+            Error::NoBundleId => ErrorKind::PlatformUnavailable,
+            #[cfg(all(any(target_os = "macos", target_os = "ios"), feature = "macos"))]
+            Error::NotMainThread => ErrorKind::Other,
+            #[cfg(all(any(target_os = "macos", target_os = "ios"), feature = "macos"))]
+            Error::NSError { domain, code, .. } => ns_error_kind(domain, *code),
+            Error::PermissionDenied => ErrorKind::PermissionDenied,
+            Error::PlatformUnavailable(_) => ErrorKind::PlatformUnavailable,
+            Error::Infallible(_) => ErrorKind::Other,
+            // the completion handler was dropped without being called
+            Error::TokioRecv(_) => ErrorKind::BackendIo,
+            Error::TokioTryLock(_) => ErrorKind::Other,
+            Error::ParseUrlFromPath(_) | Error::UnsupportedSoundFile(_) => {
+                ErrorKind::InvalidContent
+            }
+            Error::Io(err) => io_error_kind(err),
+            Error::ImageDownload { .. } => ErrorKind::BackendIo,
+            Error::NotificationNotFound(_) => ErrorKind::NotFound,
+            Error::RateLimited => ErrorKind::RateLimited,
+            Error::Unsupported(_) => ErrorKind::Unsupported,
+            Error::InvalidNotification(_)
+            | Error::InvalidTemplate(_)
+            | Error::MissingTemplateParameter(_)
+            | Error::FailedToParseUserInfo(_) => ErrorKind::InvalidContent,
+            #[cfg(all(target_os = "windows", feature = "windows"))]
+            Error::Windows(err) => windows_error_kind(err),
+            #[cfg(all(target_os = "windows", feature = "windows"))]
+            Error::XmlEscape(_) | Error::UrlParse(_) | Error::Base64Decode(_) => {
+                ErrorKind::InvalidContent
+            }
+            Error::SettingHandler => ErrorKind::Other,
+            #[cfg(all(
+                any(
+                    target_os = "linux",
+                    target_os = "dragonfly",
+                    target_os = "freebsd",
+                    target_os = "openbsd",
+                    target_os = "netbsd"
+                ),
+                feature = "linux-dbus"
+            ))]
+            Error::RustNotifyError(_) => ErrorKind::BackendIo,
+            #[cfg(all(
+                any(
+                    target_os = "linux",
+                    target_os = "dragonfly",
+                    target_os = "freebsd",
+                    target_os = "openbsd",
+                    target_os = "netbsd"
+                ),
+                any(feature = "linux-dbus", feature = "linux-portal")
+            ))]
+            Error::Zbus(err) => zbus_error_kind(err),
+            #[cfg(all(target_arch = "wasm32", feature = "web"))]
+            Error::Js(_) => ErrorKind::BackendIo,
+        }
+    }
+
+    /// The error code of the underlying OS error:
+    /// errno for io errors, the HRESULT on Windows and the NSError code on macOS
+    pub fn os_error_code(&self) -> Option<i64> {
+        match self {
+            #[cfg(all(any(target_os = "macos", target_os = "ios"), feature = "macos"))]
+            Error::NSError { code, .. } => Some(*code as i64),
+            Error::Io(err) => err.raw_os_error().map(i64::from),
+            #[cfg(all(target_os = "windows", feature = "windows"))]
+            Error::Windows(err) => Some(i64::from(err.code().0)),
+            #[cfg(all(
+                any(
+                    target_os = "linux",
+                    target_os = "dragonfly",
+                    target_os = "freebsd",
+                    target_os = "openbsd",
+                    target_os = "netbsd"
+                ),
+                any(feature = "linux-dbus", feature = "linux-portal")
+            ))]
+            Error::Zbus(zbus::Error::InputOutput(err)) => err.raw_os_error().map(i64::from),
+            _ => None,
+        }
+    }
+
+    /// True for errors that may go away when the call is repeated later
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::TokioTryLock(_) | Error::RateLimited => true,
+            Error::Io(err)
+                if matches!(
+                    err.kind(),
+                    std::io::ErrorKind::Interrupted | std::io::ErrorKind::WouldBlock
+                ) =>
+            {
+                true
+            }
+            _ => self.kind() == ErrorKind::Timeout,
+        }
+    }
+}
+
+fn io_error_kind(err: &std::io::Error) -> ErrorKind {
+    match err.kind() {
+        std::io::ErrorKind::NotFound | std::io::ErrorKind::InvalidData => ErrorKind::InvalidContent,
+        std::io::ErrorKind::TimedOut => ErrorKind::Timeout,
+        _ => ErrorKind::BackendIo,
+    }
+}
+
+/// https://developer.apple.com/documentation/usernotifications/unerror/code
+#[cfg(all(any(target_os = "macos", target_os = "ios"), feature = "macos"))]
+fn ns_error_kind(domain: &str, code: isize) -> ErrorKind {
+    if domain != "UNErrorDomain" {
+        return ErrorKind::BackendIo;
+    }
+    match code {
+        // attachment errors, notificationInvalidNoDate, notificationInvalidNoContent and badgeInputInvalid
+        100..=105 | 1400 | 1401 | 1600 => ErrorKind::InvalidContent,
+        _ => ErrorKind::BackendIo,
+    }
+}
+
+#[cfg(all(target_os = "windows", feature = "windows"))]
+fn windows_error_kind(err: &windows::core::Error) -> ErrorKind {
+    use windows::Win32::Foundation::{E_ACCESSDENIED, ERROR_TIMEOUT};
+
+    match err.code() {
+        E_ACCESSDENIED => ErrorKind::PermissionDenied,
+        code if code == ERROR_TIMEOUT.to_hresult() => ErrorKind::Timeout,
+        _ => ErrorKind::BackendIo,
+    }
+}
+
+#[cfg(all(
+    any(
         target_os = "linux",
         target_os = "dragonfly",
         target_os = "freebsd",
         target_os = "openbsd",
         target_os = "netbsd"
-    ))]
-    #[error(transparent)]
-    RustNotifyError(#[from] notify_rust::error::Error),
+    ),
+    any(feature = "linux-dbus", feature = "linux-portal")
+))]
+fn zbus_error_kind(err: &zbus::Error) -> ErrorKind {
+    match err {
+        zbus::Error::MethodError(name, _, _) => match name.as_str() {
+            "org.freedesktop.portal.Error.NotAllowed"
+            | "org.freedesktop.DBus.Error.AccessDenied" => ErrorKind::PermissionDenied,
+            "org.freedesktop.DBus.Error.ServiceUnknown"
+            | "org.freedesktop.DBus.Error.NameHasNoOwner"
+            | "org.freedesktop.DBus.Error.UnknownMethod" => ErrorKind::PlatformUnavailable,
+            "org.freedesktop.DBus.Error.NoReply"
+            | "org.freedesktop.DBus.Error.Timeout"
+            | "org.freedesktop.DBus.Error.TimedOut" => ErrorKind::Timeout,
+            "org.freedesktop.DBus.Error.InvalidArgs" => ErrorKind::InvalidContent,
+            _ => ErrorKind::BackendIo,
+        },
+        zbus::Error::FDO(err) => match err.as_ref() {
+            zbus::fdo::Error::AccessDenied(_) => ErrorKind::PermissionDenied,
+            zbus::fdo::Error::ServiceUnknown(_)
+            | zbus::fdo::Error::NameHasNoOwner(_)
+            | zbus::fdo::Error::UnknownMethod(_) => ErrorKind::PlatformUnavailable,
+            zbus::fdo::Error::NoReply(_)
+            | zbus::fdo::Error::Timeout(_)
+            | zbus::fdo::Error::TimedOut(_) => ErrorKind::Timeout,
+            zbus::fdo::Error::InvalidArgs(_) => ErrorKind::InvalidContent,
+            _ => ErrorKind::BackendIo,
+        },
+        // no session bus
+        zbus::Error::Address(_) | zbus::Error::Handshake(_) => ErrorKind::PlatformUnavailable,
+        zbus::Error::InputOutput(err) => match err.kind() {
+            std::io::ErrorKind::NotFound | std::io::ErrorKind::ConnectionRefused => {
+                ErrorKind::PlatformUnavailable
+            }
+            std::io::ErrorKind::TimedOut => ErrorKind::Timeout,
+            _ => ErrorKind::BackendIo,
+        },
+        _ => ErrorKind::BackendIo,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kind_and_retryable() {
+        let err = Error::from(std::io::Error::from_raw_os_error(4));
+        assert_eq!(err.os_error_code(), Some(4));
+        let timeout = Error::from(std::io::Error::from(std::io::ErrorKind::TimedOut));
+        assert_eq!(timeout.kind(), ErrorKind::Timeout);
+        assert!(timeout.is_retryable());
+
+        assert_eq!(Error::PermissionDenied.kind(), ErrorKind::PermissionDenied);
+        assert!(!Error::PermissionDenied.is_retryable());
+        assert_eq!(
+            Error::NotificationNotFound("id".to_owned()).kind(),
+            ErrorKind::NotFound
+        );
+        assert_eq!(
+            Error::Unsupported("location triggers".to_owned()).kind(),
+            ErrorKind::Unsupported
+        );
+    }
 }
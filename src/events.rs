@@ -0,0 +1,179 @@
+//! Lifecycle events of notifications, see [NotificationManager::subscribe_events]
+//!
+//! [NotificationManager::subscribe_events]: crate::NotificationManager::subscribe_events
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use tokio::sync::broadcast;
+
+use crate::{
+    NotificationDismissReason, NotificationResponse, NotificationResponseAction,
+    notification::{OnResponse, ResponseCallback},
+    open_url_on_click,
+};
+
+/// Events that were not received yet by a subscriber are dropped after this many newer events
+const EVENT_CAPACITY: usize = 64;
+
+/// Something that happened to the notification with `notification_id`
+#[derive(Debug, Clone, PartialEq)]
+pub struct NotificationEvent {
+    pub notification_id: String,
+    pub kind: NotificationEventKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NotificationEventKind {
+    /// The notification was shown, also when it was replaced by an update
+    Presented,
+    /// The notification itself was clicked
+    Clicked,
+    /// A button of the notification was clicked, `user_text` is set for text and selection inputs
+    Action {
+        identifier: String,
+        user_text: Option<String>,
+    },
+    /// The notification left the notification center
+    Dismissed(NotificationDismissReason),
+}
+
+impl From<&NotificationResponse> for NotificationEvent {
+    fn from(response: &NotificationResponse) -> Self {
+        let kind = match &response.action {
+            NotificationResponseAction::Default => NotificationEventKind::Clicked,
+            NotificationResponseAction::Dismiss => NotificationEventKind::Dismissed(
+                response
+                    .dismiss_reason
+                    .unwrap_or(NotificationDismissReason::DismissedByUser),
+            ),
+            NotificationResponseAction::Other(identifier) => NotificationEventKind::Action {
+                identifier: identifier.clone(),
+                user_text: response.user_text.clone(),
+            },
+        };
+        Self {
+            notification_id: response.notification_id.clone(),
+            kind,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct NotificationEvents {
+    sender: broadcast::Sender<NotificationEvent>,
+    /// The callbacks of [crate::NotificationBuilder::on_response] by notification id
+    response_callbacks: Arc<Mutex<HashMap<String, ResponseCallback>>>,
+}
+
+impl std::fmt::Debug for NotificationEvents {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NotificationEvents")
+            .field("sender", &self.sender)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for NotificationEvents {
+    fn default() -> Self {
+        Self {
+            sender: broadcast::channel(EVENT_CAPACITY).0,
+            response_callbacks: Default::default(),
+        }
+    }
+}
+
+impl NotificationEvents {
+    pub(crate) fn subscribe(&self) -> broadcast::Receiver<NotificationEvent> {
+        self.sender.subscribe()
+    }
+
+    pub(crate) fn emit(&self, notification_id: &str, kind: NotificationEventKind) {
+        // fails when there is no subscriber, which is fine
+        let _ = self.sender.send(NotificationEvent {
+            notification_id: notification_id.to_owned(),
+            kind,
+        });
+    }
+
+    /// Links the callback of [crate::NotificationBuilder::on_response] to the sent notification,
+    /// an update without callback unlinks the one of the replaced notification
+    pub(crate) fn set_response_callback(&self, notification_id: &str, callback: &OnResponse) {
+        let Ok(mut callbacks) = self.response_callbacks.lock() else {
+            log::error!("response callbacks lock is poisoned");
+            return;
+        };
+        match &callback.0 {
+            Some(callback) => callbacks.insert(notification_id.to_owned(), callback.clone()),
+            None => callbacks.remove(notification_id),
+        };
+    }
+
+    /// Forgets the callbacks of removed notifications, `None` removes all
+    pub(crate) fn remove_response_callbacks(&self, notification_ids: Option<&[&str]>) {
+        let Ok(mut callbacks) = self.response_callbacks.lock() else {
+            log::error!("response callbacks lock is poisoned");
+            return;
+        };
+        match notification_ids {
+            Some(notification_ids) => {
+                for notification_id in notification_ids {
+                    callbacks.remove(*notification_id);
+                }
+            }
+            None => callbacks.clear(),
+        }
+    }
+
+    /// Wraps the handler passed to [crate::NotificationManager::register],
+    /// so every response is also sent as event and opens the url of [crate::NotificationBuilder::on_click_open_url].
+    /// The entries that the crate adds to the user info are moved out of [NotificationResponse::user_info].
+    /// Responses to notifications with [crate::NotificationBuilder::on_response] go to their callback instead.
+    #[allow(clippy::type_complexity)]
+    pub(crate) fn wrap_handler(
+        &self,
+        handler: Box<dyn Fn(NotificationResponse) + Send + Sync + 'static>,
+    ) -> Box<dyn Fn(NotificationResponse) + Send + Sync + 'static> {
+        let sender = self.sender.clone();
+        let response_callbacks = self.response_callbacks.clone();
+        Box::new(move |response: NotificationResponse| {
+            let response = response.split_internal_info();
+            let _ = sender.send(NotificationEvent::from(&response));
+            open_url_on_click(&response);
+            if let Some(response) = route_to_response_callback(&response_callbacks, response) {
+                handler(response)
+            }
+        })
+    }
+}
+
+/// Passes the response to the callback of [crate::NotificationBuilder::on_response],
+/// returns it when the notification has none, so it goes to the handler.
+/// The callback is kept for later responses, like a reply after a click, and removed with the dismissal,
+/// so it is not kept for notifications that are gone.
+fn route_to_response_callback(
+    response_callbacks: &Mutex<HashMap<String, ResponseCallback>>,
+    response: NotificationResponse,
+) -> Option<NotificationResponse> {
+    let callback = match response_callbacks.lock() {
+        Ok(mut callbacks) if response.is_dismissed() => callbacks.remove(&response.notification_id),
+        Ok(callbacks) => callbacks.get(&response.notification_id).cloned(),
+        Err(_) => {
+            log::error!("response callbacks lock is poisoned");
+            None
+        }
+    };
+    // the snooze manager shows the notification again, with its callback
+    if response.action_id() == Some(crate::snooze::SNOOZE_ACTION) {
+        return Some(response);
+    }
+    match callback {
+        Some(callback) => {
+            callback(response);
+            None
+        }
+        None => Some(response),
+    }
+}
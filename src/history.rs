@@ -0,0 +1,490 @@
+//! Log of the sent notifications and the responses of the user, to show a history in the app
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), user_notify::Error> {
+//! use std::time::Duration;
+//! use user_notify::{
+//!     NotificationBuilder, NotificationManager,
+//!     history::{HistoryFilter, HistoryNotificationManager},
+//! };
+//!
+//! let manager = HistoryNotificationManager::new(user_notify::get_notification_manager(
+//!     "com.example.app".to_owned(),
+//!     None,
+//! ))
+//! .set_max_entries(500)
+//! .set_max_age(Duration::from_secs(7 * 24 * 60 * 60));
+//! manager.register(Box::new(|response| println!("{response:?}")), vec![])?;
+//! manager
+//!     .send_notification(NotificationBuilder::new().title("Build finished"))
+//!     .await?;
+//!
+//! for entry in manager.history(&HistoryFilter::new().set_limit(20)) {
+//!     println!("{} {}: {:?}", entry.recorded_at, entry.notification_id, entry.kind);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Responses are only recorded for the handler that was registered through this manager.
+//! Scheduled notifications are recorded when they are scheduled, not when the system shows them.
+//! The history is kept in memory, so it starts empty with every run of the app.
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use crate::{
+    CalendarTrigger, Error, LocationTrigger, NotificationBuilder, NotificationCategory,
+    NotificationEvent, NotificationEventKind, NotificationHandle, NotificationManager,
+    NotificationResponse, RepeatInterval,
+};
+
+/// What happened to a notification
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum HistoryEntryKind {
+    /// Sent or updated with this content
+    Sent(NotificationBuilder),
+    /// Scheduled with this content, the system shows it later
+    Scheduled(NotificationBuilder),
+    /// Sending failed with this error
+    Failed {
+        builder: NotificationBuilder,
+        error: String,
+    },
+    /// The user responded to the notification
+    Response(NotificationEventKind),
+}
+
+/// One record of the history
+#[derive(Debug, Clone)]
+pub struct HistoryEntry {
+    /// Empty for [HistoryEntryKind::Failed] when the notification had no id yet
+    pub notification_id: String,
+    /// The thread id of the notification, for responses the one it was sent with
+    pub thread_id: Option<String>,
+    pub recorded_at: DateTime<Utc>,
+    pub kind: HistoryEntryKind,
+}
+
+/// Selects the entries returned by [HistoryNotificationManager::history], the default matches all
+#[derive(Debug, Clone, Default)]
+pub struct HistoryFilter {
+    notification_id: Option<String>,
+    thread_id: Option<String>,
+    since: Option<DateTime<Utc>>,
+    responses_only: bool,
+    limit: Option<usize>,
+}
+
+impl HistoryFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only entries of the notification with this id
+    pub fn set_notification_id(mut self, id: &str) -> Self {
+        self.notification_id = Some(id.to_owned());
+        self
+    }
+
+    /// Only entries of notifications in this thread, see [NotificationBuilder::set_thread_id]
+    pub fn set_thread_id(mut self, thread_id: &str) -> Self {
+        self.thread_id = Some(thread_id.to_owned());
+        self
+    }
+
+    /// Only entries recorded at or after this time
+    pub fn set_since(mut self, since: DateTime<Utc>) -> Self {
+        self.since = Some(since);
+        self
+    }
+
+    /// Only the responses of the user
+    pub fn set_responses_only(mut self, responses_only: bool) -> Self {
+        self.responses_only = responses_only;
+        self
+    }
+
+    /// At most this many entries, the newest ones
+    pub fn set_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    fn matches(&self, entry: &HistoryEntry) -> bool {
+        self.notification_id
+            .as_ref()
+            .is_none_or(|id| *id == entry.notification_id)
+            && self
+                .thread_id
+                .as_ref()
+                .is_none_or(|thread_id| entry.thread_id.as_ref() == Some(thread_id))
+            && self.since.is_none_or(|since| entry.recorded_at >= since)
+            && (!self.responses_only || matches!(entry.kind, HistoryEntryKind::Response(_)))
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// oldest first
+    entries: VecDeque<HistoryEntry>,
+    max_entries: Option<usize>,
+    max_age: Option<Duration>,
+}
+
+impl State {
+    fn prune(&mut self) {
+        if let Some(max_age) = self
+            .max_age
+            .and_then(|max_age| chrono::Duration::from_std(max_age).ok())
+            && let Some(cutoff) = Utc::now().checked_sub_signed(max_age)
+        {
+            while self
+                .entries
+                .front()
+                .is_some_and(|entry| entry.recorded_at < cutoff)
+            {
+                self.entries.pop_front();
+            }
+        }
+        if let Some(max_entries) = self.max_entries {
+            let excess = self.entries.len().saturating_sub(max_entries);
+            self.entries.drain(..excess);
+        }
+    }
+
+    /// The thread of the last entry of this notification that has one
+    fn thread_id_of(&self, id: &str) -> Option<String> {
+        self.entries
+            .iter()
+            .rev()
+            .filter(|entry| entry.notification_id == id)
+            .find_map(|entry| entry.thread_id.clone())
+    }
+}
+
+/// Wraps a [NotificationManager] and records a history of its notifications,
+/// see the [module documentation](self)
+#[derive(Debug, Clone)]
+pub struct HistoryNotificationManager {
+    inner: Arc<dyn NotificationManager>,
+    state: Arc<Mutex<State>>,
+}
+
+impl HistoryNotificationManager {
+    /// Keeps all entries until they are pruned with [Self::set_max_entries] or [Self::set_max_age]
+    pub fn new(inner: Arc<dyn NotificationManager>) -> Self {
+        Self {
+            inner,
+            state: Arc::default(),
+        }
+    }
+
+    /// Keep at most this many entries, the oldest are removed first
+    pub fn set_max_entries(self, max_entries: usize) -> Self {
+        if let Ok(mut state) = self.state.lock() {
+            state.max_entries = Some(max_entries);
+            state.prune();
+        }
+        self
+    }
+
+    /// Remove the entries that are older than this
+    pub fn set_max_age(self, max_age: Duration) -> Self {
+        if let Ok(mut state) = self.state.lock() {
+            state.max_age = Some(max_age);
+            state.prune();
+        }
+        self
+    }
+
+    /// The wrapped manager
+    pub fn as_inner(&self) -> &Arc<dyn NotificationManager> {
+        &self.inner
+    }
+
+    /// The entries that match the filter, newest first
+    pub fn history(&self, filter: &HistoryFilter) -> Vec<HistoryEntry> {
+        let Ok(mut state) = self.state.lock() else {
+            log::error!("history lock is poisoned");
+            return Vec::new();
+        };
+        state.prune();
+        state
+            .entries
+            .iter()
+            .rev()
+            .filter(|entry| filter.matches(entry))
+            .take(filter.limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect()
+    }
+
+    /// Removes the entries that are older than `before`
+    pub fn prune_before(&self, before: DateTime<Utc>) {
+        if let Ok(mut state) = self.state.lock() {
+            state.entries.retain(|entry| entry.recorded_at >= before);
+        }
+    }
+
+    /// Removes all entries
+    pub fn clear(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            state.entries.clear();
+        }
+    }
+
+    fn record(&self, notification_id: String, thread_id: Option<String>, kind: HistoryEntryKind) {
+        let Ok(mut state) = self.state.lock() else {
+            log::error!("history lock is poisoned");
+            return;
+        };
+        state.entries.push_back(HistoryEntry {
+            notification_id,
+            thread_id,
+            recorded_at: Utc::now(),
+            kind,
+        });
+        state.prune();
+    }
+
+    fn record_response(&self, response: &NotificationResponse) {
+        let event = NotificationEvent::from(response);
+        let thread_id = self
+            .state
+            .lock()
+            .ok()
+            .and_then(|state| state.thread_id_of(&event.notification_id));
+        self.record(
+            event.notification_id,
+            thread_id,
+            HistoryEntryKind::Response(event.kind),
+        );
+    }
+
+    /// Wraps the handler, so the responses are recorded before they are passed on
+    fn record_responses(
+        &self,
+        handler_callback: Box<dyn Fn(NotificationResponse) + Send + Sync + 'static>,
+    ) -> Box<dyn Fn(NotificationResponse) + Send + Sync + 'static> {
+        let manager = self.clone();
+        Box::new(move |response| {
+            manager.record_response(&response);
+            handler_callback(response);
+        })
+    }
+
+    /// Records the outcome of sending or scheduling the builder
+    fn record_result<T>(
+        &self,
+        builder: NotificationBuilder,
+        id: Option<&str>,
+        scheduled: bool,
+        result: &Result<T, Error>,
+    ) {
+        let thread_id = builder.get_thread_id().map(str::to_owned);
+        let id = id.unwrap_or_default().to_owned();
+        match result {
+            Ok(_) if scheduled => self.record(id, thread_id, HistoryEntryKind::Scheduled(builder)),
+            Ok(_) => self.record(id, thread_id, HistoryEntryKind::Sent(builder)),
+            Err(err) => self.record(
+                id,
+                thread_id,
+                HistoryEntryKind::Failed {
+                    builder,
+                    error: err.to_string(),
+                },
+            ),
+        }
+    }
+
+    fn record_scheduled(
+        &self,
+        builder: NotificationBuilder,
+        result: Result<String, Error>,
+    ) -> Result<String, Error> {
+        let id = result.as_ref().ok().cloned();
+        self.record_result(builder, id.as_deref(), true, &result);
+        result
+    }
+}
+
+#[async_trait]
+impl crate::delegate::DelegatingManager for HistoryNotificationManager {
+    fn inner(&self) -> &Arc<dyn NotificationManager> {
+        &self.inner
+    }
+
+    /// The responses are recorded before they are passed to the handler
+    fn register(
+        &self,
+        handler_callback: Box<dyn Fn(NotificationResponse) + Send + Sync + 'static>,
+        categories: Vec<NotificationCategory>,
+    ) -> Result<(), Error> {
+        self.inner
+            .register(self.record_responses(handler_callback), categories)
+    }
+
+    fn set_response_handler(
+        &self,
+        handler_callback: Box<dyn Fn(NotificationResponse) + Send + Sync + 'static>,
+    ) -> Result<(), Error> {
+        self.inner
+            .set_response_handler(self.record_responses(handler_callback))
+    }
+
+    /// The responses are still recorded after the handler was cleared
+    fn clear_response_handler(&self) -> Result<(), Error> {
+        self.inner
+            .set_response_handler(self.record_responses(crate::response_handler::dropping()))
+    }
+
+    /// The responses are recorded before they are sent to the receiver
+    fn responses(
+        &self,
+        categories: Vec<NotificationCategory>,
+    ) -> Result<tokio::sync::mpsc::UnboundedReceiver<NotificationResponse>, Error> {
+        let (handler, rx) = crate::response_handler::channel();
+        self.inner
+            .register(self.record_responses(handler), categories)?;
+        Ok(rx)
+    }
+
+    fn take_launch_response(&self) -> Option<NotificationResponse> {
+        let response = self.inner.take_launch_response()?;
+        self.record_response(&response);
+        Some(response)
+    }
+
+    async fn send_notification(
+        &self,
+        builder: NotificationBuilder,
+    ) -> Result<Box<dyn NotificationHandle>, Error> {
+        let result = self.inner.send_notification(builder.clone()).await;
+        let id = result.as_ref().ok().map(|handle| handle.get_id());
+        self.record_result(builder, id.as_deref(), false, &result);
+        result
+    }
+
+    async fn update_notification(
+        &self,
+        id: &str,
+        builder: NotificationBuilder,
+    ) -> Result<Box<dyn NotificationHandle>, Error> {
+        let result = self.inner.update_notification(id, builder.clone()).await;
+        self.record_result(builder, Some(id), false, &result);
+        result
+    }
+
+    async fn schedule_notification(
+        &self,
+        builder: NotificationBuilder,
+        at: DateTime<Utc>,
+    ) -> Result<String, Error> {
+        let result = self.inner.schedule_notification(builder.clone(), at).await;
+        self.record_scheduled(builder, result)
+    }
+
+    async fn schedule_repeating_notification(
+        &self,
+        builder: NotificationBuilder,
+        interval: RepeatInterval,
+    ) -> Result<String, Error> {
+        let result = self
+            .inner
+            .schedule_repeating_notification(builder.clone(), interval)
+            .await;
+        self.record_scheduled(builder, result)
+    }
+
+    async fn schedule_calendar_notification(
+        &self,
+        builder: NotificationBuilder,
+        trigger: CalendarTrigger,
+    ) -> Result<String, Error> {
+        let result = self
+            .inner
+            .schedule_calendar_notification(builder.clone(), trigger)
+            .await;
+        self.record_scheduled(builder, result)
+    }
+
+    async fn schedule_location_notification(
+        &self,
+        builder: NotificationBuilder,
+        trigger: LocationTrigger,
+    ) -> Result<String, Error> {
+        let result = self
+            .inner
+            .schedule_location_notification(builder.clone(), trigger)
+            .await;
+        self.record_scheduled(builder, result)
+    }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use super::*;
+    use crate::{NotificationResponseAction, mock::NotificationManagerMock};
+
+    #[tokio::test]
+    async fn history() {
+        let mock = NotificationManagerMock::new();
+        let manager = HistoryNotificationManager::new(Arc::new(mock.clone())).set_max_entries(3);
+        manager.register(Box::new(|_| {}), vec![]).unwrap();
+
+        let first = manager
+            .send_notification(NotificationBuilder::new().title("first").set_thread_id("a"))
+            .await
+            .unwrap()
+            .get_id();
+        let second = manager
+            .send_notification(NotificationBuilder::new().title("second"))
+            .await
+            .unwrap()
+            .get_id();
+        mock.simulate_response(&first, NotificationResponseAction::Default, None)
+            .await
+            .unwrap();
+
+        let all = manager.history(&HistoryFilter::new());
+        assert_eq!(all.len(), 3);
+        assert!(matches!(
+            &all[0].kind,
+            HistoryEntryKind::Response(NotificationEventKind::Clicked)
+        ));
+        assert_eq!(all[0].notification_id, first);
+        assert_eq!(all[1].notification_id, second);
+
+        let thread = manager.history(&HistoryFilter::new().set_thread_id("a"));
+        assert_eq!(thread.len(), 2);
+        assert_eq!(
+            manager
+                .history(&HistoryFilter::new().set_responses_only(true))
+                .len(),
+            1
+        );
+        assert_eq!(manager.history(&HistoryFilter::new().set_limit(1)).len(), 1);
+
+        // the oldest entry is pruned
+        manager
+            .send_notification(NotificationBuilder::new().title("third"))
+            .await
+            .unwrap();
+        let all = manager.history(&HistoryFilter::new());
+        assert_eq!(all.len(), 3);
+        assert!(all.iter().all(|entry| {
+            !matches!(&entry.kind, HistoryEntryKind::Sent(builder) if builder.get_title() == Some("first"))
+        }));
+
+        manager.prune_before(Utc::now());
+        assert!(manager.history(&HistoryFilter::new()).is_empty());
+    }
+}
@@ -0,0 +1,97 @@
+//! Responses that arrive before [NotificationManager::register] was called,
+//! for example the click on a notification that started the app.
+//!
+//! [NotificationManager::register]: crate::NotificationManager::register
+
+use std::sync::{Arc, Mutex};
+
+use crate::NotificationResponse;
+
+#[derive(Debug, Default)]
+struct Buffer {
+    registered: bool,
+    /// the first response before registration, see [crate::NotificationManager::take_launch_response]
+    launch: Option<NotificationResponse>,
+    /// later responses before registration, they are passed to the handler once it is registered
+    early: Vec<NotificationResponse>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub(crate) struct LaunchResponses {
+    buffer: Arc<Mutex<Buffer>>,
+}
+
+impl LaunchResponses {
+    /// Keeps the response if there is no handler yet,
+    /// otherwise it is returned so the caller passes it to the handler
+    pub(crate) fn buffer(&self, response: NotificationResponse) -> Option<NotificationResponse> {
+        let Ok(mut buffer) = self.buffer.lock() else {
+            log::error!("launch response lock is poisoned, dropping {response:?}");
+            return None;
+        };
+        if buffer.registered {
+            return Some(response);
+        }
+        if buffer.launch.is_none() {
+            buffer.launch = Some(response);
+        } else {
+            buffer.early.push(response);
+        }
+        None
+    }
+
+    /// Call it after the handler was set,
+    /// returns the buffered responses that the handler should get now
+    pub(crate) fn registered(&self) -> Vec<NotificationResponse> {
+        let Ok(mut buffer) = self.buffer.lock() else {
+            log::error!("launch response lock is poisoned");
+            return Vec::new();
+        };
+        buffer.registered = true;
+        std::mem::take(&mut buffer.early)
+    }
+
+    pub(crate) fn take(&self) -> Option<NotificationResponse> {
+        self.buffer.lock().ok()?.launch.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::NotificationResponseAction;
+
+    fn response(id: &str) -> NotificationResponse {
+        NotificationResponse::new(
+            id.to_owned(),
+            NotificationResponseAction::Default,
+            None,
+            HashMap::new(),
+        )
+    }
+
+    #[test]
+    fn buffer_until_registered() {
+        let launch_responses = LaunchResponses::default();
+        assert!(launch_responses.buffer(response("launch")).is_none());
+        assert!(launch_responses.buffer(response("early")).is_none());
+
+        let early = launch_responses.registered();
+        assert_eq!(early.len(), 1);
+        assert_eq!(early[0].notification_id, "early");
+        assert_eq!(
+            launch_responses
+                .buffer(response("late"))
+                .map(|r| r.notification_id),
+            Some("late".to_owned())
+        );
+
+        assert_eq!(
+            launch_responses.take().map(|r| r.notification_id),
+            Some("launch".to_owned())
+        );
+        assert!(launch_responses.take().is_none());
+    }
+}
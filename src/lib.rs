@@ -1,63 +1,306 @@
+pub mod activation;
+// blocking the only thread of the browser would never let the promises resolve
+#[cfg(not(target_arch = "wasm32"))]
+pub mod blocking;
+mod delegate;
+pub mod dispatch;
 mod error;
+// used by all backends except the noop one
+#[cfg_attr(
+    not(any(
+        feature = "mock",
+        all(target_os = "macos", feature = "macos"),
+        all(target_os = "windows", feature = "windows"),
+        all(target_arch = "wasm32", feature = "web"),
+        all(
+            any(
+                target_os = "linux",
+                target_os = "dragonfly",
+                target_os = "freebsd",
+                target_os = "openbsd",
+                target_os = "netbsd"
+            ),
+            any(feature = "linux-dbus", feature = "linux-portal")
+        )
+    )),
+    allow(dead_code)
+)]
+mod events;
+pub mod history;
+// used by the backends that can be launched by a notification
+#[cfg_attr(
+    not(any(
+        feature = "mock",
+        all(target_os = "macos", feature = "macos"),
+        all(target_os = "windows", feature = "windows")
+    )),
+    allow(dead_code)
+)]
+mod launch_response;
+pub mod localization;
+mod manager_options;
+mod markdown;
+pub mod metrics;
 mod notification;
+// browsers have no file system
+#[cfg(not(target_arch = "wasm32"))]
+pub mod persistent_schedule;
 mod platform_impl;
+pub mod rate_limit;
+// browsers load image urls themselves
+#[cfg(not(target_arch = "wasm32"))]
+pub mod remote_image;
+// used by all backends except the noop one
+#[cfg_attr(
+    not(any(
+        feature = "mock",
+        all(target_os = "macos", feature = "macos"),
+        all(target_os = "windows", feature = "windows"),
+        all(target_arch = "wasm32", feature = "web"),
+        all(
+            any(
+                target_os = "linux",
+                target_os = "dragonfly",
+                target_os = "freebsd",
+                target_os = "openbsd",
+                target_os = "netbsd"
+            ),
+            any(feature = "linux-dbus", feature = "linux-portal")
+        )
+    )),
+    allow(dead_code)
+)]
+mod response_handler;
+mod scheduler;
+pub mod snooze;
+// the backends that depend on the image crate
+#[cfg(any(
+    all(target_os = "windows", feature = "windows"),
+    all(
+        any(
+            target_os = "linux",
+            target_os = "dragonfly",
+            target_os = "freebsd",
+            target_os = "openbsd",
+            target_os = "netbsd"
+        ),
+        feature = "linux-portal"
+    )
+))]
+mod still_image;
+pub mod summary;
+#[cfg(feature = "tauri")]
+pub mod tauri;
+mod template;
 mod xdg_category;
 
-use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    sync::{Arc, LazyLock, Mutex, PoisonError},
+};
 
-pub use error::Error;
+pub use error::{Error, ErrorKind};
+pub use events::{NotificationEvent, NotificationEventKind};
+pub use manager_options::NotificationManagerOptions;
 pub use notification::*;
 pub use platform_impl::*;
+pub use template::NotificationTemplate;
 pub use xdg_category::*;
 
 /// Get the notification manager for the platform
 ///
-/// app_id and notification_protocol are only used on windows
-#[allow(unused_variables)]
+/// Returns the [noop::NoopNotificationManager], which only logs the calls,
+/// in headless environments (see [noop::is_headless]) and when notifications are not available,
+/// like without bundle id on macOS or without D-Bus session bus on a Linux server.
+/// Use [try_get_notification_manager] to handle that case yourself.
+///
+/// Calls with the same app id return the same manager, see [try_get_notification_manager_with_options],
+/// that is also the manager of [NotificationManagerOptions::set_backend] when one was set for the app id.
+/// The first call decides the notification_protocol, a different one in a later call is ignored and logged as warning.
+///
+/// notification_protocol is only used on windows, app_id on windows and as desktop entry on linux
+/// (see [NotificationManagerOptions::set_desktop_entry]),
+/// see [get_notification_manager_with_options] for the name and icon of the app
 pub fn get_notification_manager(
     app_id: String,
     notification_protocol: Option<String>,
 ) -> Arc<dyn NotificationManager> {
-    #[cfg(target_os = "macos")]
+    get_notification_manager_with_options(options(app_id, notification_protocol))
+}
+
+/// Like [get_notification_manager], with the name, desktop entry and icon of the app
+pub fn get_notification_manager_with_options(
+    options: NotificationManagerOptions,
+) -> Arc<dyn NotificationManager> {
+    // a custom backend knows itself whether it can show notifications
+    if options.backend.is_none()
+        && let Some(reason) = platform_impl::noop::headless_reason()
+    {
+        log::info!("using noop notification manager: {reason}");
+        return Arc::new(platform_impl::noop::NoopNotificationManager::new());
+    }
+    try_get_notification_manager_with_options(options).unwrap_or_else(|err| {
+        log::error!("falling back to noop notification manager: {err}");
+        Arc::new(platform_impl::noop::NoopNotificationManager::new())
+            as Arc<dyn NotificationManager>
+    })
+}
+
+/// Get the notification manager for the platform,
+/// or an error of kind [ErrorKind::PlatformUnavailable] when notifications are not available
+/// or the backend for the platform was not enabled with its cargo feature
+///
+/// Unlike [get_notification_manager] it does not check for a headless environment
+///
+/// notification_protocol is only used on windows, app_id on windows and as desktop entry on linux,
+/// like with [get_notification_manager] the first call for an app id decides the notification_protocol
+pub fn try_get_notification_manager(
+    app_id: String,
+    notification_protocol: Option<String>,
+) -> Result<Arc<dyn NotificationManager>, Error> {
+    try_get_notification_manager_with_options(options(app_id, notification_protocol))
+}
+
+fn options(app_id: String, notification_protocol: Option<String>) -> NotificationManagerOptions {
+    NotificationManagerOptions {
+        app_id,
+        notification_protocol,
+        ..Default::default()
+    }
+}
+
+/// The manager with the options it was created with
+type CachedManager = (NotificationManagerOptions, Arc<dyn NotificationManager>);
+
+/// The managers that were created by app id, each app id has only one manager,
+/// so the handler, the categories and the delegate are not registered twice
+static MANAGERS: LazyLock<Mutex<HashMap<String, CachedManager>>> = LazyLock::new(Default::default);
+
+/// Like [try_get_notification_manager], with the name, desktop entry and icon of the app
+///
+/// The manager is created once per app id (on macOS once per app bundle),
+/// later calls with the same app id return the same instance, even when their other options differ,
+/// the differing options are ignored and logged as warning. Failures are not remembered, so it can be tried again.
+pub fn try_get_notification_manager_with_options(
+    options: NotificationManagerOptions,
+) -> Result<Arc<dyn NotificationManager>, Error> {
+    // on macOS the manager belongs to the bundle of the app, the app id is not used
+    let key = if cfg!(all(target_os = "macos", feature = "macos")) {
+        String::new()
+    } else {
+        options.app_id.clone()
+    };
+    if let Some(manager) = cached_notification_manager(&key, &options) {
+        return Ok(manager);
+    }
+    // not locked while it is created, that can take a while and call back into the crate
+    let manager = create_notification_manager(options.clone())?;
+    // the cache only holds finished managers, so a panic while it was locked does not leave it broken
+    let mut managers = MANAGERS.lock().unwrap_or_else(PoisonError::into_inner);
+    let (_, manager) = managers
+        .entry(key)
+        // another thread was faster, then its manager is used by everyone
+        .or_insert((options, manager));
+    Ok(manager.clone())
+}
+
+fn cached_notification_manager(
+    key: &str,
+    options: &NotificationManagerOptions,
+) -> Option<Arc<dyn NotificationManager>> {
+    let managers = MANAGERS.lock().unwrap_or_else(PoisonError::into_inner);
+    let (existing_options, manager) = managers.get(key)?;
+    if existing_options != options {
+        log::warn!(
+            "the notification manager for {} already exists with other options, ignoring {options:?}",
+            options.app_id
+        );
+    }
+    Some(manager.clone())
+}
+
+#[allow(unused_variables, unreachable_code)]
+fn create_notification_manager(
+    options: NotificationManagerOptions,
+) -> Result<Arc<dyn NotificationManager>, Error> {
+    if let Some(backend) = options.backend {
+        return Ok(backend.0);
+    }
+    #[cfg(all(target_os = "macos", feature = "macos"))]
     {
         use objc2_foundation::NSBundle;
         if unsafe { NSBundle::mainBundle().bundleIdentifier().is_none() } {
-            return Arc::new(platform_impl::mock::NotificationManagerMock::new())
-                as Arc<dyn NotificationManager>;
+            return Err(Error::NoBundleId);
         }
-        Arc::new(platform_impl::mac_os::NotificationManagerMacOS::new())
-            as Arc<dyn NotificationManager>
+        return Ok(
+            Arc::new(platform_impl::mac_os::NotificationManagerMacOS::new())
+                as Arc<dyn NotificationManager>,
+        );
     }
-    #[cfg(target_os = "windows")]
+    #[cfg(all(target_os = "windows", feature = "windows"))]
     {
         use ::windows::core::HSTRING;
-        match ::windows::UI::Notifications::ToastNotificationManager::CreateToastNotifierWithId(
-            &HSTRING::from(&app_id),
-        ) {
-            Ok(_tf) => Arc::new(platform_impl::windows::NotificationManagerWindows::new(
-                app_id.clone(),
-                notification_protocol,
-            )) as Arc<dyn NotificationManager>,
-            Err(err) => {
-                log::error!(
-                    "failed to get toast notifier for {app_id}, falling back to mock notifification manager: {err:?}"
-                );
-                Arc::new(platform_impl::mock::NotificationManagerMock::new())
-                    as Arc<dyn NotificationManager>
-            }
+        ::windows::UI::Notifications::ToastNotificationManager::CreateToastNotifierWithId(
+            &HSTRING::from(&options.app_id),
+        )?;
+        if let Some(app_name) = &options.app_name {
+            platform_impl::windows::register_app_id(
+                &options.app_id,
+                app_name,
+                options.icon.as_deref(),
+            )?;
         }
+        return Ok(
+            Arc::new(platform_impl::windows::NotificationManagerWindows::new(
+                options.app_id,
+                options.notification_protocol,
+            )) as Arc<dyn NotificationManager>,
+        );
     }
-    #[cfg(any(
-        target_os = "linux",
-        target_os = "dragonfly",
-        target_os = "freebsd",
-        target_os = "openbsd",
-        target_os = "netbsd"
+    #[cfg(all(
+        any(
+            target_os = "linux",
+            target_os = "dragonfly",
+            target_os = "freebsd",
+            target_os = "openbsd",
+            target_os = "netbsd"
+        ),
+        any(feature = "linux-dbus", feature = "linux-portal")
     ))]
     {
         // user_notify::xdg::NotificationBuilderXdg::new()
         //     .category_hint(user_notify::xdg::NotificationCategory::ImReceived)
         //     .appname("Delta Chat")
-        Arc::new(platform_impl::xdg::NotificationManagerXdg::new()) as Arc<dyn NotificationManager>
+        if !platform_impl::xdg::has_session_bus() {
+            return Err(Error::PlatformUnavailable(
+                "no D-Bus session bus found".to_owned(),
+            ));
+        }
+        #[cfg(feature = "linux-portal")]
+        if platform_impl::xdg::portal::is_sandboxed() || cfg!(not(feature = "linux-dbus")) {
+            return Ok(Arc::new(
+                platform_impl::xdg::portal::NotificationManagerPortal::with_options(options),
+            ) as Arc<dyn NotificationManager>);
+        }
+        #[cfg(feature = "linux-dbus")]
+        return Ok(
+            Arc::new(platform_impl::xdg::NotificationManagerXdg::with_options(
+                options,
+            )) as Arc<dyn NotificationManager>,
+        );
+    }
+    #[cfg(all(target_arch = "wasm32", feature = "web"))]
+    {
+        if !platform_impl::web::is_supported() {
+            return Err(Error::PlatformUnavailable(
+                "the browser has no Notification api".to_owned(),
+            ));
+        }
+        return Ok(Arc::new(platform_impl::web::NotificationManagerWeb::new())
+            as Arc<dyn NotificationManager>);
     }
+    Err(Error::PlatformUnavailable(format!(
+        "no notification backend for {} was enabled",
+        std::env::consts::OS
+    )))
 }
@@ -0,0 +1,281 @@
+//! Notification texts in the language the user has when the notification is shown
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), user_notify::Error> {
+//! use std::collections::HashMap;
+//! use user_notify::{NotificationBuilder, NotificationManager, localization::LocalizedNotificationManager};
+//!
+//! let manager = LocalizedNotificationManager::new(
+//!     user_notify::get_notification_manager("com.example.app".to_owned(), None),
+//!     |key: &str, args: &HashMap<String, String>| match key {
+//!         // look up the current language of the app here
+//!         "reminder-title" => Some(format!("Meeting with {}", args.get("name")?)),
+//!         _ => None,
+//!     },
+//! );
+//! manager
+//!     .schedule_notification(
+//!         NotificationBuilder::new().title_localized(
+//!             "reminder-title",
+//!             HashMap::from([("name".to_owned(), "Alice".to_owned())]),
+//!         ),
+//!         chrono::Utc::now() + chrono::Duration::hours(1),
+//!     )
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [NotificationBuilder::title_localized] and [NotificationBuilder::body_localized] only store the key and the arguments,
+//! the [Localizer] turns them into text right before the notification is shown.
+//! Scheduled notifications with localized texts wait in this process, like with [crate::persistent_schedule],
+//! so they are lost when the app quits. Location triggers are resolved when they are scheduled.
+//!
+//! When the localizer has no text for a key, the text of [NotificationBuilder::title] or [NotificationBuilder::body]
+//! is used, or else the key itself.
+
+use std::{collections::HashMap, fmt::Debug, sync::Arc};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    CalendarTrigger, Error, LocationTrigger, NotificationBuilder, NotificationHandle,
+    NotificationManager, RepeatInterval,
+    scheduler::{Schedule, Scheduler},
+};
+
+/// A text of [NotificationBuilder::title_localized] or [NotificationBuilder::body_localized]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LocalizedText {
+    pub key: String,
+    pub args: HashMap<String, String>,
+}
+
+/// Looks up the text of a key in the current language of the app, like from a Fluent bundle,
+/// closures with the same signature implement it
+pub trait Localizer: Send + Sync {
+    /// `None` if there is no text for the key
+    fn localize(&self, key: &str, args: &HashMap<String, String>) -> Option<String>;
+}
+
+impl<F> Localizer for F
+where
+    F: Fn(&str, &HashMap<String, String>) -> Option<String> + Send + Sync,
+{
+    fn localize(&self, key: &str, args: &HashMap<String, String>) -> Option<String> {
+        self(key, args)
+    }
+}
+
+/// Wraps a [NotificationManager] and localizes the notifications right before they are shown,
+/// see the [module documentation](self)
+#[derive(Clone)]
+pub struct LocalizedNotificationManager {
+    inner: Arc<dyn NotificationManager>,
+    localizer: Arc<dyn Localizer>,
+    scheduler: Scheduler,
+}
+
+impl Debug for LocalizedNotificationManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LocalizedNotificationManager")
+            .field("inner", &self.inner)
+            .field("scheduler", &self.scheduler)
+            .finish_non_exhaustive()
+    }
+}
+
+impl LocalizedNotificationManager {
+    pub fn new(inner: Arc<dyn NotificationManager>, localizer: impl Localizer + 'static) -> Self {
+        Self {
+            inner,
+            localizer: Arc::new(localizer),
+            scheduler: Scheduler::default(),
+        }
+    }
+
+    /// The wrapped manager
+    pub fn as_inner(&self) -> &Arc<dyn NotificationManager> {
+        &self.inner
+    }
+
+    /// Replaces the localized texts with the texts of the localizer
+    fn localize(&self, mut builder: NotificationBuilder) -> NotificationBuilder {
+        let localize = |text: Option<LocalizedText>, fallback: Option<String>| {
+            let Some(text) = text else {
+                return fallback;
+            };
+            self.localizer
+                .localize(&text.key, &text.args)
+                .or_else(|| {
+                    log::warn!("no localization for {:?}", text.key);
+                    fallback
+                })
+                .or(Some(text.key))
+        };
+        builder.title = localize(builder.title_localized.take(), builder.title.take());
+        builder.body = localize(builder.body_localized.take(), builder.body.take());
+        builder
+    }
+
+    /// Schedules it here, so it is localized at the time it is shown
+    async fn schedule(
+        &self,
+        builder: NotificationBuilder,
+        schedule: Schedule,
+    ) -> Result<String, Error> {
+        builder.validate(&self.inner.capabilities().await?)?;
+        let manager = self.clone();
+        self.scheduler.schedule_notification(
+            uuid::Uuid::new_v4().to_string(),
+            builder,
+            schedule,
+            move |id, builder| {
+                let manager = manager.clone();
+                async move {
+                    // shown with the id that was returned when it was scheduled
+                    manager
+                        .inner
+                        .update_notification(&id, manager.localize(builder))
+                        .await
+                        .map(drop)
+                }
+            },
+        )
+    }
+}
+
+fn is_localized(builder: &NotificationBuilder) -> bool {
+    builder.title_localized.is_some() || builder.body_localized.is_some()
+}
+
+#[async_trait]
+impl crate::delegate::DelegatingManager for LocalizedNotificationManager {
+    fn inner(&self) -> &Arc<dyn NotificationManager> {
+        &self.inner
+    }
+
+    async fn send_notification(
+        &self,
+        builder: NotificationBuilder,
+    ) -> Result<Box<dyn NotificationHandle>, Error> {
+        self.inner.send_notification(self.localize(builder)).await
+    }
+
+    async fn update_notification(
+        &self,
+        id: &str,
+        builder: NotificationBuilder,
+    ) -> Result<Box<dyn NotificationHandle>, Error> {
+        self.inner
+            .update_notification(id, self.localize(builder))
+            .await
+    }
+
+    async fn schedule_notification(
+        &self,
+        builder: NotificationBuilder,
+        at: DateTime<Utc>,
+    ) -> Result<String, Error> {
+        if !is_localized(&builder) {
+            return self.inner.schedule_notification(builder, at).await;
+        }
+        self.schedule(builder, Schedule::At(at)).await
+    }
+
+    async fn schedule_repeating_notification(
+        &self,
+        builder: NotificationBuilder,
+        interval: RepeatInterval,
+    ) -> Result<String, Error> {
+        if !is_localized(&builder) {
+            return self
+                .inner
+                .schedule_repeating_notification(builder, interval)
+                .await;
+        }
+        self.schedule(builder, Schedule::Every(interval)).await
+    }
+
+    async fn schedule_calendar_notification(
+        &self,
+        builder: NotificationBuilder,
+        trigger: CalendarTrigger,
+    ) -> Result<String, Error> {
+        if !is_localized(&builder) {
+            return self
+                .inner
+                .schedule_calendar_notification(builder, trigger)
+                .await;
+        }
+        self.schedule(builder, Schedule::Calendar(trigger)).await
+    }
+
+    async fn schedule_location_notification(
+        &self,
+        builder: NotificationBuilder,
+        trigger: LocationTrigger,
+    ) -> Result<String, Error> {
+        self.inner
+            .schedule_location_notification(self.localize(builder), trigger)
+            .await
+    }
+
+    fn cancel_scheduled_notification(&self, id: &str) -> Result<(), Error> {
+        if self.scheduler.cancel(id) {
+            return Ok(());
+        }
+        self.inner.cancel_scheduled_notification(id)
+    }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use std::{sync::Mutex, time::Duration};
+
+    use super::*;
+    use crate::mock::NotificationManagerMock;
+
+    #[tokio::test]
+    async fn localize_when_shown() {
+        let language = Arc::new(Mutex::new("en"));
+        let localizer = {
+            let language = language.clone();
+            move |key: &str, args: &HashMap<String, String>| {
+                let name = args.get("name")?;
+                match (*language.lock().unwrap(), key) {
+                    ("en", "greeting") => Some(format!("Hello {name}")),
+                    ("de", "greeting") => Some(format!("Hallo {name}")),
+                    _ => None,
+                }
+            }
+        };
+        let mock = NotificationManagerMock::new();
+        let manager = LocalizedNotificationManager::new(Arc::new(mock.clone()), localizer);
+        let builder = NotificationBuilder::new()
+            .title_localized(
+                "greeting",
+                HashMap::from([("name".to_owned(), "Alice".to_owned())]),
+            )
+            .body_localized("missing", HashMap::new());
+
+        let handle = manager.send_notification(builder.clone()).await.unwrap();
+        assert_eq!(handle.get_content().title.as_deref(), Some("Hello Alice"));
+        assert_eq!(handle.get_content().body.as_deref(), Some("missing"));
+
+        let id = manager
+            .schedule_notification(builder, Utc::now() + chrono::Duration::milliseconds(100))
+            .await
+            .unwrap();
+        *language.lock().unwrap() = "de";
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        let active = mock.get_active_notifications().await.unwrap();
+        let scheduled = active.iter().find(|n| n.get_id() == id).unwrap();
+        assert_eq!(
+            scheduled.get_content().title.as_deref(),
+            Some("Hallo Alice")
+        );
+    }
+}
@@ -0,0 +1,191 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::NotificationManager;
+
+/// How the app is presented, for [crate::get_notification_manager_with_options]
+///
+/// ```no_run
+/// use user_notify::NotificationManagerOptions;
+///
+/// let manager = user_notify::get_notification_manager_with_options(
+///     NotificationManagerOptions::new("com.example.chat")
+///         .set_app_name("Example Chat")
+///         .set_desktop_entry("com.example.chat")
+///         .set_icon("/usr/share/icons/hicolor/256x256/apps/com.example.chat.png"),
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NotificationManagerOptions {
+    pub(crate) app_id: String,
+    pub(crate) notification_protocol: Option<String>,
+    pub(crate) app_name: Option<String>,
+    pub(crate) desktop_entry: Option<String>,
+    pub(crate) icon: Option<PathBuf>,
+    pub(crate) backend: Option<Backend>,
+}
+
+/// A manager that is not part of the crate, see [NotificationManagerOptions::set_backend]
+#[derive(Clone)]
+pub(crate) struct Backend(pub(crate) Arc<dyn NotificationManager>);
+
+impl std::fmt::Debug for Backend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Backend")
+    }
+}
+
+impl PartialEq for Backend {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Backend {}
+
+impl NotificationManagerOptions {
+    /// The app id is used on windows as AUMID of the toasts,
+    /// and on linux as default of [NotificationManagerOptions::set_desktop_entry]
+    pub fn new(app_id: &str) -> Self {
+        Self {
+            app_id: app_id.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    /// Only used on windows, the protocol that activates the app when a toast is clicked
+    pub fn set_notification_protocol(mut self, notification_protocol: &str) -> Self {
+        self.notification_protocol = Some(notification_protocol.to_owned());
+        self
+    }
+
+    /// The human readable name of the app, like "Example Chat"
+    ///
+    /// Plaform specific:
+    /// - MacOS: ignored, the name comes from the app bundle
+    /// - Linux / XDG: the [app_name](https://specifications.freedesktop.org/notification-spec/latest/protocol.html#command-notify) parameter,
+    ///   instead of the name of the executable, [NotificationBuilder::set_xdg_app_name](crate::NotificationBuilder::set_xdg_app_name) overrides it.
+    ///   The notification portal takes the name from the desktop entry of the sandbox.
+    /// - Windows: registered as display name of the app id like with `windows::register_app_id`,
+    ///   which is shown for unpackaged apps without start menu shortcut
+    /// - Web: ignored, the browser shows the site
+    pub fn set_app_name(mut self, app_name: &str) -> Self {
+        self.app_name = Some(app_name.to_owned());
+        self
+    }
+
+    /// The name of the desktop entry of the app, without the `.desktop` suffix.
+    /// Defaults to the app id, when it is a valid desktop file id like `com.example.chat`.
+    ///
+    /// Plaform specific:
+    /// - Linux / XDG: ["desktop-entry"](https://specifications.freedesktop.org/notification-spec/latest/hints.html) hint,
+    ///   notification daemons use it for the settings of the app, its icon and for grouping.
+    ///   The notification portal knows the desktop entry of the sandbox.
+    /// - Other: ignored
+    pub fn set_desktop_entry(mut self, desktop_entry: &str) -> Self {
+        self.desktop_entry = Some(desktop_entry.to_owned());
+        self
+    }
+
+    /// The icon of notifications without [NotificationBuilder::app_icon](crate::NotificationBuilder::app_icon)
+    ///
+    /// Plaform specific:
+    /// - MacOS: ignored, the icon comes from the app bundle
+    /// - Linux / XDG: the app_icon parameter, instead of the icon that is guessed from the executable name,
+    ///   the notification portal gets the content of the file as icon
+    /// - Windows: registered as icon of the app id together with [NotificationManagerOptions::set_app_name]
+    /// - Web: ignored
+    pub fn set_icon(mut self, path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        self.icon = Some(std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf()));
+        self
+    }
+
+    /// Uses `backend` instead of the manager for the platform,
+    /// for platforms that are not supported out of the box, like a kiosk system with its own notification daemon.
+    ///
+    /// [crate::get_notification_manager_with_options] returns it also in headless environments,
+    /// and later calls with the same app id get it as well, also [crate::get_notification_manager].
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use user_notify::{NotificationManager, NotificationManagerOptions, noop::NoopNotificationManager};
+    ///
+    /// let backend: Arc<dyn NotificationManager> = Arc::new(NoopNotificationManager::new());
+    /// let manager = user_notify::get_notification_manager_with_options(
+    ///     NotificationManagerOptions::new("com.example.kiosk").set_backend(backend.clone()),
+    /// );
+    /// assert!(Arc::ptr_eq(&manager, &backend));
+    /// ```
+    pub fn set_backend(mut self, backend: Arc<dyn NotificationManager>) -> Self {
+        self.backend = Some(Backend(backend));
+        self
+    }
+
+    pub fn get_app_id(&self) -> &str {
+        &self.app_id
+    }
+
+    pub fn get_notification_protocol(&self) -> Option<&str> {
+        self.notification_protocol.as_deref()
+    }
+
+    pub fn get_app_name(&self) -> Option<&str> {
+        self.app_name.as_deref()
+    }
+
+    /// The desktop entry from [NotificationManagerOptions::set_desktop_entry] or the one derived from the app id
+    pub fn get_desktop_entry(&self) -> Option<&str> {
+        self.desktop_entry
+            .as_deref()
+            .or_else(|| is_desktop_file_id(&self.app_id).then_some(self.app_id.as_str()))
+    }
+
+    pub fn get_icon(&self) -> Option<&Path> {
+        self.icon.as_deref()
+    }
+
+    pub fn get_backend(&self) -> Option<&Arc<dyn NotificationManager>> {
+        self.backend.as_ref().map(|backend| &backend.0)
+    }
+}
+
+/// Reverse DNS names like `com.example.chat`, see the
+/// [desktop file id](https://specifications.freedesktop.org/desktop-entry-spec/latest/file-naming.html)
+fn is_desktop_file_id(id: &str) -> bool {
+    let elements: Vec<&str> = id.split('.').collect();
+    elements.len() >= 2
+        && elements.iter().all(|element| {
+            element
+                .chars()
+                .next()
+                .is_some_and(|first| !first.is_ascii_digit())
+                && element
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn desktop_entry_from_app_id() {
+        let options = NotificationManagerOptions::new("ai.gety.desktop");
+        assert_eq!(options.get_desktop_entry(), Some("ai.gety.desktop"));
+        assert_eq!(
+            options.set_desktop_entry("gety").get_desktop_entry(),
+            Some("gety")
+        );
+        for app_id in ["", "gety", "ai..gety", "ai.1gety", "ai.gety app"] {
+            assert_eq!(
+                NotificationManagerOptions::new(app_id).get_desktop_entry(),
+                None,
+                "{app_id}"
+            );
+        }
+    }
+}
@@ -0,0 +1,260 @@
+//! The markdown subset of [NotificationBuilder::set_body_markdown]: `**bold**`, `*italic*`,
+//! `[links](https://example.com)` and line breaks, `\` escapes the next character.
+//! Everything else is text, so a markdown body never fails to send.
+//!
+//! [NotificationBuilder::set_body_markdown]: crate::NotificationBuilder::set_body_markdown
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Style {
+    Bold,
+    Italic,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Inline {
+    Text(String),
+    Open(Style),
+    Close(Style),
+    Link { text: String, url: String },
+    LineBreak,
+}
+
+/// `*`, `**`, `_` or `__` before it is known whether it opens or closes a style or is text
+#[derive(Debug)]
+struct Marker {
+    style: Style,
+    raw: &'static str,
+    /// not followed by whitespace
+    can_open: bool,
+    /// not preceded by whitespace
+    can_close: bool,
+}
+
+enum Token {
+    Inline(Inline),
+    Marker(Marker),
+}
+
+/// Parses the markdown, the styles are always closed in the reverse order they were opened
+pub(crate) fn parse(markdown: &str) -> Vec<Inline> {
+    let mut tokens = tokenize(markdown);
+
+    // pairs the markers like brackets, the markers without a partner are text
+    let mut open: Vec<usize> = Vec::new();
+    for index in 0..tokens.len() {
+        let Token::Marker(marker) = &tokens[index] else {
+            continue;
+        };
+        let (style, can_open, can_close) = (marker.style, marker.can_open, marker.can_close);
+        if can_close
+            && let Some(&top) = open.last()
+            && matches!(&tokens[top], Token::Marker(opener) if opener.style == style)
+        {
+            open.pop();
+            tokens[top] = Token::Inline(Inline::Open(style));
+            tokens[index] = Token::Inline(Inline::Close(style));
+        } else if can_open {
+            open.push(index);
+        }
+    }
+
+    let mut inlines: Vec<Inline> = Vec::new();
+    for token in tokens {
+        let inline = match token {
+            Token::Inline(inline) => inline,
+            Token::Marker(marker) => Inline::Text(marker.raw.to_owned()),
+        };
+        // merges the text of unpaired markers into the text around them
+        match (inlines.last_mut(), inline) {
+            (Some(Inline::Text(previous)), Inline::Text(text)) => previous.push_str(&text),
+            (_, inline) => inlines.push(inline),
+        }
+    }
+    inlines
+}
+
+fn tokenize(markdown: &str) -> Vec<Token> {
+    let chars: Vec<char> = markdown.chars().collect();
+    let mut tokens = Vec::new();
+    let mut text = String::new();
+    let flush = |text: &mut String, tokens: &mut Vec<Token>| {
+        if !text.is_empty() {
+            tokens.push(Token::Inline(Inline::Text(std::mem::take(text))));
+        }
+    };
+
+    let mut index = 0;
+    while index < chars.len() {
+        let char = chars[index];
+        match char {
+            '\\' if chars
+                .get(index + 1)
+                .is_some_and(|next| next.is_ascii_punctuation()) =>
+            {
+                text.push(chars[index + 1]);
+                index += 2;
+                continue;
+            }
+            '\r' => {}
+            '\n' => {
+                // the spaces of a markdown hard line break
+                text.truncate(text.trim_end_matches(' ').len());
+                flush(&mut text, &mut tokens);
+                tokens.push(Token::Inline(Inline::LineBreak));
+            }
+            '*' | '_' => {
+                let double = chars.get(index + 1) == Some(&char);
+                let length = if double { 2 } else { 1 };
+                let before = index.checked_sub(1).map(|before| chars[before]);
+                let after = chars.get(index + length).copied();
+                // underscores inside of words, like in snake_case, are text
+                if char == '_'
+                    && before.is_some_and(char::is_alphanumeric)
+                    && after.is_some_and(char::is_alphanumeric)
+                {
+                    text.extend(&chars[index..index + length]);
+                    index += length;
+                    continue;
+                }
+                flush(&mut text, &mut tokens);
+                tokens.push(Token::Marker(Marker {
+                    style: if double { Style::Bold } else { Style::Italic },
+                    raw: match (char, double) {
+                        ('*', true) => "**",
+                        ('*', false) => "*",
+                        (_, true) => "__",
+                        (_, false) => "_",
+                    },
+                    can_open: after.is_some_and(|after| !after.is_whitespace()),
+                    can_close: before.is_some_and(|before| !before.is_whitespace()),
+                }));
+                index += length;
+                continue;
+            }
+            '[' => {
+                if let Some((link, end)) = parse_link(&chars, index) {
+                    flush(&mut text, &mut tokens);
+                    tokens.push(Token::Inline(link));
+                    index = end;
+                    continue;
+                }
+                text.push(char);
+            }
+            _ => text.push(char),
+        }
+        index += 1;
+    }
+    flush(&mut text, &mut tokens);
+    tokens
+}
+
+/// `[text](url)` starting at `start`, returns the link and the index after it
+fn parse_link(chars: &[char], start: usize) -> Option<(Inline, usize)> {
+    let text_end = start
+        + 1
+        + chars[start + 1..]
+            .iter()
+            .position(|&char| matches!(char, ']' | '[' | '\n'))?;
+    if chars[text_end] != ']' || chars.get(text_end + 1) != Some(&'(') {
+        return None;
+    }
+    let url_start = text_end + 2;
+    let url_end = url_start
+        + chars[url_start..]
+            .iter()
+            .position(|&char| char == ')' || char.is_whitespace())?;
+    if chars[url_end] != ')' || url_end == url_start {
+        return None;
+    }
+    Some((
+        Inline::Link {
+            text: chars[start + 1..text_end].iter().collect(),
+            url: chars[url_start..url_end].iter().collect(),
+        },
+        url_end + 1,
+    ))
+}
+
+/// For platforms that show the body as it is, keeps the text of links and drops their url
+pub(crate) fn to_plain_text(markdown: &str) -> String {
+    parse(markdown)
+        .into_iter()
+        .map(|inline| match inline {
+            Inline::Text(text) | Inline::Link { text, .. } => text,
+            Inline::Open(_) | Inline::Close(_) => String::new(),
+            Inline::LineBreak => "\n".to_owned(),
+        })
+        .collect()
+}
+
+// used by the platform backends without the windows one
+#[cfg_attr(
+    not(any(
+        all(target_os = "macos", feature = "macos"),
+        all(target_arch = "wasm32", feature = "web"),
+        all(
+            any(
+                target_os = "linux",
+                target_os = "dragonfly",
+                target_os = "freebsd",
+                target_os = "openbsd",
+                target_os = "netbsd"
+            ),
+            any(feature = "linux-dbus", feature = "linux-portal")
+        )
+    )),
+    allow(dead_code)
+)]
+/// Escapes the characters that have a meaning in the markdown subset, for text that is appended to a markdown body
+pub(crate) fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for char in text.chars() {
+        if matches!(char, '\\' | '*' | '_' | '[' | ']') {
+            escaped.push('\\');
+        }
+        escaped.push(char);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_text() {
+        assert_eq!(
+            to_plain_text("**Alice** sent *3 files*  \nsee [the chat](https://example.com/chat)"),
+            "Alice sent 3 files\nsee the chat"
+        );
+        assert_eq!(
+            to_plain_text("5 * 3 = 15, snake_case"),
+            "5 * 3 = 15, snake_case"
+        );
+        assert_eq!(
+            to_plain_text("**unclosed *crossed** end*"),
+            "**unclosed crossed** end"
+        );
+        assert_eq!(
+            to_plain_text(r"\*literal\* [not a link]"),
+            "*literal* [not a link]"
+        );
+        assert_eq!(to_plain_text(&escape("50% [*] done_")), "50% [*] done_");
+    }
+
+    #[test]
+    fn nested() {
+        assert_eq!(
+            parse("__bold _italic_ bold__"),
+            [
+                Inline::Open(Style::Bold),
+                Inline::Text("bold ".to_owned()),
+                Inline::Open(Style::Italic),
+                Inline::Text("italic".to_owned()),
+                Inline::Close(Style::Italic),
+                Inline::Text(" bold".to_owned()),
+                Inline::Close(Style::Bold),
+            ]
+        );
+    }
+}
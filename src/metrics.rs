@@ -0,0 +1,388 @@
+//! Hooks to count the notifications for metrics, like a funnel from sent to clicked
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), user_notify::Error> {
+//! use std::sync::Arc;
+//! use user_notify::{
+//!     NotificationBuilder, NotificationManager,
+//!     metrics::{MetricsNotificationManager, NotificationCounters},
+//! };
+//!
+//! let counters = Arc::new(NotificationCounters::default());
+//! let manager = MetricsNotificationManager::new(user_notify::get_notification_manager(
+//!     "com.example.app".to_owned(),
+//!     None,
+//! ))
+//! .add_observer(counters.clone());
+//! manager.register(Box::new(|response| println!("{response:?}")), vec![])?;
+//! manager
+//!     .send_notification(NotificationBuilder::new().title("Build finished"))
+//!     .await?;
+//!
+//! let counts = counters.snapshot();
+//! println!("sent {} clicked {}", counts.sent, counts.clicked);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Implement [MetricsObserver] to export the counts, for example to Prometheus.
+//! Sent, scheduled and failed notifications are reported when the call returns,
+//! the other events come from [NotificationManager::subscribe_events] of the wrapped manager,
+//! so clicks and dismissals by the user are only reported after a handler was registered.
+
+use std::sync::{
+    Arc, Mutex,
+    atomic::{AtomicU64, Ordering},
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use tokio::sync::{
+    broadcast::{self, error::RecvError},
+    oneshot,
+};
+
+use crate::{
+    CalendarTrigger, Error, LocationTrigger, NotificationBuilder, NotificationDismissReason,
+    NotificationEvent, NotificationEventKind, NotificationHandle, NotificationManager,
+    RepeatInterval,
+};
+
+/// Called by [MetricsNotificationManager], all methods do nothing by default
+///
+/// The methods are called on the thread of the platform, so they should only update counters.
+pub trait MetricsObserver: Send + Sync + std::fmt::Debug {
+    /// A notification was sent or updated
+    fn on_sent(&self, _notification_id: &str, _builder: &NotificationBuilder) {}
+    /// A notification was scheduled, it is reported with [Self::on_delivered] when it is shown
+    fn on_scheduled(&self, _notification_id: &str, _builder: &NotificationBuilder) {}
+    /// The platform showed the notification
+    fn on_delivered(&self, _notification_id: &str) {}
+    /// The user clicked on the notification itself
+    fn on_clicked(&self, _notification_id: &str) {}
+    /// The user clicked a button of the notification
+    fn on_action(&self, _notification_id: &str, _action_id: &str) {}
+    /// The notification was closed
+    fn on_dismissed(&self, _notification_id: &str, _reason: NotificationDismissReason) {}
+    /// Sending, updating or scheduling a notification failed,
+    /// the id is only known for updates
+    fn on_error(&self, _notification_id: Option<&str>, _error: &Error) {}
+}
+
+/// Counts returned by [NotificationCounters::snapshot]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct NotificationCounts {
+    pub sent: u64,
+    pub scheduled: u64,
+    pub delivered: u64,
+    pub clicked: u64,
+    pub actions: u64,
+    pub dismissed: u64,
+    pub errors: u64,
+}
+
+/// A [MetricsObserver] that counts every kind of event
+#[derive(Debug, Default)]
+pub struct NotificationCounters {
+    sent: AtomicU64,
+    scheduled: AtomicU64,
+    delivered: AtomicU64,
+    clicked: AtomicU64,
+    actions: AtomicU64,
+    dismissed: AtomicU64,
+    errors: AtomicU64,
+}
+
+impl NotificationCounters {
+    /// The counts since the counters were created
+    pub fn snapshot(&self) -> NotificationCounts {
+        NotificationCounts {
+            sent: self.sent.load(Ordering::Relaxed),
+            scheduled: self.scheduled.load(Ordering::Relaxed),
+            delivered: self.delivered.load(Ordering::Relaxed),
+            clicked: self.clicked.load(Ordering::Relaxed),
+            actions: self.actions.load(Ordering::Relaxed),
+            dismissed: self.dismissed.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl MetricsObserver for NotificationCounters {
+    fn on_sent(&self, _notification_id: &str, _builder: &NotificationBuilder) {
+        self.sent.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn on_scheduled(&self, _notification_id: &str, _builder: &NotificationBuilder) {
+        self.scheduled.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn on_delivered(&self, _notification_id: &str) {
+        self.delivered.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn on_clicked(&self, _notification_id: &str) {
+        self.clicked.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn on_action(&self, _notification_id: &str, _action_id: &str) {
+        self.actions.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn on_dismissed(&self, _notification_id: &str, _reason: NotificationDismissReason) {
+        self.dismissed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn on_error(&self, _notification_id: Option<&str>, _error: &Error) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Observers(Arc<Mutex<Vec<Arc<dyn MetricsObserver>>>>);
+
+impl Observers {
+    fn each(&self, f: impl Fn(&dyn MetricsObserver)) {
+        // cloned, so an observer can not deadlock by adding another one
+        let observers = match self.0.lock() {
+            Ok(observers) => observers.clone(),
+            Err(_) => {
+                log::error!("metrics observers lock is poisoned");
+                return;
+            }
+        };
+        for observer in &observers {
+            f(observer.as_ref());
+        }
+    }
+
+    fn on_event(&self, event: &NotificationEvent) {
+        let id = event.notification_id.as_str();
+        match &event.kind {
+            NotificationEventKind::Presented => self.each(|o| o.on_delivered(id)),
+            NotificationEventKind::Clicked => self.each(|o| o.on_clicked(id)),
+            NotificationEventKind::Action { identifier, .. } => {
+                self.each(|o| o.on_action(id, identifier))
+            }
+            NotificationEventKind::Dismissed(reason) => self.each(|o| o.on_dismissed(id, *reason)),
+        }
+    }
+
+    fn on_result<T>(
+        &self,
+        id: Option<&str>,
+        builder: &NotificationBuilder,
+        scheduled: bool,
+        result: &Result<T, Error>,
+    ) {
+        match (result, id) {
+            (Err(err), _) => self.each(|o| o.on_error(id, err)),
+            (Ok(_), Some(id)) if scheduled => self.each(|o| o.on_scheduled(id, builder)),
+            (Ok(_), Some(id)) => self.each(|o| o.on_sent(id, builder)),
+            (Ok(_), None) => {}
+        }
+    }
+}
+
+/// Wraps a [NotificationManager] and reports its notifications to the [MetricsObserver]s,
+/// see the [module documentation](self)
+#[derive(Debug, Clone)]
+pub struct MetricsNotificationManager {
+    inner: Arc<dyn NotificationManager>,
+    observers: Observers,
+    /// dropping the last clone stops the task that receives the events
+    _stop_events: Arc<oneshot::Sender<()>>,
+}
+
+impl MetricsNotificationManager {
+    /// Starts to receive the events of `inner`, until it is dropped.
+    pub fn new(inner: Arc<dyn NotificationManager>) -> Self {
+        let observers = Observers::default();
+        let stop_events = receive_events(inner.subscribe_events(), observers.clone());
+        Self {
+            inner,
+            observers,
+            _stop_events: Arc::new(stop_events),
+        }
+    }
+
+    /// Reports the events from now on to this observer too
+    pub fn add_observer(self, observer: Arc<dyn MetricsObserver>) -> Self {
+        if let Ok(mut observers) = self.observers.0.lock() {
+            observers.push(observer);
+        }
+        self
+    }
+
+    /// The wrapped manager
+    pub fn as_inner(&self) -> &Arc<dyn NotificationManager> {
+        &self.inner
+    }
+
+    fn scheduled(
+        &self,
+        builder: &NotificationBuilder,
+        result: Result<String, Error>,
+    ) -> Result<String, Error> {
+        self.observers
+            .on_result(result.as_deref().ok(), builder, true, &result);
+        result
+    }
+}
+
+/// Reports the events to the observers until the returned sender is dropped.
+/// The loop waits for the whole lifetime of the manager, so it gets its own thread
+/// instead of a timer of the scheduler, on the web it is a task of the browser's event loop.
+fn receive_events(
+    mut events: broadcast::Receiver<NotificationEvent>,
+    observers: Observers,
+) -> oneshot::Sender<()> {
+    let (stop, mut stopped) = oneshot::channel::<()>();
+    let receive = async move {
+        loop {
+            let event = futures_lite::future::or(async { Some(events.recv().await) }, async {
+                let _ = (&mut stopped).await;
+                None
+            })
+            .await;
+            match event {
+                Some(Ok(event)) => observers.on_event(&event),
+                Some(Err(RecvError::Lagged(skipped))) => {
+                    log::warn!("metrics missed {skipped} notification events");
+                }
+                Some(Err(RecvError::Closed)) | None => break,
+            }
+        }
+    };
+    #[cfg(all(target_arch = "wasm32", feature = "web"))]
+    wasm_bindgen_futures::spawn_local(receive);
+    #[cfg(not(all(target_arch = "wasm32", feature = "web")))]
+    std::thread::spawn(move || futures_lite::future::block_on(receive));
+    stop
+}
+
+#[async_trait]
+impl crate::delegate::DelegatingManager for MetricsNotificationManager {
+    fn inner(&self) -> &Arc<dyn NotificationManager> {
+        &self.inner
+    }
+
+    async fn send_notification(
+        &self,
+        builder: NotificationBuilder,
+    ) -> Result<Box<dyn NotificationHandle>, Error> {
+        let result = self.inner.send_notification(builder.clone()).await;
+        let id = result.as_ref().ok().map(|handle| handle.get_id());
+        self.observers
+            .on_result(id.as_deref(), &builder, false, &result);
+        result
+    }
+
+    async fn update_notification(
+        &self,
+        id: &str,
+        builder: NotificationBuilder,
+    ) -> Result<Box<dyn NotificationHandle>, Error> {
+        let result = self.inner.update_notification(id, builder.clone()).await;
+        self.observers.on_result(Some(id), &builder, false, &result);
+        result
+    }
+
+    async fn schedule_notification(
+        &self,
+        builder: NotificationBuilder,
+        at: DateTime<Utc>,
+    ) -> Result<String, Error> {
+        let result = self.inner.schedule_notification(builder.clone(), at).await;
+        self.scheduled(&builder, result)
+    }
+
+    async fn schedule_repeating_notification(
+        &self,
+        builder: NotificationBuilder,
+        interval: RepeatInterval,
+    ) -> Result<String, Error> {
+        let result = self
+            .inner
+            .schedule_repeating_notification(builder.clone(), interval)
+            .await;
+        self.scheduled(&builder, result)
+    }
+
+    async fn schedule_calendar_notification(
+        &self,
+        builder: NotificationBuilder,
+        trigger: CalendarTrigger,
+    ) -> Result<String, Error> {
+        let result = self
+            .inner
+            .schedule_calendar_notification(builder.clone(), trigger)
+            .await;
+        self.scheduled(&builder, result)
+    }
+
+    async fn schedule_location_notification(
+        &self,
+        builder: NotificationBuilder,
+        trigger: LocationTrigger,
+    ) -> Result<String, Error> {
+        let result = self
+            .inner
+            .schedule_location_notification(builder.clone(), trigger)
+            .await;
+        self.scheduled(&builder, result)
+    }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{NotificationResponseAction, mock::NotificationManagerMock};
+
+    #[tokio::test]
+    async fn counters() {
+        let mock = NotificationManagerMock::new();
+        let counters = Arc::new(NotificationCounters::default());
+        let manager =
+            MetricsNotificationManager::new(Arc::new(mock.clone())).add_observer(counters.clone());
+        manager.register(Box::new(|_| {}), vec![]).unwrap();
+
+        let id = manager
+            .send_notification(NotificationBuilder::new().title("first"))
+            .await
+            .unwrap()
+            .get_id();
+        manager
+            .schedule_notification(NotificationBuilder::new(), Utc::now())
+            .await
+            .unwrap();
+        mock.simulate_response(&id, NotificationResponseAction::Default, None)
+            .await
+            .unwrap();
+        mock.simulate_response(&id, NotificationResponseAction::Dismiss, None)
+            .await
+            .unwrap();
+        // february never has 30 days
+        assert!(
+            manager
+                .schedule_calendar_notification(
+                    NotificationBuilder::new(),
+                    CalendarTrigger::new(9, 0).set_day(30).set_month(2),
+                )
+                .await
+                .is_err()
+        );
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let counts = counters.snapshot();
+        assert_eq!(counts.sent, 1);
+        assert_eq!(counts.scheduled, 1);
+        assert_eq!(counts.delivered, 2);
+        assert_eq!(counts.clicked, 1);
+        assert_eq!(counts.dismissed, 1);
+        assert_eq!(counts.errors, 1);
+    }
+}
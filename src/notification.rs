@@ -1,23 +1,72 @@
-use std::{collections::HashMap, fmt::Debug, path::PathBuf};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::Debug,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 
 use async_trait::async_trait;
+use chrono::{DateTime, Datelike, Local, NaiveTime, TimeZone, Utc, Weekday};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 
-use crate::{Error, xdg_category::XdgNotificationCategory};
+use crate::{
+    Error, NotificationEvent, localization::LocalizedText, xdg_category::XdgNotificationCategory,
+};
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 pub struct NotificationBuilder {
     pub(crate) body: Option<String>,
+    /// the body is markdown, see [NotificationBuilder::set_body_markdown]
+    #[serde(default)]
+    pub(crate) body_markdown: bool,
     pub(crate) title: Option<String>,
+    pub(crate) title_localized: Option<LocalizedText>,
+    pub(crate) body_localized: Option<LocalizedText>,
     pub(crate) subtitle: Option<String>,
     pub(crate) image: Option<std::path::PathBuf>,
+    pub(crate) image_url: Option<String>,
+    pub(crate) attachment: Option<NotificationAttachment>,
     pub(crate) icon: Option<std::path::PathBuf>,
     pub(crate) icon_round_crop: bool,
     pub(crate) thread_id: Option<String>,
     pub(crate) category_id: Option<String>,
     pub(crate) xdg_category: Option<XdgNotificationCategory>,
     pub(crate) xdg_app_name: Option<String>,
+    pub(crate) xdg_resident: Option<bool>,
+    pub(crate) xdg_transient: Option<bool>,
+    pub(crate) xdg_replace_thread: Option<bool>,
+    #[serde(default)]
+    pub(crate) windows_protocol_activation: bool,
+    /// by the identifier of the action, see [NotificationBuilder::set_windows_action_uri]
+    #[serde(default)]
+    pub(crate) windows_action_uris: BTreeMap<String, String>,
+    pub(crate) windows_collection_id: Option<String>,
     pub(crate) user_info: Option<HashMap<String, String>>,
-    pub(crate) sound: Option<String>,
+    pub(crate) sound: Option<Sound>,
+    pub(crate) interruption_level: Option<InterruptionLevel>,
+    pub(crate) relevance_score: Option<f64>,
+    pub(crate) filter_criteria: Option<String>,
+    pub(crate) summary_argument: Option<String>,
+    pub(crate) summary_argument_count: Option<u32>,
+    pub(crate) sender: Option<NotificationSender>,
+    pub(crate) actions: Vec<NotificationCategoryAction>,
+    pub(crate) progress: Option<NotificationProgress>,
+    pub(crate) scenario: Option<NotificationScenario>,
+    pub(crate) hero_image: Option<std::path::PathBuf>,
+    pub(crate) hero_image_url: Option<String>,
+    pub(crate) attribution: Option<String>,
+    pub(crate) expiration: Option<DateTime<Utc>>,
+    pub(crate) auto_dismiss_after: Option<Duration>,
+    pub(crate) suppress_popup: bool,
+    pub(crate) urgency: Option<NotificationUrgency>,
+    pub(crate) foreground_presentation: Option<ForegroundPresentation>,
+    pub(crate) priority: Option<NotificationPriority>,
+    #[serde(skip)]
+    pub(crate) response_callback: OnResponse,
+    /// the id to show the notification with instead of a new one, see [NotificationBuilder::with_preset_id]
+    #[serde(skip)]
+    pub(crate) preset_id: Option<String>,
 }
 
 impl NotificationBuilder
@@ -29,6 +78,32 @@ where
             ..Default::default()
         }
     }
+
+    /// Starts a notification from a pre-configured builder, so only the texts have to be set for each notification
+    ///
+    /// It is a copy of the template, except for the [NotificationBuilder::set_dedupe_key], which belongs to one notification.
+    /// The callback of [NotificationBuilder::on_response] is shared with the template, not copied,
+    /// each sent notification keeps it until it is dismissed.
+    ///
+    /// ```
+    /// use user_notify::{NotificationBuilder, SystemSound};
+    ///
+    /// let template = NotificationBuilder::new()
+    ///     .set_category_id("chat.message")
+    ///     .set_thread_id("chat-42")
+    ///     .set_sound(user_notify::Sound::System(SystemSound::Message));
+    /// for (sender, text) in [("Alice", "Hi"), ("Bob", "Hello")] {
+    ///     let builder = NotificationBuilder::build_from(&template).title(sender).body(text);
+    ///     assert_eq!(builder.get_thread_id(), Some("chat-42"));
+    /// }
+    /// ```
+    pub fn build_from(template: &NotificationBuilder) -> Self {
+        let mut builder = template.clone();
+        if let Some(user_info) = builder.user_info.as_mut() {
+            user_info.remove(DEDUPE_KEY);
+        }
+        builder
+    }
     /// main content of notification
     ///
     /// Plaform specific:
@@ -37,6 +112,20 @@ where
     /// - Windows: [text2](https://docs.rs/tauri-winrt-notification/latest/tauri_winrt_notification/struct.Toast.html#method.text2)
     pub fn body(mut self, body: &str) -> Self {
         self.body = Some(body.to_owned());
+        self.body_markdown = false;
+        self
+    }
+
+    /// Set the body as markdown, with `**bold**`, `*italic*`, `[links](https://example.com)` and line breaks.
+    /// Other markdown is shown as it is, `\` escapes a character.
+    ///
+    /// Plaform specific:
+    /// - Linux / XDG: [markup](https://specifications.freedesktop.org/notification-spec/latest/markup.html)
+    ///   if the notification server has the body-markup capability, links need the body-hyperlinks capability
+    /// - Others: plain text, links show only their text
+    pub fn set_body_markdown(mut self, markdown: &str) -> Self {
+        self.body = Some(markdown.to_owned());
+        self.body_markdown = true;
         self
     }
     /// primary description of notification
@@ -49,6 +138,27 @@ where
         self.title = Some(title.to_owned());
         self
     }
+    /// Title that is looked up by the [crate::localization::Localizer] right before the notification is shown,
+    /// see [crate::localization::LocalizedNotificationManager]. The text of [NotificationBuilder::title]
+    /// is the fallback when the localizer has no text for the key.
+    pub fn title_localized(mut self, key: &str, args: HashMap<String, String>) -> Self {
+        self.title_localized = Some(LocalizedText {
+            key: key.to_owned(),
+            args,
+        });
+        self
+    }
+
+    /// Body that is looked up by the [crate::localization::Localizer] right before the notification is shown,
+    /// like [NotificationBuilder::title_localized]
+    pub fn body_localized(mut self, key: &str, args: HashMap<String, String>) -> Self {
+        self.body_localized = Some(LocalizedText {
+            key: key.to_owned(),
+            args,
+        });
+        self
+    }
+
     /// Sets secondary description of Notification
     ///
     /// Plaform specific:
@@ -60,43 +170,150 @@ where
         self
     }
 
-    /// Set notification sound
+    /// Set notification sound by its platform specific name, "default" is [Sound::Default].
+    /// For the same sound on all platforms use [NotificationBuilder::set_sound] instead.
     ///
     /// Platform specific:
     /// - MacOS: [UNNotificationContent/sound](https://developer.apple.com/documentation/usernotifications/unnotificationcontent/sound)
     ///   - Use "default" for default system sound
     ///   - Use filename without extension for custom sounds (must be in app bundle)
-    /// - Linux / XDG: **not supported yet!**
-    /// - Windows: **not supported yet!**
-    pub fn sound(mut self, sound: &str) -> Self {
-        self.sound = Some(sound.to_owned());
+    /// - Linux / XDG: ["sound-name"](https://specifications.freedesktop.org/notification-spec/latest/hints.html) hint,
+    ///   a name from the [sound naming specification](https://specifications.freedesktop.org/sound-naming-spec/latest/) like "message-new-instant"
+    /// - Windows: [`<audio>`](https://learn.microsoft.com/uwp/schemas/tiles/toastschema/element-audio)
+    ///   - Use "default" for `ms-winsoundevent:Notification.Default`
+    ///   - Use a name like "Mail" for `ms-winsoundevent:Notification.Mail`, or pass a full `ms-winsoundevent:`/`ms-appx:` uri
+    pub fn sound(self, sound: &str) -> Self {
+        self.set_sound(match sound {
+            "default" => Sound::Default,
+            name => Sound::Named(name.to_owned()),
+        })
+    }
+
+    /// Deliver the notification without any sound, not even the default one, the same as [Sound::None].
+    /// The notification is still shown, unlike with [InterruptionLevel::Passive] it also pops up.
+    ///
+    /// Platform specific:
+    /// - MacOS: no [UNNotificationContent/sound](https://developer.apple.com/documentation/usernotifications/unnotificationcontent/sound)
+    /// - Linux / XDG: ["suppress-sound"](https://specifications.freedesktop.org/notification-spec/latest/hints.html) hint,
+    ///   the "silent" sound of the notification portal
+    /// - Windows: [`<audio silent="true" />`](https://learn.microsoft.com/uwp/schemas/tiles/toastschema/element-audio)
+    /// - Web: [silent](https://developer.mozilla.org/docs/Web/API/Notification/silent)
+    pub fn silent(self) -> Self {
+        self.set_sound(Sound::None)
+    }
+
+    /// Set the notification sound, replaces the sound of [NotificationBuilder::sound] and [NotificationBuilder::set_sound_file].
+    /// Without a sound the platform plays its usual notification sound.
+    pub fn set_sound(mut self, sound: Sound) -> Self {
+        self.sound = Some(sound);
         self
     }
 
-    // IDEA: sound support
+    /// Set a custom notification sound from a file, the same as [Sound::File]
+    ///
+    /// Platform specific:
+    /// - MacOS: the file is copied to `~/Library/Sounds` and played with [UNNotificationSound(named:)](https://developer.apple.com/documentation/usernotifications/unnotificationsound/init(named:)),
+    ///   must be aiff, wav or caf and shorter than 30 seconds, otherwise the default sound is played
+    /// - Linux / XDG: ["sound-file"](https://specifications.freedesktop.org/notification-spec/latest/hints.html) hint, the notification daemon decides which formats it can play
+    /// - Windows: **not supported**, toasts can only play sounds from the app package (`ms-appx:///`), which can be set with [NotificationBuilder::sound]
+    pub fn set_sound_file(self, path: impl AsRef<Path>) -> Self {
+        self.set_sound(Sound::File(path.as_ref().to_path_buf()))
+    }
 
-    /// Set Image Attachment
+    /// Set Image Attachment, for example a thumbnail of a received picture
+    ///
+    /// GIFs are only animated where [NotificationCapabilities::animated_images] is set, elsewhere their first frame is shown.
+    /// Images bigger than [NotificationCapabilities::max_image_size] fail to send.
     ///
     /// Plaform specific:
-    /// - MacOS: passed by file path, must be gif, jpg, or png
-    /// - For linux the file is read and transfered over dbus (in case you are in a flatpak and it can't read from files) ["image-data"](https://specifications.freedesktop.org/notification-spec/latest/icons-and-images.html#icons-and-images-formats)
-    /// - Windows: passed by file path. [image](https://docs.rs/tauri-winrt-notification/latest/tauri_winrt_notification/struct.Toast.html#method.image)
+    /// - MacOS: passed by file path as [UNNotificationAttachment](https://developer.apple.com/documentation/usernotifications/unnotificationattachment), must be gif, jpg, or png, at most 10 MB
+    /// - For linux the file is read and transfered over dbus (in case you are in a flatpak and it can't read from files) ["image-data"](https://specifications.freedesktop.org/notification-spec/latest/icons-and-images.html#icons-and-images-formats),
+    ///   if the image can not be decoded it falls back to passing the file path as "image-path"
+    /// - Windows: passed by file path. [image](https://docs.rs/tauri-winrt-notification/latest/tauri_winrt_notification/struct.Toast.html#method.image), at most 3 MB,
+    ///   GIFs are converted to PNG
     pub fn set_image(mut self, path: PathBuf) -> Self {
         self.image = Some(path);
+        self.image_url = None;
+        self
+    }
+
+    /// Like [NotificationBuilder::set_image], for any kind of path
+    ///
+    /// ```
+    /// use user_notify::NotificationBuilder;
+    ///
+    /// let builder = NotificationBuilder::new().attach_image("thumbnails/cat.jpg");
+    /// assert!(builder.get_image().is_some());
+    /// ```
+    pub fn attach_image(self, path: impl AsRef<Path>) -> Self {
+        self.set_image(path.as_ref().to_path_buf())
+    }
+
+    /// Set the image from an `https://` url, replaces the image of [NotificationBuilder::set_image].
+    /// Fails to send with [Error::Unsupported] when [NotificationCapabilities::remote_images] is false,
+    /// [crate::remote_image::RemoteImageManager] downloads it on the other platforms.
+    ///
+    /// Plaform specific:
+    /// - Web: the browser loads the url
+    pub fn set_image_url(mut self, url: &str) -> Self {
+        self.image_url = Some(url.to_owned());
+        self.image = None;
+        self
+    }
+
+    /// Attach a movie or audio file, like a voice message, that can be played from the notification.
+    /// Fails to send with [Error::Unsupported] when [NotificationCapabilities::media_attachments] is false.
+    ///
+    /// Plaform specific:
+    /// - MacOS: [UNNotificationAttachment](https://developer.apple.com/documentation/usernotifications/unnotificationattachment)
+    ///   with the thumbnail options, in front of the image of [NotificationBuilder::set_image]
+    /// - Linux / XDG, Windows, Web: not supported
+    pub fn set_attachment(mut self, attachment: NotificationAttachment) -> Self {
+        self.attachment = Some(attachment);
         self
     }
 
-    /// Set App icon
+    /// Set a large banner image shown at the top of the notification, for news style notifications
     ///
     /// Plaform specific:
-    /// - MacOS: not supported to change the app icon?
-    /// - For linux the file is read and transfered over dbus (in case you are in a flatpak and it can't read from files) [app_icon](https://specifications.freedesktop.org/notification-spec/latest/icons-and-images.html#icons-and-images-formats)
-    /// - Windows: [`<image placement="appLogoOverride" />`](https://learn.microsoft.com/uwp/schemas/tiles/toastschema/element-image)
+    /// - MacOS: used as image attachment, if no image is set with [NotificationBuilder::set_image]
+    /// - Linux / XDG: used as image, if no image is set
+    /// - Windows: [`<image placement="hero" />`](https://learn.microsoft.com/windows/apps/design/shell/tiles-and-notifications/adaptive-interactive-toasts#hero-image)
+    pub fn set_hero_image(mut self, path: impl AsRef<Path>) -> Self {
+        self.hero_image = Some(path.as_ref().to_path_buf());
+        self.hero_image_url = None;
+        self
+    }
+
+    /// Set the hero image from an `https://` url, like [NotificationBuilder::set_image_url]
+    pub fn set_hero_image_url(mut self, url: &str) -> Self {
+        self.hero_image_url = Some(url.to_owned());
+        self.hero_image = None;
+        self
+    }
+
+    /// Set App icon, see [NotificationBuilder::app_icon]
     pub fn set_icon(mut self, path: PathBuf) -> Self {
         self.icon = Some(path);
         self
     }
 
+    /// Show another icon than the one of the app on this notification, like the icon of the bot that sent a message.
+    /// Otherwise the icon comes from the desktop entry or the AUMID and is the same for every notification.
+    /// Relative paths are made absolute, the platforms load the icon by `file://` url.
+    ///
+    /// Plaform specific:
+    /// - MacOS: ignored, notifications always show the icon of the app bundle
+    /// - Linux / XDG: [app_icon](https://specifications.freedesktop.org/notification-spec/latest/icons-and-images.html#icons-and-images-formats) parameter,
+    ///   the notification portal gets the content of the file as icon
+    /// - Windows: [`<image placement="appLogoOverride" />`](https://learn.microsoft.com/uwp/schemas/tiles/toastschema/element-image),
+    ///   also for unpackaged apps
+    /// - Web: [icon](https://developer.mozilla.org/docs/Web/API/Notification/icon)
+    pub fn app_icon(self, path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        self.set_icon(std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf()))
+    }
+
     /// Set App icon to be round
     ///
     /// Plaform specific:
@@ -108,14 +325,186 @@ where
         self
     }
 
+    /// Set where the notification comes from, like "via Gety"
+    ///
+    /// Plaform specific:
+    /// - MacOS: used as subtitle, or appended to it
+    /// - Linux / XDG: **not suported!**, like subtitles
+    /// - Windows: [`<text placement="attribution">`](https://learn.microsoft.com/windows/apps/design/shell/tiles-and-notifications/adaptive-interactive-toasts#attribution-text)
+    pub fn set_attribution(mut self, attribution: &str) -> Self {
+        self.attribution = Some(attribution.to_owned());
+        self
+    }
+
     /// Set Thread id, this is used to group related notifications
     ///
     /// Plaform specific:
     /// - MacOS: [UNNotificationContent/threadIdentifier](https://developer.apple.com/documentation/usernotifications/unnotificationcontent/threadidentifier)
     /// - Linux not specified yet:
-    /// - Windows: not supported
+    /// - Windows: used as [toast group](https://learn.microsoft.com/uwp/api/windows.ui.notifications.toastnotification.group),
+    ///   the action center does not show them grouped, but [NotificationManager::dismiss_thread] removes them together
+    ///
+    /// It is also stored in the user info, so the responses can be routed by it, see [crate::dispatch]
     pub fn set_thread_id(mut self, thread_id: &str) -> Self {
         self.thread_id = Some(thread_id.to_owned());
+        self.user_info
+            .get_or_insert_default()
+            .insert(THREAD_KEY.to_owned(), thread_id.to_owned());
+        self
+    }
+
+    /// Set how important the notification is and whether it may interrupt the user,
+    /// for example to let reminders break through focus modes and scheduled summaries
+    ///
+    /// Plaform specific:
+    /// - MacOS: [UNNotificationContent/interruptionLevel](https://developer.apple.com/documentation/usernotifications/unnotificationcontent/interruptionlevel), time sensitive and critical need the matching entitlements
+    /// - Linux / XDG: [urgency](https://specifications.freedesktop.org/notification-spec/latest/urgency-levels.html), passive is low and time sensitive and critical are critical
+    /// - Windows: passive toasts are silent, time sensitive uses the [reminder scenario](https://learn.microsoft.com/uwp/schemas/tiles/toastschema/element-toast) and critical the urgent scenario
+    pub fn set_interruption_level(mut self, interruption_level: InterruptionLevel) -> Self {
+        self.interruption_level = Some(interruption_level);
+        self
+    }
+
+    /// Set the urgency of the notification, critical notifications stay on screen until they are dismissed
+    ///
+    /// Plaform specific:
+    /// - Linux / XDG: [urgency](https://specifications.freedesktop.org/notification-spec/latest/urgency-levels.html),
+    ///   overrides the urgency derived from [NotificationBuilder::set_interruption_level]
+    /// - MacOS and Windows: mapped to an interruption level, if none is set with [NotificationBuilder::set_interruption_level]
+    pub fn set_urgency(mut self, urgency: NotificationUrgency) -> Self {
+        self.urgency = Some(urgency);
+        self
+    }
+
+    /// Set what the notification is used for, so alarms can loop their sound and stay on screen
+    /// and incoming calls get a layout for answering them
+    ///
+    /// Plaform specific:
+    /// - MacOS: mapped to an interruption level, if none is set with [NotificationBuilder::set_interruption_level]
+    /// - Linux / XDG: mapped to an urgency, if no interruption level is set
+    /// - Windows: the [toast scenario](https://learn.microsoft.com/uwp/schemas/tiles/toastschema/element-toast),
+    ///   alarms and incoming calls are only shown this way if the notification has at least one action
+    pub fn set_scenario(mut self, scenario: NotificationScenario) -> Self {
+        self.scenario = Some(scenario);
+        self
+    }
+
+    /// Set when the notification is outdated and should be removed from the notification center
+    ///
+    /// Plaform specific:
+    /// - Windows: [ToastNotification.ExpirationTime](https://learn.microsoft.com/uwp/api/windows.ui.notifications.toastnotification.expirationtime)
+    /// - MacOS and Linux: the notification is removed by a timer, so only while the app is running
+    pub fn set_expiration_time(mut self, expiration: DateTime<Utc>) -> Self {
+        self.expiration = Some(expiration);
+        self
+    }
+
+    /// Remove the notification this long after it was delivered,
+    /// together with [NotificationBuilder::set_expiration_time] the earlier one wins
+    ///
+    /// Plaform specific:
+    /// - Windows: [ToastNotification.ExpirationTime](https://learn.microsoft.com/uwp/api/windows.ui.notifications.toastnotification.expirationtime)
+    /// - Linux / XDG: also sent as `expire_timeout` to the notification daemon
+    /// - MacOS and Linux: the notification is removed by a timer, so only while the app is running,
+    ///   on MacOS only the first delivery of a repeating notification is removed
+    pub fn set_auto_dismiss_after(mut self, duration: Duration) -> Self {
+        self.auto_dismiss_after = Some(duration);
+        self
+    }
+
+    /// Only add the notification to the notification center, without showing a popup,
+    /// for low priority notifications
+    ///
+    /// Plaform specific:
+    /// - Windows: [ToastNotification.SuppressPopup](https://learn.microsoft.com/uwp/api/windows.ui.notifications.toastnotification.suppresspopup)
+    /// - MacOS and Linux: uses the passive interruption level, if no interruption level is set
+    pub fn set_suppress_popup(mut self, suppress_popup: bool) -> Self {
+        self.suppress_popup = suppress_popup;
+        self
+    }
+
+    /// How the notification is presented while the app is frontmost,
+    /// by default it is shown as banner, plays its sound and updates the badge
+    ///
+    /// Plaform specific:
+    /// - MacOS: the options passed to [userNotificationCenter(_:willPresent:withCompletionHandler:)](https://developer.apple.com/documentation/usernotifications/unusernotificationcenterdelegate/usernotificationcenter(_:willpresent:withcompletionhandler:))
+    /// - Windows and Linux: not supported, notifications are always presented
+    pub fn set_foreground_presentation(mut self, presentation: ForegroundPresentation) -> Self {
+        self.foreground_presentation = Some(presentation);
+        self
+    }
+
+    /// Set the order in which waiting notifications are sent by [crate::rate_limit::RateLimitedNotificationManager],
+    /// notifications with higher priority jump ahead of the others
+    ///
+    /// Defaults to [NotificationPriority::High] for [InterruptionLevel::Critical] and [InterruptionLevel::TimeSensitive],
+    /// [NotificationPriority::Low] for [InterruptionLevel::Passive] and [NotificationPriority::Normal] otherwise.
+    /// The platforms do not see it.
+    pub fn set_priority(mut self, priority: NotificationPriority) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    /// Set how relevant the notification is compared to the other notifications of the app,
+    /// between `0.0` and `1.0`, the most relevant notification is featured in the notification summary
+    ///
+    /// Plaform specific:
+    /// - MacOS: [UNNotificationContent/relevanceScore](https://developer.apple.com/documentation/usernotifications/unnotificationcontent/relevancescore)
+    /// - Linux / XDG: not supported
+    /// - Windows: not supported
+    pub fn set_relevance_score(mut self, relevance_score: f64) -> Self {
+        self.relevance_score = Some(relevance_score);
+        self
+    }
+
+    /// Set the filter criteria, which a Focus filter of the app can use to decide
+    /// whether the notification is shown in the current Focus, for example the id of a work account
+    ///
+    /// Plaform specific:
+    /// - MacOS: [UNNotificationContent/filterCriteria](https://developer.apple.com/documentation/usernotifications/unnotificationcontent/filtercriteria), macOS 13 and newer
+    /// - Linux / XDG: not supported
+    /// - Windows: not supported
+    pub fn set_filter_criteria(mut self, filter_criteria: &str) -> Self {
+        self.filter_criteria = Some(filter_criteria.to_owned());
+        self
+    }
+
+    /// The name that the summary of the thread uses for this notification, like the name of the chat
+    ///
+    /// Plaform specific:
+    /// - MacOS: [UNNotificationContent/summaryArgument](https://developer.apple.com/documentation/usernotifications/unnotificationcontent/summaryargument),
+    ///   ignored since macOS 12, the system summarizes the threads itself
+    /// - Others: the text of the summary notification of [crate::summary::ThreadSummaryManager]
+    pub fn set_summary_argument(mut self, summary_argument: &str) -> Self {
+        self.summary_argument = Some(summary_argument.to_owned());
+        self
+    }
+
+    /// How many items the notification stands for in the summary of the thread,
+    /// like 3 for one notification about 3 new messages. Defaults to 1, 0 is raised to 1.
+    ///
+    /// Plaform specific:
+    /// - MacOS: [UNNotificationContent/summaryArgumentCount](https://developer.apple.com/documentation/usernotifications/unnotificationcontent/summaryargumentcount),
+    ///   ignored since macOS 12
+    /// - Others: counted by [crate::summary::ThreadSummaryManager]
+    pub fn set_summary_argument_count(mut self, summary_argument_count: u32) -> Self {
+        self.summary_argument_count = Some(summary_argument_count.max(1));
+        self
+    }
+
+    /// Mark the notification as a message from a person, for chat apps.
+    ///
+    /// The name of the sender is used as title and the avatar as icon, if they are not set explicitly.
+    ///
+    /// Plaform specific:
+    /// - MacOS: a [communication notification](https://developer.apple.com/documentation/usernotifications/implementing-communication-notifications)
+    ///   with the avatar and name of the sender, an `INSendMessageIntent` is donated for it and the thread id is the conversation.
+    ///   Needs macOS 12, the communication notifications entitlement and `INSendMessageIntent` in the `NSUserActivityTypes` of the Info.plist,
+    ///   without them the avatar is attached as image
+    /// - Linux / XDG: the avatar is used as app icon
+    /// - Windows: the avatar is used as round app logo
+    pub fn set_sender(mut self, sender: NotificationSender) -> Self {
+        self.sender = Some(sender);
         self
     }
 
@@ -124,11 +513,60 @@ where
     /// It is used to add a text field or buttons to the notification.
     ///
     /// Categories are defined by passing them to [NotificationManager::register] on app startup
+    ///
+    /// It is also stored in the user info, so the responses can be routed by it, see [crate::dispatch]
     pub fn set_category_id(mut self, category_id: &str) -> Self {
         self.category_id = Some(category_id.to_owned());
+        self.user_info
+            .get_or_insert_default()
+            .insert(CATEGORY_KEY.to_owned(), category_id.to_owned());
+        self
+    }
+
+    /// Add an action to this notification only, for buttons that change between notifications
+    /// like "Approve request from {user}", instead of registering a category for them up front.
+    ///
+    /// The actions are shown after the actions of the category set with [NotificationBuilder::set_category_id].
+    ///
+    /// Plaform specific:
+    /// - MacOS: actions only exist on categories, so a category is created for each distinct set of actions,
+    ///   [NotificationManager::register] needs to be called before
+    pub fn add_action(mut self, action: NotificationCategoryAction) -> Self {
+        self.actions.push(action);
+        self
+    }
+
+    /// Show a progress bar, for long running tasks like exports,
+    /// update it with [NotificationManager::update_progress]
+    ///
+    /// Plaform specific:
+    /// - Windows: [`<progress>`](https://learn.microsoft.com/uwp/schemas/tiles/toastschema/element-progress)
+    /// - Linux / XDG: the ["value"](https://specifications.freedesktop.org/notification-spec/latest/hints.html) hint,
+    ///   for the daemons that render it (see [NotificationCapabilities::progress]) the title and status are appended to the body,
+    ///   otherwise the whole progress is appended to the body as text
+    /// - MacOS and the notification portal: there is no progress bar, so the progress is appended to the body as text
+    pub fn set_progress(mut self, progress: NotificationProgress) -> Self {
+        self.progress = Some(progress);
         self
     }
 
+    /// Show a progress bar without title, like [NotificationBuilder::set_progress],
+    /// `state` is a [ProgressState] or a value between `0.0` and `1.0`
+    ///
+    /// ```
+    /// use user_notify::{NotificationBuilder, ProgressState};
+    ///
+    /// let export = NotificationBuilder::new()
+    ///     .title("Export")
+    ///     .progress(0.42, "Exporting 3 of 10 files");
+    /// let connecting = NotificationBuilder::new()
+    ///     .title("Upload")
+    ///     .progress(ProgressState::Indeterminate, "Connecting");
+    /// ```
+    pub fn progress(self, state: impl Into<ProgressState>, status: &str) -> Self {
+        self.set_progress(NotificationProgress::new(state, status))
+    }
+
     /// Set the xdg notification Category
     ///
     /// The type of notification this is acording to https://specifications.freedesktop.org/notification-spec/latest/categories.html
@@ -147,128 +585,2586 @@ where
         self
     }
 
+    /// Set the xdg `resident` hint, whether the notification stays on screen after one of its actions was clicked.
+    /// Defaults to `true`, the handler is then called for every action click until the notification is closed.
+    ///
+    /// See https://specifications.freedesktop.org/notification-spec/latest/hints.html
+    ///
+    /// Platform specific: only work on linux (not with the xdg portal), this does nothing on other platforms
+    pub fn set_xdg_resident(mut self, resident: bool) -> Self {
+        self.xdg_resident = Some(resident);
+        self
+    }
+
+    /// Set the xdg `transient` hint, transient notifications bypass the notification history of the server.
+    /// Defaults to `false`.
+    ///
+    /// See https://specifications.freedesktop.org/notification-spec/latest/hints.html
+    ///
+    /// Platform specific: only work on linux (not with the xdg portal), this does nothing on other platforms
+    pub fn set_xdg_transient(mut self, transient: bool) -> Self {
+        self.xdg_transient = Some(transient);
+        self
+    }
+
+    /// Whether the notification replaces the one of the same [NotificationBuilder::set_thread_id] that is still shown,
+    /// in place like [NotificationManager::update_notification], instead of being shown next to it.
+    /// Defaults to `false`, the notification daemons do not group notifications by thread.
+    /// Responses to the replaced notification are reported with the id of the new one.
+    ///
+    /// Platform specific: only work on linux (not with the xdg portal), this does nothing on other platforms
+    pub fn set_xdg_replace_thread(mut self, replace_thread: bool) -> Self {
+        self.xdg_replace_thread = Some(replace_thread);
+        self
+    }
+
+    /// Clicks on the toast and its buttons start the app with a deeplink of the `notification_protocol` of the manager
+    /// ([protocol activation](https://learn.microsoft.com/windows/apps/design/shell/tiles-and-notifications/send-local-toast-other-apps)),
+    /// instead of only reaching the app while it is running. Defaults to `false`.
+    ///
+    /// Without the COM activator from [crate::windows::NotificationManagerWindows::register_com_activator]
+    /// clicks on the toast itself already use protocol activation, this also applies it to the buttons.
+    /// The deeplink is passed as command line argument, a new instance decodes it into [NotificationManager::take_launch_response],
+    /// a running single instance app has to forward it and decode it with [crate::windows::decode_deeplink].
+    /// Buttons of text and selection inputs stay foreground activations, protocol activation does not pass the input.
+    ///
+    /// Platform specific: only work on windows with a `notification_protocol`, this does nothing on other platforms
+    pub fn set_windows_protocol_activation(mut self, protocol_activation: bool) -> Self {
+        self.windows_protocol_activation = protocol_activation;
+        self
+    }
+
+    /// The button with this action identifier opens `uri` with protocol activation,
+    /// like a deep link `myapp://inbox/42/archive` or a web page, the app does not receive the click.
+    ///
+    /// Platform specific: only work on windows, this does nothing on other platforms
+    pub fn set_windows_action_uri(mut self, action_identifier: &str, uri: &str) -> Self {
+        self.windows_action_uris
+            .insert(action_identifier.to_owned(), uri.to_owned());
+        self
+    }
+
+    /// Shows the toast in a [collection](https://learn.microsoft.com/windows/apps/design/shell/tiles-and-notifications/toast-collections),
+    /// its own header in the action center, like one per account of a mail client.
+    /// The collection has to be saved first with [crate::windows::NotificationManagerWindows::save_collection].
+    ///
+    /// Platform specific: only work on windows, this does nothing on other platforms
+    pub fn set_windows_collection_id(mut self, collection_id: &str) -> Self {
+        self.windows_collection_id = Some(collection_id.to_owned());
+        self
+    }
+
     /// set metadata for a notification
     ///
     /// ## Platform Specific
     /// - on MacOS this uses UserInfo field in the notification content, so it works accross sessions
     /// - windows stores this in toast [NotificationData](https://learn.microsoft.com/en-us/uwp/api/windows.ui.notifications.notificationdata?view=winrt-26100)
     /// - linux: on linux we emulate this by storing this info inside of NotificationManager
-    pub fn set_user_info(mut self, user_info: HashMap<String, String>) -> Self {
+    pub fn set_user_info(mut self, mut user_info: HashMap<String, String>) -> Self {
+        // keep data that the other setters stored in the user info before
+        if let Some(mut previous) = self.user_info.take() {
+            for key in INTERNAL_KEYS {
+                if let Some(value) = previous.remove(key) {
+                    user_info.entry(key.to_owned()).or_insert(value);
+                }
+            }
+        }
         self.user_info = Some(user_info);
         self
     }
-}
-
-// Handle to a sent notification
-pub trait NotificationHandle
-where
-    Self: Send + Sync + Debug,
-{
-    /// close the notification
-    fn close(&self) -> Result<(), Error>;
-
-    fn get_id(&self) -> String;
-
-    fn get_user_info(&self) -> &HashMap<String, String>;
-}
-
-// https://developer.apple.com/documentation/usernotifications/unnotificationcontent/targetcontentidentifier
-// maybe offer a seperate api to catch notifications from previous sessions?
-// https://github.com/deltachat/deltachat-desktop/issues/2438
-
-#[async_trait]
-pub trait NotificationManager
-where
-    Self: Send + Sync + Debug,
-{
-    /// Needs to be called from main thread
-    async fn get_notification_permission_state(&self) -> Result<bool, crate::Error>;
 
-    /// Needs to be called from main thread
-    async fn first_time_ask_for_notification_permission(&self) -> Result<bool, Error>;
+    /// set structured metadata for a notification, for data that does not fit into [NotificationBuilder::set_user_info]
+    ///
+    /// It is stored as json string inside of the user info, so it is transported the same way on all platforms,
+    /// read it back with [NotificationHandle::get_user_data] or [NotificationResponse::get_user_data].
+    pub fn set_user_data(mut self, user_data: serde_json::Value) -> Self {
+        self.user_info
+            .get_or_insert_default()
+            .insert(USER_DATA_KEY.to_owned(), user_data.to_string());
+        self
+    }
 
-    /// registers and initializes the notification handler and categories.
-    /// Set a function to handle user responses (clicking notification, closing it, clicking an action on it)
+    /// Stores a serializable payload, like the ids of the message and the account, read it back with
+    /// [NotificationResponse::user_info_as].
     ///
-    /// ## Platform specific:
-    /// - MacOS: sets the UNUserNotificationCenterDelegate
-    fn register(
-        &self,
-        handler_callback: Box<dyn Fn(crate::NotificationResponse) + Send + Sync + 'static>,
-        categories: Vec<NotificationCategory>,
-    ) -> Result<(), Error>;
+    /// It is stored like [NotificationBuilder::set_user_data], so it does not need to be a map of strings.
+    /// Fails with [Error::FailedToParseUserInfo] when it can not be serialized to json.
+    pub fn set_user_info_typed<T: Serialize + ?Sized>(self, user_info: &T) -> Result<Self, Error> {
+        let user_data = serde_json::to_value(user_info).map_err(Error::FailedToParseUserInfo)?;
+        Ok(self.set_user_data(user_data))
+    }
 
-    /// Removes all of your app's delivered notifications from Notification Center.
+    /// [NotificationManager::send_notification] does not show the notification again
+    /// while a notification with the same key is still active, it returns the handle of the active one instead.
+    /// Use it for notifications that are sent repeatedly, like a build failure that a watcher sees on every poll.
+    ///
+    /// Like [NotificationBuilder::set_user_data] it is stored in the user info.
     ///
     /// ## Platform specific:
-    /// - MacOS: [UNUserNotificationCenter.removeAllDeliveredNotifications](https://developer.apple.com/documentation/usernotifications/unusernotificationcenter/removealldeliverednotifications())
-    fn remove_all_delivered_notifications(&self) -> Result<(), Error>;
+    /// - Linux / XDG and Web: only notifications of the current session are found
+    pub fn set_dedupe_key(mut self, dedupe_key: &str) -> Self {
+        self.user_info
+            .get_or_insert_default()
+            .insert(DEDUPE_KEY.to_owned(), dedupe_key.to_owned());
+        self
+    }
 
-    /// Removes specific delivered notifications by their id from Notification Center.
-    fn remove_delivered_notifications(&self, ids: Vec<&str>) -> Result<(), Error>;
+    /// Adds a snooze button, a click on it shows the notification again after `delay`.
+    /// Only has an effect when it is sent with a [crate::snooze::SnoozeNotificationManager].
+    ///
+    /// Like [NotificationBuilder::set_user_data] it is stored in the user info.
+    pub fn set_snooze(mut self, delay: Duration) -> Self {
+        self.user_info
+            .get_or_insert_default()
+            .insert(SNOOZE_KEY.to_owned(), delay.as_millis().to_string());
+        self
+    }
 
-    /// Get all deliverd notifications from UNUserNotificationCenter that are still active.
+    /// Opens the url when the notification itself is clicked, like a deep link `myapp://inbox/42` or a web page.
+    /// The click is still passed to the handler from [NotificationManager::register].
+    ///
+    /// Like [NotificationBuilder::set_user_data] it is stored in the user info.
     ///
     /// ## Platform specific:
-    /// - MacOS:
-    ///   - also includes notifications from previous sessions
-    ///   - [UNUserNotificationCenter.getDeliveredNotificationsWithCompletionHandler](https://developer.apple.com/documentation/usernotifications/unusernotificationcenter/getdeliverednotifications(completionhandler:))
-    /// - Others: TODO: implemented/emulated by keeping track of all notifications in memory
-    async fn get_active_notifications(&self) -> Result<Vec<Box<dyn NotificationHandle>>, Error>;
-
-    /// Shows notification and returns Notification handle
-    async fn send_notification(
-        &self,
-        builder: NotificationBuilder,
-    ) -> Result<Box<dyn NotificationHandle>, Error>;
-}
+    /// - Windows: the toast uses [protocol activation](https://learn.microsoft.com/en-us/windows/apps/design/shell/tiles-and-notifications/send-local-toast-other-apps),
+    ///   so Windows opens the url itself and the app does not receive the click
+    /// - MacOS and Linux: opened with `open` and `xdg-open` when the handler receives the click,
+    ///   clicks before [NotificationManager::register] was called do not open it
+    /// - Web: opened with `window.open`, not when the click is forwarded from a service worker
+    pub fn on_click_open_url(mut self, url: &str) -> Self {
+        self.user_info
+            .get_or_insert_default()
+            .insert(OPEN_URL_KEY.to_owned(), url.to_owned());
+        self
+    }
 
-/// Emmited when user clicked on a notification
-///
-/// ## Platform-specific
-///
-/// - **macOS**: https://developer.apple.com/documentation/usernotifications/unusernotificationcenterdelegate/usernotificationcenter(_:didreceive:withcompletionhandler:)?language=objc
-/// - **Other**: Unsupported.
-#[non_exhaustive]
-#[derive(Debug, Clone, PartialEq)]
-pub struct NotificationResponse {
-    /// id of the notification that was assigned by the system
-    pub notification_id: String,
-    pub action: NotificationResponseAction,
-    /// The text that the user typed in as reponse
+    /// Passes the responses to this notification to `callback` instead of the handler from [NotificationManager::register],
+    /// so the reply handling can stay next to the code that sent the notification.
     ///
-    /// corresponds to [UNTextInputNotificationResponse.userText](https://developer.apple.com/documentation/usernotifications/untextinputnotificationresponse/usertext?language=objc)
-    pub user_text: Option<String>,
-
-    pub user_info: HashMap<String, String>,
-}
+    /// The manager keeps the callback until the notification is dismissed, or until it is removed with
+    /// [NotificationManager::remove_delivered_notifications] or [NotificationManager::remove_all_delivered_notifications],
+    /// so a click and the later dismissal of a notification that stayed on screen both go to the callback.
+    /// Responses to notifications of an earlier run of the app go to the handler as well, because the callback is gone.
+    /// Clicks on the snooze button of [NotificationBuilder::set_snooze] go to the handler as well,
+    /// so the [crate::snooze::SnoozeNotificationManager] receives them.
+    ///
+    /// The response does not pass the wrappers around the handler, like
+    /// [crate::history::HistoryNotificationManager], it is still sent to [NotificationManager::subscribe_events].
+    pub fn on_response(
+        mut self,
+        callback: impl Fn(NotificationResponse) + Send + Sync + 'static,
+    ) -> Self {
+        self.response_callback = OnResponse(Some(Arc::new(callback)));
+        self
+    }
 
-#[derive(Debug, Clone, PartialEq)]
-pub enum NotificationResponseAction {
-    /// When user clicks on the notification
+    /// Checks that the notification can be shown as it is on a platform with these [NotificationCapabilities],
+    /// the managers call it before sending, so they fail early instead of cutting off texts or dropping buttons.
     ///
-    /// ## Platform Specific
-    /// - MacOS: corresponds to [UNNotificationDefaultActionIdentifier](https://developer.apple.com/documentation/usernotifications/unnotificationdefaultactionidentifier?language=objc)
+    /// Fails with [Error::InvalidNotification] when
+    /// - there is no title and no [NotificationBuilder::set_sender]
+    /// - the title or subtitle is longer than [MAX_TITLE_LENGTH] or the body longer than [MAX_BODY_LENGTH]
+    /// - it has actions but [NotificationCapabilities::actions] is false,
+    ///   or a [NotificationCategoryAction::TextInputAction] but [NotificationCapabilities::inline_reply] is false
+    /// - the image or hero image is bigger than [NotificationCapabilities::max_image_size]
+    ///
+    /// Fails with [Error::Unsupported] when it has a [NotificationBuilder::set_attachment]
+    /// but [NotificationCapabilities::media_attachments] is false,
+    /// or image urls but [NotificationCapabilities::remote_images] is false.
+    ///
+    /// Whether the category is registered is checked by the managers when sending.
+    pub fn validate(&self, capabilities: &NotificationCapabilities) -> Result<(), Error> {
+        let title = self
+            .title
+            .as_deref()
+            .or(self
+                .title_localized
+                .as_ref()
+                .map(|title| title.key.as_str()))
+            .or(self.sender.as_ref().map(|sender| sender.name.as_str()));
+        if title.is_none_or(|title| title.trim().is_empty()) {
+            let has_body = self
+                .body
+                .as_deref()
+                .is_some_and(|body| !body.trim().is_empty())
+                || self.body_localized.is_some();
+            return Err(Error::InvalidNotification(
+                if has_body {
+                    "the notification has no title"
+                } else {
+                    "the notification is empty, it has no title and no body"
+                }
+                .to_owned(),
+            ));
+        }
+        for (field, text, max_length) in [
+            ("title", &self.title, MAX_TITLE_LENGTH),
+            ("subtitle", &self.subtitle, MAX_TITLE_LENGTH),
+            ("body", &self.body, MAX_BODY_LENGTH),
+        ] {
+            if let Some(text) = text
+                && text.chars().count() > max_length
+            {
+                return Err(Error::InvalidNotification(format!(
+                    "the {field} is longer than {max_length} characters"
+                )));
+            }
+        }
+        if !self.actions.is_empty() && !capabilities.actions {
+            return Err(Error::InvalidNotification(
+                "buttons are not supported by the notification system".to_owned(),
+            ));
+        }
+        if !capabilities.inline_reply
+            && self
+                .actions
+                .iter()
+                .any(|action| matches!(action, NotificationCategoryAction::TextInputAction { .. }))
+        {
+            return Err(Error::InvalidNotification(
+                "text input is not supported by the notification system".to_owned(),
+            ));
+        }
+        if let Some(max_size) = capabilities.max_image_size {
+            for path in [&self.image, &self.hero_image].into_iter().flatten() {
+                // converted to their first frame when they are sent
+                if !capabilities.animated_images && is_gif(path) {
+                    continue;
+                }
+                if let Ok(metadata) = std::fs::metadata(path)
+                    && metadata.len() > max_size
+                {
+                    return Err(Error::InvalidNotification(format!(
+                        "the image {path:?} is bigger than {max_size} bytes"
+                    )));
+                }
+            }
+        }
+        if self.has_image_urls() && !capabilities.remote_images {
+            return Err(Error::Unsupported(
+                "image urls without RemoteImageManager".to_owned(),
+            ));
+        }
+        if let Some(attachment) = &self.attachment
+            && !capabilities.media_attachments
+        {
+            return Err(Error::Unsupported(format!(
+                "{} attachments",
+                match attachment.kind {
+                    AttachmentKind::Movie => "movie",
+                    AttachmentKind::Audio => "audio",
+                }
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Longest title and subtitle that [NotificationBuilder::validate] accepts, in characters
+pub const MAX_TITLE_LENGTH: usize = 256;
+/// Longest body that [NotificationBuilder::validate] accepts, in characters,
+/// Windows limits the whole toast to 5 KB
+pub const MAX_BODY_LENGTH: usize = 2048;
+
+impl NotificationBuilder {
+    /// Whether an image, hero image or avatar is set by url
+    pub(crate) fn has_image_urls(&self) -> bool {
+        self.image_url.is_some()
+            || self.hero_image_url.is_some()
+            || self
+                .sender
+                .as_ref()
+                .is_some_and(|sender| sender.avatar_url.is_some())
+    }
+}
+
+/// Whether the file starts with the GIF signature, whatever its extension is
+pub(crate) fn is_gif(path: &Path) -> bool {
+    let mut signature = [0; 6];
+    std::fs::File::open(path)
+        .and_then(|mut file| std::io::Read::read_exact(&mut file, &mut signature))
+        .is_ok_and(|_| &signature == b"GIF87a" || &signature == b"GIF89a")
+}
+
+// used by the platform backends
+#[cfg_attr(
+    not(any(
+        all(target_os = "macos", feature = "macos"),
+        all(target_os = "windows", feature = "windows"),
+        all(target_arch = "wasm32", feature = "web"),
+        all(
+            any(
+                target_os = "linux",
+                target_os = "dragonfly",
+                target_os = "freebsd",
+                target_os = "openbsd",
+                target_os = "netbsd"
+            ),
+            any(feature = "linux-dbus", feature = "linux-portal")
+        )
+    )),
+    allow(dead_code)
+)]
+/// Error for a notification with a category that was not registered
+pub(crate) fn unknown_category(category_id: &str) -> Error {
+    Error::InvalidNotification(format!(
+        "category '{category_id}' is not registered, see NotificationManager::register"
+    ))
+}
+
+// used by the platform backends
+#[cfg_attr(
+    not(any(
+        all(target_os = "macos", feature = "macos"),
+        all(target_os = "windows", feature = "windows"),
+        all(target_arch = "wasm32", feature = "web"),
+        all(
+            any(
+                target_os = "linux",
+                target_os = "dragonfly",
+                target_os = "freebsd",
+                target_os = "openbsd",
+                target_os = "netbsd"
+            ),
+            any(feature = "linux-dbus", feature = "linux-portal")
+        )
+    )),
+    allow(dead_code)
+)]
+impl NotificationBuilder {
+    /// Uses the sender set with [NotificationBuilder::set_sender] as title and icon, if they are not set
+    pub(crate) fn apply_sender(mut self) -> Self {
+        if let Some(sender) = &self.sender {
+            if self.title.is_none() {
+                self.title = Some(sender.name.clone());
+            }
+            if self.icon.is_none() && sender.avatar.is_some() {
+                self.icon = sender.avatar.clone();
+                self.icon_round_crop = true;
+            }
+        }
+        self
+    }
+}
+
+// used by the platform backends without the windows one
+#[cfg_attr(
+    not(any(
+        all(target_os = "macos", feature = "macos"),
+        all(target_arch = "wasm32", feature = "web"),
+        all(
+            any(
+                target_os = "linux",
+                target_os = "dragonfly",
+                target_os = "freebsd",
+                target_os = "openbsd",
+                target_os = "netbsd"
+            ),
+            any(feature = "linux-dbus", feature = "linux-portal")
+        )
+    )),
+    allow(dead_code)
+)]
+impl NotificationBuilder {
+    /// For platforms without hero images and attribution text, uses the hero image as image
+    /// and puts the attribution into the subtitle
+    pub(crate) fn apply_hero_image_and_attribution(mut self) -> Self {
+        if self.image.is_none() {
+            self.image = self.hero_image.take();
+        }
+        if let Some(attribution) = self.attribution.take() {
+            self.subtitle = Some(match self.subtitle {
+                Some(subtitle) => format!("{subtitle} · {attribution}"),
+                None => attribution,
+            });
+        }
+        self
+    }
+}
+
+// used by the platform backends
+#[cfg_attr(
+    not(any(
+        all(target_os = "macos", feature = "macos"),
+        all(target_os = "windows", feature = "windows"),
+        all(target_arch = "wasm32", feature = "web"),
+        all(
+            any(
+                target_os = "linux",
+                target_os = "dragonfly",
+                target_os = "freebsd",
+                target_os = "openbsd",
+                target_os = "netbsd"
+            ),
+            any(feature = "linux-dbus", feature = "linux-portal")
+        )
+    )),
+    allow(dead_code)
+)]
+impl NotificationBuilder {
+    /// For platforms without scenarios, urgency and popup suppression, uses the passive level for [NotificationBuilder::set_suppress_popup]
+    /// or the interruption level of the urgency set with [NotificationBuilder::set_urgency] or the scenario set with [NotificationBuilder::set_scenario],
+    /// if there is no interruption level set
+    pub(crate) fn apply_interruption_level(mut self) -> Self {
+        if self.interruption_level.is_none() {
+            self.interruption_level = if self.suppress_popup {
+                Some(InterruptionLevel::Passive)
+            } else {
+                self.urgency
+                    .map(NotificationUrgency::interruption_level)
+                    .or(self.scenario.map(NotificationScenario::interruption_level))
+            };
+        }
+        self
+    }
+}
+
+impl NotificationBuilder {
+    // used by the platform backends
+    #[cfg_attr(
+        not(any(
+            all(target_os = "macos", feature = "macos"),
+            all(target_os = "windows", feature = "windows"),
+            all(target_arch = "wasm32", feature = "web"),
+            all(
+                any(
+                    target_os = "linux",
+                    target_os = "dragonfly",
+                    target_os = "freebsd",
+                    target_os = "openbsd",
+                    target_os = "netbsd"
+                ),
+                any(feature = "linux-dbus", feature = "linux-portal")
+            )
+        )),
+        allow(dead_code)
+    )]
+    /// Turns the duration set with [NotificationBuilder::set_auto_dismiss_after] into the expiration time
+    /// of a notification delivered at `delivered_at`, keeps the expiration time if it is earlier
+    pub(crate) fn apply_auto_dismiss(mut self, delivered_at: DateTime<Utc>) -> Self {
+        if let Some(auto_dismiss_at) = self
+            .auto_dismiss_after
+            .take()
+            .and_then(|duration| chrono::Duration::from_std(duration).ok())
+            .and_then(|duration| delivered_at.checked_add_signed(duration))
+        {
+            self.expiration = Some(match self.expiration {
+                Some(expiration) => expiration.min(auto_dismiss_at),
+                None => auto_dismiss_at,
+            });
+        }
+        self
+    }
+
+    /// Makes the backend show the notification with `id`, for notifications that handed out
+    /// their id before they were shown, like the queued ones of the rate limiter
+    pub(crate) fn with_preset_id(mut self, id: String) -> Self {
+        self.preset_id = Some(id);
+        self
+    }
+
+    /// The preset id, or a new one made by `new_id`
+    pub(crate) fn notification_id(&self, new_id: impl FnOnce() -> String) -> String {
+        self.preset_id.clone().unwrap_or_else(new_id)
+    }
+}
+
+// the linux backends check for markup support themselves
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "netbsd"
+)))]
+impl NotificationBuilder {
+    /// For platforms without markup, turns the body of [NotificationBuilder::set_body_markdown] into plain text
+    pub(crate) fn apply_plain_body(mut self) -> Self {
+        if self.body_markdown {
+            self.body = self.body.as_deref().map(crate::markdown::to_plain_text);
+            self.body_markdown = false;
+        }
+        self
+    }
+}
+
+// used by the platform backends without the windows one
+#[cfg_attr(
+    not(any(
+        all(target_os = "macos", feature = "macos"),
+        all(target_arch = "wasm32", feature = "web"),
+        all(
+            any(
+                target_os = "linux",
+                target_os = "dragonfly",
+                target_os = "freebsd",
+                target_os = "openbsd",
+                target_os = "netbsd"
+            ),
+            any(feature = "linux-dbus", feature = "linux-portal")
+        )
+    )),
+    allow(dead_code)
+)]
+impl NotificationBuilder {
+    /// For platforms without progress bars, appends the progress set with [NotificationBuilder::set_progress] to the body
+    pub(crate) fn apply_progress(self) -> Self {
+        let progress_text = self.progress.as_ref().map(ToString::to_string);
+        self.append_progress_text(progress_text)
+    }
+
+    /// For platforms with a progress bar but no place for its texts, appends the title and status to the body
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "openbsd",
+        target_os = "netbsd"
+    ))]
+    // used by the notification daemon backend
+    #[cfg_attr(not(feature = "linux-dbus"), allow(dead_code))]
+    pub(crate) fn apply_progress_status(self) -> Self {
+        let progress_text = self
+            .progress
+            .as_ref()
+            .map(|progress| match &progress.title {
+                Some(title) => format!("{title}: {}", progress.status),
+                None => progress.status.clone(),
+            });
+        self.append_progress_text(progress_text)
+    }
+
+    fn append_progress_text(mut self, progress_text: Option<String>) -> Self {
+        if let Some(mut progress_text) = progress_text.filter(|text| !text.is_empty()) {
+            if self.body_markdown {
+                progress_text = crate::markdown::escape(&progress_text);
+            }
+            self.body = Some(match self.body {
+                Some(body) => format!("{body}\n{progress_text}"),
+                None => progress_text,
+            });
+        }
+        self
+    }
+}
+
+/// How far a task is, see [NotificationBuilder::progress] and [NotificationManager::update_progress]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ProgressState {
+    /// Between `0.0` and `1.0`
+    Determinate(f64),
+    /// The task is running, but it is unknown how long it takes, like while connecting
+    Indeterminate,
+}
+
+impl From<f64> for ProgressState {
+    fn from(value: f64) -> Self {
+        ProgressState::Determinate(value)
+    }
+}
+
+/// Progress bar of a notification, see [NotificationBuilder::set_progress]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NotificationProgress {
+    /// Shown above the progress bar
+    pub title: Option<String>,
+    pub state: ProgressState,
+    /// Shown below the progress bar, like "Exporting 3 of 10 files"
+    pub status: String,
+}
+
+impl NotificationProgress {
+    /// `state` is a [ProgressState] or a value between `0.0` and `1.0`
+    pub fn new(state: impl Into<ProgressState>, status: &str) -> Self {
+        Self {
+            title: None,
+            state: state.into(),
+            status: status.to_owned(),
+        }
+    }
+
+    pub fn set_title(mut self, title: &str) -> Self {
+        self.title = Some(title.to_owned());
+        self
+    }
+
+    /// The value as percentage for displaying it, `None` when it is indeterminate
+    pub(crate) fn percent(&self) -> Option<u32> {
+        match self.state {
+            ProgressState::Determinate(value) => {
+                Some((value.clamp(0.0, 1.0) * 100.0).round() as u32)
+            }
+            ProgressState::Indeterminate => None,
+        }
+    }
+}
+
+/// Text representation for platforms without progress bars, like `Export: 42% — Exporting 3 of 10 files`
+impl std::fmt::Display for NotificationProgress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(title) = &self.title {
+            write!(f, "{title}: ")?;
+        }
+        match (self.percent(), self.status.is_empty()) {
+            (Some(percent), false) => write!(f, "{percent}% — {}", self.status),
+            (Some(percent), true) => write!(f, "{percent}%"),
+            (None, _) => write!(f, "{}", self.status),
+        }
+    }
+}
+
+/// The person a notification is from, see [NotificationBuilder::set_sender]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NotificationSender {
+    pub name: String,
+    /// Path to the avatar image of the sender
+    pub avatar: Option<PathBuf>,
+    /// `https://` url of the avatar image, like [NotificationBuilder::set_image_url]
+    pub avatar_url: Option<String>,
+}
+
+impl NotificationSender {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            avatar: None,
+            avatar_url: None,
+        }
+    }
+
+    pub fn set_avatar(mut self, path: impl AsRef<Path>) -> Self {
+        self.avatar = Some(path.as_ref().to_path_buf());
+        self.avatar_url = None;
+        self
+    }
+
+    pub fn set_avatar_url(mut self, url: &str) -> Self {
+        self.avatar_url = Some(url.to_owned());
+        self.avatar = None;
+        self
+    }
+}
+
+/// A movie or audio file attached to a notification, see [NotificationBuilder::set_attachment]
+///
+/// ```
+/// use user_notify::{NotificationAttachment, NotificationBuilder};
+///
+/// let builder = NotificationBuilder::new()
+///     .title("Alice")
+///     .body("Voice message (0:12)")
+///     .set_attachment(NotificationAttachment::audio("/tmp/voice-message.m4a"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NotificationAttachment {
+    pub path: PathBuf,
+    pub kind: AttachmentKind,
+    /// Second of the movie whose frame is the thumbnail, the first frame by default
+    pub thumbnail_time: Option<f64>,
+    /// Part of the frame that is shown as thumbnail
+    pub thumbnail_clipping: Option<ThumbnailClipping>,
+    /// Show no thumbnail at all
+    pub thumbnail_hidden: bool,
+}
+
+impl NotificationAttachment {
+    pub fn movie(path: impl AsRef<Path>) -> Self {
+        Self::new(path, AttachmentKind::Movie)
+    }
+
+    pub fn audio(path: impl AsRef<Path>) -> Self {
+        Self::new(path, AttachmentKind::Audio)
+    }
+
+    fn new(path: impl AsRef<Path>, kind: AttachmentKind) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            kind,
+            thumbnail_time: None,
+            thumbnail_clipping: None,
+            thumbnail_hidden: false,
+        }
+    }
+
+    pub fn set_thumbnail_time(mut self, seconds: f64) -> Self {
+        self.thumbnail_time = Some(seconds);
+        self
+    }
+
+    pub fn set_thumbnail_clipping(mut self, clipping: ThumbnailClipping) -> Self {
+        self.thumbnail_clipping = Some(clipping);
+        self
+    }
+
+    pub fn set_thumbnail_hidden(mut self, hidden: bool) -> Self {
+        self.thumbnail_hidden = hidden;
+        self
+    }
+}
+
+/// See [NotificationAttachment]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AttachmentKind {
+    /// mpeg, mpeg2, mp4 or avi, at most 50 MB on MacOS
+    Movie,
+    /// aiff, wav, mp3 or m4a, at most 5 MB on MacOS
+    Audio,
+}
+
+/// A rectangle in unit coordinates, from 0 to 1 relative to the size of the frame,
+/// see [NotificationAttachment::thumbnail_clipping]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ThumbnailClipping {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// Read access to the notification content, for custom [NotificationManager] implementations
+impl NotificationBuilder {
+    pub fn get_body(&self) -> Option<&str> {
+        self.body.as_deref()
+    }
+
+    pub fn get_title_localized(&self) -> Option<&LocalizedText> {
+        self.title_localized.as_ref()
+    }
+
+    pub fn get_body_localized(&self) -> Option<&LocalizedText> {
+        self.body_localized.as_ref()
+    }
+
+    /// Whether the body was set with [NotificationBuilder::set_body_markdown]
+    pub fn is_body_markdown(&self) -> bool {
+        self.body_markdown
+    }
+
+    pub fn get_title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    pub fn get_subtitle(&self) -> Option<&str> {
+        self.subtitle.as_deref()
+    }
+
+    pub fn get_sound(&self) -> Option<&Sound> {
+        self.sound.as_ref()
+    }
+
+    pub fn get_sound_file(&self) -> Option<&Path> {
+        match &self.sound {
+            Some(Sound::File(path)) => Some(path),
+            _ => None,
+        }
+    }
+
+    /// Whether the notification is delivered without sound, see [NotificationBuilder::silent]
+    pub fn is_silent(&self) -> bool {
+        self.sound == Some(Sound::None)
+    }
+
+    pub fn get_image(&self) -> Option<&Path> {
+        self.image.as_deref()
+    }
+
+    pub fn get_image_url(&self) -> Option<&str> {
+        self.image_url.as_deref()
+    }
+
+    pub fn get_hero_image_url(&self) -> Option<&str> {
+        self.hero_image_url.as_deref()
+    }
+
+    pub fn get_attachment(&self) -> Option<&NotificationAttachment> {
+        self.attachment.as_ref()
+    }
+
+    pub fn get_hero_image(&self) -> Option<&Path> {
+        self.hero_image.as_deref()
+    }
+
+    pub fn get_attribution(&self) -> Option<&str> {
+        self.attribution.as_deref()
+    }
+
+    pub fn get_icon(&self) -> Option<&Path> {
+        self.icon.as_deref()
+    }
+
+    pub fn get_icon_round_crop(&self) -> bool {
+        self.icon_round_crop
+    }
+
+    pub fn get_thread_id(&self) -> Option<&str> {
+        self.thread_id.as_deref()
+    }
+
+    pub fn get_interruption_level(&self) -> Option<InterruptionLevel> {
+        self.interruption_level
+    }
+
+    pub fn get_urgency(&self) -> Option<NotificationUrgency> {
+        self.urgency
+    }
+
+    pub fn get_scenario(&self) -> Option<NotificationScenario> {
+        self.scenario
+    }
+
+    pub fn get_expiration_time(&self) -> Option<DateTime<Utc>> {
+        self.expiration
+    }
+
+    pub fn get_auto_dismiss_after(&self) -> Option<Duration> {
+        self.auto_dismiss_after
+    }
+
+    pub fn get_suppress_popup(&self) -> bool {
+        self.suppress_popup
+    }
+
+    pub fn get_foreground_presentation(&self) -> Option<ForegroundPresentation> {
+        self.foreground_presentation
+    }
+
+    /// The priority that was set, or the one derived from the interruption level, see [NotificationBuilder::set_priority]
+    pub fn get_priority(&self) -> NotificationPriority {
+        self.priority.unwrap_or(match self.interruption_level {
+            Some(InterruptionLevel::Critical | InterruptionLevel::TimeSensitive) => {
+                NotificationPriority::High
+            }
+            Some(InterruptionLevel::Passive) => NotificationPriority::Low,
+            Some(InterruptionLevel::Active) | None => NotificationPriority::Normal,
+        })
+    }
+
+    pub fn get_relevance_score(&self) -> Option<f64> {
+        self.relevance_score
+    }
+
+    pub fn get_filter_criteria(&self) -> Option<&str> {
+        self.filter_criteria.as_deref()
+    }
+
+    pub fn get_summary_argument(&self) -> Option<&str> {
+        self.summary_argument.as_deref()
+    }
+
+    pub fn get_summary_argument_count(&self) -> Option<u32> {
+        self.summary_argument_count
+    }
+
+    pub fn get_sender(&self) -> Option<&NotificationSender> {
+        self.sender.as_ref()
+    }
+
+    pub fn get_category_id(&self) -> Option<&str> {
+        self.category_id.as_deref()
+    }
+
+    pub fn get_actions(&self) -> &[NotificationCategoryAction] {
+        &self.actions
+    }
+
+    pub fn get_progress(&self) -> Option<&NotificationProgress> {
+        self.progress.as_ref()
+    }
+
+    pub fn get_xdg_category(&self) -> Option<&XdgNotificationCategory> {
+        self.xdg_category.as_ref()
+    }
+
+    pub fn get_xdg_app_name(&self) -> Option<&str> {
+        self.xdg_app_name.as_deref()
+    }
+
+    pub fn get_xdg_resident(&self) -> Option<bool> {
+        self.xdg_resident
+    }
+
+    pub fn get_xdg_transient(&self) -> Option<bool> {
+        self.xdg_transient
+    }
+
+    pub fn get_xdg_replace_thread(&self) -> Option<bool> {
+        self.xdg_replace_thread
+    }
+
+    pub fn get_windows_protocol_activation(&self) -> bool {
+        self.windows_protocol_activation
+    }
+
+    pub fn get_windows_action_uri(&self, action_identifier: &str) -> Option<&str> {
+        self.windows_action_uris
+            .get(action_identifier)
+            .map(String::as_str)
+    }
+
+    pub fn get_windows_collection_id(&self) -> Option<&str> {
+        self.windows_collection_id.as_deref()
+    }
+
+    /// The user info of [NotificationBuilder::set_user_info],
+    /// without the entries that the other setters add, like the thread id
+    pub fn get_user_info(&self) -> Option<HashMap<String, String>> {
+        self.user_info.as_ref().map(without_internal_keys)
+    }
+
+    pub fn get_dedupe_key(&self) -> Option<&str> {
+        self.user_info.as_ref()?.get(DEDUPE_KEY).map(String::as_str)
+    }
+
+    pub fn get_snooze(&self) -> Option<Duration> {
+        let milliseconds = self.user_info.as_ref()?.get(SNOOZE_KEY)?.parse().ok()?;
+        Some(Duration::from_millis(milliseconds))
+    }
+
+    pub fn get_on_click_open_url(&self) -> Option<&str> {
+        self.user_info
+            .as_ref()?
+            .get(OPEN_URL_KEY)
+            .map(String::as_str)
+    }
+}
+
+/// Content of a sent notification, see [NotificationHandle::get_content]
+#[non_exhaustive]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct NotificationContent {
+    pub title: Option<String>,
+    pub subtitle: Option<String>,
+    /// Plain text, also for [NotificationBuilder::set_body_markdown]
+    pub body: Option<String>,
+    /// The category that was set with [NotificationBuilder::set_category_id]
+    pub category_id: Option<String>,
+    /// When the notification was delivered, `None` if the platform does not tell
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+/// Content of a notification that is delivered right now
+impl From<&NotificationBuilder> for NotificationContent {
+    fn from(builder: &NotificationBuilder) -> Self {
+        Self {
+            title: builder.title.clone(),
+            subtitle: builder.subtitle.clone(),
+            body: match builder.body_markdown {
+                true => builder.body.as_deref().map(crate::markdown::to_plain_text),
+                false => builder.body.clone(),
+            },
+            category_id: builder.category_id.clone(),
+            delivered_at: Some(Utc::now()),
+        }
+    }
+}
+
+/// Key in the user info under which [NotificationBuilder::set_user_data] is stored
+const USER_DATA_KEY: &str = "user_notify.user_data";
+const DEDUPE_KEY: &str = "user_notify.dedupe_key";
+/// Delay of [NotificationBuilder::set_snooze] in milliseconds
+const SNOOZE_KEY: &str = "user_notify.snooze";
+const OPEN_URL_KEY: &str = "user_notify.open_url";
+const CATEGORY_KEY: &str = "user_notify.category_id";
+const THREAD_KEY: &str = "user_notify.thread_id";
+/// The number of items a notification stands for in [crate::summary::ThreadSummaryManager]
+pub(crate) const SUMMARY_COUNT_KEY: &str = "user_notify.summary_count";
+/// Marks the summary notifications of [crate::summary::ThreadSummaryManager]
+pub(crate) const SUMMARY_KEY: &str = "user_notify.summary";
+/// The keys in the user info that are set by other setters than [NotificationBuilder::set_user_info]
+const INTERNAL_KEYS: [&str; 8] = [
+    USER_DATA_KEY,
+    DEDUPE_KEY,
+    SNOOZE_KEY,
+    OPEN_URL_KEY,
+    CATEGORY_KEY,
+    THREAD_KEY,
+    SUMMARY_COUNT_KEY,
+    SUMMARY_KEY,
+];
+
+pub(crate) type ResponseCallback = Arc<dyn Fn(NotificationResponse) + Send + Sync + 'static>;
+
+/// The callback of [NotificationBuilder::on_response], the manager links it to the notification when it is sent.
+/// It can not be serialized, builders that are restored from json have none.
+#[derive(Clone, Default)]
+pub(crate) struct OnResponse(pub(crate) Option<ResponseCallback>);
+
+impl Debug for OnResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(if self.0.is_some() {
+            "Some(callback)"
+        } else {
+            "None"
+        })
+    }
+}
+
+/// Builders are equal when they share the same callback
+impl PartialEq for OnResponse {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.0, &other.0) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+// used by all backends except the noop one
+#[cfg_attr(
+    not(any(
+        feature = "mock",
+        all(target_os = "macos", feature = "macos"),
+        all(target_os = "windows", feature = "windows"),
+        all(target_arch = "wasm32", feature = "web"),
+        all(
+            any(
+                target_os = "linux",
+                target_os = "dragonfly",
+                target_os = "freebsd",
+                target_os = "openbsd",
+                target_os = "netbsd"
+            ),
+            any(feature = "linux-dbus", feature = "linux-portal")
+        )
+    )),
+    allow(dead_code)
+)]
+/// The active notification with the dedupe key of the builder, see [NotificationBuilder::set_dedupe_key]
+pub(crate) async fn find_duplicate<M: NotificationManager + ?Sized>(
+    manager: &M,
+    builder: &NotificationBuilder,
+) -> Result<Option<Box<dyn NotificationHandle>>, Error> {
+    let Some(dedupe_key) = builder.get_dedupe_key() else {
+        return Ok(None);
+    };
+    let duplicate = manager
+        .get_active_notifications()
+        .await?
+        .into_iter()
+        .find(|handle| {
+            handle
+                .get_stored_user_info()
+                .get(DEDUPE_KEY)
+                .map(String::as_str)
+                == Some(dedupe_key)
+        });
+    if let Some(duplicate) = &duplicate {
+        log::info!(
+            "not sending notification, {} with dedupe key {dedupe_key} is still active",
+            duplicate.get_id()
+        );
+    }
+    Ok(duplicate)
+}
+
+// used by all backends except the noop one
+#[cfg_attr(
+    not(any(
+        feature = "mock",
+        all(target_os = "macos", feature = "macos"),
+        all(target_os = "windows", feature = "windows"),
+        all(target_arch = "wasm32", feature = "web"),
+        all(
+            any(
+                target_os = "linux",
+                target_os = "dragonfly",
+                target_os = "freebsd",
+                target_os = "openbsd",
+                target_os = "netbsd"
+            ),
+            any(feature = "linux-dbus", feature = "linux-portal")
+        )
+    )),
+    allow(dead_code)
+)]
+/// Opens the url of [NotificationBuilder::on_click_open_url] when the notification itself was clicked
+pub(crate) fn open_url_on_click(response: &NotificationResponse) {
+    if !response.is_clicked() {
+        return;
+    }
+    let Some(url) = response.internal_value(OPEN_URL_KEY) else {
+        return;
+    };
+    log::info!(
+        "opening {url} for notification {}",
+        response.notification_id
+    );
+    if let Err(err) = open_url(url) {
+        log::error!("failed to open {url}: {err}");
+    }
+}
+
+#[cfg(all(target_arch = "wasm32", feature = "web"))]
+fn open_url(url: &str) -> Result<(), String> {
+    let window = web_sys::window().ok_or("there is no window in a service worker")?;
+    window
+        .open_with_url(url)
+        .map(|_| ())
+        .map_err(|err| format!("{err:?}"))
+}
+
+#[cfg(all(target_arch = "wasm32", not(feature = "web")))]
+fn open_url(url: &str) -> Result<(), String> {
+    Err(format!("no backend to open {url}"))
+}
+
+/// The toast opens the url with protocol activation, so the app does not get the click
+#[cfg(target_os = "windows")]
+fn open_url(_url: &str) -> Result<(), String> {
+    Ok(())
+}
+
+#[cfg(not(any(target_arch = "wasm32", target_os = "windows")))]
+fn open_url(url: &str) -> Result<(), String> {
+    let program = if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+    let mut child = std::process::Command::new(program)
+        .arg(url)
+        .spawn()
+        .map_err(|err| format!("failed to run {program}: {err}"))?;
+    // waits in the background, so the finished process does not stay around as zombie
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+fn user_data_from_user_info(user_info: &HashMap<String, String>) -> Option<serde_json::Value> {
+    let user_data = user_info.get(USER_DATA_KEY)?;
+    serde_json::from_str(user_data)
+        .inspect_err(|err| log::error!("failed to parse user data {user_data:?}: {err:?}"))
+        .ok()
+}
+
+/// The user info that the app set, the internal entries are only used by the crate
+fn without_internal_keys(user_info: &HashMap<String, String>) -> HashMap<String, String> {
+    user_info
+        .iter()
+        .filter(|(key, _)| !INTERNAL_KEYS.contains(&key.as_str()))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect()
+}
+
+/// The payload of [NotificationBuilder::set_user_info_typed],
+/// or without one the user info itself, without the keys of the other setters
+fn user_info_as<T: DeserializeOwned>(user_info: &HashMap<String, String>) -> Result<T, Error> {
+    let result = match user_info.get(USER_DATA_KEY) {
+        Some(user_data) => serde_json::from_str(user_data),
+        None => serde_json::from_value(serde_json::Value::Object(
+            user_info
+                .iter()
+                .filter(|(key, _)| !INTERNAL_KEYS.contains(&key.as_str()))
+                .map(|(key, value)| (key.clone(), serde_json::Value::String(value.clone())))
+                .collect(),
+        )),
+    };
+    result.map_err(Error::FailedToParseUserInfo)
+}
+
+// Handle to a sent notification
+pub trait NotificationHandle
+where
+    Self: Send + Sync + Debug,
+{
+    /// close the notification, removing it from the notification center if it was already delivered
+    ///
+    /// ## Platform specific:
+    /// - MacOS: [UNUserNotificationCenter.removeDeliveredNotifications](https://developer.apple.com/documentation/usernotifications/unusernotificationcenter/removedeliverednotifications(withidentifiers:))
+    /// - Windows: [ToastNotificationHistory.Remove](https://learn.microsoft.com/en-us/uwp/api/windows.ui.notifications.toastnotificationhistory.remove)
+    /// - Linux / XDG: [CloseNotification](https://specifications.freedesktop.org/notification-spec/latest/protocol.html#command-close-notification)
+    fn close(&self) -> Result<(), Error>;
+
+    fn get_id(&self) -> String;
+
+    /// The user info as it is stored with the notification,
+    /// including the entries that the crate adds for the setters of [NotificationBuilder], like the thread id
+    fn get_stored_user_info(&self) -> &HashMap<String, String>;
+
+    /// The user info of [NotificationBuilder::set_user_info], without the entries that the crate adds
+    fn get_user_info(&self) -> HashMap<String, String> {
+        without_internal_keys(self.get_stored_user_info())
+    }
+
+    /// The data that was set with [NotificationBuilder::set_user_data]
+    fn get_user_data(&self) -> Option<serde_json::Value> {
+        user_data_from_user_info(self.get_stored_user_info())
+    }
+
+    /// The thread id that was set with [NotificationBuilder::set_thread_id]
+    fn get_thread_id(&self) -> Option<&str>;
+
+    /// Title, body and other content of the notification,
+    /// useful to mirror the delivered notifications inside of the app
+    ///
+    /// ## Platform specific:
+    /// - Linux / XDG: only for notifications of the current session
+    fn get_content(&self) -> &NotificationContent;
+}
+
+/// What the user allowed for the app in the system settings, see [NotificationManager::get_notification_settings].
+/// `None` when the platform does not tell.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NotificationSettings {
+    /// Same as [NotificationManager::get_notification_permission_state]
+    pub authorized: bool,
+    /// How notifications appear while the app is not in the foreground
+    pub alert_style: Option<NotificationAlertStyle>,
+    /// Notifications play their sound
+    pub sound: Option<bool>,
+    /// The app icon shows the badge count
+    pub badge: Option<bool>,
+    /// Notifications are shown on the lock screen
+    pub lock_screen: Option<bool>,
+    /// Notifications are kept in the notification center
+    pub notification_center: Option<bool>,
+}
+
+/// See [NotificationSettings::alert_style]
+///
+/// https://developer.apple.com/documentation/usernotifications/unalertstyle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationAlertStyle {
+    /// No banner, the notification only goes to the notification center
+    None,
+    /// Banners that go away on their own
+    Banner,
+    /// Stays on screen until the user acts on it
+    Alert,
+}
+
+// https://developer.apple.com/documentation/usernotifications/unnotificationcontent/targetcontentidentifier
+// maybe offer a seperate api to catch notifications from previous sessions?
+// https://github.com/deltachat/deltachat-desktop/issues/2438
+
+#[async_trait]
+pub trait NotificationManager
+where
+    Self: Send + Sync + Debug,
+{
+    /// Needs to be called from main thread
+    async fn get_notification_permission_state(&self) -> Result<bool, crate::Error>;
+
+    /// Needs to be called from main thread
+    async fn first_time_ask_for_notification_permission(&self) -> Result<bool, Error>;
+
+    /// The notification settings of the app, like whether banners are turned off,
+    /// to explain to the user why notifications are not shown
+    ///
+    /// ## Platform specific:
+    /// - MacOS: from [UNNotificationSettings](https://developer.apple.com/documentation/usernotifications/unnotificationsettings),
+    ///   needs to be called from main thread
+    /// - Windows: whether toasts are enabled from [ToastNotifier.Setting](https://learn.microsoft.com/uwp/api/windows.ui.notifications.toastnotifier.setting)
+    /// - Other: only [NotificationSettings::authorized]
+    async fn get_notification_settings(&self) -> Result<NotificationSettings, Error> {
+        Ok(NotificationSettings {
+            authorized: self.get_notification_permission_state().await?,
+            ..Default::default()
+        })
+    }
+
+    /// Requests provisional authorization, notifications are then delivered quietly
+    /// to the notification center without asking the user first.
+    ///
+    /// ## Platform specific:
+    /// - MacOS: [UNAuthorizationOptionProvisional](https://developer.apple.com/documentation/usernotifications/unauthorizationoptions/provisional), needs to be called from main thread
+    /// - Other: same as [NotificationManager::first_time_ask_for_notification_permission]
+    async fn request_provisional_permission(&self) -> Result<bool, Error> {
+        self.first_time_ask_for_notification_permission().await
+    }
+
+    /// What the notification server supports, so the notification can be adapted to it
+    /// instead of losing buttons or markup silently
+    ///
+    /// ## Platform specific:
+    /// - Linux / XDG: [GetCapabilities](https://specifications.freedesktop.org/notification-spec/latest/protocol.html#command-get-capabilities)
+    ///   and [GetServerInformation](https://specifications.freedesktop.org/notification-spec/latest/protocol.html#command-get-server-information)
+    /// - MacOS: badges and critical alerts depend on the [notification settings](https://developer.apple.com/documentation/usernotifications/unnotificationsettings)
+    /// - Other: what the platform supports in general
+    ///
+    /// Defaults to nothing supported, so a manager only claims what it implements.
+    async fn capabilities(&self) -> Result<NotificationCapabilities, Error> {
+        Ok(NotificationCapabilities::default())
+    }
+
+    /// Whether do not disturb or a focus mode is on, so the app can show an in-app banner instead.
+    /// Fails with [Error::Unsupported] when the platform does not tell.
+    ///
+    /// ## Platform specific:
+    /// - MacOS: [INFocusStatusCenter](https://developer.apple.com/documentation/intents/infocusstatuscenter), macOS 12 and newer.
+    ///   Needs the communication notifications entitlement and `NSFocusStatusUsageDescription` in the Info.plist,
+    ///   the user is asked for the permission the first time, fails with [Error::PermissionDenied] if they decline
+    /// - Windows: [SHQueryUserNotificationState](https://learn.microsoft.com/en-us/windows/win32/api/shellapi/nf-shellapi-shqueryusernotificationstate),
+    ///   true during quiet hours, presentations and full screen apps
+    /// - Linux / XDG: the `Inhibited` property of notification daemons that have it, like KDE Plasma,
+    ///   the xdg portal does not tell
+    /// - Others: not supported
+    async fn is_do_not_disturb_active(&self) -> Result<bool, Error> {
+        Err(Error::Unsupported("do not disturb detection".to_owned()))
+    }
+
+    /// Sends the new state whenever do not disturb is turned on or off,
+    /// on the other platforms [NotificationManager::is_do_not_disturb_active] needs to be polled.
+    ///
+    /// ## Platform specific:
+    /// - Linux / XDG: changes of the `Inhibited` property of the notification daemon,
+    ///   nothing is sent when the daemon does not have it. The xdg portal does not tell
+    /// - Others: not supported
+    fn subscribe_do_not_disturb(&self) -> Result<tokio::sync::broadcast::Receiver<bool>, Error> {
+        Err(Error::Unsupported("do not disturb changes".to_owned()))
+    }
+
+    /// registers and initializes the notification handler and categories.
+    /// Set a function to handle user responses (clicking notification, closing it, clicking an action on it)
+    ///
+    /// Responses that arrive before this is called are buffered and passed to the handler here,
+    /// except the first one, which is kept for [NotificationManager::take_launch_response].
+    ///
+    /// ## Platform specific:
+    /// - MacOS: sets the UNUserNotificationCenterDelegate, if it was not already set when the manager was created on the main thread
+    fn register(
+        &self,
+        handler_callback: Box<dyn Fn(crate::NotificationResponse) + Send + Sync + 'static>,
+        categories: Vec<NotificationCategory>,
+    ) -> Result<(), Error>;
+
+    /// Replaces the handler from [NotificationManager::register], for example after a reload of the frontend.
+    ///
+    /// Fails with [Error::SettingHandler] when [NotificationManager::register] was not called before,
+    /// the responses are still sent as events and the url of [crate::NotificationBuilder::on_click_open_url] is still opened.
+    fn set_response_handler(
+        &self,
+        _handler_callback: Box<dyn Fn(crate::NotificationResponse) + Send + Sync + 'static>,
+    ) -> Result<(), Error> {
+        Err(Error::Unsupported(
+            "replacing the response handler".to_owned(),
+        ))
+    }
+
+    /// Removes the handler from [NotificationManager::register], responses are then only sent as events
+    /// until a new one is set with [NotificationManager::set_response_handler].
+    fn clear_response_handler(&self) -> Result<(), Error> {
+        self.set_response_handler(crate::response_handler::dropping())
+    }
+
+    /// The response to the notification that launched the app, so the app can open the right view on a cold start.
+    ///
+    /// This is the first response that arrived before [NotificationManager::register] was called,
+    /// it is not passed to the handler and can only be taken once.
+    ///
+    /// ## Platform specific:
+    /// - MacOS: the manager needs to be created on the main thread before the app finished launching,
+    ///   otherwise the delegate is set too late to receive the response
+    /// - Windows: the click that started the app through the `notification_protocol` or the COM activator
+    ///   (see [crate::windows::NotificationManagerWindows::register_com_activator])
+    /// - Linux: notifications can not launch the app, so this is always `None`
+    fn take_launch_response(&self) -> Option<NotificationResponse> {
+        None
+    }
+
+    /// Lifecycle events of all notifications, to mirror them for example in an in-app inbox.
+    /// Responses are sent as events too, before the handler from [NotificationManager::register] is called.
+    ///
+    /// Subscribers that fall behind miss events, they then receive [tokio::sync::broadcast::error::RecvError::Lagged].
+    ///
+    /// ## Platform specific:
+    /// - MacOS: scheduled notifications are delivered by the system, so they are not reported as presented.
+    /// - Windows: notifications that reached their expiration time are not reported
+    /// - Linux: the xdg portal does not report dismissals, except the expiration
+    fn subscribe_events(&self) -> tokio::sync::broadcast::Receiver<NotificationEvent> {
+        // receiver of a dropped sender, so it is closed right away
+        tokio::sync::broadcast::channel(1).1
+    }
+
+    /// Adds categories after [NotificationManager::register] was called,
+    /// for action sets that are only known later, categories with the same identifier are replaced.
+    ///
+    /// ## Platform specific:
+    /// - MacOS: calls [UNUserNotificationCenter.setNotificationCategories](https://developer.apple.com/documentation/usernotifications/unusernotificationcenter/setnotificationcategories(_:)) with all categories
+    fn add_categories(&self, categories: Vec<NotificationCategory>) -> Result<(), Error>;
+
+    /// Replaces all categories, so action sets of previous app versions don't linger.
+    ///
+    /// ## Platform specific:
+    /// - MacOS: [UNUserNotificationCenter.setNotificationCategories](https://developer.apple.com/documentation/usernotifications/unusernotificationcenter/setnotificationcategories(_:))
+    fn set_categories(&self, categories: Vec<NotificationCategory>) -> Result<(), Error>;
+
+    /// Replaces all categories with their action titles for `locale`, see [NotificationCategory::localized].
+    /// Call it again with the same categories when the language of the app changes.
+    fn set_categories_for_locale(
+        &self,
+        categories: Vec<NotificationCategory>,
+        locale: &str,
+    ) -> Result<(), Error> {
+        self.set_categories(
+            categories
+                .iter()
+                .map(|category| category.localized(locale))
+                .collect(),
+        )
+    }
+
+    /// Removes the category with this identifier, notifications that use it are shown without its actions.
+    fn remove_category(&self, identifier: &str) -> Result<(), Error>;
+
+    /// Like [NotificationManager::register], but delivers the user responses over a channel
+    /// instead of a callback, so they can be awaited from an async task:
+    ///
+    /// ```no_run
+    /// # async fn example(manager: std::sync::Arc<dyn user_notify::NotificationManager>) -> Result<(), user_notify::Error> {
+    /// let mut responses = manager.responses(vec![])?;
+    /// tokio::spawn(async move {
+    ///     while let Some(response) = responses.recv().await {
+    ///         println!("{response:?}");
+    ///     }
+    /// });
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Like [NotificationManager::register] this can only be called once.
+    fn responses(
+        &self,
+        categories: Vec<NotificationCategory>,
+    ) -> Result<tokio::sync::mpsc::UnboundedReceiver<crate::NotificationResponse>, Error> {
+        let (handler, rx) = crate::response_handler::channel();
+        self.register(handler, categories)?;
+        Ok(rx)
+    }
+
+    /// Removes all of your app's delivered notifications from Notification Center,
+    /// for example on logout or when switching accounts.
+    ///
+    /// ## Platform specific:
+    /// - MacOS: [UNUserNotificationCenter.removeAllDeliveredNotifications](https://developer.apple.com/documentation/usernotifications/unusernotificationcenter/removealldeliverednotifications())
+    /// - Windows: [ToastNotificationHistory.Clear](https://learn.microsoft.com/en-us/uwp/api/windows.ui.notifications.toastnotificationhistory.clear)
+    /// - Linux / XDG: closes every notification sent in the current session, notifications of previous sessions are not known
+    fn remove_all_delivered_notifications(&self) -> Result<(), Error>;
+
+    /// Removes specific delivered notifications by their id from Notification Center.
+    ///
+    /// Use [NotificationHandle::close] to remove a single notification via its handle.
+    ///
+    /// ## Platform specific:
+    /// - MacOS: [UNUserNotificationCenter.removeDeliveredNotifications](https://developer.apple.com/documentation/usernotifications/unusernotificationcenter/removedeliverednotifications(withidentifiers:))
+    /// - Windows: [ToastNotificationHistory.Remove](https://learn.microsoft.com/en-us/uwp/api/windows.ui.notifications.toastnotificationhistory.remove)
+    /// - Linux / XDG: [CloseNotification](https://specifications.freedesktop.org/notification-spec/latest/protocol.html#command-close-notification), only for notifications of the current session
+    fn remove_delivered_notifications(&self, ids: Vec<&str>) -> Result<(), Error>;
+
+    /// Removes all delivered notifications that have this thread id set with [NotificationBuilder::set_thread_id],
+    /// for example when the user opens the conversation the notifications belong to.
+    ///
+    /// Uses [NotificationManager::get_active_notifications] and [NotificationManager::remove_delivered_notifications],
+    /// so on Linux only notifications of the current session are removed.
+    ///
+    /// ## Platform specific:
+    /// - Windows: [ToastNotificationHistory.RemoveGroup](https://learn.microsoft.com/uwp/api/windows.ui.notifications.toastnotificationhistory.removegroup)
+    async fn dismiss_thread(&self, thread_id: &str) -> Result<(), Error> {
+        let ids: Vec<String> = self
+            .get_active_notifications()
+            .await?
+            .iter()
+            .filter(|n| n.get_thread_id() == Some(thread_id))
+            .map(|n| n.get_id())
+            .collect();
+        self.remove_delivered_notifications(ids.iter().map(String::as_str).collect())
+    }
+
+    /// The number of delivered notifications with this thread id, like the unread messages of a conversation.
+    ///
+    /// Uses [NotificationManager::get_active_notifications], so on Linux only notifications of the current session are counted.
+    async fn active_count_for_thread(&self, thread_id: &str) -> Result<usize, Error> {
+        Ok(self
+            .get_active_notifications()
+            .await?
+            .iter()
+            .filter(|n| n.get_thread_id() == Some(thread_id))
+            .count())
+    }
+
+    /// The distinct thread ids of the delivered notifications, sorted,
+    /// to go through the conversations with [NotificationManager::active_count_for_thread]
+    async fn active_thread_ids(&self) -> Result<Vec<String>, Error> {
+        let thread_ids: BTreeSet<String> = self
+            .get_active_notifications()
+            .await?
+            .iter()
+            .filter_map(|n| n.get_thread_id().map(str::to_owned))
+            .collect();
+        Ok(thread_ids.into_iter().collect())
+    }
+
+    /// Get all deliverd notifications from UNUserNotificationCenter that are still active.
+    ///
+    /// ## Platform specific:
+    /// - MacOS:
+    ///   - also includes notifications from previous sessions
+    ///   - [UNUserNotificationCenter.getDeliveredNotificationsWithCompletionHandler](https://developer.apple.com/documentation/usernotifications/unusernotificationcenter/getdeliverednotifications(completionhandler:))
+    /// - Others: TODO: implemented/emulated by keeping track of all notifications in memory
+    async fn get_active_notifications(&self) -> Result<Vec<Box<dyn NotificationHandle>>, Error>;
+
+    /// Shows notification and returns Notification handle,
+    /// which carries the id of the notification, its thread id and its user info
+    ///
+    /// Fails with [Error::InvalidNotification] ([crate::ErrorKind::InvalidContent]) before anything is sent
+    /// when [NotificationBuilder::validate] rejects the notification, like one without title
+    ///
+    /// With [NotificationBuilder::set_dedupe_key] it returns the handle of the active notification with the same key instead
+    async fn send_notification(
+        &self,
+        builder: NotificationBuilder,
+    ) -> Result<Box<dyn NotificationHandle>, Error>;
+
+    /// Replaces the content of a delivered notification, for example to update the progress of a long running task,
+    /// instead of showing a new notification.
+    ///
+    /// If the notification is not shown anymore, it is shown again.
+    ///
+    /// ## Platform specific:
+    /// - MacOS: adds a new request with the same identifier
+    /// - Windows: shows a toast with the same tag and group, which replaces the old one
+    /// - Linux / XDG: uses [replaces_id](https://specifications.freedesktop.org/notification-spec/latest/protocol.html#command-notify),
+    ///   only works for notifications of the current session
+    async fn update_notification(
+        &self,
+        id: &str,
+        builder: NotificationBuilder,
+    ) -> Result<Box<dyn NotificationHandle>, Error>;
+
+    /// Updates the progress bar of a notification that was sent with [NotificationBuilder::progress]
+    /// or [NotificationBuilder::set_progress], the title stays the same
+    ///
+    /// ## Platform specific:
+    /// - Windows: updates the data binding of the toast with [ToastNotifier.Update](https://learn.microsoft.com/en-us/uwp/api/windows.ui.notifications.toastnotifier.update)
+    /// - MacOS and Linux: replaces the notification like [NotificationManager::update_notification],
+    ///   only for notifications of the current session
+    /// - Others: not supported
+    async fn update_progress(
+        &self,
+        id: &str,
+        state: ProgressState,
+        status: &str,
+    ) -> Result<(), Error> {
+        log::debug!("not updating progress of {id} to {state:?} {status}");
+        Err(Error::Unsupported("progress updates".to_owned()))
+    }
+
+    /// Sets the number shown on the app icon, for example the unread message count.
+    ///
+    /// ## Platform specific:
+    /// - MacOS: [UNUserNotificationCenter.setBadgeCount](https://developer.apple.com/documentation/usernotifications/unusernotificationcenter/setbadgecount(_:withcompletionhandler:)), requires macOS 14
+    /// - Windows: [BadgeUpdateManager](https://learn.microsoft.com/en-us/uwp/api/windows.ui.notifications.badgeupdatemanager)
+    /// - Linux / XDG: **not supported**, the count is only remembered
+    /// - Others: not supported
+    async fn set_badge_count(&self, count: u32) -> Result<(), Error> {
+        log::debug!("not setting badge count {count}");
+        Err(Error::Unsupported("badges".to_owned()))
+    }
+
+    /// Returns the badge count that was last set with [NotificationManager::set_badge_count] in this session
+    async fn get_badge_count(&self) -> Result<u32, Error> {
+        Err(Error::Unsupported("badges".to_owned()))
+    }
+
+    /// Removes the badge from the app icon
+    async fn clear_badge(&self) -> Result<(), Error> {
+        self.set_badge_count(0).await
+    }
+
+    /// Schedules the notification to be shown at `at` and returns its id,
+    /// which can be used to cancel it with [NotificationManager::cancel_scheduled_notification].
+    ///
+    /// ## Platform specific:
+    /// - MacOS: [UNTimeIntervalNotificationTrigger](https://developer.apple.com/documentation/usernotifications/untimeintervalnotificationtrigger)
+    /// - Windows: [ScheduledToastNotification](https://learn.microsoft.com/en-us/uwp/api/windows.ui.notifications.scheduledtoastnotification)
+    /// - Others: emulated with a timer in this process, so the notification is lost when the app quits before it is delivered
+    ///
+    /// Fails with [Error::Unsupported] when [NotificationCapabilities::scheduling] is false.
+    async fn schedule_notification(
+        &self,
+        builder: NotificationBuilder,
+        at: DateTime<Utc>,
+    ) -> Result<String, Error> {
+        log::debug!(
+            "not scheduling notification at {at}: {:?}",
+            NotificationContent::from(&builder)
+        );
+        Err(Error::Unsupported("scheduling".to_owned()))
+    }
+
+    /// Shows the notification repeatedly, the first time one `interval` from now, and returns its id,
+    /// which can be used to stop the repetition with [NotificationManager::cancel_scheduled_notification].
+    ///
+    /// Each delivery replaces the previous one.
+    ///
+    /// ## Platform specific:
+    /// - MacOS: repeating [UNTimeIntervalNotificationTrigger](https://developer.apple.com/documentation/usernotifications/untimeintervalnotificationtrigger), the interval needs to be at least 1 minute
+    /// - Others: emulated with a timer in this process, so it stops when the app quits
+    async fn schedule_repeating_notification(
+        &self,
+        builder: NotificationBuilder,
+        interval: RepeatInterval,
+    ) -> Result<String, Error> {
+        log::debug!(
+            "not scheduling repeating notification {interval:?}: {:?}",
+            NotificationContent::from(&builder)
+        );
+        Err(Error::Unsupported("scheduling".to_owned()))
+    }
+
+    /// Shows the notification at the next time the [CalendarTrigger] matches, and at every later match if it repeats,
+    /// and returns its id, which can be used with [NotificationManager::cancel_scheduled_notification].
+    ///
+    /// Each delivery replaces the previous one.
+    /// Fails with [Error::InvalidNotification] when the trigger never matches, like on February 30.
+    ///
+    /// ## Platform specific:
+    /// - MacOS: [UNCalendarNotificationTrigger](https://developer.apple.com/documentation/usernotifications/uncalendarnotificationtrigger)
+    /// - Others: emulated with a timer in this process, so it stops when the app quits,
+    ///   the times are computed in the time zone of the system when the timer is set
+    async fn schedule_calendar_notification(
+        &self,
+        builder: NotificationBuilder,
+        trigger: CalendarTrigger,
+    ) -> Result<String, Error> {
+        log::debug!(
+            "not scheduling calendar notification {trigger:?}: {:?}",
+            NotificationContent::from(&builder)
+        );
+        Err(Error::Unsupported("scheduling".to_owned()))
+    }
+
+    /// Shows the notification when the device enters or leaves the region of the [LocationTrigger],
+    /// and returns its id, which can be used with [NotificationManager::cancel_scheduled_notification].
+    ///
+    /// The app needs the permission to use the location, request it with CoreLocation before.
+    /// Fails with [Error::Unsupported] when [NotificationCapabilities::location_triggers] is false.
+    ///
+    /// ## Platform specific:
+    /// - MacOS: [UNLocationNotificationTrigger](https://developer.apple.com/documentation/usernotifications/unlocationnotificationtrigger)
+    ///   with the `macos-location` feature, Apple only provides it on iOS, Mac Catalyst and visionOS
+    /// - Others: not supported
+    async fn schedule_location_notification(
+        &self,
+        builder: NotificationBuilder,
+        trigger: LocationTrigger,
+    ) -> Result<String, Error> {
+        log::debug!(
+            "not scheduling location notification {trigger:?}: {:?}",
+            NotificationContent::from(&builder)
+        );
+        Err(Error::Unsupported("location triggers".to_owned()))
+    }
+
+    /// Cancels a notification that was scheduled with [NotificationManager::schedule_notification],
+    /// [NotificationManager::schedule_repeating_notification], [NotificationManager::schedule_calendar_notification]
+    /// or [NotificationManager::schedule_location_notification] and was not delivered yet.
+    ///
+    /// Fails with [Error::NotificationNotFound] when no notification with this id is pending.
+    ///
+    /// ## Platform specific:
+    /// - MacOS: [UNUserNotificationCenter.removePendingNotificationRequests](https://developer.apple.com/documentation/usernotifications/unusernotificationcenter/removependingnotificationrequests(withidentifiers:)),
+    ///   which does not report unknown ids, so it does not fail for them
+    fn cancel_scheduled_notification(&self, id: &str) -> Result<(), Error> {
+        log::debug!("not cancelling scheduled notification {id}");
+        Err(Error::Unsupported("scheduling".to_owned()))
+    }
+}
+
+/// See [NotificationBuilder::set_foreground_presentation]
+///
+/// https://developer.apple.com/documentation/usernotifications/unnotificationpresentationoptions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ForegroundPresentation {
+    /// Show the popup
+    pub banner: bool,
+    /// Add the notification to the notification center
+    pub list: bool,
+    /// Play the sound of the notification
+    pub sound: bool,
+    /// Update the badge of the app icon
+    pub badge: bool,
+}
+
+impl ForegroundPresentation {
+    /// The notification is not presented while the app is frontmost
+    pub const NONE: Self = Self {
+        banner: false,
+        list: false,
+        sound: false,
+        badge: false,
+    };
+}
+
+impl Default for ForegroundPresentation {
+    fn default() -> Self {
+        Self {
+            banner: true,
+            list: false,
+            sound: true,
+            badge: true,
+        }
+    }
+}
+
+/// See [NotificationBuilder::set_priority]
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub enum NotificationPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+/// See [NotificationBuilder::set_interruption_level]
+///
+/// https://developer.apple.com/documentation/usernotifications/unnotificationinterruptionlevel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InterruptionLevel {
+    /// Added to the notification list without lighting up the screen or playing a sound
+    Passive,
+    /// Presented immediately, this is the default
+    Active,
+    /// Presented immediately and may break through focus modes and notification summaries
+    TimeSensitive,
+    /// Presented immediately, even when the device is muted or in a focus mode
+    Critical,
+}
+
+/// See [NotificationBuilder::set_sound]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Sound {
+    /// The usual notification sound of the platform
+    Default,
+    /// No sound, the notification is still shown
+    None,
+    /// A sound from the catalog, each platform plays a similar sound of its own
+    System(SystemSound),
+    /// A platform specific name, see [NotificationBuilder::sound]
+    Named(String),
+    /// A sound file, see [NotificationBuilder::set_sound_file]
+    File(PathBuf),
+    /// Rings until the notification is dismissed, for alarms and incoming calls, see [LoopingSound]
+    Looping(LoopingSound),
+}
+
+/// The looping sounds of the [Windows catalog](https://learn.microsoft.com/uwp/schemas/tiles/toastschema/element-audio),
+/// see [Sound::Looping]
+///
+/// On Windows the toast gets `loop="true"`, a long duration and, if it has no [NotificationScenario],
+/// the alarm or incoming call scenario, which keeps it on screen until it is dismissed.
+/// Windows only shows these scenarios with their special look when the toast has at least one action.
+///
+/// The other platforms can not loop sounds, they play the [SystemSound::Alarm] or [SystemSound::Call] once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LoopingSound {
+    Alarm,
+    Alarm2,
+    Alarm3,
+    Alarm4,
+    Alarm5,
+    Alarm6,
+    Alarm7,
+    Alarm8,
+    Alarm9,
+    Alarm10,
+    Call,
+    Call2,
+    Call3,
+    Call4,
+    Call5,
+    Call6,
+    Call7,
+    Call8,
+    Call9,
+    Call10,
+}
+
+impl LoopingSound {
+    /// The sound that the platforms without looping sounds play once
+    pub fn system_sound(self) -> SystemSound {
+        if self.is_call() {
+            SystemSound::Call
+        } else {
+            SystemSound::Alarm
+        }
+    }
+
+    pub(crate) fn is_call(self) -> bool {
+        matches!(
+            self,
+            Self::Call
+                | Self::Call2
+                | Self::Call3
+                | Self::Call4
+                | Self::Call5
+                | Self::Call6
+                | Self::Call7
+                | Self::Call8
+                | Self::Call9
+                | Self::Call10
+        )
+    }
+}
+
+/// Sounds that every platform has in some form, see [Sound::System]
+///
+/// | | MacOS | Windows | Linux / XDG |
+/// |---|---|---|---|
+/// | Message | Tink | Notification.IM | message-new-instant |
+/// | Mail | Glass | Notification.Mail | message-new-email |
+/// | Reminder | Ping | Notification.Reminder | dialog-information |
+/// | Alarm | Sosumi | Notification.Looping.Alarm | alarm-clock-elapsed |
+/// | Call | Submarine | Notification.Looping.Call | phone-incoming-call |
+///
+/// On MacOS these are the sounds from `/System/Library/Sounds`, which are copied like [NotificationBuilder::set_sound_file].
+/// On Windows the looping sounds only loop with the alarm and incoming call [NotificationScenario].
+/// Linux plays them from the sound theme, if the notification daemon supports sounds.
+/// The web notifications api has no sounds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SystemSound {
+    /// A new chat message
+    Message,
+    /// A new email
+    Mail,
+    /// A reminder for an appointment or a task
+    Reminder,
+    /// An alarm clock or timer that went off
+    Alarm,
+    /// An incoming call
+    Call,
+}
+
+/// See [NotificationManager::capabilities]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NotificationCapabilities {
+    /// Buttons from [NotificationCategoryAction] are shown
+    pub actions: bool,
+    /// The body may contain simple markup like `<b>`
+    pub body_markup: bool,
+    /// Notifications stay in a notification center after their popup is gone
+    pub persistence: bool,
+    /// Replies can be typed into the notification,
+    /// on MacOS, Windows and KDE Plasma [NotificationCategoryAction::TextInputAction] shows a text field.
+    /// Other notification daemons on Linux show the text input as a plain button that opens the app.
+    pub inline_reply: bool,
+    /// The text field of a [NotificationCategoryAction::TextInputAction] is pre-filled with its `default_text`
+    pub input_default_text: bool,
+    /// Images from [NotificationBuilder::set_image] are shown
+    pub attachments: bool,
+    /// Movies and audio files from [NotificationBuilder::set_attachment] can be played
+    pub media_attachments: bool,
+    /// Animated GIF images play, otherwise their first frame is shown
+    pub animated_images: bool,
+    /// The largest image file in bytes, [NotificationBuilder::validate] rejects bigger images.
+    /// For GIFs without [NotificationCapabilities::animated_images] it applies to their first frame.
+    pub max_image_size: Option<u64>,
+    /// Images from [NotificationBuilder::set_image_url] are loaded,
+    /// by the platform or by [crate::remote_image::RemoteImageManager]
+    pub remote_images: bool,
+    /// [NotificationBuilder::set_progress] shows a real progress bar, instead of text in the body
+    pub progress: bool,
+    /// [NotificationManager::schedule_notification] is supported
+    pub scheduling: bool,
+    /// [NotificationManager::schedule_location_notification] is supported
+    pub location_triggers: bool,
+    /// [NotificationManager::set_badge_count] shows a badge on the app icon
+    pub badges: bool,
+    /// [InterruptionLevel::Critical] breaks through mute and focus modes
+    pub critical_alerts: bool,
+    /// Notifications with the same [NotificationBuilder::set_thread_id] are grouped and summarized by the system,
+    /// otherwise [crate::summary::ThreadSummaryManager] can collapse them
+    pub thread_summaries: bool,
+    /// The capabilities as reported by the notification server, only on Linux
+    pub raw: Vec<String>,
+    /// Only on Linux
+    pub server: Option<NotificationServerInformation>,
+}
+
+impl NotificationCapabilities {
+    // used by the notification daemon backend
+    #[cfg_attr(
+        not(any(all(
+            any(
+                target_os = "linux",
+                target_os = "dragonfly",
+                target_os = "freebsd",
+                target_os = "openbsd",
+                target_os = "netbsd"
+            ),
+            feature = "linux-dbus"
+        ))),
+        allow(dead_code)
+    )]
+    /// Parses the capabilities from [GetCapabilities](https://specifications.freedesktop.org/notification-spec/latest/protocol.html#command-get-capabilities)
+    pub(crate) fn from_xdg(
+        raw: Vec<String>,
+        server: Option<NotificationServerInformation>,
+    ) -> Self {
+        let has = |capability: &str| raw.iter().any(|c| c == capability);
+        Self {
+            actions: has("actions"),
+            body_markup: has("body-markup"),
+            persistence: has("persistence"),
+            inline_reply: has("inline-reply"),
+            input_default_text: false,
+            // there is no capability for the image hints, servers that do not support them ignore them
+            attachments: true,
+            media_attachments: false,
+            // decoded by us or the server, only the first frame is sent
+            animated_images: false,
+            max_image_size: None,
+            remote_images: false,
+            // there is no capability for the "value" hint, the daemons that ignore it get the progress as text
+            progress: server.as_ref().is_some_and(renders_value_hint),
+            // done by our scheduler
+            scheduling: true,
+            location_triggers: false,
+            badges: false,
+            critical_alerts: false,
+            thread_summaries: false,
+            raw,
+            server,
+        }
+    }
+}
+
+// used by the notification daemon backend
+#[cfg_attr(
+    not(any(all(
+        any(
+            target_os = "linux",
+            target_os = "dragonfly",
+            target_os = "freebsd",
+            target_os = "openbsd",
+            target_os = "netbsd"
+        ),
+        feature = "linux-dbus"
+    ))),
+    allow(dead_code)
+)]
+/// The daemons known to draw a progress bar for the "value" hint
+fn renders_value_hint(server: &NotificationServerInformation) -> bool {
+    ["dunst", "Xfce Notify Daemon", "SwayNotificationCenter"].contains(&server.name.as_str())
+}
+
+/// Answer of [GetServerInformation](https://specifications.freedesktop.org/notification-spec/latest/protocol.html#command-get-server-information)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NotificationServerInformation {
+    /// Like "gnome-shell" or "Plasma"
+    pub name: String,
+    pub vendor: String,
+    pub version: String,
+    /// Version of the notification spec the server implements
+    pub spec_version: String,
+}
+
+/// See [NotificationBuilder::set_urgency]
+///
+/// https://specifications.freedesktop.org/notification-spec/latest/urgency-levels.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NotificationUrgency {
+    Low,
+    Normal,
+    /// Stays on screen until the user dismisses it
+    Critical,
+}
+
+impl NotificationUrgency {
+    /// The closest interruption level, for platforms without urgency
+    pub fn interruption_level(self) -> InterruptionLevel {
+        match self {
+            NotificationUrgency::Low => InterruptionLevel::Passive,
+            NotificationUrgency::Normal => InterruptionLevel::Active,
+            NotificationUrgency::Critical => InterruptionLevel::TimeSensitive,
+        }
+    }
+}
+
+/// See [NotificationBuilder::set_scenario]
+///
+/// https://learn.microsoft.com/uwp/schemas/tiles/toastschema/element-toast
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NotificationScenario {
+    /// Stays on screen and loops its sound until the user dismisses it
+    Alarm,
+    /// Stays on screen until the user dismisses it
+    Reminder,
+    /// Stays on screen, loops a ringtone and gets a special layout for incoming calls
+    IncomingCall,
+    /// Important notification that may break through do not disturb
+    Urgent,
+}
+
+impl NotificationScenario {
+    /// The closest interruption level, for platforms without scenarios
+    pub fn interruption_level(self) -> InterruptionLevel {
+        match self {
+            NotificationScenario::Alarm
+            | NotificationScenario::Reminder
+            | NotificationScenario::IncomingCall => InterruptionLevel::TimeSensitive,
+            NotificationScenario::Urgent => InterruptionLevel::Critical,
+        }
+    }
+}
+
+/// How often a notification scheduled with [NotificationManager::schedule_repeating_notification] is repeated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RepeatInterval {
+    /// every n minutes
+    Minutes(u32),
+    /// every n hours
+    Hours(u32),
+    Daily,
+    Weekly,
+}
+
+impl RepeatInterval {
+    pub fn as_duration(&self) -> std::time::Duration {
+        const MINUTE: u64 = 60;
+        const HOUR: u64 = 60 * MINUTE;
+        const DAY: u64 = 24 * HOUR;
+        std::time::Duration::from_secs(match self {
+            RepeatInterval::Minutes(minutes) => *minutes as u64 * MINUTE,
+            RepeatInterval::Hours(hours) => *hours as u64 * HOUR,
+            RepeatInterval::Daily => DAY,
+            RepeatInterval::Weekly => 7 * DAY,
+        })
+    }
+}
+
+/// When a notification scheduled with [NotificationManager::schedule_calendar_notification] is shown,
+/// by date components in the local time zone, like every day at 09:00 or every Monday at 08:30:
+///
+/// ```
+/// use user_notify::CalendarTrigger;
+///
+/// let every_morning = CalendarTrigger::new(9, 0);
+/// let every_monday = CalendarTrigger::new(8, 30).set_weekday(chrono::Weekday::Mon);
+/// let new_year = CalendarTrigger::new(0, 0).set_month(1).set_day(1).set_repeats(false);
+/// ```
+///
+/// A notification is only shown at times that match all components that are set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CalendarTrigger {
+    /// 0 to 23
+    pub hour: u32,
+    /// 0 to 59
+    pub minute: u32,
+    pub weekday: Option<Weekday>,
+    /// Day of the month, 1 to 31
+    pub day: Option<u32>,
+    /// 1 to 12
+    pub month: Option<u32>,
+    /// Whether it is shown at every match or only at the next one
+    pub repeats: bool,
+}
+
+impl CalendarTrigger {
+    /// Every day at this time, until more components are set
+    pub fn new(hour: u32, minute: u32) -> Self {
+        Self {
+            hour,
+            minute,
+            weekday: None,
+            day: None,
+            month: None,
+            repeats: true,
+        }
+    }
+
+    pub fn set_weekday(mut self, weekday: Weekday) -> Self {
+        self.weekday = Some(weekday);
+        self
+    }
+
+    pub fn set_day(mut self, day: u32) -> Self {
+        self.day = Some(day);
+        self
+    }
+
+    pub fn set_month(mut self, month: u32) -> Self {
+        self.month = Some(month);
+        self
+    }
+
+    /// Repeating triggers are shown at every match, the default
+    pub fn set_repeats(mut self, repeats: bool) -> Self {
+        self.repeats = repeats;
+        self
+    }
+
+    /// The first time after `after` at which the trigger matches in the local time zone,
+    /// `None` if it never matches.
+    /// Days on which the time does not exist because of a daylight saving time change are skipped.
+    pub fn next_fire_time(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let time = NaiveTime::from_hms_opt(self.hour, self.minute, 0)?;
+        let mut date = after.with_timezone(&Local).date_naive();
+        // 8 years contain at least one February 29
+        for _ in 0..8 * 366 {
+            if self.weekday.is_none_or(|weekday| date.weekday() == weekday)
+                && self.day.is_none_or(|day| date.day() == day)
+                && self.month.is_none_or(|month| date.month() == month)
+                && let Some(fire_time) = Local.from_local_datetime(&date.and_time(time)).earliest()
+                && fire_time.with_timezone(&Utc) > after
+            {
+                return Some(fire_time.with_timezone(&Utc));
+            }
+            date = date.succ_opt()?;
+        }
+        None
+    }
+
+    /// [CalendarTrigger::next_fire_time] from now, fails when the trigger never matches
+    pub(crate) fn first_fire_time(&self) -> Result<DateTime<Utc>, Error> {
+        self.next_fire_time(Utc::now()).ok_or_else(|| {
+            Error::InvalidNotification(format!("calendar trigger {self:?} never matches"))
+        })
+    }
+}
+
+/// Region for [NotificationManager::schedule_location_notification]
+///
+/// ```
+/// // shown every time the device arrives within 100 meters of the job site
+/// let trigger = user_notify::LocationTrigger::new(52.5163, 13.3777, 100.0).set_repeats(true);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LocationTrigger {
+    pub latitude: f64,
+    pub longitude: f64,
+    /// In meters
+    pub radius: f64,
+    pub notify_on_entry: bool,
+    pub notify_on_exit: bool,
+    /// Whether it is shown every time the region is entered or left, or only the first time
+    pub repeats: bool,
+}
+
+impl LocationTrigger {
+    /// Shown once when the device enters the region
+    pub fn new(latitude: f64, longitude: f64, radius: f64) -> Self {
+        Self {
+            latitude,
+            longitude,
+            radius,
+            notify_on_entry: true,
+            notify_on_exit: false,
+            repeats: false,
+        }
+    }
+
+    pub fn set_notify_on_entry(mut self, notify_on_entry: bool) -> Self {
+        self.notify_on_entry = notify_on_entry;
+        self
+    }
+
+    pub fn set_notify_on_exit(mut self, notify_on_exit: bool) -> Self {
+        self.notify_on_exit = notify_on_exit;
+        self
+    }
+
+    pub fn set_repeats(mut self, repeats: bool) -> Self {
+        self.repeats = repeats;
+        self
+    }
+}
+
+/// Emmited when user clicked on a notification
+///
+/// Besides the fields, [NotificationResponse::get_thread_id], [NotificationResponse::get_category_id]
+/// and [NotificationResponse::action_id] tell which notification and button it was.
+///
+/// ## Platform-specific
+///
+/// - **macOS**: https://developer.apple.com/documentation/usernotifications/unusernotificationcenterdelegate/usernotificationcenter(_:didreceive:withcompletionhandler:)?language=objc
+/// - **Windows**: toast activation and dismissal, text input is not supported yet.
+/// - **Linux**: `ActionInvoked` and `NotificationClosed` signals of the notification daemon or `ActionInvoked` of the portal, text input is not supported.
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq)]
+pub struct NotificationResponse {
+    /// id of the notification that was assigned by the system
+    pub notification_id: String,
+    pub action: NotificationResponseAction,
+    /// The text that the user typed in as reponse,
+    /// or the identifier of the option that was picked in a [NotificationCategoryAction::SelectionInputAction]
+    ///
+    /// corresponds to [UNTextInputNotificationResponse.userText](https://developer.apple.com/documentation/usernotifications/untextinputnotificationresponse/usertext?language=objc)
+    pub user_text: Option<String>,
+
+    /// The user info of [NotificationBuilder::set_user_info], without the entries that the crate adds
+    pub user_info: HashMap<String, String>,
+
+    /// The entries of the user info that the crate adds for the setters of [NotificationBuilder], like the thread id,
+    /// moved out of [NotificationResponse::user_info] by [NotificationResponse::split_internal_info]
+    pub(crate) internal_info: HashMap<String, String>,
+
+    /// Why the notification was closed, set for [NotificationResponseAction::Dismiss]
+    ///
+    /// ## Platform specific:
+    /// - MacOS and Windows: only dismissals by the user are reported
+    /// - Linux / XDG: from the [NotificationClosed](https://specifications.freedesktop.org/notification-spec/latest/protocol.html#signal-notification-closed) signal
+    pub dismiss_reason: Option<NotificationDismissReason>,
+
+    /// When the notification was shown, like [NotificationContent::delivered_at]
+    ///
+    /// ## Platform specific:
+    /// - MacOS: the [date](https://developer.apple.com/documentation/usernotifications/unnotification/date) of the notification
+    /// - Windows: not for clicks that arrive through the COM activator or the `notification_protocol`
+    /// - Linux and Web: not for notifications of an earlier run of the app
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+impl NotificationResponse {
+    /// Creates a response, for custom [NotificationManager] implementations that need to call the handler
+    ///
+    /// A [NotificationResponseAction::Dismiss] is treated as dismissed by the user
+    pub fn new(
+        notification_id: String,
+        action: NotificationResponseAction,
+        user_text: Option<String>,
+        user_info: HashMap<String, String>,
+    ) -> Self {
+        let dismiss_reason = (action == NotificationResponseAction::Dismiss)
+            .then_some(NotificationDismissReason::DismissedByUser);
+        Self {
+            notification_id,
+            action,
+            user_text,
+            user_info,
+            internal_info: HashMap::new(),
+            dismiss_reason,
+            delivered_at: None,
+        }
+        .split_internal_info()
+    }
+
+    /// Moves the entries that the crate adds out of [NotificationResponse::user_info],
+    /// before the response is passed to the app
+    pub(crate) fn split_internal_info(mut self) -> Self {
+        for key in INTERNAL_KEYS {
+            if let Some(value) = self.user_info.remove(key) {
+                self.internal_info.insert(key.to_owned(), value);
+            }
+        }
+        self
+    }
+
+    /// The user info with the entries that the crate adds, like it is stored with the notification
+    pub(crate) fn stored_user_info(&self) -> HashMap<String, String> {
+        let mut user_info = self.user_info.clone();
+        user_info.extend(self.internal_info.clone());
+        user_info
+    }
+
+    fn internal_value(&self, key: &str) -> Option<&str> {
+        self.internal_info
+            .get(key)
+            .or_else(|| self.user_info.get(key))
+            .map(String::as_str)
+    }
+
+    /// See [NotificationResponse::delivered_at]
+    pub fn set_delivered_at(mut self, delivered_at: Option<DateTime<Utc>>) -> Self {
+        self.delivered_at = delivered_at;
+        self
+    }
+
+    /// True when the user clicked on the notification itself
+    pub fn is_clicked(&self) -> bool {
+        self.action == NotificationResponseAction::Default
+    }
+
+    /// True when the user closed the notification
+    pub fn is_dismissed(&self) -> bool {
+        self.action == NotificationResponseAction::Dismiss
+    }
+
+    /// The data that was set with [NotificationBuilder::set_user_data]
+    pub fn get_user_data(&self) -> Option<serde_json::Value> {
+        user_data_from_user_info(&self.stored_user_info())
+    }
+
+    /// Deserializes the payload of [NotificationBuilder::set_user_info_typed].
+    /// Without one the user info of [NotificationBuilder::set_user_info] is deserialized,
+    /// so `T` can also be a struct with string fields.
+    ///
+    /// Fails with [Error::FailedToParseUserInfo] when it does not match `T`.
+    pub fn user_info_as<T: DeserializeOwned>(&self) -> Result<T, Error> {
+        user_info_as(&self.stored_user_info())
+    }
+
+    /// The category that was set with [NotificationBuilder::set_category_id]
+    pub fn get_category_id(&self) -> Option<&str> {
+        self.internal_value(CATEGORY_KEY)
+    }
+
+    /// The thread that was set with [NotificationBuilder::set_thread_id]
+    pub fn get_thread_id(&self) -> Option<&str> {
+        self.internal_value(THREAD_KEY)
+    }
+
+    /// The identifier of the [NotificationCategoryAction] the user selected,
+    /// `None` when the notification was clicked or dismissed
+    pub fn action_id(&self) -> Option<&str> {
+        match &self.action {
+            NotificationResponseAction::Other(identifier) => Some(identifier),
+            _ => None,
+        }
+    }
+
+    /// The identifier of the [NotificationCategoryAction::TextInputAction] and the text the user typed in
+    pub fn text_input(&self) -> Option<(&str, &str)> {
+        Some((self.action_id()?, self.user_text.as_deref()?))
+    }
+
+    /// Like [NotificationResponse::text_input], as owned value that can be passed on to the chat code.
+    /// An empty reply is returned as well, it is up to the app to ignore it.
+    pub fn text_reply(&self) -> Option<TextReply> {
+        let (action_id, text) = self.text_input()?;
+        Some(TextReply {
+            action_id: action_id.to_owned(),
+            text: text.to_owned(),
+        })
+    }
+
+    /// The identifier of the [NotificationCategoryAction::SelectionInputAction] and of the option the user picked
+    pub fn selection(&self) -> Option<(&str, &str)> {
+        self.text_input()
+    }
+
+    /// What the user did, to match on instead of the action identifiers
+    ///
+    /// ```
+    /// use user_notify::{NotificationResponse, ResponseKind};
+    ///
+    /// fn handle(response: NotificationResponse) {
+    ///     match response.kind() {
+    ///         ResponseKind::Clicked { notification_id, .. } => println!("open {notification_id}"),
+    ///         ResponseKind::TextInput { text, .. } => println!("reply {text}"),
+    ///         ResponseKind::ActionButton { action_id, .. } => println!("button {action_id}"),
+    ///         ResponseKind::Dismissed { .. } => {}
+    ///     }
+    /// }
+    /// ```
+    pub fn kind(&self) -> ResponseKind {
+        let notification_id = self.notification_id.clone();
+        let user_info = self.user_info.clone();
+        match (&self.action, &self.user_text) {
+            (NotificationResponseAction::Default, _) => ResponseKind::Clicked {
+                notification_id,
+                user_info,
+            },
+            (NotificationResponseAction::Dismiss, _) => ResponseKind::Dismissed {
+                notification_id,
+                user_info,
+            },
+            (NotificationResponseAction::Other(action_id), Some(text)) => ResponseKind::TextInput {
+                notification_id,
+                action_id: action_id.clone(),
+                text: text.clone(),
+                user_info,
+            },
+            (NotificationResponseAction::Other(action_id), None) => ResponseKind::ActionButton {
+                notification_id,
+                action_id: action_id.clone(),
+                user_info,
+            },
+        }
+    }
+
+    // used by the platform backends without the windows one
+    #[cfg_attr(
+        not(any(
+            all(target_os = "macos", feature = "macos"),
+            all(target_arch = "wasm32", feature = "web"),
+            all(
+                any(
+                    target_os = "linux",
+                    target_os = "dragonfly",
+                    target_os = "freebsd",
+                    target_os = "openbsd",
+                    target_os = "netbsd"
+                ),
+                any(feature = "linux-dbus", feature = "linux-portal")
+            )
+        )),
+        allow(dead_code)
+    )]
+    /// Splits the identifiers of the buttons created by [NotificationCategoryAction::expand_selection]
+    /// into the identifier of the selection action and the picked option
+    pub(crate) fn split_selection(mut self) -> Self {
+        if let NotificationResponseAction::Other(identifier) = &self.action
+            && let Some((identifier, option)) = identifier.split_once(SELECTION_OPTION_SEPARATOR)
+        {
+            self.user_text = Some(option.to_owned());
+            self.action = NotificationResponseAction::Other(identifier.to_owned());
+        }
+        self
+    }
+}
+
+/// See [NotificationResponse::kind]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResponseKind {
+    /// The user clicked on the notification itself
+    Clicked {
+        notification_id: String,
+        user_info: HashMap<String, String>,
+    },
+    /// The user clicked the button of a [NotificationCategoryAction::Action]
+    ActionButton {
+        notification_id: String,
+        action_id: String,
+        user_info: HashMap<String, String>,
+    },
+    /// The user sent the text of a [NotificationCategoryAction::TextInputAction],
+    /// or picked an option of a [NotificationCategoryAction::SelectionInputAction]
+    TextInput {
+        notification_id: String,
+        action_id: String,
+        text: String,
+        user_info: HashMap<String, String>,
+    },
+    /// The user closed the notification, see [NotificationResponse::dismiss_reason]
+    Dismissed {
+        notification_id: String,
+        user_info: HashMap<String, String>,
+    },
+}
+
+/// What the user typed into a [NotificationCategoryAction::TextInputAction], see [NotificationResponse::text_reply]
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextReply {
+    /// The identifier of the text input action
+    pub action_id: String,
+    pub text: String,
+}
+
+/// See [NotificationResponse::dismiss_reason]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationDismissReason {
+    /// The user closed the notification
+    DismissedByUser,
+    /// The notification timed out
+    Expired,
+    /// The notification was closed by the app, for example with [NotificationHandle::close]
+    ClosedByApp,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NotificationResponseAction {
+    /// When user clicks on the notification
+    ///
+    /// ## Platform Specific
+    /// - MacOS: corresponds to [UNNotificationDefaultActionIdentifier](https://developer.apple.com/documentation/usernotifications/unnotificationdefaultactionidentifier?language=objc)
     Default,
-    /// When user closes the notification
+    /// When user closes the notification, never sent for a click on the notification itself.
+    /// [NotificationResponse::dismiss_reason] tells why it was closed.
     ///
     /// ## Platform Specific
-    /// - MacOS: corresponds to [UNNotificationDismissActionIdentifier](https://developer.apple.com/documentation/usernotifications/unnotificationdismissactionidentifier?language=objc)
+    /// - MacOS: corresponds to [UNNotificationDismissActionIdentifier](https://developer.apple.com/documentation/usernotifications/unnotificationdismissactionidentifier?language=objc),
+    ///   every notification gets a category with [UNNotificationCategoryOptionCustomDismissAction](https://developer.apple.com/documentation/usernotifications/unnotificationcategoryoptions/customdismissaction) for it
+    /// - Linux / XDG: the xdg portal only reports the expiration
     Dismiss,
     /// The identifier string of the action that the user selected, if it is not one of the other actions in [NotificationResponseAction]
     Other(String),
 }
 
 /// Notification Categories are used to define actions for notifications that have this category set
-#[derive(Debug, Clone)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
 pub struct NotificationCategory {
     /// Id of the category by which it is referenced on notifications [NotificationBuilder::set_category_id]
     pub identifier: String,
     /// The actions to display when the system delivers notifications of this type.
     pub actions: Vec<NotificationCategoryAction>,
+    /// The identifier of the action that a click on the notification itself triggers, instead of showing it as a button
+    ///
+    /// ## Platform specific
+    /// - Linux / XDG: the `default-action` of the notification portal, the handler receives the click as [NotificationResponseAction::Other]
+    /// - Other: not supported, a click is [NotificationResponseAction::Default] and the action stays a button
+    pub default_action: Option<String>,
+    /// Shown instead of the body when the user disabled notification previews, for example on the lock screen
+    ///
+    /// ## Platform specific
+    /// - MacOS: [UNNotificationCategory.hiddenPreviewsBodyPlaceholder](https://developer.apple.com/documentation/usernotifications/unnotificationcategory/hiddenpreviewsbodyplaceholder)
+    /// - Other: not supported
+    pub hidden_previews_body_placeholder: Option<String>,
+    /// Summary for a group of notifications of this category, like `"%u new messages"`
+    ///
+    /// ## Platform specific
+    /// - MacOS: [UNNotificationCategory.categorySummaryFormat](https://developer.apple.com/documentation/usernotifications/unnotificationcategory/categorysummaryformat)
+    /// - Other: not supported
+    pub summary_format: Option<String>,
+    /// Translations of the action titles, by the identifier of the action and then by locale like `"de"` or `"pt-BR"`.
+    /// [NotificationCategory::localized] picks the best match, actions without a match keep their title.
+    pub action_titles: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl NotificationCategory {
+    /// Adds the title of the action with this identifier for a locale, see [NotificationCategory::action_titles]
+    pub fn set_action_title(mut self, action_identifier: &str, locale: &str, title: &str) -> Self {
+        self.action_titles
+            .entry(action_identifier.to_owned())
+            .or_default()
+            .insert(locale.to_owned(), title.to_owned());
+        self
+    }
+
+    /// The category with the action titles for `locale`, like `"de-AT"`.
+    /// A title for "de-AT" is preferred, then one for "de", then one for another region like "de-DE".
+    pub fn localized(&self, locale: &str) -> NotificationCategory {
+        let mut category = self.clone();
+        for action in &mut category.actions {
+            let (NotificationCategoryAction::Action { identifier, title }
+            | NotificationCategoryAction::TextInputAction {
+                identifier, title, ..
+            }
+            | NotificationCategoryAction::SelectionInputAction {
+                identifier, title, ..
+            }) = action;
+            if let Some(localized) = self
+                .action_titles
+                .get(identifier)
+                .and_then(|titles| best_locale_match(titles, locale))
+            {
+                *title = localized.clone();
+            }
+        }
+        category
+    }
+}
+
+/// The value of the best matching locale, see [NotificationCategory::localized]
+fn best_locale_match<'a, T>(values: &'a BTreeMap<String, T>, locale: &str) -> Option<&'a T> {
+    let normalize = |locale: &str| locale.replace('_', "-").to_ascii_lowercase();
+    let locale = normalize(locale);
+    let language = |locale: &str| locale.split('-').next().unwrap_or_default().to_owned();
+    let candidates: Vec<(String, &T)> = values
+        .iter()
+        .map(|(candidate, value)| (normalize(candidate), value))
+        .collect();
+    candidates
+        .iter()
+        .find(|(candidate, _)| *candidate == locale)
+        .or_else(|| {
+            candidates
+                .iter()
+                .find(|(candidate, _)| *candidate == language(&locale))
+        })
+        .or_else(|| {
+            candidates
+                .iter()
+                .find(|(candidate, _)| language(candidate) == language(&locale))
+        })
+        .map(|(_, value)| *value)
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum NotificationCategoryAction {
     /// ## Platform specific
     /// https://developer.apple.com/documentation/usernotifications/unnotificationaction?language=objc
@@ -283,5 +3179,453 @@ pub enum NotificationCategoryAction {
         /* IDEA: also support icon and option https://developer.apple.com/documentation/usernotifications/untextinputnotificationaction/init(identifier:title:options:textinputbuttontitle:textinputplaceholder:)?language=objc */
         input_button_title: String,
         input_placeholder: String,
+        /// Text the field is pre-filled with, like "On my way!",
+        /// only used when [NotificationCapabilities::input_default_text] is true
+        #[serde(default)]
+        default_text: Option<String>,
     },
+    /// Lets the user pick one of the options,
+    /// the identifier of the picked option is passed as [NotificationResponse::user_text]
+    ///
+    /// ## Platform specific
+    /// - Windows: [`<input type="selection">`](https://learn.microsoft.com/uwp/schemas/tiles/toastschema/element-input) with a button titled `title`
+    /// - MacOS and Linux: one button per option
+    SelectionInputAction {
+        identifier: String,
+        title: String,
+        options: Vec<NotificationSelectionOption>,
+    },
+}
+
+// used by the platform backends without the windows one
+#[cfg_attr(
+    not(any(
+        all(target_os = "macos", feature = "macos"),
+        all(target_arch = "wasm32", feature = "web"),
+        all(
+            any(
+                target_os = "linux",
+                target_os = "dragonfly",
+                target_os = "freebsd",
+                target_os = "openbsd",
+                target_os = "netbsd"
+            ),
+            any(feature = "linux-dbus", feature = "linux-portal")
+        )
+    )),
+    allow(dead_code)
+)]
+/// Separates the identifier of the selection action and the option in the identifiers of the buttons
+/// that replace [NotificationCategoryAction::SelectionInputAction] on platforms without selection inputs
+const SELECTION_OPTION_SEPARATOR: &str = "\u{1f}";
+
+// used by the platform backends without the windows one
+#[cfg_attr(
+    not(any(
+        all(target_os = "macos", feature = "macos"),
+        all(target_arch = "wasm32", feature = "web"),
+        all(
+            any(
+                target_os = "linux",
+                target_os = "dragonfly",
+                target_os = "freebsd",
+                target_os = "openbsd",
+                target_os = "netbsd"
+            ),
+            any(feature = "linux-dbus", feature = "linux-portal")
+        )
+    )),
+    allow(dead_code)
+)]
+impl NotificationCategoryAction {
+    /// For platforms without selection inputs, replaces [NotificationCategoryAction::SelectionInputAction] with one button per option
+    pub(crate) fn expand_selection(&self) -> Vec<NotificationCategoryAction> {
+        match self {
+            NotificationCategoryAction::SelectionInputAction {
+                identifier,
+                options,
+                ..
+            } => options
+                .iter()
+                .map(|option| NotificationCategoryAction::Action {
+                    identifier: format!(
+                        "{identifier}{SELECTION_OPTION_SEPARATOR}{}",
+                        option.identifier
+                    ),
+                    title: option.title.clone(),
+                })
+                .collect(),
+            action => vec![action.clone()],
+        }
+    }
+}
+
+/// An option of [NotificationCategoryAction::SelectionInputAction]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct NotificationSelectionOption {
+    pub identifier: String,
+    pub title: String,
+}
+
+impl NotificationSelectionOption {
+    pub fn new(identifier: &str, title: &str) -> Self {
+        Self {
+            identifier: identifier.to_owned(),
+            title: title.to_owned(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate() {
+        let capabilities = NotificationCapabilities {
+            actions: true,
+            ..Default::default()
+        };
+        let valid = NotificationBuilder::new().title("title");
+        assert!(valid.validate(&capabilities).is_ok());
+        assert!(
+            NotificationBuilder::new()
+                .set_sender(NotificationSender::new("Alice"))
+                .validate(&capabilities)
+                .is_ok()
+        );
+
+        assert_eq!(
+            NotificationBuilder::new()
+                .title(" ")
+                .validate(&capabilities)
+                .unwrap_err()
+                .to_string(),
+            "Invalid notification: the notification is empty, it has no title and no body"
+        );
+        let invalid = [
+            NotificationBuilder::new().body("no title"),
+            valid.clone().body(&"a".repeat(MAX_BODY_LENGTH + 1)),
+            valid
+                .clone()
+                .add_action(NotificationCategoryAction::TextInputAction {
+                    identifier: "reply".to_owned(),
+                    title: "Reply".to_owned(),
+                    input_button_title: "Send".to_owned(),
+                    input_placeholder: "Message".to_owned(),
+                    default_text: None,
+                }),
+        ];
+        for builder in invalid {
+            let err = builder.validate(&capabilities).unwrap_err();
+            assert_eq!(err.kind(), crate::ErrorKind::InvalidContent, "{err}");
+        }
+        assert!(
+            valid
+                .add_action(NotificationCategoryAction::Action {
+                    identifier: "open".to_owned(),
+                    title: "Open".to_owned(),
+                })
+                .validate(&NotificationCapabilities::default())
+                .is_err()
+        );
+
+        let voice_message = NotificationBuilder::new()
+            .title("Alice")
+            .set_attachment(NotificationAttachment::audio("voice.m4a"));
+        let err = voice_message.validate(&capabilities).unwrap_err();
+        assert_eq!(err.kind(), crate::ErrorKind::Unsupported, "{err}");
+        assert!(
+            voice_message
+                .validate(&NotificationCapabilities {
+                    media_attachments: true,
+                    ..capabilities.clone()
+                })
+                .is_ok()
+        );
+
+        let gif = std::env::temp_dir().join(format!("user-notify-{}.gif", uuid::Uuid::new_v4()));
+        std::fs::write(&gif, [b"GIF89a".as_slice(), &[0; 64]].concat()).unwrap();
+        let limited = NotificationCapabilities {
+            max_image_size: Some(32),
+            ..capabilities
+        };
+        let with_gif = NotificationBuilder::new().title("title").attach_image(&gif);
+        // only its first frame is sent
+        assert!(with_gif.validate(&limited).is_ok());
+        let err = with_gif
+            .validate(&NotificationCapabilities {
+                animated_images: true,
+                ..limited
+            })
+            .unwrap_err();
+        assert_eq!(err.kind(), crate::ErrorKind::InvalidContent, "{err}");
+        std::fs::remove_file(gif).unwrap();
+    }
+
+    #[test]
+    fn auto_dismiss() {
+        let delivered_at = Utc::now();
+        let builder = NotificationBuilder::new()
+            .title("title")
+            .set_auto_dismiss_after(Duration::from_secs(60))
+            .apply_auto_dismiss(delivered_at);
+        assert_eq!(
+            builder.get_expiration_time(),
+            Some(delivered_at + chrono::Duration::seconds(60))
+        );
+        // applied once, so scheduled notifications keep the time of their delivery
+        assert_eq!(builder.get_auto_dismiss_after(), None);
+
+        let earlier = delivered_at + chrono::Duration::seconds(10);
+        let builder = NotificationBuilder::new()
+            .set_expiration_time(earlier)
+            .set_auto_dismiss_after(Duration::from_secs(60))
+            .apply_auto_dismiss(delivered_at);
+        assert_eq!(builder.get_expiration_time(), Some(earlier));
+    }
+
+    #[test]
+    fn localized_category() {
+        let category = NotificationCategory {
+            identifier: "message".to_owned(),
+            actions: vec![
+                NotificationCategoryAction::Action {
+                    identifier: "mark-read".to_owned(),
+                    title: "Mark as read".to_owned(),
+                },
+                NotificationCategoryAction::Action {
+                    identifier: "mute".to_owned(),
+                    title: "Mute".to_owned(),
+                },
+            ],
+            ..Default::default()
+        }
+        .set_action_title("mark-read", "de", "Als gelesen markieren")
+        .set_action_title("mark-read", "pt_BR", "Marcar como lida")
+        .set_action_title("mark-read", "pt-PT", "Marcar como lido");
+        let titles = |locale: &str| -> Vec<String> {
+            category
+                .localized(locale)
+                .actions
+                .into_iter()
+                .map(|action| match action {
+                    NotificationCategoryAction::Action { title, .. } => title,
+                    _ => unreachable!(),
+                })
+                .collect()
+        };
+
+        assert_eq!(titles("de-AT"), ["Als gelesen markieren", "Mute"]);
+        assert_eq!(titles("pt-PT"), ["Marcar como lido", "Mute"]);
+        assert_eq!(titles("pt-br")[0], "Marcar como lida");
+        assert!(titles("pt")[0].starts_with("Marcar como"));
+        assert_eq!(titles("fr")[0], "Mark as read");
+    }
+
+    #[test]
+    fn app_icon() {
+        let builder = NotificationBuilder::new().app_icon("icons/bot.png");
+        let icon = builder.get_icon().unwrap();
+        assert!(icon.is_absolute());
+        assert!(icon.ends_with("icons/bot.png"));
+    }
+
+    #[test]
+    fn sound() {
+        assert_eq!(
+            NotificationBuilder::new().sound("default").get_sound(),
+            Some(&Sound::Default)
+        );
+        let builder = NotificationBuilder::new()
+            .set_sound_file("/tmp/ping.wav")
+            .set_sound(Sound::System(SystemSound::Mail));
+        assert_eq!(builder.get_sound(), Some(&Sound::System(SystemSound::Mail)));
+        assert_eq!(builder.get_sound_file(), None);
+        assert!(!builder.is_silent());
+        assert!(builder.silent().is_silent());
+    }
+
+    #[test]
+    fn response_kind() {
+        let user_info = HashMap::from([("chat".to_owned(), "42".to_owned())]);
+        let response = |action, user_text: Option<&str>| {
+            NotificationResponse::new(
+                "id".to_owned(),
+                action,
+                user_text.map(str::to_owned),
+                user_info.clone(),
+            )
+            .kind()
+        };
+        assert_eq!(
+            response(NotificationResponseAction::Default, None),
+            ResponseKind::Clicked {
+                notification_id: "id".to_owned(),
+                user_info: user_info.clone(),
+            }
+        );
+        assert_eq!(
+            response(NotificationResponseAction::Other("like".to_owned()), None),
+            ResponseKind::ActionButton {
+                notification_id: "id".to_owned(),
+                action_id: "like".to_owned(),
+                user_info: user_info.clone(),
+            }
+        );
+        assert_eq!(
+            response(
+                NotificationResponseAction::Other("reply".to_owned()),
+                Some("hi")
+            ),
+            ResponseKind::TextInput {
+                notification_id: "id".to_owned(),
+                action_id: "reply".to_owned(),
+                text: "hi".to_owned(),
+                user_info: user_info.clone(),
+            }
+        );
+        assert!(matches!(
+            response(NotificationResponseAction::Dismiss, None),
+            ResponseKind::Dismissed { .. }
+        ));
+    }
+
+    #[test]
+    fn typed_user_info() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Message {
+            message_id: u64,
+            account_id: String,
+        }
+        let message = Message {
+            message_id: 42,
+            account_id: "work".to_owned(),
+        };
+        let response = |builder: NotificationBuilder| {
+            NotificationResponse::new(
+                "id".to_owned(),
+                NotificationResponseAction::Default,
+                None,
+                builder.user_info.clone().unwrap_or_default(),
+            )
+        };
+
+        let typed = NotificationBuilder::new()
+            .set_thread_id("inbox")
+            .set_user_info_typed(&message)
+            .unwrap();
+        assert_eq!(response(typed).user_info_as::<Message>().unwrap(), message);
+
+        let plain = NotificationBuilder::new()
+            .set_thread_id("inbox")
+            .set_user_info(HashMap::from([("account".to_owned(), "work".to_owned())]));
+        assert_eq!(
+            response(plain.clone())
+                .user_info_as::<HashMap<String, String>>()
+                .unwrap(),
+            HashMap::from([("account".to_owned(), "work".to_owned())])
+        );
+        assert!(response(plain).user_info_as::<Message>().is_err());
+    }
+
+    #[test]
+    fn open_url_survives_user_info() {
+        let builder = NotificationBuilder::new()
+            .on_click_open_url("myapp://inbox/42")
+            .set_user_info(HashMap::from([("mail".to_owned(), "42".to_owned())]));
+        assert_eq!(builder.get_on_click_open_url(), Some("myapp://inbox/42"));
+        assert_eq!(
+            builder
+                .get_user_info()
+                .and_then(|info| info.get("mail").cloned()),
+            Some("42".to_owned())
+        );
+    }
+
+    #[test]
+    fn calendar_trigger() {
+        let after = Local
+            .with_ymd_and_hms(2025, 3, 5, 12, 0, 0)
+            .unwrap()
+            .with_timezone(&Utc);
+        let next = |trigger: CalendarTrigger| {
+            trigger
+                .next_fire_time(after)
+                .map(|time| time.with_timezone(&Local).naive_local().to_string())
+        };
+        assert_eq!(
+            next(CalendarTrigger::new(9, 0)).as_deref(),
+            Some("2025-03-06 09:00:00")
+        );
+        assert_eq!(
+            next(CalendarTrigger::new(13, 30)).as_deref(),
+            Some("2025-03-05 13:30:00")
+        );
+        // March 5 2025 is a Wednesday
+        assert_eq!(
+            next(CalendarTrigger::new(8, 0).set_weekday(Weekday::Mon)).as_deref(),
+            Some("2025-03-10 08:00:00")
+        );
+        assert_eq!(
+            next(CalendarTrigger::new(0, 0).set_month(2).set_day(29)).as_deref(),
+            Some("2028-02-29 00:00:00")
+        );
+        assert_eq!(
+            next(CalendarTrigger::new(0, 0).set_month(2).set_day(30)),
+            None
+        );
+        assert_eq!(next(CalendarTrigger::new(24, 0)), None);
+    }
+
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "openbsd",
+        target_os = "netbsd"
+    ))]
+    #[test]
+    fn progress_text() {
+        let builder = NotificationBuilder::new()
+            .body("Photos")
+            .set_progress(NotificationProgress::new(0.421, "3 of 10 files").set_title("Export"));
+        assert_eq!(
+            builder.clone().apply_progress().get_body(),
+            Some("Photos\nExport: 42% — 3 of 10 files")
+        );
+        assert_eq!(
+            builder.apply_progress_status().get_body(),
+            Some("Photos\nExport: 3 of 10 files")
+        );
+
+        assert_eq!(
+            NotificationProgress::new(ProgressState::Indeterminate, "Connecting").to_string(),
+            "Connecting"
+        );
+        assert_eq!(NotificationProgress::new(1.0, "").to_string(), "100%");
+
+        let server = |name: &str| NotificationServerInformation {
+            name: name.to_owned(),
+            ..Default::default()
+        };
+        assert!(NotificationCapabilities::from_xdg(vec![], Some(server("dunst"))).progress);
+        assert!(!NotificationCapabilities::from_xdg(vec![], Some(server("gnome-shell"))).progress);
+    }
+
+    #[test]
+    fn build_from_template() {
+        let template = NotificationBuilder::new()
+            .set_thread_id("chat-42")
+            .set_dedupe_key("message")
+            .on_response(|_| {});
+        let first = NotificationBuilder::build_from(&template).title("first");
+        let second = NotificationBuilder::build_from(&template).title("second");
+        assert_eq!(first.get_thread_id(), Some("chat-42"));
+        assert_eq!(first.get_title(), Some("first"));
+        assert_eq!(first.get_dedupe_key(), None);
+        assert_eq!(template.get_title(), None);
+        assert_eq!(first.response_callback, template.response_callback);
+        assert_eq!(second.response_callback, template.response_callback);
+    }
 }
@@ -0,0 +1,287 @@
+//! Keeps the scheduled notifications in a json file, so they survive a restart of the app
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), user_notify::Error> {
+//! use std::sync::Arc;
+//! use user_notify::persistent_schedule::PersistentScheduleManager;
+//!
+//! let manager = PersistentScheduleManager::new(
+//!     user_notify::get_notification_manager("com.example.app".to_owned(), None),
+//!     "/home/user/.local/share/example/scheduled-notifications.json".into(),
+//! )?;
+//! let manager: Arc<dyn user_notify::NotificationManager> = Arc::new(manager);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! On Linux the scheduled notifications wait in the process, so they are lost when the app quits.
+//! This wrapper schedules them itself and stores them, [PersistentScheduleManager::new] reloads and rearms them.
+//! Notifications that were due while the app was not running are shown right away,
+//! repeating notifications start a new interval and calendar notifications wait for their next match.
+//!
+//! MacOS and Windows keep the scheduled notifications themselves,
+//! with this wrapper they are only shown while the app is running.
+
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    CalendarTrigger, Error, NotificationBuilder, NotificationCapabilities, NotificationManager,
+    RepeatInterval,
+    scheduler::{Schedule, Scheduler},
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Scheduled {
+    schedule: Schedule,
+    builder: NotificationBuilder,
+}
+
+/// Wraps a [NotificationManager] and stores its scheduled notifications,
+/// see the [module documentation](self)
+#[derive(Debug, Clone)]
+pub struct PersistentScheduleManager {
+    inner: Arc<dyn NotificationManager>,
+    path: PathBuf,
+    scheduled: Arc<Mutex<HashMap<String, Scheduled>>>,
+    /// held while the file is written, so an older state can not overwrite a newer one
+    saving: Arc<Mutex<()>>,
+    scheduler: Scheduler,
+}
+
+impl PersistentScheduleManager {
+    /// Loads the notifications that were scheduled in the file at `path` and schedules them again,
+    /// the file is created when the first notification is scheduled.
+    pub fn new(inner: Arc<dyn NotificationManager>, path: PathBuf) -> Result<Self, Error> {
+        let scheduled: HashMap<String, Scheduled> = match std::fs::read(&path) {
+            Ok(json) => serde_json::from_slice(&json).map_err(std::io::Error::from)?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(err) => return Err(err.into()),
+        };
+        let manager = Self {
+            inner,
+            path,
+            scheduled: Arc::default(),
+            saving: Arc::default(),
+            scheduler: Scheduler::default(),
+        };
+        log::info!(
+            "rearming {} scheduled notifications from {:?}",
+            scheduled.len(),
+            manager.path
+        );
+        for (id, scheduled) in scheduled {
+            if let Err(err) = manager.arm(id.clone(), scheduled) {
+                log::warn!("not rearming scheduled notification {id}: {err:?}");
+            }
+        }
+        Ok(manager)
+    }
+
+    /// The wrapped manager
+    pub fn as_inner(&self) -> &Arc<dyn NotificationManager> {
+        &self.inner
+    }
+
+    /// Ids of the notifications that wait for their delivery
+    pub fn scheduled_ids(&self) -> Vec<String> {
+        self.scheduled
+            .lock()
+            .map(|scheduled| scheduled.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Remembers the notification and starts its timer, fails if the calendar trigger never matches
+    fn arm(&self, id: String, scheduled: Scheduled) -> Result<(), Error> {
+        if let Ok(mut all) = self.scheduled.lock() {
+            all.insert(id.clone(), scheduled.clone());
+        }
+        let once = match &scheduled.schedule {
+            Schedule::At(_) => true,
+            Schedule::Every(_) => false,
+            Schedule::Calendar(trigger) => !trigger.repeats,
+        };
+        let manager = self.clone();
+        let armed = self.scheduler.schedule_notification(
+            id.clone(),
+            scheduled.builder,
+            scheduled.schedule,
+            move |id, builder| {
+                let manager = manager.clone();
+                async move {
+                    // shown with the id that was returned when it was scheduled
+                    let shown = manager
+                        .inner
+                        .send_notification(builder.with_preset_id(id.clone()))
+                        .await;
+                    if once {
+                        manager.remove(&id);
+                    }
+                    shown.map(drop)
+                }
+            },
+        );
+        if armed.is_err()
+            && let Ok(mut all) = self.scheduled.lock()
+        {
+            all.remove(&id);
+        }
+        armed.map(drop)
+    }
+
+    /// Forgets the delivered or cancelled notification, returns false if it was not scheduled here
+    fn remove(&self, id: &str) -> bool {
+        let removed = self
+            .scheduled
+            .lock()
+            .is_ok_and(|mut scheduled| scheduled.remove(id).is_some());
+        if removed {
+            self.log_save_error();
+        }
+        removed
+    }
+
+    fn save(&self) -> Result<(), Error> {
+        let _saving = self.saving.lock().unwrap_or_else(PoisonError::into_inner);
+        let json = {
+            let scheduled = self.scheduled.lock().map_err(|_| Error::SettingHandler)?;
+            serde_json::to_vec_pretty(&*scheduled).map_err(std::io::Error::from)?
+        };
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // a crash while writing leaves the previous file intact
+        let temporary = self.path.with_extension("json.tmp");
+        std::fs::write(&temporary, json)?;
+        std::fs::rename(&temporary, &self.path)?;
+        Ok(())
+    }
+
+    fn log_save_error(&self) {
+        if let Err(err) = self.save() {
+            log::error!(
+                "failed to save scheduled notifications to {:?}: {err:?}",
+                self.path
+            );
+        }
+    }
+
+    async fn schedule(
+        &self,
+        builder: NotificationBuilder,
+        schedule: Schedule,
+    ) -> Result<String, Error> {
+        builder.validate(&self.inner.capabilities().await?)?;
+        let id = uuid::Uuid::new_v4().to_string();
+        self.arm(id.clone(), Scheduled { schedule, builder })?;
+        if let Err(err) = self.save() {
+            self.scheduler.cancel(&id);
+            if let Ok(mut scheduled) = self.scheduled.lock() {
+                scheduled.remove(&id);
+            }
+            return Err(err);
+        }
+        Ok(id)
+    }
+}
+
+#[async_trait]
+impl crate::delegate::DelegatingManager for PersistentScheduleManager {
+    fn inner(&self) -> &Arc<dyn NotificationManager> {
+        &self.inner
+    }
+
+    /// Scheduling is supported on every platform with this wrapper
+    async fn capabilities(&self) -> Result<NotificationCapabilities, Error> {
+        let mut capabilities = self.inner.capabilities().await?;
+        capabilities.scheduling = true;
+        Ok(capabilities)
+    }
+
+    async fn schedule_notification(
+        &self,
+        builder: NotificationBuilder,
+        at: DateTime<Utc>,
+    ) -> Result<String, Error> {
+        self.schedule(builder, Schedule::At(at)).await
+    }
+
+    async fn schedule_repeating_notification(
+        &self,
+        builder: NotificationBuilder,
+        interval: RepeatInterval,
+    ) -> Result<String, Error> {
+        self.schedule(builder, Schedule::Every(interval)).await
+    }
+
+    async fn schedule_calendar_notification(
+        &self,
+        builder: NotificationBuilder,
+        trigger: CalendarTrigger,
+    ) -> Result<String, Error> {
+        trigger.first_fire_time()?;
+        self.schedule(builder, Schedule::Calendar(trigger)).await
+    }
+
+    /// Also cancels the notifications of the wrapped manager, like the ones scheduled before this wrapper was used
+    fn cancel_scheduled_notification(&self, id: &str) -> Result<(), Error> {
+        self.scheduler.cancel(id);
+        if self.remove(id) {
+            return Ok(());
+        }
+        self.inner.cancel_scheduled_notification(id)
+    }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::mock::NotificationManagerMock;
+
+    #[tokio::test]
+    async fn rearm_after_restart() {
+        let path = std::env::temp_dir().join(format!(
+            "user-notify-schedule-{}.json",
+            uuid::Uuid::new_v4()
+        ));
+        let mock = NotificationManagerMock::new();
+        let manager = PersistentScheduleManager::new(Arc::new(mock.clone()), path.clone()).unwrap();
+        let at = Utc::now() + chrono::Duration::milliseconds(200);
+        let id = manager
+            .schedule_notification(NotificationBuilder::new().title("later"), at)
+            .await
+            .unwrap();
+        let cancelled = manager
+            .schedule_notification(NotificationBuilder::new().title("cancelled"), at)
+            .await
+            .unwrap();
+        manager.cancel_scheduled_notification(&cancelled).unwrap();
+        // the app quits before the delivery
+        manager.scheduler.cancel(&id);
+        drop(manager);
+
+        let mock = NotificationManagerMock::new();
+        let manager = PersistentScheduleManager::new(Arc::new(mock.clone()), path.clone()).unwrap();
+        assert_eq!(manager.scheduled_ids(), [id.as_str()]);
+        tokio::time::sleep(Duration::from_millis(350)).await;
+        let active = mock.get_active_notifications().await.unwrap();
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].get_id(), id);
+        assert_eq!(active[0].get_content().title.as_deref(), Some("later"));
+        assert!(manager.scheduled_ids().is_empty());
+
+        let stored: HashMap<String, Scheduled> =
+            serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert!(stored.is_empty());
+        std::fs::remove_file(path).unwrap();
+    }
+}
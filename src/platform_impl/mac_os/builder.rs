@@ -1,33 +1,97 @@
-use std::{collections::HashMap, ops::Deref};
+use std::{
+    collections::HashMap,
+    ops::Deref,
+    path::{Path, PathBuf},
+};
 
-use super::{NotificationManagerMacOS, handle::NotificationHandleMacOS};
-use objc2::{rc::Retained, runtime::AnyObject};
-use objc2_foundation::{NSArray, NSDictionary, NSString, NSURL, ns_string};
+use super::{
+    NotificationManagerMacOS, handle::NotificationHandleMacOS, manager::ns_error_to_error,
+};
+use chrono::Utc;
+use objc2::{
+    msg_send,
+    rc::{Allocated, Retained},
+    runtime::{AnyClass, AnyObject, MessageReceiver},
+    sel,
+};
+use objc2_foundation::{
+    NSArray, NSData, NSDictionary, NSError, NSInteger, NSNumber, NSObject, NSString, NSURL,
+    ns_string,
+};
 use objc2_user_notifications::{
-    UNMutableNotificationContent, UNNotificationAttachment, UNNotificationRequest, UNNotificationSound,
+    UNMutableNotificationContent, UNNotificationAttachment,
+    UNNotificationAttachmentOptionsThumbnailClippingRectKey,
+    UNNotificationAttachmentOptionsThumbnailHiddenKey,
+    UNNotificationAttachmentOptionsThumbnailTimeKey, UNNotificationAttachmentOptionsTypeHintKey,
+    UNNotificationContent, UNNotificationInterruptionLevel, UNNotificationRequest,
+    UNNotificationSound, UNNotificationTrigger,
 };
 use uuid::Uuid;
 
-use crate::{Error, NotificationBuilder};
+use crate::{
+    AttachmentKind, Error, InterruptionLevel, NotificationAttachment, NotificationBuilder,
+    NotificationContent, NotificationEventKind, NotificationSender, Sound, SystemSound,
+};
 
+/// - `id`: passing the id of an existing notification replaces it, `None` generates a new id
+/// - `trigger`: is used for scheduled notifications, `None` delivers the notification right away
 pub(super) fn build_and_send(
     builder: NotificationBuilder,
     manager: &NotificationManagerMacOS,
+    id: Option<String>,
+    trigger: Option<Retained<UNNotificationTrigger>>,
     tx: tokio::sync::oneshot::Sender<Result<(), Error>>,
 ) -> Result<NotificationHandleMacOS, Error> {
-    let (request, id, user_info) = build(builder, manager)?;
+    let progress_builder = builder.progress.is_some().then(|| builder.clone());
+    let response_callback = builder.response_callback.clone();
+    // scheduled notifications have it applied with their delivery time already
+    let builder = builder
+        .apply_hero_image_and_attribution()
+        .apply_auto_dismiss(Utc::now())
+        .apply_sender()
+        .apply_progress()
+        .apply_plain_body()
+        .apply_interruption_level();
+    let thread_id = builder.thread_id.clone();
+    let expiration = builder.expiration;
+    let foreground_presentation = builder.foreground_presentation;
+    let scheduled = trigger.is_some();
+    let mut content = NotificationContent::from(&builder);
+    if scheduled {
+        content.delivered_at = None;
+    }
+    let (request, id, user_info) = build(builder, manager, id, trigger)?;
+    if let Some(progress_builder) = progress_builder {
+        manager.remember_progress_notification(id.clone(), progress_builder);
+    }
+    manager.remember_foreground_presentation(id.clone(), foreground_presentation);
+    manager
+        .inner
+        .events
+        .set_response_callback(&id, &response_callback);
+    manager.schedule_expiration(&id, expiration);
+    let events = manager.inner.events.clone();
+    let presented_id = id.clone();
     manager.add_notification(&request, move |result| {
+        // scheduled notifications are delivered later by the system
+        if result.is_ok() && !scheduled {
+            events.emit(&presented_id, NotificationEventKind::Presented);
+        }
         if let Err(err) = tx.send(result) {
             log::error!("add_notification tx.send error {err:?}");
         }
     });
-    Ok(NotificationHandleMacOS::new(id, user_info))
+    Ok(NotificationHandleMacOS::new(
+        id, thread_id, user_info, content,
+    ))
 }
 
 #[allow(clippy::type_complexity)]
 fn build(
     builder: NotificationBuilder,
     manager: &NotificationManagerMacOS,
+    id: Option<String>,
+    trigger: Option<Retained<UNNotificationTrigger>>,
 ) -> Result<
     (
         Retained<UNNotificationRequest>,
@@ -37,6 +101,24 @@ fn build(
     Error,
 > {
     let mut user_info = HashMap::new();
+    let sender = builder.sender.clone();
+    let conversation_id = builder.thread_id.clone();
+    // the app icon can not be changed on macOS, so without communication notification the avatar is attached instead
+    let avatar = builder
+        .sender
+        .as_ref()
+        .and_then(|sender| sender.avatar.clone())
+        .filter(|_| builder.image.is_none());
+    // the system shows the thumbnail of the first attachment
+    let mut attachments = Vec::new();
+
+    // without a category the delegate is not told when the user dismisses the notification,
+    // so notifications without one get a generated category without actions
+    let category_id = if builder.actions.is_empty() && builder.category_id.is_some() {
+        builder.category_id
+    } else {
+        Some(manager.category_with_actions(builder.category_id.as_deref(), &builder.actions)?)
+    };
 
     let notification: Retained<UNMutableNotificationContent> = unsafe {
         let notification = UNMutableNotificationContent::new();
@@ -53,41 +135,61 @@ fn build(
             notification.setSubtitle(&NSString::from_str(&subtitle));
         }
 
-        if let Some(sound_name) = builder.sound {
-            let sound = if sound_name == "default" {
-                UNNotificationSound::defaultSound()
-            } else {
-                UNNotificationSound::soundNamed(&NSString::from_str(&sound_name))
-            };
-            notification.setSound(Some(&sound));
-        } else {
-            notification.setSound(Some(&UNNotificationSound::defaultSound()));
-        }
+        let named = |name: &str| UNNotificationSound::soundNamed(&NSString::from_str(name));
+        let sound = match builder.sound {
+            None | Some(Sound::Default) => Some(UNNotificationSound::defaultSound()),
+            Some(Sound::None) => None,
+            Some(Sound::System(sound)) => {
+                Some(named(&install_sound_file(&system_sound_path(sound))?))
+            }
+            // played once, sounds can not loop on macOS
+            Some(Sound::Looping(sound)) => Some(named(&install_sound_file(&system_sound_path(
+                sound.system_sound(),
+            ))?)),
+            Some(Sound::Named(name)) => Some(named(&name)),
+            Some(Sound::File(path)) => Some(named(&install_sound_file(&path)?)),
+        };
+        notification.setSound(sound.as_deref());
 
+        if let Some(attachment) = builder.attachment {
+            attachments.push(media_attachment(&attachment)?);
+        }
         if let Some(path) = builder.image {
-            let ns_url =
-                NSURL::fileURLWithPath(&NSString::from_str(path.to_string_lossy().as_ref()));
-            log::trace!("{ns_url:?}");
-            let attachment = UNNotificationAttachment::attachmentWithIdentifier_URL_options_error(
-                ns_string!(""),
-                &ns_url,
-                None,
-            )
-            .map_err(|ns_err| {
-                let description = ns_err.localizedDescription();
-                Error::NSError(description.to_string())
-            })?;
-
+            attachments.push(image_attachment(&path)?);
+        }
+        if !attachments.is_empty() {
             let ns_array: Retained<NSArray<UNNotificationAttachment>> =
-                NSArray::from_retained_slice(&[attachment]);
+                NSArray::from_retained_slice(&attachments);
 
             notification.setAttachments(&ns_array);
         }
 
+        if let Some(interruption_level) = builder.interruption_level {
+            notification.setInterruptionLevel(match interruption_level {
+                InterruptionLevel::Passive => UNNotificationInterruptionLevel::Passive,
+                InterruptionLevel::Active => UNNotificationInterruptionLevel::Active,
+                InterruptionLevel::TimeSensitive => UNNotificationInterruptionLevel::TimeSensitive,
+                InterruptionLevel::Critical => UNNotificationInterruptionLevel::Critical,
+            });
+        }
+
+        if let Some(relevance_score) = builder.relevance_score {
+            notification.setRelevanceScore(relevance_score.clamp(0.0, 1.0));
+        }
+
+        if let Some(filter_criteria) = builder.filter_criteria {
+            notification.setFilterCriteria(Some(&NSString::from_str(&filter_criteria)));
+        }
+
         if let Some(thread_id) = builder.thread_id {
             notification.setThreadIdentifier(&NSString::from_str(&thread_id));
         }
-        if let Some(category_id) = builder.category_id {
+        set_summary(
+            &notification,
+            builder.summary_argument.as_deref(),
+            builder.summary_argument_count,
+        );
+        if let Some(category_id) = category_id {
             notification.setCategoryIdentifier(&NSString::from_str(&category_id));
         }
 
@@ -119,6 +221,25 @@ fn build(
         notification
     };
 
+    let notification: Retained<UNNotificationContent> = match sender
+        .as_ref()
+        .map(|sender| communication_content(&notification, sender, conversation_id.as_deref()))
+    {
+        Some(Ok(content)) => content,
+        result => {
+            if let Some(Err(err)) = result {
+                log::warn!("failed to show a communication notification: {err:?}");
+            }
+            if let Some(avatar) = avatar {
+                attachments.push(image_attachment(&avatar)?);
+                let ns_array: Retained<NSArray<UNNotificationAttachment>> =
+                    NSArray::from_retained_slice(&attachments);
+                notification.setAttachments(&ns_array);
+            }
+            Retained::into_super(notification)
+        }
+    };
+
     unsafe {
         let bundle_id = manager
             .inner
@@ -128,12 +249,12 @@ fn build(
             .ok_or(Error::NoBundleId)?;
         // log::trace!("bundle_id: {bundle_id:?}");
 
-        let id = format!("{}.{}", Uuid::new_v4(), bundle_id);
+        let id = id.unwrap_or_else(|| format!("{}.{}", Uuid::new_v4(), bundle_id));
 
         let r = UNNotificationRequest::requestWithIdentifier_content_trigger(
             &NSString::from_str(&id),
             &notification,
-            None,
+            trigger.as_deref(),
         );
 
         log::trace!("{r:?}  -- {:?}", r.identifier());
@@ -141,3 +262,211 @@ fn build(
         Ok((r, id, user_info))
     }
 }
+
+fn image_attachment(path: &Path) -> Result<Retained<UNNotificationAttachment>, Error> {
+    let ns_url = NSURL::fileURLWithPath(&NSString::from_str(path.to_string_lossy().as_ref()));
+    log::trace!("{ns_url:?}");
+    unsafe {
+        UNNotificationAttachment::attachmentWithIdentifier_URL_options_error(
+            ns_string!(""),
+            &ns_url,
+            None,
+        )
+    }
+    .map_err(|ns_err| ns_error_to_error(Some(&*ns_err)))
+}
+
+/// [INPersonHandleType.unknown](https://developer.apple.com/documentation/intents/inpersonhandletype/unknown)
+const PERSON_HANDLE_TYPE_UNKNOWN: NSInteger = 0;
+/// [INOutgoingMessageType.outgoingMessageText](https://developer.apple.com/documentation/intents/inoutgoingmessagetype/outgoingmessagetext)
+const OUTGOING_MESSAGE_TEXT: NSInteger = 1;
+/// [INInteractionDirection.incoming](https://developer.apple.com/documentation/intents/ininteractiondirection/incoming)
+const INTERACTION_DIRECTION_INCOMING: NSInteger = 2;
+
+/// Makes the notification a [communication notification](https://developer.apple.com/documentation/usernotifications/implementing-communication-notifications)
+/// from `sender`, which shows the name and avatar of the sender like Messages does.
+/// An `INSendMessageIntent` of the message is donated and the content is updated with it.
+///
+/// The Intents framework is linked and called through the objc runtime like `INFocusStatusCenter`.
+/// Fails before macOS 12 and when the system does not accept the intent,
+/// like without the communication notifications entitlement.
+fn communication_content(
+    content: &UNMutableNotificationContent,
+    sender: &NotificationSender,
+    conversation_id: Option<&str>,
+) -> Result<Retained<UNNotificationContent>, Error> {
+    let class = |name: &std::ffi::CStr| {
+        AnyClass::get(name).ok_or_else(|| {
+            Error::Unsupported("communication notifications before macOS 12".to_owned())
+        })
+    };
+    let name = NSString::from_str(&sender.name);
+    let image: Option<Retained<AnyObject>> = match &sender.avatar {
+        Some(avatar) => {
+            let data = NSData::with_bytes(&std::fs::read(avatar)?);
+            unsafe { msg_send![class(c"INImage")?, imageWithImageData: &*data] }
+        }
+        None => None,
+    };
+    let conversation_id = conversation_id.map(NSString::from_str);
+    let body = content.body();
+    unsafe {
+        // `type` is a keyword, so the selector can not be written with msg_send
+        let handle: *mut AnyObject = class(c"INPersonHandle")?.send_message(sel!(alloc), ());
+        let handle: *mut AnyObject = handle.send_message(
+            sel!(initWithValue:type:),
+            (&*name, PERSON_HANDLE_TYPE_UNKNOWN),
+        );
+        let handle = Retained::from_raw(handle)
+            .ok_or_else(|| Error::Unsupported("communication notifications".to_owned()))?;
+
+        let person: Allocated<AnyObject> = msg_send![class(c"INPerson")?, alloc];
+        let person: Retained<AnyObject> = msg_send![
+            person,
+            initWithPersonHandle: &*handle,
+            nameComponents: None::<&AnyObject>,
+            displayName: &*name,
+            image: image.as_deref(),
+            contactIdentifier: None::<&NSString>,
+            customIdentifier: None::<&NSString>,
+        ];
+
+        let intent: Allocated<AnyObject> = msg_send![class(c"INSendMessageIntent")?, alloc];
+        let intent: Retained<AnyObject> = msg_send![
+            intent,
+            initWithRecipients: None::<&NSArray<AnyObject>>,
+            outgoingMessageType: OUTGOING_MESSAGE_TEXT,
+            content: &*body,
+            speakableGroupName: None::<&AnyObject>,
+            conversationIdentifier: conversation_id.as_deref(),
+            serviceName: None::<&NSString>,
+            sender: &*person,
+            attachments: None::<&NSArray<AnyObject>>,
+        ];
+
+        let interaction: Allocated<AnyObject> = msg_send![class(c"INInteraction")?, alloc];
+        let interaction: Retained<AnyObject> =
+            msg_send![interaction, initWithIntent: &*intent, response: None::<&AnyObject>];
+        let _: () = msg_send![&*interaction, setDirection: INTERACTION_DIRECTION_INCOMING];
+        let block = block2::RcBlock::new(|err: *mut NSError| {
+            if let Some(err) = err.as_ref() {
+                log::warn!("failed to donate the message intent: {err:?}");
+            }
+        });
+        let _: () = msg_send![&*interaction, donateInteractionWithCompletion: &*block];
+
+        let content: Result<Retained<UNNotificationContent>, Retained<NSError>> =
+            msg_send![content, contentByUpdatingWithProvider: &*intent, error: _];
+        content.map_err(|err| ns_error_to_error(Some(&*err)))
+    }
+}
+
+/// Only used before macOS 12, later the system writes the summary of the thread itself
+#[allow(deprecated)]
+fn set_summary(
+    notification: &UNMutableNotificationContent,
+    summary_argument: Option<&str>,
+    summary_argument_count: Option<u32>,
+) {
+    if let Some(summary_argument) = summary_argument {
+        notification.setSummaryArgument(&NSString::from_str(summary_argument));
+    }
+    if let Some(summary_argument_count) = summary_argument_count {
+        notification.setSummaryArgumentCount(summary_argument_count as usize);
+    }
+}
+
+/// See the table of [SystemSound]
+fn system_sound_path(sound: SystemSound) -> PathBuf {
+    let name = match sound {
+        SystemSound::Message => "Tink",
+        SystemSound::Mail => "Glass",
+        SystemSound::Reminder => "Ping",
+        SystemSound::Alarm => "Sosumi",
+        SystemSound::Call => "Submarine",
+    };
+    PathBuf::from(format!("/System/Library/Sounds/{name}.aiff"))
+}
+
+/// `UNNotificationSound(named:)` only looks for sounds in the app bundle and in `~/Library/Sounds`,
+/// so the file is copied there, returns the name of the sound
+fn install_sound_file(path: &Path) -> Result<String, Error> {
+    let supported = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            ["aiff", "aif", "wav", "caf"].contains(&extension.to_ascii_lowercase().as_str())
+        });
+    let file_name = path
+        .file_name()
+        .filter(|_| supported && path.is_file())
+        .ok_or_else(|| Error::UnsupportedSoundFile(path.to_path_buf()))?;
+
+    let sounds_dir = std::env::var_os("HOME")
+        .map(|home| PathBuf::from(home).join("Library/Sounds"))
+        .ok_or_else(|| Error::UnsupportedSoundFile(path.to_path_buf()))?;
+    std::fs::create_dir_all(&sounds_dir)?;
+    let destination = sounds_dir.join(file_name);
+    if destination != path {
+        std::fs::copy(path, &destination)?;
+    }
+    Ok(file_name.to_string_lossy().into_owned())
+}
+
+/// The attachment with the [options](https://developer.apple.com/documentation/usernotifications/unnotificationattachment/init(identifier:url:options:))
+/// for its type and thumbnail
+fn media_attachment(
+    attachment: &NotificationAttachment,
+) -> Result<Retained<UNNotificationAttachment>, Error> {
+    let number = |value: f64| Retained::into_super(Retained::into_super(NSNumber::new_f64(value)));
+    let type_hint = NSString::from_str(match attachment.kind {
+        AttachmentKind::Movie => "public.movie",
+        AttachmentKind::Audio => "public.audio",
+    });
+    let mut keys: Vec<&NSString> = vec![unsafe { UNNotificationAttachmentOptionsTypeHintKey }];
+    let mut values: Vec<Retained<NSObject>> = vec![Retained::into_super(type_hint)];
+    if let Some(seconds) = attachment.thumbnail_time {
+        keys.push(unsafe { UNNotificationAttachmentOptionsThumbnailTimeKey });
+        values.push(number(seconds));
+    }
+    if let Some(clipping) = attachment.thumbnail_clipping {
+        // the dictionary representation of a CGRect, like from CGRectCreateDictionaryRepresentation
+        let rect = NSDictionary::from_retained_objects(
+            &[
+                ns_string!("X"),
+                ns_string!("Y"),
+                ns_string!("Width"),
+                ns_string!("Height"),
+            ],
+            &[
+                number(clipping.x),
+                number(clipping.y),
+                number(clipping.width),
+                number(clipping.height),
+            ],
+        );
+        keys.push(unsafe { UNNotificationAttachmentOptionsThumbnailClippingRectKey });
+        values.push(Retained::into_super(rect));
+    }
+    if attachment.thumbnail_hidden {
+        keys.push(unsafe { UNNotificationAttachmentOptionsThumbnailHiddenKey });
+        values.push(Retained::into_super(Retained::into_super(
+            NSNumber::new_bool(true),
+        )));
+    }
+    let options = NSDictionary::from_retained_objects(&keys, &values);
+    let options =
+        unsafe { Retained::cast_unchecked::<NSDictionary<AnyObject, AnyObject>>(options) };
+
+    let ns_url = NSURL::fileURLWithPath(&NSString::from_str(
+        attachment.path.to_string_lossy().as_ref(),
+    ));
+    unsafe {
+        UNNotificationAttachment::attachmentWithIdentifier_URL_options_error(
+            ns_string!(""),
+            &ns_url,
+            Some(&options),
+        )
+    }
+    .map_err(|ns_err| ns_error_to_error(Some(&*ns_err)))
+}
@@ -5,15 +5,22 @@ use objc2_user_notifications::{
     UNNotificationPresentationOptions, UNNotificationResponse, UNTextInputNotificationResponse,
     UNUserNotificationCenter, UNUserNotificationCenterDelegate,
 };
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
 use tokio::sync::mpsc::Sender;
 
-use crate::{NotificationResponse, NotificationResponseAction};
+use crate::{ForegroundPresentation, NotificationResponse, NotificationResponseAction};
 
 use super::manager::user_info_dictionary_to_hashmap;
 
 #[derive(Clone)]
 pub struct Ivars {
     pub sender: Sender<NotificationResponse>,
+    /// set with [crate::NotificationBuilder::set_foreground_presentation], by notification id
+    pub foreground_presentations: Arc<Mutex<HashMap<String, ForegroundPresentation>>>,
 }
 
 // for info on how to use the macro see
@@ -37,15 +44,19 @@ define_class!(
         fn will_present_notification(
             &self,
             _center: &UNUserNotificationCenter,
-            _notification: &UNNotification,
+            notification: &UNNotification,
             completion_handler: &block2::Block<dyn Fn(UNNotificationPresentationOptions)>,
         ) {
             log::debug!("triggered `userNotificationCenter:willPresentNotification:withCompletionHandler:`");
-            let presentation_options = UNNotificationPresentationOptions::empty()
-                .union(UNNotificationPresentationOptions::Badge)
-                .union(UNNotificationPresentationOptions::Banner)
-                .union(UNNotificationPresentationOptions::Sound);
-            completion_handler.call((presentation_options,));
+            let notification_id = notification.request().identifier().to_string();
+            let presentation = self
+                .ivars()
+                .foreground_presentations
+                .lock()
+                .ok()
+                .and_then(|presentations| presentations.get(&notification_id).copied())
+                .unwrap_or_default();
+            completion_handler.call((presentation_options(presentation),));
             log::debug!("completed `userNotificationCenter:willPresentNotification:withCompletionHandler:`");
         }
         #[unsafe(method(userNotificationCenter:didReceiveNotificationResponse:withCompletionHandler:))]
@@ -80,13 +91,16 @@ define_class!(
               let notification_id = request.identifier().to_string();
 
               let user_info = user_info_dictionary_to_hashmap(request.content().userInfo());
+              let delivered_at = chrono::DateTime::from_timestamp_millis(
+                  (notification.date().timeIntervalSince1970() * 1000.0) as i64,
+              );
 
-              let event = NotificationResponse {
+              let event = NotificationResponse::new(
                   notification_id,
                   action,
                   user_text,
                   user_info
-              };
+              ).set_delivered_at(delivered_at).split_selection();
               log::debug!("NotificationResponse {event:?}");
 
                 if let Err(err) = self.ivars().sender.try_send(event) {
@@ -102,12 +116,31 @@ define_class!(
 );
 
 impl NotificationDelegate {
-    pub fn new(mtm: MainThreadMarker, tx: Sender<NotificationResponse>) -> Retained<Self> {
-        let this = Self::alloc(mtm).set_ivars(Ivars { sender: tx });
+    pub fn new(
+        mtm: MainThreadMarker,
+        tx: Sender<NotificationResponse>,
+        foreground_presentations: Arc<Mutex<HashMap<String, ForegroundPresentation>>>,
+    ) -> Retained<Self> {
+        let this = Self::alloc(mtm).set_ivars(Ivars {
+            sender: tx,
+            foreground_presentations,
+        });
         unsafe { msg_send![super(this), init] }
     }
 }
 
+fn presentation_options(presentation: ForegroundPresentation) -> UNNotificationPresentationOptions {
+    let mut options = UNNotificationPresentationOptions::empty();
+    options.set(
+        UNNotificationPresentationOptions::Banner,
+        presentation.banner,
+    );
+    options.set(UNNotificationPresentationOptions::List, presentation.list);
+    options.set(UNNotificationPresentationOptions::Sound, presentation.sound);
+    options.set(UNNotificationPresentationOptions::Badge, presentation.badge);
+    options
+}
+
 impl Drop for NotificationDelegate {
     fn drop(&mut self) {
         panic!("dropped NotificationDelegate")
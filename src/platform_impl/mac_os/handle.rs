@@ -4,19 +4,28 @@ use objc2::{MainThreadMarker, rc::Retained};
 use objc2_foundation::{NSArray, NSBundle, NSString};
 use objc2_user_notifications::UNUserNotificationCenter;
 
-use crate::{Error, NotificationHandle};
+use crate::{Error, NotificationContent, NotificationHandle};
 
 #[derive(Debug)]
 pub struct NotificationHandleMacOS {
     id: String,
+    thread_id: Option<String>,
     user_info: HashMap<String, String>,
+    content: NotificationContent,
 }
 
 impl NotificationHandleMacOS {
-    pub(super) fn new(id: String, user_data: HashMap<String, String>) -> Self {
+    pub(super) fn new(
+        id: String,
+        thread_id: Option<String>,
+        user_data: HashMap<String, String>,
+        content: NotificationContent,
+    ) -> Self {
         Self {
             id,
+            thread_id,
             user_info: user_data,
+            content,
         }
     }
 }
@@ -43,7 +52,15 @@ impl NotificationHandle for NotificationHandleMacOS {
         self.id.to_string()
     }
 
-    fn get_user_info(&self) -> &HashMap<String, String> {
+    fn get_stored_user_info(&self) -> &HashMap<String, String> {
         &self.user_info
     }
+
+    fn get_thread_id(&self) -> Option<&str> {
+        self.thread_id.as_deref()
+    }
+
+    fn get_content(&self) -> &NotificationContent {
+        &self.content
+    }
 }
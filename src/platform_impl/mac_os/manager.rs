@@ -1,36 +1,88 @@
 use std::cell::{OnceCell, RefCell};
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::ops::Deref;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
 use std::thread;
 use std::{collections::HashMap, ptr::NonNull};
 
 use async_trait::async_trait;
-use objc2::Message;
+use chrono::{DateTime, Utc};
 use objc2::runtime::{AnyObject, ProtocolObject};
+use objc2::{Message, msg_send};
 use send_wrapper::SendWrapper;
 
 use objc2::{MainThreadMarker, rc::Retained, runtime::Bool};
-use objc2_foundation::{NSArray, NSBundle, NSDictionary, NSError, NSSet, NSString};
+use objc2_foundation::{
+    NSArray, NSBundle, NSDateComponents, NSDictionary, NSError, NSInteger, NSNumber, NSSet,
+    NSString,
+};
 use objc2_user_notifications::{
-    UNAuthorizationOptions, UNAuthorizationStatus, UNNotification, UNNotificationAction,
-    UNNotificationActionOptions, UNNotificationCategory, UNNotificationCategoryOptions,
-    UNNotificationRequest, UNNotificationSettings, UNTextInputNotificationAction,
-    UNUserNotificationCenter, UNUserNotificationCenterDelegate,
+    UNAlertStyle, UNAuthorizationOptions, UNAuthorizationStatus, UNCalendarNotificationTrigger,
+    UNNotification, UNNotificationAction, UNNotificationActionOptions, UNNotificationCategory,
+    UNNotificationCategoryOptions, UNNotificationRequest, UNNotificationSetting,
+    UNNotificationSettings, UNNotificationTrigger, UNTextInputNotificationAction,
+    UNTimeIntervalNotificationTrigger, UNUserNotificationCenter, UNUserNotificationCenterDelegate,
 };
 
+use crate::{
+    CalendarTrigger, ForegroundPresentation, NotificationAlertStyle, NotificationBuilder,
+    NotificationCapabilities, NotificationCategory, NotificationCategoryAction,
+    NotificationContent, NotificationDismissReason, NotificationEvent, NotificationEventKind,
+    NotificationHandle, NotificationResponse, NotificationSettings, ProgressState, RepeatInterval,
+    events::NotificationEvents,
+    find_duplicate,
+    launch_response::LaunchResponses,
+    response_handler::ResponseHandler,
+    scheduler::{MIN_REPEAT_INTERVAL, Scheduler},
+    unknown_category,
+};
 use crate::{Error, NotificationManager, mac_os::delegate::NotificationDelegate};
-use crate::{NotificationBuilder, NotificationCategory, NotificationHandle, NotificationResponse};
 
 use super::builder::build_and_send;
 use super::handle::NotificationHandleMacOS;
 
-#[derive(Debug)]
+/// Prefix of the categories for notifications with actions or without a category,
+/// see [NotificationManagerMacOS::category_with_actions]
+const GENERATED_CATEGORY_PREFIX: &str = "user_notify.actions.";
+
 pub struct NotificationManagerMacOSInner {
     /// reference to the delegate so that it isn't dropped immitiately
     delegate_reference:
         SendWrapper<OnceCell<Retained<ProtocolObject<dyn UNUserNotificationCenterDelegate>>>>,
     listener_loop: SendWrapper<OnceCell<thread::JoinHandle<()>>>,
+    handler: ResponseHandler,
+    /// responses before [NotificationManager::register], see [NotificationManager::take_launch_response]
+    launch_responses: LaunchResponses,
+    pub(super) events: NotificationEvents,
     pub(crate) bundle_id: Option<String>,
+    /// there is no api to read the badge count, so we remember the last one we set
+    badge_count: AtomicU32,
+    /// registered categories, `setNotificationCategories` replaces all of them
+    categories: Mutex<Vec<NotificationCategory>>,
+    /// notifications with progress, to send them again with the new progress
+    progress_notifications: Mutex<HashMap<String, NotificationBuilder>>,
+    /// read by the delegate when a notification is presented while the app is frontmost
+    foreground_presentations: Arc<Mutex<HashMap<String, ForegroundPresentation>>>,
+    /// there is no expiration date for notifications, so they are removed by a timer
+    scheduler: Scheduler,
+}
+
+impl std::fmt::Debug for NotificationManagerMacOSInner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NotificationManagerMacOSInner")
+            .field(
+                "handler",
+                match &self.handler.get() {
+                    Some(_) => &"handler",
+                    None => &"no handler",
+                },
+            )
+            .field("bundle_id", &self.bundle_id)
+            .field("badge_count", &self.badge_count)
+            .field("categories", &self.categories)
+            .finish()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -42,18 +94,170 @@ impl NotificationManagerMacOS {
     #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
         log::debug!("NotificationManager.new called");
-        Self {
+        let manager = Self {
             inner: Arc::new(NotificationManagerMacOSInner {
                 delegate_reference: SendWrapper::new(OnceCell::new()),
                 listener_loop: SendWrapper::new(OnceCell::new()),
+                handler: ResponseHandler::default(),
+                launch_responses: LaunchResponses::default(),
+                events: NotificationEvents::default(),
                 bundle_id: unsafe {
                     NSBundle::mainBundle()
                         .bundleIdentifier()
                         .map(|ns_string| ns_string.to_string())
                 },
+                badge_count: AtomicU32::new(0),
+                categories: Mutex::new(Vec::new()),
+                progress_notifications: Mutex::new(HashMap::new()),
+                foreground_presentations: Arc::new(Mutex::new(HashMap::new())),
+                scheduler: Scheduler::default(),
             }),
+        };
+        // the delegate needs to be set before the app finished launching to receive the response that launched it
+        if manager.inner.bundle_id.is_some()
+            && let Some(mtm) = MainThreadMarker::new()
+        {
+            manager.set_delegate(mtm);
+        }
+        manager
+    }
+
+    /// Sets the UNUserNotificationCenterDelegate, responses are buffered until [NotificationManager::register] sets the handler
+    fn set_delegate(&self, mtm: MainThreadMarker) {
+        if self.inner.delegate_reference.get().is_some() {
+            return;
+        }
+        let (tx, mut rx) = tokio::sync::mpsc::channel::<NotificationResponse>(10);
+        let notification_delegate =
+            NotificationDelegate::new(mtm, tx, self.inner.foreground_presentations.clone());
+        let proto: Retained<ProtocolObject<dyn UNUserNotificationCenterDelegate>> =
+            ProtocolObject::from_retained(notification_delegate);
+        unsafe {
+            UNUserNotificationCenter::currentNotificationCenter().setDelegate(Some(&*proto));
+        }
+        if self.inner.delegate_reference.set(proto).is_err() {
+            log::error!("delegate_reference was already set");
+        }
+
+        let handler = self.inner.handler.clone();
+        let launch_responses = self.inner.launch_responses.clone();
+        let handler_loop = thread::spawn(move || {
+            while let Some(response) = rx.blocking_recv() {
+                if let Some(response) = launch_responses.buffer(response)
+                    && let Some(handler) = handler.get()
+                {
+                    handler(response)
+                }
+            }
+        });
+        if self.inner.listener_loop.set(handler_loop).is_err() {
+            log::error!("listener_loop was already set");
+        }
+    }
+
+    /// See [NotificationBuilder::validate], also checks that the category is registered
+    async fn validate(&self, builder: &NotificationBuilder) -> Result<(), Error> {
+        builder.validate(&self.capabilities().await?)?;
+        if let Some(category_id) = &builder.category_id
+            && !self
+                .inner
+                .categories
+                .lock()
+                .map_err(|_| Error::SettingHandler)?
+                .iter()
+                .any(|category| &category.identifier == category_id)
+        {
+            return Err(unknown_category(category_id));
+        }
+        Ok(())
+    }
+
+    /// macOS only knows actions through categories, so notifications with their own actions get a category
+    /// that combines them with the actions of the category that was set on the notification
+    pub(super) fn category_with_actions(
+        &self,
+        category_id: Option<&str>,
+        actions: &[NotificationCategoryAction],
+    ) -> Result<String, Error> {
+        let mut categories = self
+            .inner
+            .categories
+            .lock()
+            .map_err(|_| Error::SettingHandler)?;
+        let mut combined_actions = category_id
+            .and_then(|id| categories.iter().find(|c| c.identifier == id))
+            .map(|category| category.actions.clone())
+            .unwrap_or_default();
+        combined_actions.extend(actions.iter().cloned());
+
+        let mut hasher = DefaultHasher::new();
+        combined_actions.hash(&mut hasher);
+        let identifier = format!("{GENERATED_CATEGORY_PREFIX}{:x}", hasher.finish());
+
+        if !categories.iter().any(|c| c.identifier == identifier) {
+            categories.push(NotificationCategory {
+                identifier: identifier.clone(),
+                actions: combined_actions,
+                ..Default::default()
+            });
+            set_native_categories(&categories);
+        }
+        Ok(identifier)
+    }
+
+    pub(super) fn remember_progress_notification(&self, id: String, builder: NotificationBuilder) {
+        if let Ok(mut progress_notifications) = self.inner.progress_notifications.lock() {
+            progress_notifications.insert(id, builder);
+        }
+    }
+
+    /// `None` resets it to the default presentation, when a notification is replaced
+    pub(super) fn remember_foreground_presentation(
+        &self,
+        id: String,
+        presentation: Option<ForegroundPresentation>,
+    ) {
+        if let Ok(mut foreground_presentations) = self.inner.foreground_presentations.lock() {
+            match presentation {
+                Some(presentation) => foreground_presentations.insert(id, presentation),
+                None => foreground_presentations.remove(&id),
+            };
+        }
+    }
+
+    /// Removes the delivered notification at `expiration`
+    pub(super) fn schedule_expiration(&self, id: &str, expiration: Option<DateTime<Utc>>) {
+        let manager = self.clone();
+        let expired_id = id.to_owned();
+        self.inner
+            .scheduler
+            .schedule_expiration(id, expiration, move || {
+                if let Err(err) = manager.remove_delivered_notifications(vec![&expired_id]) {
+                    log::error!("failed to remove expired notification: {err:?}");
+                }
+                manager.inner.events.emit(
+                    &expired_id,
+                    NotificationEventKind::Dismissed(NotificationDismissReason::Expired),
+                );
+            });
+    }
+
+    /// Adds the request with the trigger, the returned future resolves to its id once the notification center accepted it.
+    /// It is not an async fn, because the trigger is not Send.
+    fn add_scheduled(
+        &self,
+        builder: NotificationBuilder,
+        trigger: Option<Retained<UNNotificationTrigger>>,
+    ) -> impl Future<Output = Result<String, Error>> + Send {
+        let (tx, rx) = tokio::sync::oneshot::channel::<Result<(), Error>>();
+        let id = build_and_send(builder, self, None, trigger, tx).map(|handle| handle.get_id());
+        async move {
+            let id = id?;
+            rx.await??;
+            Ok(id)
         }
     }
+
     /// adds a notification to the notification center
     pub(super) fn add_notification<F: FnOnce(Result<(), Error>) + Send + 'static>(
         &self,
@@ -69,11 +273,7 @@ impl NotificationManagerMacOS {
                         cb(Ok(()));
                     }
                 } else if let Some(cb) = cb.take() {
-                    let Some(err_ref) = error.as_ref() else {
-                        return cb(Err(Error::NSError("Failed to read error".to_string())));
-                    };
-                    let description = err_ref.localizedDescription();
-                    cb(Err(Error::NSError(description.to_string())));
+                    cb(Err(ns_error_to_error(error.as_ref())));
                 }
             });
 
@@ -117,41 +317,123 @@ impl NotificationManager for NotificationManagerMacOS {
         Ok(rx.await?)
     }
 
+    /// https://developer.apple.com/documentation/usernotifications/unnotificationsettings
+    async fn get_notification_settings(&self) -> Result<NotificationSettings, Error> {
+        self.inner.bundle_id.as_ref().ok_or(Error::NoBundleId)?;
+        let (tx, rx) = tokio::sync::oneshot::channel::<NotificationSettings>();
+        unsafe {
+            let cb = RefCell::new(Some(tx));
+            let block = block2::RcBlock::new(move |settings: NonNull<UNNotificationSettings>| {
+                if let Some(cb) = cb.take() {
+                    let settings = settings.as_ref();
+                    // NotSupported means the setting does not exist on this device
+                    let setting = |setting: UNNotificationSetting| match setting {
+                        UNNotificationSetting::Enabled => Some(true),
+                        UNNotificationSetting::Disabled => Some(false),
+                        _ => None,
+                    };
+                    let alert_style = match settings.alertStyle() {
+                        UNAlertStyle::None => Some(NotificationAlertStyle::None),
+                        UNAlertStyle::Banner => Some(NotificationAlertStyle::Banner),
+                        UNAlertStyle::Alert => Some(NotificationAlertStyle::Alert),
+                        _ => None,
+                    };
+                    let notification_settings = NotificationSettings {
+                        authorized: matches!(
+                            settings.authorizationStatus(),
+                            UNAuthorizationStatus::Authorized
+                                | UNAuthorizationStatus::Provisional
+                                | UNAuthorizationStatus::Ephemeral
+                        ),
+                        alert_style,
+                        sound: setting(settings.soundSetting()),
+                        badge: setting(settings.badgeSetting()),
+                        lock_screen: setting(settings.lockScreenSetting()),
+                        notification_center: setting(settings.notificationCenterSetting()),
+                    };
+                    if cb.send(notification_settings).is_err() {
+                        log::error!("the receiver dropped");
+                    }
+                }
+            });
+            UNUserNotificationCenter::currentNotificationCenter()
+                .getNotificationSettingsWithCompletionHandler(&block);
+        }
+        Ok(rx.await?)
+    }
+
     /// https://developer.apple.com/documentation/usernotifications/unusernotificationcenter/requestauthorization(options:completionhandler:)
     async fn first_time_ask_for_notification_permission(&self) -> Result<bool, Error> {
         self.inner.bundle_id.as_ref().ok_or(Error::NoBundleId)?;
-        let (tx, rx) = tokio::sync::oneshot::channel::<Result<bool, Error>>();
+        let mut options = UNAuthorizationOptions::empty();
+        options.set(UNAuthorizationOptions::Alert, true);
+        options.set(UNAuthorizationOptions::Sound, true);
+        options.set(UNAuthorizationOptions::Badge, true);
+        request_authorization(options).await
+    }
 
-        #[inline]
-        fn request_autorization(tx: tokio::sync::oneshot::Sender<Result<bool, Error>>) {
+    /// https://developer.apple.com/documentation/usernotifications/unauthorizationoptions/provisional
+    async fn request_provisional_permission(&self) -> Result<bool, Error> {
+        self.inner.bundle_id.as_ref().ok_or(Error::NoBundleId)?;
+        let mut options = UNAuthorizationOptions::empty();
+        options.set(UNAuthorizationOptions::Alert, true);
+        options.set(UNAuthorizationOptions::Sound, true);
+        options.set(UNAuthorizationOptions::Badge, true);
+        options.set(UNAuthorizationOptions::Provisional, true);
+        request_authorization(options).await
+    }
+
+    /// https://developer.apple.com/documentation/usernotifications/unnotificationsettings
+    async fn capabilities(&self) -> Result<NotificationCapabilities, Error> {
+        self.inner.bundle_id.as_ref().ok_or(Error::NoBundleId)?;
+        let (tx, rx) = tokio::sync::oneshot::channel::<(bool, bool)>();
+        unsafe {
             let cb = RefCell::new(Some(tx));
-            let block = block2::RcBlock::new(move |authorized: Bool, error: *mut NSError| {
+            let block = block2::RcBlock::new(move |settings: NonNull<UNNotificationSettings>| {
                 if let Some(cb) = cb.take() {
-                    let result: Result<bool, Error> = if error.is_null() {
-                        Ok(authorized.as_bool())
-                    } else if let Some(err_ref) = unsafe { error.as_ref() } {
-                        let description = err_ref.localizedDescription();
-                        Err(Error::NSError(description.to_string()))
-                    } else {
-                        Err(Error::NSError("Failed to read error".to_string()))
-                    };
-                    if cb.send(result).is_err() {
+                    let settings = settings.as_ref();
+                    let badges = settings.badgeSetting() == UNNotificationSetting::Enabled;
+                    let critical_alerts =
+                        settings.criticalAlertSetting() == UNNotificationSetting::Enabled;
+                    if cb.send((badges, critical_alerts)).is_err() {
                         log::error!("the receiver dropped");
                     }
                 }
             });
+            UNUserNotificationCenter::currentNotificationCenter()
+                .getNotificationSettingsWithCompletionHandler(&block);
+        }
+        let (badges, critical_alerts) = rx.await?;
+        Ok(NotificationCapabilities {
+            actions: true,
+            body_markup: false,
+            persistence: true,
+            inline_reply: true,
+            attachments: true,
+            media_attachments: true,
+            animated_images: true,
+            max_image_size: Some(10_000_000),
+            // shown as text in the body
+            progress: false,
+            scheduling: true,
+            location_triggers: location_triggers_available(),
+            badges,
+            critical_alerts,
+            thread_summaries: true,
+            ..Default::default()
+        })
+    }
 
-            let mut options = UNAuthorizationOptions::empty();
-            options.set(UNAuthorizationOptions::Alert, true);
-            options.set(UNAuthorizationOptions::Sound, true);
-            options.set(UNAuthorizationOptions::Badge, true);
-            unsafe {
-                UNUserNotificationCenter::currentNotificationCenter()
-                    .requestAuthorizationWithOptions_completionHandler(options, &block);
-            }
+    /// https://developer.apple.com/documentation/intents/infocusstatus/isfocused
+    async fn is_do_not_disturb_active(&self) -> Result<bool, Error> {
+        let mut status = focus_authorization_status()?;
+        if status == FOCUS_STATUS_NOT_DETERMINED {
+            status = request_focus_status_authorization().await?;
         }
-        request_autorization(tx);
-        Ok(rx.await??)
+        if status != FOCUS_STATUS_AUTHORIZED {
+            return Err(Error::PermissionDenied);
+        }
+        is_focused()
     }
 
     // TODO find out if it makes a difference when this is called
@@ -163,41 +445,93 @@ impl NotificationManager for NotificationManagerMacOS {
     ) -> Result<(), crate::Error> {
         log::debug!("NotificationManager.register called");
         let mtm = MainThreadMarker::new().expect("not on main thread");
-        let (tx, mut rx) = tokio::sync::mpsc::channel::<NotificationResponse>(10);
-        let notification_delegate = NotificationDelegate::new(mtm, tx);
+        self.inner
+            .handler
+            .register(self.inner.events.wrap_handler(handler_callback))?;
+        self.set_delegate(mtm);
+        if let Some(handler) = self.inner.handler.get() {
+            for response in self.inner.launch_responses.registered() {
+                handler(response);
+            }
+        }
         unsafe {
-            let proto: Retained<ProtocolObject<dyn UNUserNotificationCenterDelegate>> =
-                ProtocolObject::from_retained(notification_delegate);
-
             let notification_center = UNUserNotificationCenter::currentNotificationCenter();
-            notification_center.setDelegate(Some(&*proto));
-
-            self.inner.delegate_reference
-                .set(proto)
-                .expect("failed to set delegate_reference, did you call register multiple times so that the once_cell was already taken?");
 
+            if let Ok(mut stored_categories) = self.inner.categories.lock() {
+                *stored_categories = categories.clone();
+            }
             let categories: Retained<NSSet<_>> = categories
                 .into_iter()
                 .map(|category| W(category_to_native_category(category)))
                 .collect();
             notification_center.setNotificationCategories(&categories);
-
-            let handler_loop = thread::spawn(move || {
-                while let Some(response) = rx.blocking_recv() {
-                    handler_callback(response)
-                }
-            });
-            self.inner.listener_loop.set(handler_loop).expect("failed to set delegate_reference, did you call register multiple times so that the once_cell was already taken?");
         }
         log::debug!("NotificationManager.register completed");
         Ok(())
     }
 
+    fn set_response_handler(
+        &self,
+        handler_callback: Box<dyn Fn(crate::NotificationResponse) + Send + Sync + 'static>,
+    ) -> Result<(), crate::Error> {
+        self.inner
+            .handler
+            .replace(self.inner.events.wrap_handler(handler_callback))
+    }
+
+    fn take_launch_response(&self) -> Option<NotificationResponse> {
+        self.inner.launch_responses.take()
+    }
+
+    fn subscribe_events(&self) -> tokio::sync::broadcast::Receiver<NotificationEvent> {
+        self.inner.events.subscribe()
+    }
+
+    fn add_categories(&self, categories: Vec<NotificationCategory>) -> Result<(), Error> {
+        self.inner.bundle_id.as_ref().ok_or(Error::NoBundleId)?;
+        let mut stored_categories = self
+            .inner
+            .categories
+            .lock()
+            .map_err(|_| Error::SettingHandler)?;
+        for category in categories {
+            stored_categories.retain(|c| c.identifier != category.identifier);
+            stored_categories.push(category);
+        }
+        set_native_categories(&stored_categories);
+        Ok(())
+    }
+
+    fn set_categories(&self, categories: Vec<NotificationCategory>) -> Result<(), Error> {
+        self.inner.bundle_id.as_ref().ok_or(Error::NoBundleId)?;
+        let mut stored_categories = self
+            .inner
+            .categories
+            .lock()
+            .map_err(|_| Error::SettingHandler)?;
+        *stored_categories = categories;
+        set_native_categories(&stored_categories);
+        Ok(())
+    }
+
+    fn remove_category(&self, identifier: &str) -> Result<(), Error> {
+        self.inner.bundle_id.as_ref().ok_or(Error::NoBundleId)?;
+        let mut stored_categories = self
+            .inner
+            .categories
+            .lock()
+            .map_err(|_| Error::SettingHandler)?;
+        stored_categories.retain(|c| c.identifier != identifier);
+        set_native_categories(&stored_categories);
+        Ok(())
+    }
+
     /// Removes all of your app’s delivered notifications from Notification Center.
     ///
     /// https://developer.apple.com/documentation/usernotifications/unusernotificationcenter/removealldeliverednotifications()
     fn remove_all_delivered_notifications(&self) -> Result<(), Error> {
         self.inner.bundle_id.as_ref().ok_or(Error::NoBundleId)?;
+        self.inner.events.remove_response_callbacks(None);
 
         unsafe {
             UNUserNotificationCenter::currentNotificationCenter().removeAllDeliveredNotifications();
@@ -207,6 +541,7 @@ impl NotificationManager for NotificationManagerMacOS {
 
     fn remove_delivered_notifications(&self, ids: Vec<&str>) -> Result<(), Error> {
         self.inner.bundle_id.as_ref().ok_or(Error::NoBundleId)?;
+        self.inner.events.remove_response_callbacks(Some(&ids));
 
         let ids: Vec<_> = ids.iter().map(|s| NSString::from_str(s)).collect();
         let array: Retained<NSArray<NSString>> = NSArray::from_retained_slice(ids.as_slice());
@@ -241,10 +576,32 @@ impl NotificationManager for NotificationManagerMacOS {
                                 let request = item.request();
                                 let id = request.identifier().to_string();
 
-                                let user_info =
-                                    user_info_dictionary_to_hashmap(request.content().userInfo());
+                                let content = request.content();
+                                let user_info = user_info_dictionary_to_hashmap(content.userInfo());
+                                let thread_id = content.threadIdentifier().to_string();
+                                let non_empty = |value: Retained<NSString>| {
+                                    let value = value.to_string();
+                                    (!value.is_empty()).then_some(value)
+                                };
+                                let delivered_at = DateTime::from_timestamp_millis(
+                                    (item.date().timeIntervalSince1970() * 1000.0) as i64,
+                                );
 
-                                handles.push(NotificationHandleMacOS::new(id, user_info));
+                                handles.push(NotificationHandleMacOS::new(
+                                    id,
+                                    (!thread_id.is_empty()).then_some(thread_id),
+                                    user_info,
+                                    NotificationContent {
+                                        title: non_empty(content.title()),
+                                        subtitle: non_empty(content.subtitle()),
+                                        body: non_empty(content.body()),
+                                        category_id: non_empty(content.categoryIdentifier())
+                                            .filter(|id| {
+                                                !id.starts_with(GENERATED_CATEGORY_PREFIX)
+                                            }),
+                                        delivered_at,
+                                    },
+                                ));
                             }
                         }
 
@@ -277,11 +634,222 @@ impl NotificationManager for NotificationManagerMacOS {
         &self,
         builder: NotificationBuilder,
     ) -> Result<Box<dyn NotificationHandle>, Error> {
+        self.validate(&builder).await?;
+        if let Some(duplicate) = find_duplicate(self, &builder).await? {
+            return Ok(duplicate);
+        }
         let (tx, rx) = tokio::sync::oneshot::channel::<Result<(), Error>>();
-        let handle = build_and_send(builder, self, tx)?;
+        let id = builder.preset_id.clone();
+        let handle = build_and_send(builder, self, id, None, tx)?;
         rx.await??;
         Ok::<_, Error>(Box::new(handle) as Box<dyn NotificationHandle>)
     }
+
+    /// Posting a request with the identifier of a delivered notification replaces it
+    async fn update_notification(
+        &self,
+        id: &str,
+        builder: NotificationBuilder,
+    ) -> Result<Box<dyn NotificationHandle>, Error> {
+        self.validate(&builder).await?;
+        let (tx, rx) = tokio::sync::oneshot::channel::<Result<(), Error>>();
+        let handle = build_and_send(builder, self, Some(id.to_owned()), None, tx)?;
+        rx.await??;
+        Ok(Box::new(handle) as Box<dyn NotificationHandle>)
+    }
+
+    async fn update_progress(
+        &self,
+        id: &str,
+        state: ProgressState,
+        status: &str,
+    ) -> Result<(), Error> {
+        let mut builder = self
+            .inner
+            .progress_notifications
+            .lock()
+            .map_err(|_| Error::SettingHandler)?
+            .get(id)
+            .cloned()
+            .ok_or_else(|| Error::NotificationNotFound(id.to_owned()))?;
+        if let Some(progress) = builder.progress.as_mut() {
+            progress.state = state;
+            progress.status = status.to_owned();
+        }
+        self.update_notification(id, builder).await?;
+        Ok(())
+    }
+
+    /// https://developer.apple.com/documentation/usernotifications/unusernotificationcenter/setbadgecount(_:withcompletionhandler:)
+    async fn set_badge_count(&self, count: u32) -> Result<(), Error> {
+        self.inner.bundle_id.as_ref().ok_or(Error::NoBundleId)?;
+        let (tx, rx) = tokio::sync::oneshot::channel::<Result<(), Error>>();
+        unsafe {
+            let cb = RefCell::new(Some(tx));
+            let block = block2::RcBlock::new(move |error: *mut NSError| {
+                if let Some(cb) = cb.take() {
+                    let result = if error.is_null() {
+                        Ok(())
+                    } else {
+                        Err(ns_error_to_error(error.as_ref()))
+                    };
+                    if cb.send(result).is_err() {
+                        log::error!("the receiver dropped");
+                    }
+                }
+            });
+            UNUserNotificationCenter::currentNotificationCenter()
+                .setBadgeCount_withCompletionHandler(count as NSInteger, Some(&block));
+        }
+        rx.await??;
+        self.inner.badge_count.store(count, Ordering::Relaxed);
+        Ok(())
+    }
+
+    async fn get_badge_count(&self) -> Result<u32, Error> {
+        Ok(self.inner.badge_count.load(Ordering::Relaxed))
+    }
+
+    async fn schedule_notification(
+        &self,
+        builder: NotificationBuilder,
+        at: DateTime<Utc>,
+    ) -> Result<String, Error> {
+        self.validate(&builder).await?;
+        let builder = builder.apply_auto_dismiss(at);
+        let interval = (at - Utc::now()).num_milliseconds() as f64 / 1000.0;
+        // the interval needs to be greater than 0, otherwise UNTimeIntervalNotificationTrigger throws
+        let trigger = (interval > 0.0).then(|| unsafe {
+            Retained::into_super(
+                UNTimeIntervalNotificationTrigger::triggerWithTimeInterval_repeats(interval, false),
+            )
+        });
+        self.add_scheduled(builder, trigger).await
+    }
+
+    async fn schedule_repeating_notification(
+        &self,
+        builder: NotificationBuilder,
+        interval: RepeatInterval,
+    ) -> Result<String, Error> {
+        self.validate(&builder).await?;
+        // repeating time interval triggers need to be at least 60 seconds
+        let interval = interval.as_duration().max(MIN_REPEAT_INTERVAL);
+        let builder = builder.apply_auto_dismiss(Utc::now() + interval);
+        let trigger = unsafe {
+            Retained::into_super(
+                UNTimeIntervalNotificationTrigger::triggerWithTimeInterval_repeats(
+                    interval.as_secs_f64(),
+                    true,
+                ),
+            )
+        };
+        self.add_scheduled(builder, Some(trigger)).await
+    }
+
+    async fn schedule_calendar_notification(
+        &self,
+        builder: NotificationBuilder,
+        trigger: CalendarTrigger,
+    ) -> Result<String, Error> {
+        self.validate(&builder).await?;
+        let first_fire_time = trigger.first_fire_time()?;
+        let builder = builder.apply_auto_dismiss(first_fire_time);
+        let components = NSDateComponents::new();
+        components.setHour(trigger.hour as NSInteger);
+        components.setMinute(trigger.minute as NSInteger);
+        if let Some(weekday) = trigger.weekday {
+            // 1 is Sunday in the gregorian calendar
+            components.setWeekday(weekday.number_from_sunday() as NSInteger);
+        }
+        if let Some(day) = trigger.day {
+            components.setDay(day as NSInteger);
+        }
+        if let Some(month) = trigger.month {
+            components.setMonth(month as NSInteger);
+        }
+        let trigger = Retained::into_super(
+            UNCalendarNotificationTrigger::triggerWithDateMatchingComponents_repeats(
+                &components,
+                trigger.repeats,
+            ),
+        );
+        self.add_scheduled(builder, Some(trigger)).await
+    }
+
+    #[cfg(feature = "macos-location")]
+    async fn schedule_location_notification(
+        &self,
+        builder: NotificationBuilder,
+        trigger: crate::LocationTrigger,
+    ) -> Result<String, Error> {
+        use objc2::AnyThread;
+        use objc2_core_location::{CLCircularRegion, CLLocationCoordinate2D};
+        use objc2_user_notifications::UNLocationNotificationTrigger;
+
+        self.validate(&builder).await?;
+        if !location_triggers_available() {
+            return Err(Error::Unsupported("location triggers".to_owned()));
+        }
+        let center = CLLocationCoordinate2D {
+            latitude: trigger.latitude,
+            longitude: trigger.longitude,
+        };
+        let identifier = NSString::from_str(&uuid::Uuid::new_v4().to_string());
+        // CLCircularRegion is deprecated in favor of CLMonitor conditions, but the notification trigger only takes regions
+        #[allow(deprecated)]
+        let region = unsafe {
+            let region = CLCircularRegion::initWithCenter_radius_identifier(
+                CLCircularRegion::alloc(),
+                center,
+                trigger.radius,
+                &identifier,
+            );
+            region.setNotifyOnEntry(trigger.notify_on_entry);
+            region.setNotifyOnExit(trigger.notify_on_exit);
+            region
+        };
+        let trigger = Retained::into_super(
+            UNLocationNotificationTrigger::triggerWithRegion_repeats(&region, trigger.repeats),
+        );
+        self.add_scheduled(builder, Some(trigger)).await
+    }
+
+    /// https://developer.apple.com/documentation/usernotifications/unusernotificationcenter/removependingnotificationrequests(withidentifiers:)
+    fn cancel_scheduled_notification(&self, id: &str) -> Result<(), Error> {
+        self.inner.bundle_id.as_ref().ok_or(Error::NoBundleId)?;
+
+        let array: Retained<NSArray<NSString>> =
+            NSArray::from_retained_slice(&[NSString::from_str(id)]);
+
+        unsafe {
+            UNUserNotificationCenter::currentNotificationCenter()
+                .removePendingNotificationRequestsWithIdentifiers(&array);
+        }
+        Ok(())
+    }
+}
+
+/// Converts the error of a completion handler, `None` if it could not be read
+pub(crate) fn ns_error_to_error(error: Option<&NSError>) -> Error {
+    let Some(error) = error else {
+        return Error::NSError {
+            domain: String::new(),
+            code: 0,
+            description: "Failed to read error".to_owned(),
+        };
+    };
+    let domain = error.domain().to_string();
+    let code = error.code();
+    // https://developer.apple.com/documentation/usernotifications/unerror/code/notificationsnotallowed
+    if domain == "UNErrorDomain" && code == 1 {
+        return Error::PermissionDenied;
+    }
+    Error::NSError {
+        domain,
+        code,
+        description: error.localizedDescription().to_string(),
+    }
 }
 
 pub(crate) fn user_info_dictionary_to_hashmap(
@@ -317,10 +885,11 @@ fn category_to_native_category(category: NotificationCategory) -> Retained<UNNot
     let actions:Retained<_> = category
         .actions
         .iter()
+        .flat_map(NotificationCategoryAction::expand_selection)
         .map(|action| {
             use crate::NotificationCategoryAction::*;
-            match action {
-                Action { identifier, title } => {
+            match &action {
+                Action { identifier, title } | SelectionInputAction { identifier, title, .. } => {
                     let identifier = NSString::from_str(identifier);
                     let title = NSString::from_str(title);
                     unsafe {
@@ -336,6 +905,8 @@ fn category_to_native_category(category: NotificationCategory) -> Retained<UNNot
                     title,
                     input_button_title,
                     input_placeholder,
+                    // UNTextInputNotificationAction has no default text
+                    ..
                 } => {
                     let identifier = NSString::from_str(identifier);
                     let title = NSString::from_str(title);
@@ -351,16 +922,39 @@ fn category_to_native_category(category: NotificationCategory) -> Retained<UNNot
         })
         .collect();
 
+    let hidden_previews_body_placeholder = category
+        .hidden_previews_body_placeholder
+        .map(|placeholder| NSString::from_str(&placeholder));
+    let summary_format = category
+        .summary_format
+        .map(|summary_format| NSString::from_str(&summary_format));
+
     unsafe {
-        UNNotificationCategory::categoryWithIdentifier_actions_intentIdentifiers_options(
+        UNNotificationCategory::categoryWithIdentifier_actions_intentIdentifiers_hiddenPreviewsBodyPlaceholder_categorySummaryFormat_options(
             &identifier,
             &actions,
             &NSArray::new(),
-            UNNotificationCategoryOptions::empty(),
+            hidden_previews_body_placeholder.as_deref(),
+            summary_format.as_deref(),
+            // so dismissing the notification is reported to the delegate
+            UNNotificationCategoryOptions::CustomDismissAction,
         )
     }
 }
 
+/// replaces all categories of the app with these categories
+fn set_native_categories(categories: &[NotificationCategory]) {
+    let native_categories: Retained<NSSet<_>> = categories
+        .iter()
+        .cloned()
+        .map(|category| W(category_to_native_category(category)))
+        .collect();
+    unsafe {
+        UNUserNotificationCenter::currentNotificationCenter()
+            .setNotificationCategories(&native_categories);
+    }
+}
+
 /// wrapper to bypass that the I can't implement traits for objc2's Retained here in this crate
 struct W<T: ?Sized + Message>(Retained<T>);
 
@@ -391,3 +985,94 @@ impl<O: Message> FromIterator<W<O>> for Retained<NSSet<O>> {
         set
     }
 }
+
+async fn request_authorization(options: UNAuthorizationOptions) -> Result<bool, Error> {
+    let (tx, rx) = tokio::sync::oneshot::channel::<Result<bool, Error>>();
+
+    #[inline]
+    fn request(
+        options: UNAuthorizationOptions,
+        tx: tokio::sync::oneshot::Sender<Result<bool, Error>>,
+    ) {
+        let cb = RefCell::new(Some(tx));
+        let block = block2::RcBlock::new(move |authorized: Bool, error: *mut NSError| {
+            if let Some(cb) = cb.take() {
+                let result: Result<bool, Error> = if error.is_null() {
+                    Ok(authorized.as_bool())
+                } else {
+                    Err(ns_error_to_error(unsafe { error.as_ref() }))
+                };
+                if cb.send(result).is_err() {
+                    log::error!("the receiver dropped");
+                }
+            }
+        });
+
+        unsafe {
+            UNUserNotificationCenter::currentNotificationCenter()
+                .requestAuthorizationWithOptions_completionHandler(options, &block);
+        }
+    }
+    request(options, tx);
+    Ok(rx.await??)
+}
+
+// INFocusStatusCenter and the INSendMessageIntent of communication notifications are in the Intents framework,
+// which has no objc2 bindings in our dependencies, so it is called through the objc runtime
+#[link(name = "Intents", kind = "framework")]
+unsafe extern "C" {}
+
+/// [INFocusStatusAuthorizationStatus](https://developer.apple.com/documentation/intents/infocusstatusauthorizationstatus)
+const FOCUS_STATUS_NOT_DETERMINED: NSInteger = 0;
+const FOCUS_STATUS_AUTHORIZED: NSInteger = 3;
+
+/// https://developer.apple.com/documentation/intents/infocusstatuscenter/default
+fn focus_status_center() -> Result<Retained<AnyObject>, Error> {
+    let class = objc2::runtime::AnyClass::get(c"INFocusStatusCenter")
+        .ok_or_else(|| Error::Unsupported("focus status before macOS 12".to_owned()))?;
+    let center: Option<Retained<AnyObject>> = unsafe { msg_send![class, defaultCenter] };
+    center.ok_or_else(|| Error::Unsupported("focus status".to_owned()))
+}
+
+fn focus_authorization_status() -> Result<NSInteger, Error> {
+    let center = focus_status_center()?;
+    Ok(unsafe { msg_send![&*center, authorizationStatus] })
+}
+
+fn is_focused() -> Result<bool, Error> {
+    let center = focus_status_center()?;
+    let focus_status: Option<Retained<AnyObject>> = unsafe { msg_send![&*center, focusStatus] };
+    let is_focused: Option<Retained<NSNumber>> =
+        focus_status.and_then(|focus_status| unsafe { msg_send![&*focus_status, isFocused] });
+    // nil when the system does not know, then notifications are not held back either
+    Ok(is_focused.is_some_and(|is_focused| is_focused.boolValue()))
+}
+
+/// https://developer.apple.com/documentation/intents/infocusstatuscenter/requestauthorization(completionhandler:)
+async fn request_focus_status_authorization() -> Result<NSInteger, Error> {
+    let (tx, rx) = tokio::sync::oneshot::channel::<NSInteger>();
+
+    #[inline]
+    fn request(tx: tokio::sync::oneshot::Sender<NSInteger>) -> Result<(), Error> {
+        let center = focus_status_center()?;
+        let cb = RefCell::new(Some(tx));
+        let block = block2::RcBlock::new(move |status: NSInteger| {
+            if let Some(cb) = cb.take()
+                && cb.send(status).is_err()
+            {
+                log::error!("the receiver dropped");
+            }
+        });
+        let _: () =
+            unsafe { msg_send![&*center, requestAuthorizationWithCompletionHandler: &*block] };
+        Ok(())
+    }
+    request(tx)?;
+    Ok(rx.await?)
+}
+
+/// UNLocationNotificationTrigger only exists on iOS, Mac Catalyst and visionOS
+fn location_triggers_available() -> bool {
+    cfg!(feature = "macos-location")
+        && objc2::runtime::AnyClass::get(c"UNLocationNotificationTrigger").is_some()
+}
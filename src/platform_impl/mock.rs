@@ -1,18 +1,41 @@
 //! This manager logs the calls to it and does nothing more.
 //! It can be used for testing application code,
-//! or as a fallback for tauri's devmode that runs the app without a bundle id
+//! for headless environments use [NoopNotificationManager](super::noop::NoopNotificationManager)
+//!
+//! For tests it records the sent notifications ([NotificationManagerMock::sent_notifications])
+//! and can simulate user responses ([NotificationManagerMock::simulate_response])
+//! and do not disturb ([NotificationManagerMock::simulate_do_not_disturb]).
 
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc, OnceLock,
+        atomic::{AtomicBool, AtomicU32, Ordering},
+    },
+};
 
 use async_trait::async_trait;
-use tokio::sync::RwLock;
+use chrono::{DateTime, Utc};
+use tokio::sync::{RwLock, broadcast};
 
-use crate::{NotificationBuilder, NotificationHandle, NotificationManager};
+use crate::{
+    CalendarTrigger, Error, NotificationBuilder, NotificationCapabilities, NotificationContent,
+    NotificationDismissReason, NotificationEvent, NotificationEventKind, NotificationHandle,
+    NotificationManager, NotificationResponse, NotificationResponseAction, ProgressState,
+    RepeatInterval,
+    events::NotificationEvents,
+    find_duplicate,
+    launch_response::LaunchResponses,
+    response_handler::ResponseHandler,
+    scheduler::{Schedule, Scheduler},
+};
 
 #[derive(Debug, Clone)]
 pub struct NotificationHandleMock {
     id: String,
+    thread_id: Option<String>,
     user_info: HashMap<String, String>,
+    content: NotificationContent,
 }
 
 impl NotificationHandle for NotificationHandleMock {
@@ -25,14 +48,41 @@ impl NotificationHandle for NotificationHandleMock {
         self.id.clone()
     }
 
-    fn get_user_info(&self) -> &HashMap<String, String> {
+    fn get_stored_user_info(&self) -> &HashMap<String, String> {
         &self.user_info
     }
+
+    fn get_thread_id(&self) -> Option<&str> {
+        self.thread_id.as_deref()
+    }
+
+    fn get_content(&self) -> &NotificationContent {
+        &self.content
+    }
 }
 
-#[derive(Debug, Default)]
+#[derive(Default, Clone)]
 pub struct NotificationManagerMock {
-    active_notifications: RwLock<Vec<NotificationHandleMock>>,
+    active_notifications: Arc<RwLock<Vec<NotificationHandleMock>>>,
+    sent_notifications: Arc<RwLock<Vec<NotificationBuilder>>>,
+    handler: ResponseHandler,
+    launch_responses: LaunchResponses,
+    events: NotificationEvents,
+    scheduler: Scheduler,
+    badge_count: Arc<AtomicU32>,
+    /// notifications with progress, to show them again with the new progress
+    progress_notifications: Arc<RwLock<HashMap<String, NotificationBuilder>>>,
+    do_not_disturb: Arc<AtomicBool>,
+    do_not_disturb_changes: Arc<OnceLock<broadcast::Sender<bool>>>,
+}
+
+impl std::fmt::Debug for NotificationManagerMock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NotificationManagerMock")
+            .field("active_notifications", &self.active_notifications)
+            .field("handler", &self.handler.is_set().to_string())
+            .finish()
+    }
 }
 
 impl NotificationManagerMock {
@@ -40,8 +90,120 @@ impl NotificationManagerMock {
         Self::default()
     }
 
+    /// Replaces the notification with the same id, like the platforms do
     async fn add_notification(&self, notification: NotificationHandleMock) {
-        self.active_notifications.write().await.push(notification);
+        let mut active_notifications = self.active_notifications.write().await;
+        active_notifications.retain(|n| n.id != notification.id);
+        active_notifications.push(notification);
+    }
+
+    /// All notifications that were shown so far, including updated and delivered scheduled notifications,
+    /// in the order they were shown
+    pub async fn sent_notifications(&self) -> Vec<NotificationBuilder> {
+        self.sent_notifications.read().await.clone()
+    }
+
+    /// Calls the handler set in [NotificationManager::register] as if the user responded to the notification,
+    /// the user info is taken from the active notification with this id.
+    ///
+    /// Before the handler is registered the response is buffered like on the real platforms,
+    /// the first one is then returned by [NotificationManager::take_launch_response].
+    pub async fn simulate_response(
+        &self,
+        notification_id: &str,
+        action: NotificationResponseAction,
+        user_text: Option<String>,
+    ) -> Result<(), Error> {
+        let (user_info, delivered_at) = self
+            .active_notifications
+            .read()
+            .await
+            .iter()
+            .find(|n| n.id == notification_id)
+            .map(|n| (n.user_info.clone(), n.content.delivered_at))
+            .unwrap_or_default();
+        // like the real platforms, a dismissed notification is not active anymore
+        if action == NotificationResponseAction::Dismiss {
+            self.active_notifications
+                .write()
+                .await
+                .retain(|n| n.id != notification_id);
+            self.progress_notifications
+                .write()
+                .await
+                .remove(notification_id);
+        }
+        let response =
+            NotificationResponse::new(notification_id.to_owned(), action, user_text, user_info)
+                .set_delivered_at(delivered_at);
+        if let Some(response) = self.launch_responses.buffer(response) {
+            let handler = self.handler.get().ok_or(Error::SettingHandler)?;
+            handler(response);
+        }
+        Ok(())
+    }
+
+    /// Turns do not disturb on or off, see [NotificationManager::is_do_not_disturb_active],
+    /// the change is sent to the subscribers of [NotificationManager::subscribe_do_not_disturb]
+    pub fn simulate_do_not_disturb(&self, active: bool) {
+        if self.do_not_disturb.swap(active, Ordering::Relaxed) != active {
+            // fails when there is no subscriber, which is fine
+            let _ = self.do_not_disturb_sender().send(active);
+        }
+    }
+
+    fn do_not_disturb_sender(&self) -> &broadcast::Sender<bool> {
+        self.do_not_disturb_changes
+            .get_or_init(|| broadcast::channel(8).0)
+    }
+
+    async fn show_notification(
+        &self,
+        id: String,
+        builder: NotificationBuilder,
+    ) -> NotificationHandleMock {
+        if builder.progress.is_some() {
+            self.progress_notifications
+                .write()
+                .await
+                .insert(id.clone(), builder.clone());
+        }
+        let handle = NotificationHandleMock {
+            id,
+            thread_id: builder.thread_id.clone(),
+            user_info: builder.user_info.clone().unwrap_or_default(),
+            content: NotificationContent::from(&builder),
+        };
+        self.events
+            .set_response_callback(&handle.id, &builder.response_callback);
+        self.sent_notifications.write().await.push(builder);
+        self.add_notification(handle.clone()).await;
+        self.events
+            .emit(&handle.id, NotificationEventKind::Presented);
+        handle
+    }
+
+    /// Without native scheduling the notification is shown by a timer in this process
+    fn schedule(
+        &self,
+        builder: NotificationBuilder,
+        schedule: Schedule,
+    ) -> Result<String, crate::Error> {
+        let manager = self.clone();
+        self.scheduler.schedule_notification(
+            uuid::Uuid::new_v4().to_string(),
+            builder,
+            schedule,
+            move |id, builder| {
+                let manager = manager.clone();
+                async move {
+                    // repeating notifications replace the previous repetition in place
+                    let handle = manager.show_notification(id, builder).await;
+                    log::info!("NotificationManagerMock - show scheduled notification {handle:?}");
+                    Ok(())
+                }
+            },
+        )
     }
 }
 
@@ -57,18 +219,90 @@ impl NotificationManager for NotificationManagerMock {
         Ok(true)
     }
 
+    /// What the mock keeps track of, it accepts the notifications that use it
+    async fn capabilities(&self) -> Result<NotificationCapabilities, crate::Error> {
+        Ok(NotificationCapabilities {
+            actions: true,
+            persistence: true,
+            inline_reply: true,
+            attachments: true,
+            scheduling: true,
+            ..Default::default()
+        })
+    }
+
+    async fn is_do_not_disturb_active(&self) -> Result<bool, crate::Error> {
+        Ok(self.do_not_disturb.load(Ordering::Relaxed))
+    }
+
+    fn subscribe_do_not_disturb(&self) -> Result<broadcast::Receiver<bool>, crate::Error> {
+        Ok(self.do_not_disturb_sender().subscribe())
+    }
+
     fn register(
         &self,
-        _handler_callback: Box<dyn Fn(crate::NotificationResponse) + Send + Sync + 'static>,
+        handler_callback: Box<dyn Fn(crate::NotificationResponse) + Send + Sync + 'static>,
         categories: Vec<crate::NotificationCategory>,
     ) -> Result<(), crate::Error> {
         log::info!("NotificationManagerMock::register {categories:?}");
+        self.handler
+            .register(self.events.wrap_handler(handler_callback))?;
+        if let Some(handler) = self.handler.get() {
+            for response in self.launch_responses.registered() {
+                handler(response);
+            }
+        }
+        Ok(())
+    }
+
+    fn set_response_handler(
+        &self,
+        handler_callback: Box<dyn Fn(crate::NotificationResponse) + Send + Sync + 'static>,
+    ) -> Result<(), crate::Error> {
+        self.handler
+            .replace(self.events.wrap_handler(handler_callback))
+    }
+
+    fn take_launch_response(&self) -> Option<NotificationResponse> {
+        self.launch_responses.take()
+    }
+
+    /// Removing notifications is reported as [NotificationDismissReason::ClosedByApp], like on Linux
+    fn subscribe_events(&self) -> tokio::sync::broadcast::Receiver<NotificationEvent> {
+        self.events.subscribe()
+    }
+
+    fn add_categories(
+        &self,
+        categories: Vec<crate::NotificationCategory>,
+    ) -> Result<(), crate::Error> {
+        log::info!("NotificationManagerMock::add_categories {categories:?}");
+        Ok(())
+    }
+
+    fn set_categories(
+        &self,
+        categories: Vec<crate::NotificationCategory>,
+    ) -> Result<(), crate::Error> {
+        log::info!("NotificationManagerMock::set_categories {categories:?}");
+        Ok(())
+    }
+
+    fn remove_category(&self, identifier: &str) -> Result<(), crate::Error> {
+        log::info!("NotificationManagerMock::remove_category {identifier}");
         Ok(())
     }
 
     fn remove_all_delivered_notifications(&self) -> Result<(), crate::Error> {
+        self.events.remove_response_callbacks(None);
         let mut active_notifications = self.active_notifications.try_write()?;
-        let removed_notifiactions = active_notifications.drain(..);
+        let removed_notifiactions: Vec<_> = active_notifications.drain(..).collect();
+        for notification in &removed_notifiactions {
+            self.events.emit(
+                &notification.id,
+                NotificationEventKind::Dismissed(NotificationDismissReason::ClosedByApp),
+            );
+        }
         log::info!(
             "NotificationManagerMock::remove_all_delivered_notifications -> removed the following notifications {removed_notifiactions:?}"
         );
@@ -76,6 +310,7 @@ impl NotificationManager for NotificationManagerMock {
     }
 
     fn remove_delivered_notifications(&self, ids: Vec<&str>) -> Result<(), crate::Error> {
+        self.events.remove_response_callbacks(Some(&ids));
         let mut active_notifications = self.active_notifications.try_write()?;
         let all_notifications = active_notifications.drain(..);
         let mut kept = Vec::new();
@@ -88,6 +323,12 @@ impl NotificationManager for NotificationManagerMock {
             }
         }
         active_notifications.append(&mut kept);
+        for notification in &removed {
+            self.events.emit(
+                &notification.id,
+                NotificationEventKind::Dismissed(NotificationDismissReason::ClosedByApp),
+            );
+        }
 
         log::info!(
             "NotificationManagerMock::remove_delivered_notifications -> removed the following notifications {removed:?}"
@@ -113,15 +354,446 @@ impl NotificationManager for NotificationManagerMock {
         &self,
         builder: NotificationBuilder,
     ) -> Result<Box<dyn NotificationHandle>, crate::Error> {
+        if let Some(duplicate) = find_duplicate(self, &builder).await? {
+            return Ok(duplicate);
+        }
         log::info!("show notification {self:?}");
-        let id = uuid::Uuid::new_v4().to_string();
+        let id = builder.notification_id(|| uuid::Uuid::new_v4().to_string());
+        let handle = self.show_notification(id, builder).await;
+        Ok(Box::new(handle) as Box<dyn NotificationHandle>)
+    }
 
-        let handle = NotificationHandleMock {
-            id,
-            user_info: builder.user_info.unwrap_or_default(),
+    async fn update_notification(
+        &self,
+        id: &str,
+        builder: NotificationBuilder,
+    ) -> Result<Box<dyn NotificationHandle>, crate::Error> {
+        log::info!("NotificationManagerMock::update_notification {id}");
+        self.active_notifications
+            .write()
+            .await
+            .retain(|n| n.id != id);
+        let handle = self.show_notification(id.to_owned(), builder).await;
+        Ok(Box::new(handle) as Box<dyn NotificationHandle>)
+    }
+
+    async fn update_progress(
+        &self,
+        id: &str,
+        state: ProgressState,
+        status: &str,
+    ) -> Result<(), crate::Error> {
+        log::info!("NotificationManagerMock::update_progress {id} {state:?} {status}");
+        let mut builder = self
+            .progress_notifications
+            .read()
+            .await
+            .get(id)
+            .cloned()
+            .ok_or_else(|| Error::NotificationNotFound(id.to_owned()))?;
+        if let Some(progress) = builder.progress.as_mut() {
+            progress.state = state;
+            progress.status = status.to_owned();
+        }
+        self.update_notification(id, builder).await?;
+        Ok(())
+    }
+
+    async fn set_badge_count(&self, count: u32) -> Result<(), crate::Error> {
+        log::info!(
+            "NotificationManagerMock::set_badge_count {count}: not supported, only remembered"
+        );
+        self.badge_count.store(count, Ordering::Relaxed);
+        Ok(())
+    }
+
+    async fn get_badge_count(&self) -> Result<u32, crate::Error> {
+        Ok(self.badge_count.load(Ordering::Relaxed))
+    }
+
+    async fn schedule_notification(
+        &self,
+        builder: NotificationBuilder,
+        at: DateTime<Utc>,
+    ) -> Result<String, crate::Error> {
+        log::info!("NotificationManagerMock::schedule_notification at {at}");
+        self.schedule(builder, Schedule::At(at))
+    }
+
+    async fn schedule_repeating_notification(
+        &self,
+        builder: NotificationBuilder,
+        interval: RepeatInterval,
+    ) -> Result<String, crate::Error> {
+        log::info!("NotificationManagerMock::schedule_repeating_notification every {interval:?}");
+        self.schedule(builder, Schedule::Every(interval))
+    }
+
+    async fn schedule_calendar_notification(
+        &self,
+        builder: NotificationBuilder,
+        trigger: CalendarTrigger,
+    ) -> Result<String, crate::Error> {
+        log::info!("NotificationManagerMock::schedule_calendar_notification {trigger:?}");
+        self.schedule(builder, Schedule::Calendar(trigger))
+    }
+
+    fn cancel_scheduled_notification(&self, id: &str) -> Result<(), crate::Error> {
+        let cancelled = self.scheduler.cancel(id);
+        log::info!(
+            "NotificationManagerMock::cancel_scheduled_notification {id} -> cancelled: {cancelled}"
+        );
+        if !cancelled {
+            return Err(Error::NotificationNotFound(id.to_owned()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::NotificationProgress;
+
+    #[tokio::test]
+    async fn record_and_respond() {
+        let manager = NotificationManagerMock::new();
+        let responses = Arc::new(Mutex::new(Vec::new()));
+        let recorded = responses.clone();
+        manager
+            .register(
+                Box::new(move |response| recorded.lock().unwrap().push(response)),
+                vec![],
+            )
+            .unwrap();
+
+        let builder = NotificationBuilder::new()
+            .title("title")
+            .set_thread_id("thread")
+            .set_user_info(HashMap::from([("key".to_owned(), "value".to_owned())]));
+        let handle = manager.send_notification(builder.clone()).await.unwrap();
+        assert_eq!(manager.sent_notifications().await, vec![builder]);
+        assert_eq!(handle.get_content().title.as_deref(), Some("title"));
+
+        manager
+            .simulate_response(
+                &handle.get_id(),
+                NotificationResponseAction::Other("reply".to_owned()),
+                Some("text".to_owned()),
+            )
+            .await
+            .unwrap();
+        let responses = responses.lock().unwrap();
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0].notification_id, handle.get_id());
+        assert_eq!(responses[0].text_input(), Some(("reply", "text")));
+        assert_eq!(
+            responses[0].text_reply(),
+            Some(crate::TextReply {
+                action_id: "reply".to_owned(),
+                text: "text".to_owned(),
+            })
+        );
+        // the thread id is stored in the user info, but not passed on to the app
+        let user_info = HashMap::from([("key".to_owned(), "value".to_owned())]);
+        assert_eq!(responses[0].user_info, user_info);
+        assert_eq!(handle.get_user_info(), user_info);
+        assert_eq!(responses[0].get_thread_id(), Some("thread"));
+        assert_eq!(responses[0].delivered_at, handle.get_content().delivered_at);
+        assert!(responses[0].delivered_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn dismiss_thread() {
+        let manager = NotificationManagerMock::new();
+        for thread_id in ["a", "b", "a"] {
+            manager
+                .send_notification(NotificationBuilder::new().set_thread_id(thread_id))
+                .await
+                .unwrap();
+        }
+        manager
+            .send_notification(NotificationBuilder::new())
+            .await
+            .unwrap();
+        assert_eq!(manager.active_thread_ids().await.unwrap(), ["a", "b"]);
+        assert_eq!(manager.active_count_for_thread("a").await.unwrap(), 2);
+
+        manager.dismiss_thread("a").await.unwrap();
+        assert_eq!(manager.active_count_for_thread("a").await.unwrap(), 0);
+        assert_eq!(manager.active_thread_ids().await.unwrap(), ["b"]);
+    }
+
+    #[tokio::test]
+    async fn dedupe() {
+        let manager = NotificationManagerMock::new();
+        let builder = NotificationBuilder::new()
+            .title("build failed")
+            .set_dedupe_key("build-failed:repo-x")
+            .set_user_info(HashMap::from([("repo".to_owned(), "x".to_owned())]));
+        let first = manager.send_notification(builder.clone()).await.unwrap();
+        let second = manager.send_notification(builder.clone()).await.unwrap();
+        assert_eq!(first.get_id(), second.get_id());
+        assert_eq!(manager.sent_notifications().await.len(), 1);
+
+        manager
+            .remove_delivered_notifications(vec![&first.get_id()])
+            .unwrap();
+        let third = manager.send_notification(builder.clone()).await.unwrap();
+        assert_ne!(first.get_id(), third.get_id());
+        assert_eq!(
+            third.get_user_info().get("repo").map(String::as_str),
+            Some("x")
+        );
+
+        // dismissed by the user
+        manager.register(Box::new(|_| {}), vec![]).unwrap();
+        manager
+            .simulate_response(&third.get_id(), NotificationResponseAction::Dismiss, None)
+            .await
+            .unwrap();
+        let fourth = manager.send_notification(builder).await.unwrap();
+        assert_ne!(third.get_id(), fourth.get_id());
+        assert_eq!(manager.sent_notifications().await.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn user_data() {
+        let manager = NotificationManagerMock::new();
+        let user_data = serde_json::json!({ "chat": { "id": 42, "muted": false } });
+        let handle = manager
+            .send_notification(
+                NotificationBuilder::new()
+                    .set_user_data(user_data.clone())
+                    .set_user_info(HashMap::from([("key".to_owned(), "value".to_owned())])),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(handle.get_user_data(), Some(user_data));
+        assert_eq!(
+            handle.get_user_info().get("key").map(String::as_str),
+            Some("value")
+        );
+    }
+
+    #[tokio::test]
+    async fn update_progress() {
+        let manager = NotificationManagerMock::new();
+        let handle = manager
+            .send_notification(
+                NotificationBuilder::new()
+                    .set_progress(NotificationProgress::new(0.1, "starting").set_title("Export")),
+            )
+            .await
+            .unwrap();
+
+        manager
+            .update_progress(&handle.get_id(), 0.5.into(), "halfway")
+            .await
+            .unwrap();
+        let sent = manager.sent_notifications().await;
+        assert_eq!(sent.len(), 2);
+        let progress = sent[1].get_progress().unwrap();
+        assert_eq!(progress.to_string(), "Export: 50% — halfway");
+
+        assert!(
+            manager
+                .update_progress("unknown", 1.0.into(), "done")
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn cancel_scheduled() {
+        let manager = NotificationManagerMock::new();
+        let id = manager
+            .schedule_notification(
+                NotificationBuilder::new(),
+                Utc::now() + chrono::Duration::hours(1),
+            )
+            .await
+            .unwrap();
+
+        manager.cancel_scheduled_notification(&id).unwrap();
+        assert!(matches!(
+            manager.cancel_scheduled_notification(&id),
+            Err(Error::NotificationNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn lifecycle_events() {
+        let manager = NotificationManagerMock::new();
+        manager.register(Box::new(|_| {}), vec![]).unwrap();
+        let mut events = manager.subscribe_events();
+
+        let handle = manager
+            .send_notification(NotificationBuilder::new())
+            .await
+            .unwrap();
+        let id = handle.get_id();
+        manager
+            .simulate_response(&id, NotificationResponseAction::Default, None)
+            .await
+            .unwrap();
+        manager
+            .simulate_response(&id, NotificationResponseAction::Dismiss, None)
+            .await
+            .unwrap();
+        // the dismissed notification is not active anymore, removing it does not close it again
+        manager.remove_delivered_notifications(vec![&id]).unwrap();
+        let second = manager
+            .send_notification(NotificationBuilder::new())
+            .await
+            .unwrap()
+            .get_id();
+        manager
+            .remove_delivered_notifications(vec![&second])
+            .unwrap();
+
+        let mut kinds = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            kinds.push((event.notification_id == id, event.kind));
+        }
+        assert_eq!(
+            kinds,
+            vec![
+                (true, NotificationEventKind::Presented),
+                (true, NotificationEventKind::Clicked),
+                (
+                    true,
+                    NotificationEventKind::Dismissed(NotificationDismissReason::DismissedByUser)
+                ),
+                (false, NotificationEventKind::Presented),
+                (
+                    false,
+                    NotificationEventKind::Dismissed(NotificationDismissReason::ClosedByApp)
+                ),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn replace_response_handler() {
+        let manager = NotificationManagerMock::new();
+        let counting = |calls: &Arc<AtomicU32>, step: u32| {
+            let calls = calls.clone();
+            Box::new(move |_| {
+                calls.fetch_add(step, Ordering::Relaxed);
+            })
         };
+        let calls = Arc::new(AtomicU32::new(0));
+        assert!(manager.set_response_handler(counting(&calls, 1)).is_err());
+        manager.register(counting(&calls, 1), vec![]).unwrap();
+        let mut events = manager.subscribe_events();
 
-        self.add_notification(handle.clone()).await;
-        Ok(Box::new(handle) as Box<dyn NotificationHandle>)
+        let id = manager
+            .send_notification(NotificationBuilder::new())
+            .await
+            .unwrap()
+            .get_id();
+        let respond = || manager.simulate_response(&id, NotificationResponseAction::Default, None);
+        respond().await.unwrap();
+        manager.set_response_handler(counting(&calls, 10)).unwrap();
+        respond().await.unwrap();
+        manager.clear_response_handler().unwrap();
+        respond().await.unwrap();
+        assert_eq!(calls.load(Ordering::Relaxed), 11);
+
+        // the events do not depend on the handler
+        let mut clicks = 0;
+        while let Ok(event) = events.try_recv() {
+            if event.kind == NotificationEventKind::Clicked {
+                clicks += 1;
+            }
+        }
+        assert_eq!(clicks, 3);
+    }
+
+    #[tokio::test]
+    async fn response_callback() {
+        let manager = NotificationManagerMock::new();
+        let handled = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let handled_clone = handled.clone();
+        manager
+            .register(
+                Box::new(move |response| {
+                    handled_clone.lock().unwrap().push(response.notification_id)
+                }),
+                vec![],
+            )
+            .unwrap();
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let with_callback = manager
+            .send_notification(NotificationBuilder::new().on_response(move |response| {
+                let _ = tx.send(response.action);
+            }))
+            .await
+            .unwrap()
+            .get_id();
+        let without_callback = manager
+            .send_notification(NotificationBuilder::new())
+            .await
+            .unwrap()
+            .get_id();
+        let removed =
+            manager
+                .send_notification(NotificationBuilder::new().on_response(|_| {
+                    panic!("the callback of a removed notification is not called")
+                }))
+                .await
+                .unwrap()
+                .get_id();
+        manager
+            .remove_delivered_notifications(vec![&removed])
+            .unwrap();
+        for action in [
+            NotificationResponseAction::Default,
+            NotificationResponseAction::Default,
+            NotificationResponseAction::Dismiss,
+            // the callback is removed with the dismissal
+            NotificationResponseAction::Default,
+        ] {
+            manager
+                .simulate_response(&with_callback, action, None)
+                .await
+                .unwrap();
+        }
+        for id in [&without_callback, &removed] {
+            manager
+                .simulate_response(id, NotificationResponseAction::Default, None)
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(rx.try_recv().unwrap(), NotificationResponseAction::Default);
+        assert_eq!(rx.try_recv().unwrap(), NotificationResponseAction::Default);
+        assert_eq!(rx.try_recv().unwrap(), NotificationResponseAction::Dismiss);
+        assert!(rx.try_recv().is_err());
+        assert_eq!(
+            *handled.lock().unwrap(),
+            vec![with_callback, without_callback, removed]
+        );
+    }
+
+    #[tokio::test]
+    async fn do_not_disturb() {
+        let manager = NotificationManagerMock::new();
+        let mut changes = manager.subscribe_do_not_disturb().unwrap();
+        assert!(!manager.is_do_not_disturb_active().await.unwrap());
+
+        manager.simulate_do_not_disturb(true);
+        // no change, nothing is sent
+        manager.simulate_do_not_disturb(true);
+        manager.simulate_do_not_disturb(false);
+        assert!(!manager.is_do_not_disturb_active().await.unwrap());
+        assert!(changes.try_recv().unwrap());
+        assert!(!changes.try_recv().unwrap());
+        assert!(changes.try_recv().is_err());
     }
 }
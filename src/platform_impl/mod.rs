@@ -1,13 +1,20 @@
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", feature = "macos"))]
 pub mod mac_os;
+#[cfg(feature = "mock")]
 pub mod mock;
-#[cfg(target_os = "windows")]
+pub mod noop;
+#[cfg(all(target_arch = "wasm32", feature = "web"))]
+pub mod web;
+#[cfg(all(target_os = "windows", feature = "windows"))]
 pub mod windows;
-#[cfg(any(
-    target_os = "linux",
-    target_os = "dragonfly",
-    target_os = "freebsd",
-    target_os = "openbsd",
-    target_os = "netbsd"
+#[cfg(all(
+    any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "openbsd",
+        target_os = "netbsd"
+    ),
+    any(feature = "linux-dbus", feature = "linux-portal")
 ))]
 pub mod xdg;
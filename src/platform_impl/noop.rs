@@ -0,0 +1,273 @@
+//! This manager accepts every call, logs it and shows nothing.
+//! It is meant for headless environments like servers, SSH sessions or CI,
+//! so the same application code runs there without cfg switches.
+//!
+//! Unlike [NotificationManagerMock](super::mock::NotificationManagerMock) it keeps no state,
+//! there are never active notifications and the user never responds.
+//!
+//! [crate::get_notification_manager] selects it automatically when [is_headless] is true.
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use crate::{
+    CalendarTrigger, Error, NotificationBuilder, NotificationCapabilities, NotificationContent,
+    NotificationHandle, NotificationManager, NotificationResponse, ProgressState, RepeatInterval,
+};
+
+#[derive(Debug, Clone)]
+pub struct NoopNotificationHandle {
+    id: String,
+    thread_id: Option<String>,
+    user_info: HashMap<String, String>,
+    content: NotificationContent,
+}
+
+impl NotificationHandle for NoopNotificationHandle {
+    fn close(&self) -> Result<(), crate::Error> {
+        log::debug!("NoopNotificationManager: close notification {}", self.id);
+        Ok(())
+    }
+
+    fn get_id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn get_stored_user_info(&self) -> &HashMap<String, String> {
+        &self.user_info
+    }
+
+    fn get_thread_id(&self) -> Option<&str> {
+        self.thread_id.as_deref()
+    }
+
+    fn get_content(&self) -> &NotificationContent {
+        &self.content
+    }
+}
+
+impl NoopNotificationHandle {
+    fn new(id: String, builder: &NotificationBuilder) -> Self {
+        Self {
+            id,
+            thread_id: builder.thread_id.clone(),
+            user_info: builder.user_info.clone().unwrap_or_default(),
+            content: NotificationContent::from(builder),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct NoopNotificationManager {}
+
+impl NoopNotificationManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Guesses whether notifications could be seen by anyone:
+/// false in CI, in SSH sessions and on Linux without `DISPLAY` or `WAYLAND_DISPLAY`
+pub fn is_headless() -> bool {
+    headless_reason().is_some()
+}
+
+pub(crate) fn headless_reason() -> Option<&'static str> {
+    let is_set = |name: &str| std::env::var_os(name).is_some_and(|value| !value.is_empty());
+    if is_set("CI") {
+        return Some("running in CI");
+    }
+    if is_set("SSH_CONNECTION") || is_set("SSH_TTY") {
+        return Some("running in an SSH session");
+    }
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "openbsd",
+        target_os = "netbsd"
+    ))]
+    if !is_set("DISPLAY") && !is_set("WAYLAND_DISPLAY") {
+        return Some("no DISPLAY or WAYLAND_DISPLAY");
+    }
+    None
+}
+
+#[async_trait]
+impl NotificationManager for NoopNotificationManager {
+    async fn get_notification_permission_state(&self) -> Result<bool, crate::Error> {
+        Ok(true)
+    }
+
+    async fn first_time_ask_for_notification_permission(&self) -> Result<bool, crate::Error> {
+        Ok(true)
+    }
+
+    /// Nothing is supported, because nothing is shown
+    async fn capabilities(&self) -> Result<NotificationCapabilities, Error> {
+        Ok(NotificationCapabilities::default())
+    }
+
+    fn register(
+        &self,
+        _handler_callback: Box<dyn Fn(NotificationResponse) + Send + Sync + 'static>,
+        categories: Vec<crate::NotificationCategory>,
+    ) -> Result<(), crate::Error> {
+        log::debug!("NoopNotificationManager::register {categories:?}");
+        Ok(())
+    }
+
+    fn set_response_handler(
+        &self,
+        _handler_callback: Box<dyn Fn(NotificationResponse) + Send + Sync + 'static>,
+    ) -> Result<(), crate::Error> {
+        Ok(())
+    }
+
+    fn add_categories(
+        &self,
+        categories: Vec<crate::NotificationCategory>,
+    ) -> Result<(), crate::Error> {
+        log::debug!("NoopNotificationManager::add_categories {categories:?}");
+        Ok(())
+    }
+
+    fn set_categories(
+        &self,
+        categories: Vec<crate::NotificationCategory>,
+    ) -> Result<(), crate::Error> {
+        log::debug!("NoopNotificationManager::set_categories {categories:?}");
+        Ok(())
+    }
+
+    fn remove_category(&self, identifier: &str) -> Result<(), crate::Error> {
+        log::debug!("NoopNotificationManager::remove_category {identifier}");
+        Ok(())
+    }
+
+    fn remove_all_delivered_notifications(&self) -> Result<(), crate::Error> {
+        Ok(())
+    }
+
+    fn remove_delivered_notifications(&self, _ids: Vec<&str>) -> Result<(), crate::Error> {
+        Ok(())
+    }
+
+    async fn get_active_notifications(
+        &self,
+    ) -> Result<Vec<Box<dyn NotificationHandle>>, crate::Error> {
+        Ok(Vec::new())
+    }
+
+    async fn send_notification(
+        &self,
+        builder: NotificationBuilder,
+    ) -> Result<Box<dyn NotificationHandle>, crate::Error> {
+        let handle = NoopNotificationHandle::new(
+            builder.notification_id(|| uuid::Uuid::new_v4().to_string()),
+            &builder,
+        );
+        log::info!(
+            "NoopNotificationManager: not showing notification {} {:?}",
+            handle.id,
+            handle.content
+        );
+        Ok(Box::new(handle) as Box<dyn NotificationHandle>)
+    }
+
+    async fn update_notification(
+        &self,
+        id: &str,
+        builder: NotificationBuilder,
+    ) -> Result<Box<dyn NotificationHandle>, crate::Error> {
+        let handle = NoopNotificationHandle::new(id.to_owned(), &builder);
+        log::info!(
+            "NoopNotificationManager: not updating notification {id} {:?}",
+            handle.content
+        );
+        Ok(Box::new(handle) as Box<dyn NotificationHandle>)
+    }
+
+    async fn update_progress(
+        &self,
+        id: &str,
+        state: ProgressState,
+        status: &str,
+    ) -> Result<(), crate::Error> {
+        log::debug!("NoopNotificationManager::update_progress {id} {state:?} {status}");
+        Ok(())
+    }
+
+    async fn set_badge_count(&self, count: u32) -> Result<(), crate::Error> {
+        log::debug!("NoopNotificationManager::set_badge_count {count}");
+        Ok(())
+    }
+
+    async fn get_badge_count(&self) -> Result<u32, crate::Error> {
+        Ok(0)
+    }
+
+    async fn schedule_notification(
+        &self,
+        builder: NotificationBuilder,
+        at: DateTime<Utc>,
+    ) -> Result<String, crate::Error> {
+        let id = uuid::Uuid::new_v4().to_string();
+        log::info!(
+            "NoopNotificationManager: not scheduling notification {id} at {at}: {:?}",
+            NotificationContent::from(&builder)
+        );
+        Ok(id)
+    }
+
+    async fn schedule_repeating_notification(
+        &self,
+        builder: NotificationBuilder,
+        interval: RepeatInterval,
+    ) -> Result<String, crate::Error> {
+        let id = uuid::Uuid::new_v4().to_string();
+        log::info!(
+            "NoopNotificationManager: not scheduling notification {id} every {interval:?}: {:?}",
+            NotificationContent::from(&builder)
+        );
+        Ok(id)
+    }
+
+    async fn schedule_calendar_notification(
+        &self,
+        builder: NotificationBuilder,
+        trigger: CalendarTrigger,
+    ) -> Result<String, crate::Error> {
+        let id = uuid::Uuid::new_v4().to_string();
+        log::info!(
+            "NoopNotificationManager: not scheduling notification {id} at {trigger:?}: {:?}",
+            NotificationContent::from(&builder)
+        );
+        Ok(id)
+    }
+
+    fn cancel_scheduled_notification(&self, id: &str) -> Result<(), crate::Error> {
+        log::debug!("NoopNotificationManager::cancel_scheduled_notification {id}");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn accepts_without_state() {
+        let manager = NoopNotificationManager::new();
+        let handle = manager
+            .send_notification(NotificationBuilder::new().title("title"))
+            .await
+            .unwrap();
+        assert_eq!(handle.get_content().title.as_deref(), Some("title"));
+        assert!(manager.get_active_notifications().await.unwrap().is_empty());
+        manager.set_badge_count(3).await.unwrap();
+        assert_eq!(manager.get_badge_count().await.unwrap(), 0);
+    }
+}
@@ -0,0 +1,758 @@
+//! Notifications with the [Web Notifications API](https://developer.mozilla.org/en-US/docs/Web/API/Notifications_API), for wasm32
+//!
+//! By default notifications are created with the `Notification` constructor, clicks and closes are reported directly.
+//! Buttons are only supported for notifications of a service worker, and some browsers (like Chrome on Android)
+//! only allow those, see [NotificationManagerWeb::with_service_worker].
+//! The service worker then needs to forward the clicks to the page, because they are delivered to the service worker:
+//!
+//! ```js
+//! async function forward(event, action) {
+//!   const clients = await self.clients.matchAll({ type: "window", includeUncontrolled: true });
+//!   for (const client of clients) {
+//!     client.postMessage({ type: "user-notify", tag: event.notification.tag, action, reply: event.reply ?? null });
+//!   }
+//! }
+//! self.addEventListener("notificationclick", (event) => event.waitUntil(forward(event, event.action || "default")));
+//! self.addEventListener("notificationclose", (event) => event.waitUntil(forward(event, "close")));
+//! ```
+
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc,
+        atomic::{AtomicU32, Ordering},
+    },
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use send_wrapper::SendWrapper;
+use tokio::sync::RwLock;
+use wasm_bindgen::{JsCast, JsValue, closure::Closure};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{NotificationPermission, ServiceWorkerRegistration};
+
+use crate::{
+    CalendarTrigger, Error, InterruptionLevel, NotificationBuilder, NotificationCapabilities,
+    NotificationCategory, NotificationCategoryAction, NotificationContent,
+    NotificationDismissReason, NotificationEvent, NotificationEventKind, NotificationHandle,
+    NotificationManager, NotificationResponse, NotificationResponseAction, ProgressState,
+    RepeatInterval, Sound,
+    events::NotificationEvents,
+    find_duplicate,
+    response_handler::ResponseHandler,
+    scheduler::{Schedule, Scheduler},
+    unknown_category,
+};
+
+/// `type` of the messages that the service worker posts for clicks and closes
+const MESSAGE_TYPE: &str = "user-notify";
+const DEFAULT_ACTION: &str = "default";
+const CLOSE_ACTION: &str = "close";
+
+/// Returns true when the browser has the Notification API,
+/// it is missing for example in insecure contexts and in some webviews
+pub fn is_supported() -> bool {
+    js_sys::Reflect::has(&js_sys::global(), &JsValue::from_str("Notification")).unwrap_or(false)
+}
+
+pub(crate) fn js_error(err: JsValue) -> Error {
+    let name = js_sys::Reflect::get(&err, &JsValue::from_str("name"))
+        .ok()
+        .and_then(|name| name.as_string());
+    if name.as_deref() == Some("NotAllowedError") {
+        return Error::PermissionDenied;
+    }
+    Error::Js(
+        err.as_string()
+            .or_else(|| {
+                js_sys::Reflect::get(&err, &JsValue::from_str("message"))
+                    .ok()
+                    .and_then(|message| message.as_string())
+            })
+            .unwrap_or_else(|| format!("{err:?}")),
+    )
+}
+
+/// Closes the notifications of the service worker with these tags
+fn close_service_worker_notifications(registration: &ServiceWorkerRegistration, ids: Vec<String>) {
+    let promise = match registration.get_notifications() {
+        Ok(promise) => promise,
+        Err(err) => {
+            log::error!("failed to get the notifications of the service worker: {err:?}");
+            return;
+        }
+    };
+    wasm_bindgen_futures::spawn_local(async move {
+        match JsFuture::from(promise).await {
+            Ok(notifications) => {
+                for notification in js_sys::Array::from(&notifications).iter() {
+                    let notification: web_sys::Notification = notification.unchecked_into();
+                    if notification.tag().is_some_and(|tag| ids.contains(&tag)) {
+                        notification.close();
+                    }
+                }
+            }
+            Err(err) => {
+                log::error!("failed to get the notifications of the service worker: {err:?}")
+            }
+        }
+    });
+}
+
+#[derive(Debug, Clone)]
+pub struct NotificationHandleWeb {
+    id: String,
+    thread_id: Option<String>,
+    user_info: HashMap<String, String>,
+    content: NotificationContent,
+    /// only set for notifications that were created with the constructor,
+    /// notifications of the service worker are found by their tag
+    notification: Option<SendWrapper<web_sys::Notification>>,
+    service_worker: Option<SendWrapper<ServiceWorkerRegistration>>,
+}
+
+impl NotificationHandle for NotificationHandleWeb {
+    fn close(&self) -> Result<(), crate::Error> {
+        log::info!("called close notification handle {}", self.id);
+        if let Some(notification) = &self.notification {
+            notification.close();
+        } else if let Some(registration) = &self.service_worker {
+            close_service_worker_notifications(registration, vec![self.id.clone()]);
+        }
+        Ok(())
+    }
+
+    fn get_id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn get_stored_user_info(&self) -> &HashMap<String, String> {
+        &self.user_info
+    }
+
+    fn get_thread_id(&self) -> Option<&str> {
+        self.thread_id.as_deref()
+    }
+
+    fn get_content(&self) -> &NotificationContent {
+        &self.content
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct NotificationManagerWeb {
+    active_notifications: Arc<RwLock<Vec<NotificationHandleWeb>>>,
+    handler: ResponseHandler,
+    categories: Arc<std::sync::RwLock<HashMap<String, NotificationCategory>>>,
+    service_worker: Option<SendWrapper<ServiceWorkerRegistration>>,
+    scheduler: Scheduler,
+    badge_count: Arc<AtomicU32>,
+    /// notifications with progress, to show them again with the new progress
+    progress_notifications: Arc<RwLock<HashMap<String, NotificationBuilder>>>,
+    events: NotificationEvents,
+}
+
+impl std::fmt::Debug for NotificationManagerWeb {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NotificationManagerWeb")
+            .field("active_notifications", &self.active_notifications)
+            .field("handler", &self.handler.is_set().to_string())
+            .field("categories", &self.categories)
+            .field("service_worker", &self.service_worker.is_some())
+            .finish()
+    }
+}
+
+impl NotificationManagerWeb {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Shows the notifications with [ServiceWorkerRegistration.showNotification](https://developer.mozilla.org/en-US/docs/Web/API/ServiceWorkerRegistration/showNotification),
+    /// which supports buttons, see the module documentation for the script the service worker needs
+    pub fn with_service_worker(registration: ServiceWorkerRegistration) -> Self {
+        Self {
+            service_worker: Some(SendWrapper::new(registration)),
+            ..Self::default()
+        }
+    }
+
+    /// See [NotificationBuilder::validate], also checks that the category is registered
+    async fn validate(&self, builder: &NotificationBuilder) -> Result<(), Error> {
+        builder.validate(&self.capabilities().await?)?;
+        if let Some(category_id) = &builder.category_id
+            && !self
+                .categories
+                .read()
+                .map_err(|_| Error::SettingHandler)?
+                .contains_key(category_id)
+        {
+            return Err(unknown_category(category_id));
+        }
+        Ok(())
+    }
+
+    async fn add_notification(&self, notification: NotificationHandleWeb) {
+        self.active_notifications.write().await.push(notification);
+    }
+
+    /// Reports a click or close of the notification with this id to the handler
+    fn respond(&self, id: &str, action: &str, user_text: Option<String>) {
+        let Ok(mut active_notifications) = self.active_notifications.try_write() else {
+            log::error!("failed to lock the active notifications, dropping {action} of {id}");
+            return;
+        };
+        let notification = active_notifications
+            .iter()
+            .find(|n| n.id == id)
+            .map(|n| (n.user_info.clone(), n.content.delivered_at));
+        let action = match action {
+            DEFAULT_ACTION => NotificationResponseAction::Default,
+            CLOSE_ACTION => {
+                if notification.is_none() {
+                    // it was removed by the app, which is reported when it is removed
+                    return;
+                }
+                active_notifications.retain(|n| n.id != id);
+                NotificationResponseAction::Dismiss
+            }
+            action => NotificationResponseAction::Other(action.to_owned()),
+        };
+        drop(active_notifications);
+
+        let (user_info, delivered_at) = notification.unwrap_or_default();
+        let response = NotificationResponse::new(id.to_owned(), action, user_text, user_info)
+            .set_delivered_at(delivered_at)
+            .split_selection();
+        match self.handler.get() {
+            Some(handler) => handler(response),
+            None => log::warn!("no handler registered, dropping {response:?}"),
+        }
+    }
+
+    /// Forwards the clicks and closes of a notification that was created with the constructor
+    fn listen(&self, notification: &web_sys::Notification, id: &str) {
+        for (event, action) in [("click", DEFAULT_ACTION), ("close", CLOSE_ACTION)] {
+            let manager = self.clone();
+            let id = id.to_owned();
+            // owned by the notification, so it is freed together with it
+            let callback = Closure::<dyn Fn()>::new(move || manager.respond(&id, action, None))
+                .into_js_value();
+            if let Err(err) =
+                notification.add_event_listener_with_callback(event, callback.unchecked_ref())
+            {
+                log::error!("failed to listen for {event} on notification: {err:?}");
+            }
+        }
+    }
+
+    /// Forwards the clicks and closes that the service worker posts to the page
+    fn listen_to_service_worker(&self) -> Result<(), Error> {
+        let Some(window) = web_sys::window() else {
+            return Err(Error::PlatformUnavailable("no window".to_owned()));
+        };
+        let manager = self.clone();
+        let callback =
+            Closure::<dyn Fn(web_sys::MessageEvent)>::new(move |event: web_sys::MessageEvent| {
+                let data = event.data();
+                let get = |key: &str| {
+                    js_sys::Reflect::get(&data, &JsValue::from_str(key))
+                        .ok()
+                        .and_then(|value| value.as_string())
+                };
+                if get("type").as_deref() != Some(MESSAGE_TYPE) {
+                    return;
+                }
+                if let (Some(tag), Some(action)) = (get("tag"), get("action")) {
+                    manager.respond(&tag, &action, get("reply"));
+                }
+            })
+            .into_js_value();
+        window
+            .navigator()
+            .service_worker()
+            .add_event_listener_with_callback("message", callback.unchecked_ref())
+            .map_err(js_error)
+    }
+
+    fn notification_options(
+        &self,
+        id: &str,
+        builder: &NotificationBuilder,
+    ) -> Result<web_sys::NotificationOptions, Error> {
+        let options = web_sys::NotificationOptions::new();
+        options.set_tag(id);
+        options.set_timestamp(Utc::now().timestamp_millis() as f64);
+        if let Some(body) = &builder.body {
+            options.set_body(body);
+        }
+        // on the web the paths are urls
+        if let Some(icon) = &builder.icon {
+            options.set_icon(&icon.to_string_lossy());
+        }
+        if let Some(image) = &builder.image {
+            options.set_image(&image.to_string_lossy());
+        }
+        match &builder.sound {
+            None | Some(Sound::Default) => {}
+            Some(Sound::None) => options.set_silent(Some(true)),
+            Some(sound) => {
+                log::warn!(
+                    "custom sounds are not supported by the web notifications api, ignoring {sound:?}"
+                )
+            }
+        }
+        match builder.interruption_level {
+            Some(InterruptionLevel::Passive) => options.set_silent(Some(true)),
+            Some(InterruptionLevel::TimeSensitive | InterruptionLevel::Critical) => {
+                options.set_require_interaction(true)
+            }
+            Some(InterruptionLevel::Active) | None => {}
+        }
+        if let Some(user_info) = &builder.user_info {
+            match serde_json::to_string(user_info) {
+                Ok(json) => options.set_data(&JsValue::from_str(&json)),
+                Err(err) => log::error!("failed to serialize user info: {err:?}"),
+            }
+        }
+
+        let mut actions = Vec::new();
+        if let Some(category_id) = &builder.category_id {
+            let categories = self.categories.read().map_err(|_| Error::SettingHandler)?;
+            if let Some(category) = categories.get(category_id) {
+                actions.extend(category.actions.iter().cloned());
+            } else {
+                log::warn!("Category '{category_id}' not found in registered categories");
+            }
+        }
+        actions.extend(builder.actions.iter().cloned());
+        // the constructor throws for notifications with actions
+        if !actions.is_empty() && self.service_worker.is_none() {
+            log::warn!("buttons are only supported for notifications of a service worker");
+        } else if !actions.is_empty() {
+            let web_actions = js_sys::Array::new();
+            for action in actions
+                .iter()
+                .flat_map(NotificationCategoryAction::expand_selection)
+            {
+                let web_action = match action {
+                    NotificationCategoryAction::Action { identifier, title }
+                    | NotificationCategoryAction::SelectionInputAction {
+                        identifier, title, ..
+                    } => web_sys::NotificationAction::new(&identifier, &title),
+                    NotificationCategoryAction::TextInputAction {
+                        identifier,
+                        title,
+                        input_placeholder,
+                        ..
+                    } => {
+                        let web_action = web_sys::NotificationAction::new(&identifier, &title);
+                        // only supported by chromium
+                        let _ = js_sys::Reflect::set(
+                            &web_action,
+                            &JsValue::from_str("type"),
+                            &JsValue::from_str("text"),
+                        );
+                        let _ = js_sys::Reflect::set(
+                            &web_action,
+                            &JsValue::from_str("placeholder"),
+                            &JsValue::from_str(&input_placeholder),
+                        );
+                        web_action
+                    }
+                };
+                web_actions.push(&web_action);
+            }
+            options.set_actions(&web_actions);
+        }
+        Ok(options)
+    }
+
+    async fn show_notification(
+        &self,
+        id: String,
+        builder: NotificationBuilder,
+    ) -> Result<NotificationHandleWeb, Error> {
+        if web_sys::Notification::permission() == NotificationPermission::Denied {
+            return Err(Error::PermissionDenied);
+        }
+        if builder.progress.is_some() {
+            self.progress_notifications
+                .write()
+                .await
+                .insert(id.clone(), builder.clone());
+        }
+        let builder = image_urls_as_paths(builder)
+            .apply_auto_dismiss(Utc::now())
+            .apply_sender()
+            .apply_progress()
+            .apply_plain_body()
+            .apply_interruption_level()
+            .apply_hero_image_and_attribution();
+        let content = NotificationContent::from(&builder);
+        let title = builder.title.clone().unwrap_or_default();
+
+        let notification = match &self.service_worker {
+            Some(registration) => {
+                let promise = {
+                    let options = self.notification_options(&id, &builder)?;
+                    registration
+                        .show_notification_with_options(&title, &options)
+                        .map_err(js_error)?
+                };
+                SendWrapper::new(JsFuture::from(promise))
+                    .await
+                    .map_err(js_error)?;
+                None
+            }
+            None => {
+                let options = self.notification_options(&id, &builder)?;
+                let notification =
+                    web_sys::Notification::new_with_options(&title, &options).map_err(js_error)?;
+                self.listen(&notification, &id);
+                Some(SendWrapper::new(notification))
+            }
+        };
+        self.events
+            .set_response_callback(&id, &builder.response_callback);
+        self.events.emit(&id, NotificationEventKind::Presented);
+
+        let handle = NotificationHandleWeb {
+            id,
+            thread_id: builder.thread_id,
+            user_info: builder.user_info.unwrap_or_default(),
+            content,
+            notification,
+            service_worker: self.service_worker.clone(),
+        };
+
+        let manager = self.clone();
+        let expired_id = handle.id.clone();
+        self.scheduler
+            .schedule_expiration(&handle.id, builder.expiration, move || {
+                if let Err(err) = manager.close_notifications(vec![&expired_id]) {
+                    log::error!("failed to remove expired notification: {err:?}");
+                }
+                manager.events.emit(
+                    &expired_id,
+                    NotificationEventKind::Dismissed(NotificationDismissReason::Expired),
+                );
+            });
+
+        self.add_notification(handle.clone()).await;
+        Ok(handle)
+    }
+
+    /// Without native scheduling the notification is shown by a timer in this process
+    async fn schedule(
+        &self,
+        builder: NotificationBuilder,
+        schedule: Schedule,
+    ) -> Result<String, crate::Error> {
+        self.validate(&builder).await?;
+        let manager = self.clone();
+        self.scheduler.schedule_notification(
+            uuid::Uuid::new_v4().to_string(),
+            builder,
+            schedule,
+            move |id, builder| {
+                let manager = manager.clone();
+                // a notification with the same tag replaces the old one
+                async move { manager.show_notification(id, builder).await.map(drop) }
+            },
+        )
+    }
+
+    /// Closes the notifications without reporting it, returns the ids of the closed notifications
+    fn close_notifications(&self, ids: Vec<&str>) -> Result<Vec<String>, Error> {
+        let mut active_notifications = self.active_notifications.try_write()?;
+        let mut closed = Vec::new();
+        active_notifications.retain(|n| {
+            if ids.contains(&n.id.as_str()) {
+                closed.push(n.clone());
+                false
+            } else {
+                true
+            }
+        });
+        drop(active_notifications);
+        for notification in &closed {
+            if let Some(notification) = &notification.notification {
+                notification.close();
+            }
+        }
+        if let Some(registration) = &self.service_worker {
+            close_service_worker_notifications(
+                registration,
+                ids.iter().map(|id| (*id).to_owned()).collect(),
+            );
+        }
+        Ok(closed.into_iter().map(|n| n.id).collect())
+    }
+}
+
+#[async_trait]
+impl NotificationManager for NotificationManagerWeb {
+    async fn get_notification_permission_state(&self) -> Result<bool, crate::Error> {
+        Ok(web_sys::Notification::permission() == NotificationPermission::Granted)
+    }
+
+    /// Needs to be called from a user gesture like a click, browsers ignore the request otherwise
+    async fn first_time_ask_for_notification_permission(&self) -> Result<bool, crate::Error> {
+        let promise = web_sys::Notification::request_permission().map_err(js_error)?;
+        let permission = SendWrapper::new(JsFuture::from(promise))
+            .await
+            .map_err(js_error)?
+            .as_string();
+        Ok(permission.as_deref() == Some("granted"))
+    }
+
+    async fn capabilities(&self) -> Result<NotificationCapabilities, crate::Error> {
+        let with_service_worker = self.service_worker.is_some();
+        Ok(NotificationCapabilities {
+            actions: with_service_worker,
+            body_markup: false,
+            persistence: true,
+            // only chromium supports text input actions
+            inline_reply: false,
+            attachments: true,
+            media_attachments: false,
+            remote_images: true,
+            progress: false,
+            scheduling: true,
+            badges: web_sys::window().is_some_and(|window| {
+                js_sys::Reflect::has(&window.navigator(), &JsValue::from_str("setAppBadge"))
+                    .unwrap_or(false)
+            }),
+            critical_alerts: false,
+            ..Default::default()
+        })
+    }
+
+    fn register(
+        &self,
+        handler_callback: Box<dyn Fn(crate::NotificationResponse) + Send + Sync + 'static>,
+        categories: Vec<crate::NotificationCategory>,
+    ) -> Result<(), crate::Error> {
+        log::info!("NotificationManagerWeb::register {categories:?}");
+        self.handler
+            .register(self.events.wrap_handler(handler_callback))?;
+        self.set_categories(categories)?;
+        if self.service_worker.is_some() {
+            self.listen_to_service_worker()?;
+        }
+        Ok(())
+    }
+
+    fn set_response_handler(
+        &self,
+        handler_callback: Box<dyn Fn(crate::NotificationResponse) + Send + Sync + 'static>,
+    ) -> Result<(), crate::Error> {
+        self.handler
+            .replace(self.events.wrap_handler(handler_callback))
+    }
+
+    fn subscribe_events(&self) -> tokio::sync::broadcast::Receiver<NotificationEvent> {
+        self.events.subscribe()
+    }
+
+    fn add_categories(
+        &self,
+        categories: Vec<crate::NotificationCategory>,
+    ) -> Result<(), crate::Error> {
+        log::info!("NotificationManagerWeb::add_categories {categories:?}");
+        let mut stored_categories = self.categories.write().map_err(|_| Error::SettingHandler)?;
+        for category in categories {
+            stored_categories.insert(category.identifier.clone(), category);
+        }
+        Ok(())
+    }
+
+    fn set_categories(
+        &self,
+        categories: Vec<crate::NotificationCategory>,
+    ) -> Result<(), crate::Error> {
+        log::info!("NotificationManagerWeb::set_categories {categories:?}");
+        let mut stored_categories = self.categories.write().map_err(|_| Error::SettingHandler)?;
+        stored_categories.clear();
+        for category in categories {
+            stored_categories.insert(category.identifier.clone(), category);
+        }
+        Ok(())
+    }
+
+    fn remove_category(&self, identifier: &str) -> Result<(), crate::Error> {
+        log::info!("NotificationManagerWeb::remove_category {identifier}");
+        self.categories
+            .write()
+            .map_err(|_| Error::SettingHandler)?
+            .remove(identifier);
+        Ok(())
+    }
+
+    fn remove_all_delivered_notifications(&self) -> Result<(), crate::Error> {
+        let ids: Vec<String> = self
+            .active_notifications
+            .try_read()?
+            .iter()
+            .map(|n| n.id.clone())
+            .collect();
+        self.remove_delivered_notifications(ids.iter().map(String::as_str).collect())
+    }
+
+    fn remove_delivered_notifications(&self, ids: Vec<&str>) -> Result<(), crate::Error> {
+        self.events.remove_response_callbacks(Some(&ids));
+        for id in self.close_notifications(ids)? {
+            self.events.emit(
+                &id,
+                NotificationEventKind::Dismissed(NotificationDismissReason::ClosedByApp),
+            );
+        }
+        Ok(())
+    }
+
+    async fn get_active_notifications(
+        &self,
+    ) -> Result<Vec<Box<dyn NotificationHandle>>, crate::Error> {
+        // notifications of previous page loads are not known
+        let active_notifications = self.active_notifications.read().await;
+        Ok(active_notifications
+            .clone()
+            .into_iter()
+            .map(|n| Box::new(n) as Box<dyn NotificationHandle>)
+            .collect())
+    }
+
+    async fn send_notification(
+        &self,
+        builder: NotificationBuilder,
+    ) -> Result<Box<dyn NotificationHandle>, crate::Error> {
+        self.validate(&builder).await?;
+        if let Some(duplicate) = find_duplicate(self, &builder).await? {
+            return Ok(duplicate);
+        }
+        log::info!("show notification {self:?}");
+        let id = builder.notification_id(|| uuid::Uuid::new_v4().to_string());
+        let handle = self.show_notification(id, builder).await?;
+        Ok(Box::new(handle) as Box<dyn NotificationHandle>)
+    }
+
+    async fn update_notification(
+        &self,
+        id: &str,
+        builder: NotificationBuilder,
+    ) -> Result<Box<dyn NotificationHandle>, crate::Error> {
+        self.validate(&builder).await?;
+        log::info!("update notification {id} {self:?}");
+        // a notification with the same tag replaces the old one
+        self.active_notifications
+            .write()
+            .await
+            .retain(|n| n.id != id);
+        let handle = self.show_notification(id.to_owned(), builder).await?;
+        Ok(Box::new(handle) as Box<dyn NotificationHandle>)
+    }
+
+    async fn update_progress(
+        &self,
+        id: &str,
+        state: ProgressState,
+        status: &str,
+    ) -> Result<(), crate::Error> {
+        log::info!("NotificationManagerWeb::update_progress {id} {state:?} {status}");
+        let mut builder = self
+            .progress_notifications
+            .read()
+            .await
+            .get(id)
+            .cloned()
+            .ok_or_else(|| Error::NotificationNotFound(id.to_owned()))?;
+        if let Some(progress) = builder.progress.as_mut() {
+            progress.state = state;
+            progress.status = status.to_owned();
+        }
+        self.update_notification(id, builder).await?;
+        Ok(())
+    }
+
+    /// Uses [navigator.setAppBadge](https://developer.mozilla.org/en-US/docs/Web/API/Navigator/setAppBadge)
+    /// where it is available, like for installed web apps
+    async fn set_badge_count(&self, count: u32) -> Result<(), crate::Error> {
+        self.badge_count.store(count, Ordering::Relaxed);
+        let promise = {
+            let Some(navigator) = web_sys::window().map(|window| window.navigator()) else {
+                return Ok(());
+            };
+            let set_app_badge = js_sys::Reflect::get(&navigator, &JsValue::from_str("setAppBadge"))
+                .map_err(js_error)?;
+            let Some(set_app_badge) = set_app_badge.dyn_ref::<js_sys::Function>() else {
+                log::info!(
+                    "NotificationManagerWeb::set_badge_count {count}: not supported, only remembered"
+                );
+                return Ok(());
+            };
+            set_app_badge
+                .call1(&navigator, &JsValue::from(count))
+                .map_err(js_error)?
+                .dyn_into::<js_sys::Promise>()
+                .map_err(js_error)?
+        };
+        SendWrapper::new(JsFuture::from(promise))
+            .await
+            .map_err(js_error)?;
+        Ok(())
+    }
+
+    async fn get_badge_count(&self) -> Result<u32, crate::Error> {
+        Ok(self.badge_count.load(Ordering::Relaxed))
+    }
+
+    /// The page needs to stay open until the notification is shown
+    async fn schedule_notification(
+        &self,
+        builder: NotificationBuilder,
+        at: DateTime<Utc>,
+    ) -> Result<String, crate::Error> {
+        self.schedule(builder, Schedule::At(at)).await
+    }
+
+    async fn schedule_repeating_notification(
+        &self,
+        builder: NotificationBuilder,
+        interval: RepeatInterval,
+    ) -> Result<String, crate::Error> {
+        self.schedule(builder, Schedule::Every(interval)).await
+    }
+
+    async fn schedule_calendar_notification(
+        &self,
+        builder: NotificationBuilder,
+        trigger: CalendarTrigger,
+    ) -> Result<String, crate::Error> {
+        self.schedule(builder, Schedule::Calendar(trigger)).await
+    }
+
+    fn cancel_scheduled_notification(&self, id: &str) -> Result<(), crate::Error> {
+        if !self.scheduler.cancel(id) {
+            return Err(Error::NotificationNotFound(id.to_owned()));
+        }
+        Ok(())
+    }
+}
+
+/// The browser loads the image urls itself, like the paths, which are urls on the web
+fn image_urls_as_paths(mut builder: NotificationBuilder) -> NotificationBuilder {
+    if let Some(url) = builder.image_url.take() {
+        builder.image = Some(url.into());
+    }
+    if let Some(url) = builder.hero_image_url.take() {
+        builder.hero_image = Some(url.into());
+    }
+    if let Some(sender) = &mut builder.sender
+        && let Some(url) = sender.avatar_url.take()
+    {
+        sender.avatar = Some(url.into());
+    }
+    builder
+}
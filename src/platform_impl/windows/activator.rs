@@ -0,0 +1,184 @@
+//! COM server that windows calls when a toast or one of its buttons is clicked.
+//! Unlike the event handlers on the toast this also works after the app was closed,
+//! windows then starts the app with [TOAST_ACTIVATED_ARG] and calls the activator once it is registered again.
+//!
+//! https://learn.microsoft.com/windows/apps/design/shell/tiles-and-notifications/send-local-toast-other-apps
+
+use std::ffi::c_void;
+use windows::Win32::Foundation::CLASS_E_NOAGGREGATION;
+use windows::Win32::System::Com::{
+    CLSCTX_LOCAL_SERVER, COINIT_MULTITHREADED, CoInitializeEx, CoRegisterClassObject,
+    CoRevokeClassObject, IClassFactory, IClassFactory_Impl, REGCLS_MULTIPLEUSE,
+};
+use windows::Win32::System::Registry::{HKEY_CURRENT_USER, REG_SZ, RegSetKeyValueW};
+use windows::Win32::UI::Notifications::{
+    INotificationActivationCallback, INotificationActivationCallback_Impl,
+    NOTIFICATION_USER_INPUT_DATA,
+};
+use windows::core::{BOOL, GUID, HSTRING, IUnknown, Interface, PCWSTR, Ref, implement};
+
+use super::{SELECTION_INPUT_ID, TEXT_INPUT_ID, decode_deeplink};
+use crate::{Error, launch_response::LaunchResponses, response_handler::ResponseHandler};
+
+/// Command line argument windows passes to the app when it starts it because a toast was clicked
+pub const TOAST_ACTIVATED_ARG: &str = "-ToastActivated";
+
+/// The class object registration, it is revoked when this is dropped
+#[derive(Debug)]
+pub(super) struct Registration {
+    pub(super) clsid: GUID,
+    cookie: u32,
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        if let Err(err) = unsafe { CoRevokeClassObject(self.cookie) } {
+            log::error!("failed to revoke the COM activator: {err}");
+        }
+    }
+}
+
+/// Registers the activator in the registry, so windows knows which exe to start,
+/// and registers the class object so windows can call into the running app
+pub(super) fn register(
+    app_id: &str,
+    clsid: GUID,
+    handler_callback: ResponseHandler,
+    launch_responses: LaunchResponses,
+) -> Result<Registration, Error> {
+    let clsid_string = format!("{{{clsid:?}}}");
+    let exe = std::env::current_exe()?;
+    set_registry_string(
+        &format!(r"Software\Classes\CLSID\{clsid_string}\LocalServer32"),
+        None,
+        &format!(r#""{}" {TOAST_ACTIVATED_ARG}"#, exe.display()),
+    )?;
+    set_registry_string(
+        &format!(r"Software\Classes\AppUserModelId\{app_id}"),
+        Some("CustomActivator"),
+        &clsid_string,
+    )?;
+
+    // fails if COM was already initialized with another threading model, which is fine
+    let result = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) };
+    if result.is_err() {
+        log::debug!("CoInitializeEx: {result:?}");
+    }
+
+    let factory: IClassFactory = ActivatorFactory {
+        handler_callback,
+        launch_responses,
+    }
+    .into();
+    let cookie = unsafe {
+        CoRegisterClassObject(&clsid, &factory, CLSCTX_LOCAL_SERVER, REGCLS_MULTIPLEUSE)?
+    };
+    Ok(Registration { clsid, cookie })
+}
+
+/// Registers the display name and icon of the app id, which windows shows on the toasts of unpackaged apps
+/// that have no start menu shortcut with this app id
+pub fn register_app_id(
+    app_id: &str,
+    display_name: &str,
+    icon: Option<&std::path::Path>,
+) -> Result<(), Error> {
+    let sub_key = format!(r"Software\Classes\AppUserModelId\{app_id}");
+    set_registry_string(&sub_key, Some("DisplayName"), display_name)?;
+    if let Some(icon) = icon {
+        set_registry_string(&sub_key, Some("IconUri"), &icon.display().to_string())?;
+    }
+    Ok(())
+}
+
+fn set_registry_string(sub_key: &str, value_name: Option<&str>, value: &str) -> Result<(), Error> {
+    let value_name = value_name.map(HSTRING::from);
+    let data: Vec<u16> = value.encode_utf16().chain(std::iter::once(0)).collect();
+    unsafe {
+        RegSetKeyValueW(
+            HKEY_CURRENT_USER,
+            &HSTRING::from(sub_key),
+            value_name
+                .as_ref()
+                .map(|name| PCWSTR(name.as_ptr()))
+                .unwrap_or(PCWSTR::null()),
+            REG_SZ.0,
+            Some(data.as_ptr() as *const c_void),
+            (data.len() * size_of::<u16>()) as u32,
+        )
+    }
+    .ok()?;
+    Ok(())
+}
+
+#[implement(IClassFactory)]
+struct ActivatorFactory {
+    handler_callback: ResponseHandler,
+    launch_responses: LaunchResponses,
+}
+
+impl IClassFactory_Impl for ActivatorFactory_Impl {
+    fn CreateInstance(
+        &self,
+        punkouter: Ref<'_, IUnknown>,
+        riid: *const GUID,
+        ppvobject: *mut *mut c_void,
+    ) -> windows::core::Result<()> {
+        if punkouter.is_some() {
+            return Err(CLASS_E_NOAGGREGATION.into());
+        }
+        let activator: INotificationActivationCallback = Activator {
+            handler_callback: self.handler_callback.clone(),
+            launch_responses: self.launch_responses.clone(),
+        }
+        .into();
+        unsafe { activator.query(riid, ppvobject).ok() }
+    }
+
+    fn LockServer(&self, _flock: BOOL) -> windows::core::Result<()> {
+        Ok(())
+    }
+}
+
+#[implement(INotificationActivationCallback)]
+struct Activator {
+    handler_callback: ResponseHandler,
+    launch_responses: LaunchResponses,
+}
+
+impl INotificationActivationCallback_Impl for Activator_Impl {
+    fn Activate(
+        &self,
+        _appusermodelid: &PCWSTR,
+        invokedargs: &PCWSTR,
+        data: *const NOTIFICATION_USER_INPUT_DATA,
+        count: u32,
+    ) -> windows::core::Result<()> {
+        let arguments = unsafe { invokedargs.to_string() }.unwrap_or_default();
+        let inputs = if data.is_null() {
+            &[][..]
+        } else {
+            unsafe { std::slice::from_raw_parts(data, count as usize) }
+        };
+        let user_text = inputs.iter().find_map(|input| {
+            let key = unsafe { input.Key.to_string() }.ok()?;
+            (key == TEXT_INPUT_ID || key == SELECTION_INPUT_ID)
+                .then(|| unsafe { input.Value.to_string() }.ok())
+                .flatten()
+        });
+
+        match decode_deeplink(&arguments) {
+            Ok(mut response) => {
+                response.user_text = user_text;
+                // kept for NotificationManager::take_launch_response when the app was started by the click
+                if let Some(response) = self.launch_responses.buffer(response)
+                    && let Some(handler) = self.handler_callback.get()
+                {
+                    handler(response);
+                }
+            }
+            Err(err) => log::error!("failed to decode toast arguments {arguments}: {err}"),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,1670 @@
+//! This manager logs the calls to it and does nothing more.
+//! It can be used for testing application code,
+//! or as a fallback for tauri's devmode that runs the app without a bundle id
+
+mod activator;
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, OnceLock, RwLock};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use windows::Foundation::Collections::StringMap;
+use windows::Foundation::{IReference, PropertyValue, TypedEventHandler, Uri};
+use windows::UI::Notifications::{
+    BadgeNotification, BadgeUpdateManager, NotificationData, NotificationSetting,
+    NotificationUpdateResult, ScheduledToastNotification, ToastActivatedEventArgs, ToastCollection,
+    ToastCollectionManager, ToastDismissalReason, ToastDismissedEventArgs,
+    ToastNotificationHistory, ToastNotifier,
+};
+use windows::Win32::UI::Shell::{
+    QUNS_BUSY, QUNS_PRESENTATION_MODE, QUNS_QUIET_TIME, QUNS_RUNNING_D3D_FULL_SCREEN,
+    SHQueryUserNotificationState,
+};
+use windows::core::{GUID, HSTRING, IInspectable, Interface};
+use windows::{
+    Data::Xml::Dom::{XmlDocument, XmlElement},
+    UI::Notifications::ToastNotification,
+    UI::Notifications::ToastNotificationManager,
+};
+use windows_collections::{IMap, IVectorView};
+
+use crate::{
+    CalendarTrigger, Error, InterruptionLevel, NotificationBuilder, NotificationCapabilities,
+    NotificationCategory, NotificationCategoryAction, NotificationContent,
+    NotificationDismissReason, NotificationEvent, NotificationEventKind, NotificationHandle,
+    NotificationManager, NotificationProgress, NotificationResponse, NotificationResponseAction,
+    NotificationScenario, NotificationSettings, ProgressState, RepeatInterval, Sound, SystemSound,
+    events::NotificationEvents,
+    find_duplicate,
+    launch_response::LaunchResponses,
+    response_handler::ResponseHandler,
+    scheduler::{Schedule, Scheduler},
+    unknown_category,
+};
+
+use base64::Engine;
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, percent_decode_str, utf8_percent_encode};
+
+pub use activator::{TOAST_ACTIVATED_ARG, register_app_id};
+
+#[derive(Debug, Clone)]
+pub struct NotificationHandleWindows {
+    id: String,
+    app_id: String,
+    thread_id: Option<String>,
+    /// see [NotificationBuilder::set_windows_collection_id]
+    collection_id: Option<String>,
+    user_info: HashMap<String, String>,
+    content: NotificationContent,
+}
+
+impl NotificationHandle for NotificationHandleWindows {
+    fn close(&self) -> Result<(), crate::Error> {
+        log::info!("called close notification handle {self:?}");
+        toast_history(self.collection_id.as_deref())?.RemoveGroupedTagWithId(
+            &HSTRING::from(self.id.clone()),
+            &toast_group(self.thread_id.as_deref()),
+            &HSTRING::from(self.app_id.clone()),
+        )?;
+        Ok(())
+    }
+
+    fn get_id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn get_stored_user_info(&self) -> &HashMap<String, String> {
+        &self.user_info
+    }
+
+    fn get_thread_id(&self) -> Option<&str> {
+        self.thread_id.as_deref()
+    }
+
+    fn get_content(&self) -> &NotificationContent {
+        &self.content
+    }
+}
+
+#[derive(Clone)]
+pub struct NotificationManagerWindows {
+    handler_callback: ResponseHandler,
+    /// responses before [NotificationManager::register], see [NotificationManager::take_launch_response]
+    launch_responses: LaunchResponses,
+    events: NotificationEvents,
+    /// set once [NotificationManagerWindows::register_com_activator] was called,
+    /// revoked when the last clone of the manager is dropped
+    com_activator: Arc<OnceLock<activator::Registration>>,
+    app_id: String,
+    notification_protocol: Option<String>,
+    categories: Arc<RwLock<HashMap<String, NotificationCategory>>>,
+    scheduler: Scheduler,
+    badge_count: Arc<AtomicU32>,
+}
+
+impl std::fmt::Debug for NotificationManagerWindows {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NotificationManagerWindows")
+            .field(
+                "handler_callback",
+                match &self.handler_callback.get() {
+                    Some(_) => &"handler",
+                    None => &"no handler",
+                },
+            )
+            .finish()
+    }
+}
+
+// const POWERSHELL_ID: &str =
+//     "{1AC14E77-02E7-4E5D-B744-2EB1AE5198B7}\\WindowsPowerShell\\v1.0\\powershell.exe";
+
+/// Group of toasts without thread id
+const MESSAGE_GROUP: &str = "msg-group";
+
+/// The thread id is used as [ToastNotification.Group](https://learn.microsoft.com/uwp/api/windows.ui.notifications.toastnotification.group),
+/// the group gives the tag a scope, so tag and group are needed to replace or remove a toast
+fn toast_group(thread_id: Option<&str>) -> HSTRING {
+    HSTRING::from(thread_id.unwrap_or(MESSAGE_GROUP))
+}
+
+/// The history of a [ToastCollection] or the one of the app
+fn toast_history(collection_id: Option<&str>) -> Result<ToastNotificationHistory, Error> {
+    match collection_id {
+        Some(collection_id) => Ok(ToastNotificationManager::GetDefault()?
+            .GetHistoryForToastCollectionIdAsync(&HSTRING::from(collection_id))?
+            .get()?),
+        None => Ok(ToastNotificationManager::History()?),
+    }
+}
+
+impl NotificationManagerWindows {
+    pub fn new(app_id: String, notification_protocol: Option<String>) -> Self {
+        let launch_responses = LaunchResponses::default();
+        if let Some(notification_protocol) = notification_protocol.as_ref() {
+            // windows starts the app with the deeplink as argument, when a toast with protocol activation was clicked
+            let prefix = format!("{notification_protocol}://");
+            for argument in std::env::args().skip(1) {
+                if !argument.starts_with(&prefix) {
+                    continue;
+                }
+                match decode_deeplink(&argument) {
+                    Ok(response) => {
+                        launch_responses.buffer(response);
+                    }
+                    Err(err) => log::error!("failed to decode launch argument {argument}: {err}"),
+                }
+            }
+        }
+        Self {
+            handler_callback: ResponseHandler::default(),
+            launch_responses,
+            events: NotificationEvents::default(),
+            com_activator: Arc::new(OnceLock::new()),
+            app_id,
+            notification_protocol,
+            categories: Arc::new(RwLock::new(HashMap::new())),
+            scheduler: Scheduler::default(),
+            badge_count: Arc::new(AtomicU32::new(0)),
+        }
+    }
+
+    /// Registers a COM activator, so clicks on toasts and their buttons still reach the app after it was closed.
+    ///
+    /// Without it the responses are only delivered while the app that sent the toast is running.
+    ///
+    /// The `clsid` has to be a fixed GUID unique to your app.
+    /// When windows starts the app to handle a click, it passes [TOAST_ACTIVATED_ARG] as command line argument,
+    /// the app should then call this again to receive the response.
+    /// When this is called before [NotificationManager::register], the click that started the app
+    /// is returned by [NotificationManager::take_launch_response] instead of being passed to the handler.
+    ///
+    /// Calling it again with the same `clsid` does nothing, another `clsid` fails with [Error::Unsupported].
+    pub fn register_com_activator(&self, clsid: GUID) -> Result<(), Error> {
+        if self.com_activator.get().is_none() {
+            let registration = activator::register(
+                &self.app_id,
+                clsid,
+                self.handler_callback.clone(),
+                self.launch_responses.clone(),
+            )?;
+            // when another call registered first, this registration is revoked as it is dropped
+            let _ = self.com_activator.set(registration);
+        }
+        match self.com_activator.get() {
+            Some(registration) if registration.clsid != clsid => Err(Error::Unsupported(format!(
+                "the COM activator is already registered with {:?}",
+                registration.clsid
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// The toasts of this app that are still in the action center, including the ones in collections
+    ///
+    /// https://learn.microsoft.com/uwp/api/windows.ui.notifications.toastnotificationhistory.gethistory
+    pub fn action_center_notifications(&self) -> Result<Vec<NotificationHandleWindows>, Error> {
+        let mut histories = vec![(None, self.get_history()?)];
+        for collection_id in self.collection_ids() {
+            match toast_history(Some(&collection_id)).and_then(|history| Ok(history.GetHistory()?))
+            {
+                Ok(history) => histories.push((Some(collection_id), history)),
+                Err(err) => {
+                    log::error!(
+                        "failed to get the history of the collection {collection_id}: {err}"
+                    )
+                }
+            }
+        }
+
+        let mut handles = Vec::new();
+        for (collection_id, history) in histories {
+            for toast in history {
+                let user_info = Self::user_info_from_toast(&toast).unwrap_or_else(|err| {
+                    log::warn!("failed to read the user info of a toast: {err}");
+                    HashMap::new()
+                });
+                handles.push(NotificationHandleWindows {
+                    id: toast.Tag()?.to_string(),
+                    app_id: self.app_id.clone(),
+                    thread_id: Self::thread_id_from_toast(&toast),
+                    collection_id: collection_id.clone(),
+                    user_info,
+                    content: Self::content_from_toast(&toast).unwrap_or_default(),
+                });
+            }
+        }
+        Ok(handles)
+    }
+
+    /// Removes the toast with this tag (the notification id) from the action center,
+    /// in whatever group and collection it was shown
+    ///
+    /// https://learn.microsoft.com/uwp/api/windows.ui.notifications.toastnotificationhistory.removegroupedtag
+    pub fn remove_by_tag(&self, tag: &str) -> Result<(), Error> {
+        for handle in self.action_center_notifications()? {
+            if handle.id == tag {
+                handle.close()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes all toasts of a group from the action center,
+    /// the group is the thread id, `None` removes the toasts without thread id
+    ///
+    /// https://learn.microsoft.com/uwp/api/windows.ui.notifications.toastnotificationhistory.removegroup
+    pub fn remove_by_group(&self, thread_id: Option<&str>) -> Result<(), Error> {
+        let group = toast_group(thread_id);
+        ToastNotificationManager::History()?
+            .RemoveGroupWithId(&group, &HSTRING::from(&self.app_id))?;
+        for collection_id in self.collection_ids() {
+            if let Err(err) = toast_history(Some(&collection_id))
+                .and_then(|history| Ok(history.RemoveGroup(&group)?))
+            {
+                log::error!(
+                    "failed to remove the group {group} of the collection {collection_id}: {err}"
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes all toasts of this app from the action center
+    ///
+    /// https://learn.microsoft.com/uwp/api/windows.ui.notifications.toastnotificationhistory.clear
+    pub fn clear_action_center(&self) -> Result<(), Error> {
+        // `Clear` only works for packaged apps, so clear by our app id like in `get_history`
+        ToastNotificationManager::History()?.ClearWithId(&HSTRING::from(&self.app_id))?;
+        for collection_id in self.collection_ids() {
+            if let Err(err) =
+                toast_history(Some(&collection_id)).and_then(|history| Ok(history.Clear()?))
+            {
+                log::error!("failed to clear the collection {collection_id}: {err}");
+            }
+        }
+        Ok(())
+    }
+
+    /// Creates or updates a [toast collection](https://learn.microsoft.com/windows/apps/design/shell/tiles-and-notifications/toast-collections),
+    /// toasts with [NotificationBuilder::set_windows_collection_id] are shown under its own header in the action center.
+    ///
+    /// `launch_args` are passed to the app when the header is clicked.
+    pub fn save_collection(
+        &self,
+        collection_id: &str,
+        display_name: &str,
+        launch_args: &str,
+        icon: Option<&std::path::Path>,
+    ) -> Result<(), Error> {
+        let icon = icon
+            .map(|icon| Uri::CreateUri(&HSTRING::from(format!("file:///{}", icon.display()))))
+            .transpose()?;
+        let collection = ToastCollection::CreateInstance(
+            &HSTRING::from(collection_id),
+            &HSTRING::from(display_name),
+            &HSTRING::from(launch_args),
+            icon.as_ref(),
+        )?;
+        self.get_collection_manager()?
+            .SaveToastCollectionAsync(&collection)?
+            .get()?;
+        Ok(())
+    }
+
+    /// Removes a collection saved with [NotificationManagerWindows::save_collection] together with its toasts
+    pub fn remove_collection(&self, collection_id: &str) -> Result<(), Error> {
+        self.get_collection_manager()?
+            .RemoveToastCollectionAsync(&HSTRING::from(collection_id))?
+            .get()?;
+        Ok(())
+    }
+
+    fn get_collection_manager(&self) -> Result<ToastCollectionManager, Error> {
+        Ok(ToastNotificationManager::GetDefault()?
+            .GetToastCollectionManagerWithAppId(&HSTRING::from(&self.app_id))?)
+    }
+
+    /// The ids of the saved collections, empty when windows does not support collections for the app
+    fn collection_ids(&self) -> Vec<String> {
+        let collections = self
+            .get_collection_manager()
+            .and_then(|manager| Ok(manager.FindAllToastCollectionsAsync()?.get()?));
+        match collections {
+            Ok(collections) => collections
+                .into_iter()
+                .filter_map(|collection| collection.Id().ok())
+                .map(|id| id.to_string())
+                .collect(),
+            Err(err) => {
+                log::debug!("failed to get the toast collections: {err}");
+                Vec::new()
+            }
+        }
+    }
+
+    /// See [NotificationBuilder::validate], also checks that the category is registered
+    async fn validate(&self, builder: &NotificationBuilder) -> Result<(), Error> {
+        builder.validate(&self.capabilities().await?)?;
+        if let Some(category_id) = &builder.category_id
+            && !self
+                .categories
+                .read()
+                .map_err(|_| Error::SettingHandler)?
+                .contains_key(category_id)
+        {
+            return Err(unknown_category(category_id));
+        }
+        Ok(())
+    }
+
+    /// The notifier of the app, or of a collection from [NotificationBuilder::set_windows_collection_id]
+    fn get_toast_notifier(&self, collection_id: Option<&str>) -> Result<ToastNotifier, Error> {
+        if let Some(collection_id) = collection_id {
+            return Ok(ToastNotificationManager::GetDefault()?
+                .GetToastNotifierForToastCollectionIdAsync(&HSTRING::from(collection_id))?
+                .get()?);
+        }
+        let toast_notifier =
+            ToastNotificationManager::CreateToastNotifierWithId(&HSTRING::from(&self.app_id));
+        // if let Err(err) = toast_notifier {
+        //     log::error!(
+        //         "failed to get toast notifier for current app ({err:?}), falling back to using the one from powershell"
+        //     );
+        //     toast_notifier =
+        //         ToastNotificationManager::CreateToastNotifierWithId(&HSTRING::from(POWERSHELL_ID));
+        // }
+        Ok(toast_notifier?)
+    }
+
+    /// The delivered toast with this tag
+    fn find_toast(&self, id: &str) -> Result<Option<NotificationHandleWindows>, Error> {
+        Ok(self
+            .action_center_notifications()?
+            .into_iter()
+            .find(|handle| handle.id == id))
+    }
+
+    fn get_history(&self) -> Result<IVectorView<ToastNotification>, Error> {
+        let history =
+            ToastNotificationManager::History()?.GetHistoryWithId(&HSTRING::from(&self.app_id));
+        // if let Err(err) = history {
+        //     log::error!(
+        //         "failed to get toast history for current app ({err:?}), falling back to using the one from powershell"
+        //     );
+        //     history = ToastNotificationManager::History()?
+        //         .GetHistoryWithId(&HSTRING::from(POWERSHELL_ID));
+        // }
+        Ok(history?)
+    }
+
+    fn user_info_from_toast(toast: &ToastNotification) -> Result<HashMap<String, String>, Error> {
+        let Ok(user_info_string) = toast
+            .Data()
+            .and_then(|data| data.Values())
+            .and_then(|values| values.Lookup(&HSTRING::from(USER_INFO_JSON_KEY.to_string())))
+        else {
+            // scheduled toasts have no data, the launch argument has the user info too
+            let launch = toast
+                .Content()?
+                .DocumentElement()?
+                .GetAttribute(&HSTRING::from("launch"))?;
+            return Ok(decode_deeplink(&launch.to_string())
+                .map(|response| response.user_info)
+                .unwrap_or_default());
+        };
+
+        let user_info: HashMap<String, String> =
+            serde_json::from_str(&quick_xml::escape::unescape(&user_info_string.to_string())?)
+                .map_err(Error::FailedToParseUserInfo)?;
+        Ok(user_info)
+    }
+
+    fn thread_id_from_toast(toast: &ToastNotification) -> Option<String> {
+        toast
+            .Data()
+            .and_then(|data| data.Values())
+            .and_then(|values| values.Lookup(&HSTRING::from(THREAD_ID_KEY)))
+            .map(|thread_id| thread_id.to_string())
+            .ok()
+            // scheduled toasts have no data, their group is the thread id
+            .or_else(|| {
+                toast
+                    .Group()
+                    .ok()
+                    .map(|group| group.to_string())
+                    .filter(|group| !group.is_empty() && group != MESSAGE_GROUP)
+            })
+    }
+
+    /// Reads the texts back from the toast xml, the category and the delivery time from the toast data
+    fn content_from_toast(toast: &ToastNotification) -> Result<NotificationContent, Error> {
+        let mut content = NotificationContent::default();
+        for node in &toast
+            .Content()?
+            .GetElementsByTagName(&HSTRING::from("text"))?
+        {
+            let element = node.cast::<XmlElement>()?;
+            let text = Some(element.InnerText()?.to_string());
+            match element
+                .GetAttribute(&HSTRING::from("id"))?
+                .to_string()
+                .as_str()
+            {
+                "1" => content.title = text,
+                "2" => content.subtitle = text,
+                "3" => content.body = text,
+                _ => {}
+            }
+        }
+
+        // scheduled toasts have no data
+        let values = toast.Data().and_then(|data| data.Values()).ok();
+        let lookup = |key: &str| {
+            values
+                .as_ref()?
+                .Lookup(&HSTRING::from(key))
+                .map(|value| value.to_string())
+                .ok()
+        };
+        content.category_id = lookup(CATEGORY_ID_KEY);
+        content.delivered_at = lookup(DELIVERED_AT_KEY)
+            .and_then(|date| DateTime::parse_from_rfc3339(&date).ok())
+            .map(|date| date.with_timezone(&Utc));
+        Ok(content)
+    }
+
+    /// `<actions>` of the category and the actions that were added to the notification directly
+    fn generate_actions_xml(
+        &self,
+        id: &str,
+        builder: &NotificationBuilder,
+    ) -> Result<String, Error> {
+        let mut actions = Vec::new();
+        if let Some(category_id) = builder.category_id.as_deref() {
+            let categories = self.categories.read().map_err(|_| Error::SettingHandler)?;
+            if let Some(category) = categories.get(category_id) {
+                actions.extend(category.actions.iter().cloned());
+            } else {
+                log::warn!(
+                    "Category '{}' not found in registered categories",
+                    category_id
+                );
+            }
+        }
+        actions.extend(builder.actions.iter().cloned());
+
+        let mut actions_xml = String::new();
+
+        if !actions.is_empty() {
+            actions_xml.push_str("<actions>");
+
+            for action in &actions {
+                match action {
+                    crate::NotificationCategoryAction::Action { identifier, title } => {
+                        let activation = self.action_activation(id, builder, identifier, false);
+                        let escaped_title = quick_xml::escape::escape(title);
+                        actions_xml.push_str(&format!(
+                            r#"<action content="{}" {activation} />"#,
+                            escaped_title
+                        ));
+                    }
+                    crate::NotificationCategoryAction::TextInputAction {
+                        identifier,
+                        title,
+                        input_button_title,
+                        input_placeholder,
+                        default_text,
+                    } => {
+                        let activation = self.action_activation(id, builder, identifier, true);
+                        let escaped_title = quick_xml::escape::escape(title);
+                        let escaped_button_title = quick_xml::escape::escape(input_button_title);
+                        let escaped_placeholder = quick_xml::escape::escape(input_placeholder);
+                        let default_input = default_text
+                            .as_deref()
+                            .map(|text| {
+                                format!(r#" defaultInput="{}""#, quick_xml::escape::escape(text))
+                            })
+                            .unwrap_or_default();
+                        actions_xml.push_str(&format!(
+                            r#"<input id="{TEXT_INPUT_ID}" type="text" placeHolderContent="{}"{default_input} />"#,
+                            escaped_placeholder
+                        ));
+                        actions_xml.push_str(&format!(
+                            r#"<action content="{}" {activation} hint-inputId="{TEXT_INPUT_ID}" />"#,
+                            escaped_button_title
+                        ));
+                    }
+                    crate::NotificationCategoryAction::SelectionInputAction {
+                        identifier,
+                        title,
+                        options,
+                    } => {
+                        let activation = self.action_activation(id, builder, identifier, true);
+                        let escaped_title = quick_xml::escape::escape(title);
+                        let default_input = options
+                            .first()
+                            .map(|option| {
+                                format!(
+                                    r#"defaultInput="{}""#,
+                                    quick_xml::escape::escape(&option.identifier)
+                                )
+                            })
+                            .unwrap_or_default();
+                        let selections_xml: String = options
+                            .iter()
+                            .map(|option| {
+                                format!(
+                                    r#"<selection id="{}" content="{}" />"#,
+                                    quick_xml::escape::escape(&option.identifier),
+                                    quick_xml::escape::escape(&option.title)
+                                )
+                            })
+                            .collect();
+                        actions_xml.push_str(&format!(
+                            r#"<input id="{SELECTION_INPUT_ID}" type="selection" {default_input}>{selections_xml}</input>"#
+                        ));
+                        actions_xml.push_str(&format!(
+                            r#"<action content="{}" {activation} hint-inputId="{SELECTION_INPUT_ID}" />"#,
+                            escaped_title
+                        ));
+                    }
+                }
+            }
+
+            actions_xml.push_str("</actions>");
+        }
+
+        Ok(actions_xml)
+    }
+
+    /// `arguments` and `activationType` of a button,
+    /// see [NotificationBuilder::set_windows_action_uri] and [NotificationBuilder::set_windows_protocol_activation]
+    fn action_activation(
+        &self,
+        id: &str,
+        builder: &NotificationBuilder,
+        identifier: &str,
+        has_input: bool,
+    ) -> String {
+        if let Some(uri) = builder.get_windows_action_uri(identifier) {
+            return format!(
+                r#"arguments="{}" activationType="protocol""#,
+                quick_xml::escape::escape(uri)
+            );
+        }
+        // the text of inputs only reaches foreground activations
+        if !has_input
+            && builder.windows_protocol_activation
+            && let Some(notification_protocol) = self.notification_protocol.as_ref()
+        {
+            let arguments = encode_deeplink(
+                notification_protocol,
+                &NotificationResponse {
+                    notification_id: id.to_owned(),
+                    action: NotificationResponseAction::Other(identifier.to_owned()),
+                    user_text: None,
+                    user_info: builder.user_info.clone().unwrap_or_default(),
+                    internal_info: HashMap::new(),
+                    dismiss_reason: None,
+                    delivered_at: None,
+                },
+            );
+            return format!(
+                r#"arguments="{}" activationType="protocol""#,
+                quick_xml::escape::escape(&arguments)
+            );
+        }
+        let arguments = self.action_arguments(id, builder, identifier);
+        format!(
+            r#"arguments="{}" activationType="foreground""#,
+            quick_xml::escape::escape(&arguments)
+        )
+    }
+
+    /// The COM activator only gets the arguments of the clicked button,
+    /// so they need to contain the notification id and user info too
+    fn action_arguments(
+        &self,
+        id: &str,
+        builder: &NotificationBuilder,
+        identifier: &str,
+    ) -> String {
+        if self.com_activator.get().is_none() {
+            return identifier.to_owned();
+        }
+        encode_deeplink(
+            ACTIVATOR_SCHEME,
+            &NotificationResponse {
+                notification_id: id.to_owned(),
+                action: NotificationResponseAction::Other(identifier.to_owned()),
+                user_text: None,
+                user_info: builder.user_info.clone().unwrap_or_default(),
+                internal_info: HashMap::new(),
+                dismiss_reason: None,
+                delivered_at: None,
+            },
+        )
+    }
+
+    /// Prepares the builder for a toast delivered at `delivered_at`,
+    /// builds its xml and the values of its [NotificationData], for shown and scheduled toasts alike
+    fn prepare_toast(
+        &self,
+        id: &str,
+        builder: NotificationBuilder,
+        delivered_at: DateTime<Utc>,
+    ) -> Result<(NotificationBuilder, XmlDocument, StringMap), Error> {
+        let builder = builder
+            .apply_auto_dismiss(delivered_at)
+            .apply_sender()
+            .apply_plain_body()
+            .apply_interruption_level()
+            .apply_still_images();
+        let user_info_string = builder
+            .user_info
+            .as_ref()
+            .and_then(|user_info| match serde_json::to_string(user_info) {
+                Ok(user_info_string) => Some(user_info_string),
+                Err(err) => {
+                    log::error!("failed to serialize user_info: ({user_info:?}) {err:?}");
+                    None
+                }
+            })
+            .unwrap_or("{}".to_string());
+
+        let toast_xml = self.build_toast_xml(id, &builder)?;
+
+        let user_info_map = StringMap::new()?;
+        user_info_map.Insert(
+            &HSTRING::from(USER_INFO_JSON_KEY),
+            &HSTRING::from(user_info_string),
+        )?;
+        if let Some(thread_id) = &builder.thread_id {
+            user_info_map.Insert(&HSTRING::from(THREAD_ID_KEY), &HSTRING::from(thread_id))?;
+        }
+        if let Some(category_id) = &builder.category_id {
+            user_info_map.Insert(&HSTRING::from(CATEGORY_ID_KEY), &HSTRING::from(category_id))?;
+        }
+        if let Some(progress) = &builder.progress {
+            insert_progress_values(&user_info_map.cast()?, progress)?;
+            if let Some(title) = &progress.title {
+                user_info_map.Insert(&HSTRING::from(PROGRESS_TITLE_KEY), &HSTRING::from(title))?;
+            }
+        }
+        user_info_map.Insert(
+            &HSTRING::from(DELIVERED_AT_KEY),
+            &HSTRING::from(delivered_at.to_rfc3339()),
+        )?;
+        Ok((builder, toast_xml, user_info_map))
+    }
+
+    /// Shows the toast, a toast with the same id replaces the previous one
+    fn show_notification(
+        &self,
+        id: String,
+        builder: NotificationBuilder,
+    ) -> Result<NotificationHandleWindows, Error> {
+        let (builder, toast_xml, user_info_map) = self.prepare_toast(&id, builder, Utc::now())?;
+        let content = NotificationContent::from(&builder);
+
+        let toast = ToastNotification::CreateToastNotification(&toast_xml)?;
+
+        toast.SetTag(&HSTRING::from(id.clone()))?;
+
+        // the action center does not visually group toasts by it, but they can be removed together
+        toast.SetGroup(&toast_group(builder.thread_id.as_deref()))?;
+        if let Some(expiration) = builder.expiration {
+            toast.SetExpirationTime(&expiration_time(expiration)?)?;
+        }
+        toast.SetSuppressPopup(builder.suppress_popup)?;
+
+        toast.SetData(&NotificationData::CreateNotificationDataWithValues(
+            &user_info_map,
+        )?)?;
+
+        self.register_event_listeners(&toast)?;
+
+        let toast_notifier = self.get_toast_notifier(builder.windows_collection_id.as_deref())?;
+        ensure_enabled(&toast_notifier)?;
+        toast_notifier.Show(&toast)?;
+        self.events
+            .set_response_callback(&id, &builder.response_callback);
+        self.events.emit(&id, NotificationEventKind::Presented);
+
+        let handle = NotificationHandleWindows {
+            id,
+            app_id: self.app_id.clone(),
+            thread_id: builder.thread_id,
+            collection_id: builder.windows_collection_id,
+            user_info: builder.user_info.unwrap_or_default(),
+            content,
+        };
+
+        Ok(handle)
+    }
+
+    /// Shows the notification with a timer in this process, for the schedules toasts do not have
+    async fn schedule(
+        &self,
+        builder: NotificationBuilder,
+        schedule: Schedule,
+    ) -> Result<String, crate::Error> {
+        self.validate(&builder).await?;
+        let manager = self.clone();
+        self.scheduler.schedule_notification(
+            // The id has the same length limit as the tag
+            uuid::Uuid::new_v4().to_string()[..16].to_owned(),
+            builder,
+            schedule,
+            move |id, builder| std::future::ready(manager.show_notification(id, builder).map(drop)),
+        )
+    }
+
+    /// Builds the [toast xml](https://learn.microsoft.com/uwp/schemas/tiles/toastschema/schema-root) for the notification
+    fn build_toast_xml(
+        &self,
+        id: &str,
+        builder: &NotificationBuilder,
+    ) -> Result<XmlDocument, Error> {
+        let title_xml = builder
+            .title
+            .as_ref()
+            .map(|title| {
+                format!(
+                    r#"<text id="1">{}</text>"#,
+                    quick_xml::escape::escape(title)
+                )
+            })
+            .unwrap_or("".to_string());
+
+        let subtitle_xml = builder
+            .subtitle
+            .as_ref()
+            .map(|subtitle| {
+                format!(
+                    r#"<text id="2">{}</text>"#,
+                    quick_xml::escape::escape(subtitle)
+                )
+            })
+            .unwrap_or("".to_string());
+
+        let body_xml = builder
+            .body
+            .as_ref()
+            .map(|body| format!(r#"<text id="3">{}</text>"#, quick_xml::escape::escape(body)))
+            .unwrap_or("".to_string());
+
+        let image_xml = builder
+            .image
+            .as_ref()
+            .map(|image_path| {
+                format!(
+                    r#"<image id="1" src="file:///{}" />"#, // alt="image"
+                    quick_xml::escape::escape(image_path.display().to_string())
+                )
+            })
+            .unwrap_or("".to_string());
+
+        let hero_image_xml = builder
+            .hero_image
+            .as_ref()
+            .map(|image_path| {
+                format!(
+                    r#"<image placement="hero" src="file:///{}" />"#,
+                    quick_xml::escape::escape(image_path.display().to_string())
+                )
+            })
+            .unwrap_or("".to_string());
+
+        let attribution_xml = builder
+            .attribution
+            .as_ref()
+            .map(|attribution| {
+                format!(
+                    r#"<text placement="attribution">{}</text>"#,
+                    quick_xml::escape::escape(attribution)
+                )
+            })
+            .unwrap_or("".to_string());
+
+        let icon_xml = builder
+            .icon
+            .as_ref()
+            .map(|icon_path| {
+                format!(
+                    r#"<image placement='appLogoOverride' src="file:///{}" {} />"#, // alt="icon"
+                    quick_xml::escape::escape(icon_path.display().to_string()),
+                    if builder.icon_round_crop {
+                        r#"hint-crop="circle""#
+                    } else {
+                        ""
+                    }
+                )
+            })
+            .unwrap_or("".to_string());
+
+        let audio_xml = if builder.interruption_level == Some(InterruptionLevel::Passive) {
+            r#"<audio silent="true" />"#.to_owned()
+        } else {
+            audio_xml(builder.sound.as_ref())
+        };
+        // looping sounds only loop in the alarm and incoming call scenarios, and with the long duration
+        let looping = match builder.sound {
+            Some(Sound::Looping(sound))
+                if builder.interruption_level != Some(InterruptionLevel::Passive) =>
+            {
+                Some(sound)
+            }
+            _ => None,
+        };
+        let scenario = match (builder.scenario, builder.interruption_level, looping) {
+            (Some(NotificationScenario::Alarm), _, _) => r#"scenario="alarm""#,
+            (Some(NotificationScenario::Reminder), _, _) => r#"scenario="reminder""#,
+            (Some(NotificationScenario::IncomingCall), _, _) => r#"scenario="incomingCall""#,
+            (Some(NotificationScenario::Urgent), _, _) => r#"scenario="urgent""#,
+            (None, _, Some(sound)) if sound.is_call() => r#"scenario="incomingCall""#,
+            (None, _, Some(_)) => r#"scenario="alarm""#,
+            (None, Some(InterruptionLevel::TimeSensitive), None) => r#"scenario="reminder""#,
+            (None, Some(InterruptionLevel::Critical), None) => r#"scenario="urgent""#,
+            (None, _, None) => "",
+        };
+        let duration = if looping.is_some() { "long" } else { "short" };
+
+        let launch_options = if let Some(url) = builder.get_on_click_open_url() {
+            format!(
+                r#"launch="{}" activationType="protocol""#,
+                quick_xml::escape::escape(url)
+            )
+        } else if let Some(notification_protocol) = self.notification_protocol.as_ref()
+            && (builder.windows_protocol_activation || self.com_activator.get().is_none())
+        {
+            let launch_url = encode_deeplink(
+                notification_protocol,
+                &NotificationResponse {
+                    notification_id: id.to_owned(),
+                    action: NotificationResponseAction::Default,
+                    user_text: None,
+                    user_info: builder.user_info.clone().unwrap_or_default(),
+                    internal_info: HashMap::new(),
+                    dismiss_reason: None,
+                    delivered_at: None,
+                },
+            );
+            format!(r#"launch="{launch_url}" activationType="protocol""#)
+        } else if self.com_activator.get().is_some() {
+            let launch_url = encode_deeplink(
+                ACTIVATOR_SCHEME,
+                &NotificationResponse {
+                    notification_id: id.to_owned(),
+                    action: NotificationResponseAction::Default,
+                    user_text: None,
+                    user_info: builder.user_info.clone().unwrap_or_default(),
+                    internal_info: HashMap::new(),
+                    dismiss_reason: None,
+                    delivered_at: None,
+                },
+            );
+            format!(r#"launch="{launch_url}" activationType="foreground""#)
+        } else {
+            "".to_owned()
+        };
+
+        // the values are bound to the toast data, so they can be updated in place
+        // https://learn.microsoft.com/windows/apps/design/shell/tiles-and-notifications/toast-progress-bar
+        let progress_xml = builder
+            .progress
+            .as_ref()
+            .map(|progress| {
+                format!(
+                    r#"<progress {} value="{{{PROGRESS_VALUE_KEY}}}" valueStringOverride="{{{PROGRESS_VALUE_STRING_KEY}}}" status="{{{PROGRESS_STATUS_KEY}}}" />"#,
+                    if progress.title.is_some() {
+                        format!(r#"title="{{{PROGRESS_TITLE_KEY}}}""#)
+                    } else {
+                        "".to_owned()
+                    }
+                )
+            })
+            .unwrap_or("".to_string());
+
+        // Generate actions XML based on category
+        let actions_xml = self.generate_actions_xml(id, builder)?;
+
+        let toast_xml = XmlDocument::new()?;
+        // https://learn.microsoft.com/uwp/schemas/tiles/toastschema/schema-root
+        toast_xml
+            .LoadXml(&HSTRING::from(format!(
+                r#"<toast duration="{duration}" {scenario} {launch_options}>
+                    <visual>
+                        <binding template="ToastGeneric">
+                            {title_xml}
+                            {subtitle_xml}
+                            {body_xml}
+                            {icon_xml}
+                            {image_xml}
+                            {hero_image_xml}
+                            {attribution_xml}
+                            {progress_xml}
+                        </binding>
+                    </visual>
+                    {audio_xml}
+                    {actions_xml}
+                </toast>"#
+            )))
+            .expect("the xml is malformed");
+        Ok(toast_xml)
+    }
+
+    fn register_event_listeners(&self, toast: &ToastNotification) -> Result<(), Error> {
+        fn get_activated_action(insp: &Option<IInspectable>) -> Option<String> {
+            insp.as_ref().and_then(|insp| {
+                insp.cast::<ToastActivatedEventArgs>()
+                    .and_then(|args| args.Arguments())
+                    .ok()
+                    .and_then(|arguments| {
+                        if !arguments.is_empty() {
+                            Some(arguments.to_string())
+                        } else {
+                            None
+                        }
+                    })
+            })
+        }
+
+        /// the text of text inputs or the id of the picked option of selection inputs
+        fn get_user_input(insp: &Option<IInspectable>) -> Option<String> {
+            let user_input = insp
+                .as_ref()?
+                .cast::<ToastActivatedEventArgs>()
+                .and_then(|args| args.UserInput())
+                .ok()?;
+            [TEXT_INPUT_ID, SELECTION_INPUT_ID].iter().find_map(|key| {
+                user_input
+                    .Lookup(&HSTRING::from(*key))
+                    .and_then(|value| value.cast::<IReference<HSTRING>>())
+                    .and_then(|value| value.Value())
+                    .map(|value| value.to_string())
+                    .ok()
+            })
+        }
+
+        fn get_dismissed_reason(
+            args: &Option<ToastDismissedEventArgs>,
+        ) -> Option<ToastDismissalReason> {
+            args.as_ref().and_then(|args| args.Reason().ok())
+        }
+
+        let notification_id = toast.Tag()?.to_string();
+        let user_info = Self::user_info_from_toast(toast).unwrap_or_default(); // IDEA: log error
+
+        let notification_id_clone = notification_id.clone();
+        let user_info_clone = user_info.clone();
+        let delivered_at = Self::content_from_toast(toast)
+            .ok()
+            .and_then(|content| content.delivered_at);
+        let handler_callback = self.handler_callback.clone();
+        let activation_handler = TypedEventHandler::new(move |_, insp| {
+            let action = get_activated_action(&insp);
+            if let Some(handler) = handler_callback.get() {
+                handler(crate::NotificationResponse {
+                    notification_id: notification_id_clone.clone(),
+                    action: action
+                        .map(|action| {
+                            // without the COM activator the arguments of buttons are just the action identifier
+                            decode_deeplink(&action)
+                                .map(|response| response.action)
+                                .unwrap_or(NotificationResponseAction::Other(action))
+                        })
+                        .unwrap_or(NotificationResponseAction::Default),
+                    user_text: get_user_input(&insp),
+                    user_info: user_info_clone.clone(),
+                    internal_info: HashMap::new(),
+                    dismiss_reason: None,
+                    delivered_at,
+                })
+            }
+            Ok(())
+        });
+        let handler_callback = self.handler_callback.clone();
+        let dismissed_handler = TypedEventHandler::new(move |_, args| {
+            let reason = get_dismissed_reason(&args);
+            match reason {
+                Some(ToastDismissalReason::UserCanceled) => {
+                    if let Some(handler) = handler_callback.get() {
+                        handler(crate::NotificationResponse {
+                            notification_id: notification_id.clone(),
+                            action: NotificationResponseAction::Dismiss,
+                            user_text: None,
+                            user_info: user_info.clone(),
+                            internal_info: HashMap::new(),
+                            dismiss_reason: Some(NotificationDismissReason::DismissedByUser),
+                            delivered_at,
+                        })
+                    }
+                }
+                _ => log::debug!("dissmissed toast: {reason:?}"),
+            }
+            Ok(())
+        });
+
+        // the COM activator already receives the clicks
+        if self.com_activator.get().is_none() {
+            toast.Activated(&activation_handler)?;
+        }
+        toast.Dismissed(&dismissed_handler)?;
+        Ok(())
+    }
+}
+
+const USER_INFO_JSON_KEY: &str = "UserInfoJson";
+const THREAD_ID_KEY: &str = "ThreadId";
+const CATEGORY_ID_KEY: &str = "CategoryId";
+const DELIVERED_AT_KEY: &str = "DeliveredAt";
+const PROGRESS_TITLE_KEY: &str = "progressTitle";
+const PROGRESS_VALUE_KEY: &str = "progressValue";
+const PROGRESS_VALUE_STRING_KEY: &str = "progressValueString";
+const PROGRESS_STATUS_KEY: &str = "progressStatus";
+/// id of the `<input>` element of text input actions
+const TEXT_INPUT_ID: &str = "textBox";
+/// id of the `<input>` element of selection input actions
+const SELECTION_INPUT_ID: &str = "selection";
+/// scheme of the toast arguments when the COM activator is used, it is never opened as url
+const ACTIVATOR_SCHEME: &str = "user-notify";
+
+#[async_trait]
+impl NotificationManager for NotificationManagerWindows {
+    async fn get_notification_permission_state(&self) -> Result<bool, crate::Error> {
+        // log::info!("NotificationManagerMock::get_notification_permission_state");
+        // todo not implemented yet / todo find out if that is even nessesary on windows
+        Ok(true)
+    }
+
+    /// Only whether toasts are enabled is known, the other settings are not exposed
+    async fn get_notification_settings(&self) -> Result<NotificationSettings, crate::Error> {
+        let setting =
+            ToastNotificationManager::CreateToastNotifierWithId(&HSTRING::from(&self.app_id))?
+                .Setting()?;
+        Ok(NotificationSettings {
+            authorized: setting == NotificationSetting::Enabled,
+            ..Default::default()
+        })
+    }
+
+    async fn first_time_ask_for_notification_permission(&self) -> Result<bool, crate::Error> {
+        // log::info!("NotificationManagerWindows::first_time_ask_for_notification_permission");
+        // todo not implemented yet / todo find out if that is even nessesary on windows
+        Ok(true)
+    }
+
+    async fn capabilities(&self) -> Result<NotificationCapabilities, crate::Error> {
+        Ok(NotificationCapabilities {
+            actions: true,
+            body_markup: false,
+            persistence: true,
+            inline_reply: true,
+            input_default_text: true,
+            attachments: true,
+            media_attachments: false,
+            // converted to their first frame
+            animated_images: false,
+            max_image_size: Some(3_000_000),
+            progress: true,
+            scheduling: true,
+            badges: true,
+            // the urgent scenario breaks through focus assist, but not through mute
+            critical_alerts: false,
+            ..Default::default()
+        })
+    }
+
+    /// Focus assist is reported as quiet time, busy and presentation mode are on while an app is in full screen
+    async fn is_do_not_disturb_active(&self) -> Result<bool, crate::Error> {
+        let state = unsafe { SHQueryUserNotificationState()? };
+        Ok(matches!(
+            state,
+            QUNS_QUIET_TIME | QUNS_BUSY | QUNS_RUNNING_D3D_FULL_SCREEN | QUNS_PRESENTATION_MODE
+        ))
+    }
+
+    fn register(
+        &self,
+        handler_callback: Box<dyn Fn(crate::NotificationResponse) + Send + Sync + 'static>,
+        categories: Vec<crate::NotificationCategory>,
+    ) -> Result<(), crate::Error> {
+        log::info!("NotificationManagerWindows::register {categories:?}");
+
+        self.handler_callback
+            .register(self.events.wrap_handler(handler_callback))?;
+        if let Some(handler) = self.handler_callback.get() {
+            for response in self.launch_responses.registered() {
+                handler(response);
+            }
+        }
+
+        // Store categories for later use
+        {
+            let mut stored_categories =
+                self.categories.write().map_err(|_| Error::SettingHandler)?; // Reuse existing error variant
+            stored_categories.clear();
+            for category in categories {
+                stored_categories.insert(category.identifier.clone(), category);
+            }
+        }
+
+        // register handlers to all notifications of last session
+        let history = self.get_history()?;
+        for toast in history.into_iter() {
+            if let Err(err) = self.register_event_listeners(&toast) {
+                log::error!(
+                    "failed to register event listener to toast from previous session {err:?}"
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    fn set_response_handler(
+        &self,
+        handler_callback: Box<dyn Fn(crate::NotificationResponse) + Send + Sync + 'static>,
+    ) -> Result<(), crate::Error> {
+        self.handler_callback
+            .replace(self.events.wrap_handler(handler_callback))
+    }
+
+    fn take_launch_response(&self) -> Option<NotificationResponse> {
+        self.launch_responses.take()
+    }
+
+    fn subscribe_events(&self) -> tokio::sync::broadcast::Receiver<NotificationEvent> {
+        self.events.subscribe()
+    }
+
+    fn add_categories(
+        &self,
+        categories: Vec<crate::NotificationCategory>,
+    ) -> Result<(), crate::Error> {
+        log::info!("NotificationManagerWindows::add_categories {categories:?}");
+        let mut stored_categories = self.categories.write().map_err(|_| Error::SettingHandler)?;
+        for category in categories {
+            stored_categories.insert(category.identifier.clone(), category);
+        }
+        Ok(())
+    }
+
+    fn set_categories(
+        &self,
+        categories: Vec<crate::NotificationCategory>,
+    ) -> Result<(), crate::Error> {
+        log::info!("NotificationManagerWindows::set_categories {categories:?}");
+        let mut stored_categories = self.categories.write().map_err(|_| Error::SettingHandler)?;
+        stored_categories.clear();
+        for category in categories {
+            stored_categories.insert(category.identifier.clone(), category);
+        }
+        Ok(())
+    }
+
+    fn remove_category(&self, identifier: &str) -> Result<(), crate::Error> {
+        log::info!("NotificationManagerWindows::remove_category {identifier}");
+        self.categories
+            .write()
+            .map_err(|_| Error::SettingHandler)?
+            .remove(identifier);
+        Ok(())
+    }
+
+    fn remove_all_delivered_notifications(&self) -> Result<(), crate::Error> {
+        self.events.remove_response_callbacks(None);
+        self.clear_action_center()
+    }
+
+    fn remove_delivered_notifications(&self, ids: Vec<&str>) -> Result<(), crate::Error> {
+        self.events.remove_response_callbacks(Some(&ids));
+        for handle in self.action_center_notifications()? {
+            if !ids.contains(&handle.id.as_str()) {
+                continue;
+            }
+            if let Err(err) = handle.close() {
+                log::error!(
+                    "failed to remove toast notification with tag {}: {err:?}",
+                    handle.id
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn dismiss_thread(&self, thread_id: &str) -> Result<(), crate::Error> {
+        self.remove_by_group(Some(thread_id))
+    }
+
+    async fn get_active_notifications(
+        &self,
+    ) -> Result<Vec<Box<dyn NotificationHandle>>, crate::Error> {
+        let handles = self.action_center_notifications()?;
+
+        log::debug!("get_active_notifications: {handles:?}");
+
+        Ok(handles
+            .into_iter()
+            .map(|h| Box::new(h) as Box<dyn NotificationHandle>)
+            .collect())
+    }
+
+    async fn send_notification(
+        &self,
+        builder: NotificationBuilder,
+    ) -> Result<Box<dyn NotificationHandle>, crate::Error> {
+        self.validate(&builder).await?;
+        if let Some(duplicate) = find_duplicate(self, &builder).await? {
+            return Ok(duplicate);
+        }
+        log::info!("show notification {self:?}");
+
+        // The tag can be maximum 16 characters long. However, the Creators Update (15063) extends this limit to 64 characters.
+        // ~ https://learn.microsoft.com/en-us/uwp/api/windows.ui.notifications.toastnotification.tag?view=winrt-26100#remarks
+        let id = builder.notification_id(|| uuid::Uuid::new_v4().to_string()[..16].to_owned());
+
+        let handle = self.show_notification(id, builder)?;
+        Ok(Box::new(handle) as Box<dyn NotificationHandle>)
+    }
+
+    async fn update_notification(
+        &self,
+        id: &str,
+        builder: NotificationBuilder,
+    ) -> Result<Box<dyn NotificationHandle>, crate::Error> {
+        self.validate(&builder).await?;
+        log::info!("update notification {id} {self:?}");
+        // a toast with the same tag and group replaces the old one,
+        // with another thread id or collection the old one needs to be removed
+        if let Some(toast) = self.find_toast(id)?
+            && (toast.thread_id != builder.thread_id
+                || toast.collection_id != builder.windows_collection_id)
+        {
+            toast.close()?;
+        }
+        let handle = self.show_notification(id.to_owned(), builder)?;
+        Ok(Box::new(handle) as Box<dyn NotificationHandle>)
+    }
+
+    async fn update_progress(
+        &self,
+        id: &str,
+        state: ProgressState,
+        status: &str,
+    ) -> Result<(), crate::Error> {
+        let data = NotificationData::new()?;
+        insert_progress_values(&data.Values()?, &NotificationProgress::new(state, status))?;
+        // sequence number 0 always replaces the current data
+        data.SetSequenceNumber(0)?;
+        let toast = self
+            .find_toast(id)?
+            .ok_or_else(|| Error::NotificationNotFound(id.to_owned()))?;
+        let result = self
+            .get_toast_notifier(toast.collection_id.as_deref())?
+            .UpdateWithTagAndGroup(
+                &data,
+                &HSTRING::from(id),
+                &toast_group(toast.thread_id.as_deref()),
+            )?;
+        if result != NotificationUpdateResult::Succeeded {
+            return Err(Error::NotificationNotFound(id.to_owned()));
+        }
+        Ok(())
+    }
+
+    /// https://learn.microsoft.com/en-us/windows/apps/design/shell/tiles-and-notifications/badges
+    async fn set_badge_count(&self, count: u32) -> Result<(), crate::Error> {
+        let badge_updater = BadgeUpdateManager::CreateBadgeUpdaterForApplicationWithId(
+            &HSTRING::from(&self.app_id),
+        )?;
+        if count == 0 {
+            badge_updater.Clear()?;
+        } else {
+            let badge_xml = XmlDocument::new()?;
+            badge_xml.LoadXml(&HSTRING::from(format!(r#"<badge value="{count}"/>"#)))?;
+            badge_updater.Update(&BadgeNotification::CreateBadgeNotification(&badge_xml)?)?;
+        }
+        self.badge_count.store(count, Ordering::Relaxed);
+        Ok(())
+    }
+
+    async fn get_badge_count(&self) -> Result<u32, crate::Error> {
+        Ok(self.badge_count.load(Ordering::Relaxed))
+    }
+
+    async fn schedule_notification(
+        &self,
+        builder: NotificationBuilder,
+        at: DateTime<Utc>,
+    ) -> Result<String, crate::Error> {
+        self.validate(&builder).await?;
+        log::info!("schedule notification at {at} {self:?}");
+
+        // The id has the same length limit as the tag
+        let id = uuid::Uuid::new_v4().to_string()[..16].to_owned();
+
+        let (builder, toast_xml, values) = self.prepare_toast(&id, builder, at)?;
+        // scheduled toasts have no NotificationData, so the progress is written into the xml,
+        // the user info and the thread are read back from the launch argument and the group
+        bind_progress_values(&toast_xml, &values)?;
+        let toast = ScheduledToastNotification::CreateScheduledToastNotification(
+            &toast_xml,
+            to_windows_date_time(at),
+        )?;
+        toast.SetId(&HSTRING::from(id.clone()))?;
+        toast.SetTag(&HSTRING::from(id.clone()))?;
+        toast.SetGroup(&toast_group(builder.thread_id.as_deref()))?;
+        if let Some(expiration) = builder.expiration {
+            toast.SetExpirationTime(&expiration_time(expiration)?)?;
+        }
+        toast.SetSuppressPopup(builder.suppress_popup)?;
+
+        // scheduled toasts have no event handlers,
+        // responses to them only arrive through the notification_protocol or the COM activator
+        let toast_notifier = self.get_toast_notifier(builder.windows_collection_id.as_deref())?;
+        ensure_enabled(&toast_notifier)?;
+        toast_notifier.AddToSchedule(&toast)?;
+        self.events
+            .set_response_callback(&id, &builder.response_callback);
+
+        Ok(id)
+    }
+
+    async fn schedule_repeating_notification(
+        &self,
+        builder: NotificationBuilder,
+        interval: RepeatInterval,
+    ) -> Result<String, crate::Error> {
+        // toasts have no native repetition, so this uses a timer in this process
+        self.schedule(builder, Schedule::Every(interval)).await
+    }
+
+    async fn schedule_calendar_notification(
+        &self,
+        builder: NotificationBuilder,
+        trigger: CalendarTrigger,
+    ) -> Result<String, crate::Error> {
+        // toasts have no calendar triggers, so this uses a timer in this process
+        self.schedule(builder, Schedule::Calendar(trigger)).await
+    }
+
+    fn cancel_scheduled_notification(&self, id: &str) -> Result<(), crate::Error> {
+        let mut cancelled = self.scheduler.cancel(id);
+        self.events.remove_response_callbacks(Some(&[id]));
+        let collection_ids = self.collection_ids();
+        let notifiers = std::iter::once(None).chain(
+            collection_ids
+                .iter()
+                .map(|collection_id| Some(collection_id.as_str())),
+        );
+        for collection_id in notifiers {
+            let toast_notifier = self.get_toast_notifier(collection_id)?;
+            for toast in toast_notifier.GetScheduledToastNotifications()? {
+                if toast.Id()?.to_string() == id {
+                    toast_notifier.RemoveFromSchedule(&toast)?;
+                    cancelled = true;
+                }
+            }
+        }
+        if !cancelled {
+            return Err(Error::NotificationNotFound(id.to_owned()));
+        }
+        Ok(())
+    }
+}
+
+/// Toasts are dropped silently when notifications are turned off, so this checks the setting first
+fn ensure_enabled(toast_notifier: &ToastNotifier) -> Result<(), Error> {
+    match toast_notifier.Setting() {
+        Ok(NotificationSetting::Enabled) => Ok(()),
+        Ok(setting) => {
+            log::debug!("notifications are turned off: {setting:?}");
+            Err(Error::PermissionDenied)
+        }
+        // try to show the toast anyway
+        Err(err) => {
+            log::debug!("failed to read notification setting: {err:?}");
+            Ok(())
+        }
+    }
+}
+
+/// Replaces the bindings of the `<progress>` element with their values, for toasts without data
+fn bind_progress_values(toast_xml: &XmlDocument, values: &StringMap) -> Result<(), Error> {
+    for node in &toast_xml.GetElementsByTagName(&HSTRING::from("progress"))? {
+        let element = node.cast::<XmlElement>()?;
+        for attribute in ["title", "value", "valueStringOverride", "status"] {
+            let attribute = HSTRING::from(attribute);
+            let binding = element.GetAttribute(&attribute)?.to_string();
+            if let Some(key) = binding
+                .strip_prefix('{')
+                .and_then(|binding| binding.strip_suffix('}'))
+                && let Ok(value) = values.Lookup(&HSTRING::from(key))
+            {
+                element.SetAttribute(&attribute, &value)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Inserts the values the `<progress>` element of the toast binds to
+fn insert_progress_values(
+    values: &IMap<HSTRING, HSTRING>,
+    progress: &NotificationProgress,
+) -> Result<(), Error> {
+    values.Insert(
+        &HSTRING::from(PROGRESS_VALUE_KEY),
+        &HSTRING::from(match progress.state {
+            ProgressState::Determinate(value) => value.clamp(0.0, 1.0).to_string(),
+            ProgressState::Indeterminate => "indeterminate".to_owned(),
+        }),
+    )?;
+    values.Insert(
+        &HSTRING::from(PROGRESS_VALUE_STRING_KEY),
+        &HSTRING::from(
+            progress
+                .percent()
+                .map(|percent| format!("{percent}%"))
+                .unwrap_or_default(),
+        ),
+    )?;
+    values.Insert(
+        &HSTRING::from(PROGRESS_STATUS_KEY),
+        &HSTRING::from(&progress.status),
+    )?;
+    Ok(())
+}
+
+/// The expiration time is a nullable DateTime
+fn expiration_time(
+    expiration: DateTime<Utc>,
+) -> Result<IReference<windows::Foundation::DateTime>, Error> {
+    Ok(PropertyValue::CreateDateTime(to_windows_date_time(expiration))?.cast()?)
+}
+
+/// [DateTime](https://learn.microsoft.com/en-us/uwp/api/windows.foundation.datetime) counts 100ns intervals since January 1, 1601
+fn to_windows_date_time(at: DateTime<Utc>) -> windows::Foundation::DateTime {
+    const SECONDS_FROM_1601_TO_1970: i64 = 11_644_473_600;
+    windows::Foundation::DateTime {
+        UniversalTime: (at.timestamp() + SECONDS_FROM_1601_TO_1970) * 10_000_000
+            + i64::from(at.timestamp_subsec_nanos() / 100),
+    }
+}
+
+/// Maps the [Sound] to a toast [`<audio>`](https://learn.microsoft.com/uwp/schemas/tiles/toastschema/element-audio) element
+fn audio_xml(sound: Option<&Sound>) -> String {
+    let src = match sound {
+        None => "ms-winsoundevent:Notification.SMS".to_owned(),
+        Some(Sound::Default) => "ms-winsoundevent:Notification.Default".to_owned(),
+        Some(Sound::None) => return r#"<audio silent="true" />"#.to_owned(),
+        Some(Sound::System(sound)) => format!("ms-winsoundevent:{}", winsoundevent(*sound)),
+        // already an uri like `ms-appx:///sound.wav` or `ms-winsoundevent:Notification.Mail`
+        Some(Sound::Named(uri)) if uri.contains(':') => uri.to_owned(),
+        Some(Sound::Named(name)) => format!("ms-winsoundevent:Notification.{name}"),
+        Some(Sound::Looping(sound)) => {
+            return format!(
+                r#"<audio src="ms-winsoundevent:Notification.Looping.{sound:?}" loop="true" />"#
+            );
+        }
+        Some(Sound::File(path)) => {
+            log::warn!("sound files are not supported by windows toasts, ignoring {path:?}");
+            return audio_xml(None);
+        }
+    };
+    format!(r#"<audio src="{}" />"#, quick_xml::escape::escape(src))
+}
+
+/// See the table of [SystemSound]
+fn winsoundevent(sound: SystemSound) -> &'static str {
+    match sound {
+        SystemSound::Message => "Notification.IM",
+        SystemSound::Mail => "Notification.Mail",
+        SystemSound::Reminder => "Notification.Reminder",
+        SystemSound::Alarm => "Notification.Looping.Alarm",
+        SystemSound::Call => "Notification.Looping.Call",
+    }
+}
+
+/// Characters of the action id that are kept in the path of the deeplink, the others are percent-encoded,
+/// so ids with spaces or slashes survive the round trip through [url::Url]
+const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'_').remove(b'~');
+
+fn encode_deeplink(scheme: &str, response: &NotificationResponse) -> String {
+    let NotificationResponse {
+        notification_id,
+        action,
+        ..
+    } = response;
+    let user_info = response.stored_user_info();
+
+    // TODO: dedup code to not do the encoding here again
+    let user_info_string = match serde_json::to_string(&user_info) {
+        Ok(user_info_string) => Some(user_info_string),
+        Err(err) => {
+            log::error!("failed to serialize user_info: ({user_info:?}) {err:?}");
+            None
+        }
+    }
+    .unwrap_or("{}".to_string());
+
+    let launch_attribute = base64::prelude::BASE64_STANDARD.encode(&user_info_string);
+
+    let action_string = match &action {
+        NotificationResponseAction::Default => "__default__",
+        NotificationResponseAction::Dismiss => "__dismiss__",
+        NotificationResponseAction::Other(action) => action.as_ref(),
+    };
+
+    let action_segment = utf8_percent_encode(action_string, PATH_SEGMENT);
+    format!("{scheme}://{notification_id}/{action_segment}?{launch_attribute}")
+}
+
+pub fn decode_deeplink(link: &str) -> Result<NotificationResponse, Error> {
+    let url = url::Url::parse(link)?;
+
+    let user_info: HashMap<String, String> = match url.query() {
+        None => {
+            log::error!("notification deeplink has no user info");
+            HashMap::new()
+        }
+        Some(base64_userinfo) => {
+            let user_info_str = base64::prelude::BASE64_STANDARD.decode(base64_userinfo)?;
+            serde_json::from_slice(user_info_str.as_slice())
+                .map_err(Error::FailedToParseUserInfo)?
+        }
+    };
+
+    Ok(NotificationResponse {
+        notification_id: url.host().map(|host| host.to_string()).unwrap_or_default(),
+        action: match percent_decode_str(url.path().strip_prefix('/').unwrap_or(url.path()))
+            .decode_utf8_lossy()
+            .as_ref()
+        {
+            "__default__" => NotificationResponseAction::Default,
+            "__dismiss__" => NotificationResponseAction::Dismiss,
+            action => NotificationResponseAction::Other(action.to_owned()),
+        },
+        user_text: None,
+        user_info,
+        internal_info: HashMap::new(),
+        dismiss_reason: None,
+        delivered_at: None,
+    }
+    .split_internal_info())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LoopingSound;
+
+    #[test]
+    fn encode_decode() {
+        let input = NotificationResponse {
+            action: NotificationResponseAction::Default,
+            notification_id: "abcd123-abc12".to_string(),
+            user_info: HashMap::from([
+                ("a".to_string(), "b".to_string()),
+                ("c".to_string(), "d".to_string()),
+            ]),
+            user_text: None,
+            internal_info: HashMap::new(),
+            dismiss_reason: None,
+            delivered_at: None,
+        };
+        let encoded = encode_deeplink("dcnotification", &input);
+        let output = decode_deeplink(&encoded);
+        assert_eq!(input, output.unwrap());
+
+        let input = NotificationResponse {
+            action: NotificationResponseAction::Other("reply".to_string()),
+            ..input
+        };
+        let encoded = encode_deeplink(ACTIVATOR_SCHEME, &input);
+        let output = decode_deeplink(&encoded);
+        assert_eq!(input, output.unwrap());
+
+        let input = NotificationResponse {
+            action: NotificationResponseAction::Other(r#"say "hi" & <bye>/now.."#.to_string()),
+            ..input
+        };
+        let encoded = encode_deeplink(ACTIVATOR_SCHEME, &input);
+        let attribute = quick_xml::escape::escape(&encoded);
+        assert!(!attribute.contains(['"', '<']));
+        let unescaped = quick_xml::escape::unescape(&attribute).unwrap();
+        let output = decode_deeplink(&unescaped);
+        assert_eq!(input, output.unwrap());
+    }
+
+    #[test]
+    fn sound_to_audio_xml() {
+        assert_eq!(
+            audio_xml(Some(&Sound::Default)),
+            r#"<audio src="ms-winsoundevent:Notification.Default" />"#
+        );
+        assert_eq!(
+            audio_xml(Some(&Sound::Named("Mail".to_owned()))),
+            r#"<audio src="ms-winsoundevent:Notification.Mail" />"#
+        );
+        assert_eq!(
+            audio_xml(Some(&Sound::Named("ms-appx:///sounds/ping.wav".to_owned()))),
+            r#"<audio src="ms-appx:///sounds/ping.wav" />"#
+        );
+        assert_eq!(
+            audio_xml(Some(&Sound::System(SystemSound::Alarm))),
+            r#"<audio src="ms-winsoundevent:Notification.Looping.Alarm" />"#
+        );
+        assert_eq!(
+            audio_xml(Some(&Sound::Looping(LoopingSound::Call3))),
+            r#"<audio src="ms-winsoundevent:Notification.Looping.Call3" loop="true" />"#
+        );
+        assert_eq!(audio_xml(Some(&Sound::None)), r#"<audio silent="true" />"#);
+    }
+}
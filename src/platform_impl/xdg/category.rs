@@ -1,9 +1,9 @@
 use crate::xdg_category::XdgNotificationCategory;
 
-impl XdgNotificationCategory {
-    pub fn to_string(self) -> String {
+impl std::fmt::Display for XdgNotificationCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         use XdgNotificationCategory::*;
-        match &self {
+        let category = match self {
             Call => "call",
             CallEnded => "call.ended",
             CallIncoming => "call.incoming",
@@ -29,7 +29,7 @@ impl XdgNotificationCategory {
             TransferComplete => "transfer.complete",
             TransferError => "transfer.error",
             Custom(category) => category,
-        }
-        .to_owned()
+        };
+        f.write_str(category)
     }
 }
@@ -0,0 +1,1021 @@
+//! Notifications over the [notification daemon](https://specifications.freedesktop.org/notification-spec/latest/) using notify-rust
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        Arc, Mutex, OnceLock,
+        atomic::{AtomicU32, Ordering},
+    },
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures_lite::StreamExt;
+use image::ImageReader;
+use notify_rust::{ActionResponse, CloseReason, Hint, Timeout, Urgency, handle_action};
+use tokio::sync::{RwLock, broadcast};
+
+use crate::{
+    CalendarTrigger, Error, InterruptionLevel, NotificationBuilder, NotificationCapabilities,
+    NotificationCategory, NotificationCategoryAction, NotificationContent,
+    NotificationDismissReason, NotificationEvent, NotificationEventKind, NotificationHandle,
+    NotificationManager, NotificationManagerOptions, NotificationProgress, NotificationResponse,
+    NotificationResponseAction, NotificationServerInformation, NotificationUrgency, ProgressState,
+    RepeatInterval, Sound, SystemSound,
+    events::NotificationEvents,
+    find_duplicate, markdown,
+    response_handler::ResponseHandler,
+    scheduler::{Schedule, Scheduler},
+    unknown_category,
+};
+
+#[derive(Debug, Clone)]
+pub struct NotificationHandleXdg {
+    id: String,
+    thread_id: Option<String>,
+    user_info: HashMap<String, String>,
+    content: NotificationContent,
+    handle: Arc<RwLock<Option<notify_rust::NotificationHandle>>>,
+}
+
+impl NotificationHandle for NotificationHandleXdg {
+    fn close(&self) -> Result<(), crate::Error> {
+        log::info!("called close notification handle {self:?}");
+        // sends CloseNotification over dbus
+        if let Some(handle) = self.handle.try_write()?.take() {
+            handle.close();
+        } else {
+            log::warn!("notification was already closed");
+        }
+        Ok(())
+    }
+
+    fn get_id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn get_stored_user_info(&self) -> &HashMap<String, String> {
+        &self.user_info
+    }
+
+    fn get_thread_id(&self) -> Option<&str> {
+        self.thread_id.as_deref()
+    }
+
+    fn get_content(&self) -> &NotificationContent {
+        &self.content
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct NotificationManagerXdg {
+    active_notifications: Arc<RwLock<Vec<NotificationHandleXdg>>>,
+    handler: ResponseHandler,
+    categories: Arc<std::sync::RwLock<HashMap<String, NotificationCategory>>>,
+    scheduler: Scheduler,
+    badge_count: Arc<AtomicU32>,
+    /// notifications with progress, to show them again with the new progress
+    progress_notifications: Arc<RwLock<HashMap<String, NotificationBuilder>>>,
+    events: NotificationEvents,
+    /// the name, desktop entry and icon of the app
+    options: Arc<NotificationManagerOptions>,
+    /// notifications that are closed because they expired, so their close is reported as expired
+    expired_notifications: Arc<Mutex<HashSet<String>>>,
+    /// changes of the `Inhibited` property, the listener starts with the first subscriber
+    do_not_disturb: Arc<OnceLock<broadcast::Sender<bool>>>,
+    /// the id and the identifier of the text input action by the dbus id of notifications with an inline reply
+    inline_replies: Arc<Mutex<HashMap<u32, (String, String)>>>,
+    /// the listener for `NotificationReplied` starts with the first inline reply
+    inline_reply_listener: Arc<OnceLock<()>>,
+    /// the current notification id and how often a notification was shown again with it, by the dbus id.
+    /// A notification that replaces another one keeps its dbus id,
+    /// so the responses of the listener of the replaced one go to the new id
+    notification_ids: Arc<Mutex<HashMap<u32, (String, u64)>>>,
+    /// the capabilities of the notification daemon, queried once
+    capabilities: Arc<tokio::sync::OnceCell<NotificationCapabilities>>,
+}
+
+impl std::fmt::Debug for NotificationManagerXdg {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NotificationManagerXdg")
+            .field("active_notifications", &self.active_notifications)
+            .field("handler", &self.handler.is_set().to_string())
+            .field("categories", &self.categories)
+            .finish()
+    }
+}
+
+impl NotificationManagerXdg {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// See [NotificationManagerOptions]
+    pub fn with_options(options: NotificationManagerOptions) -> Self {
+        Self {
+            options: Arc::new(options),
+            ..Self::default()
+        }
+    }
+
+    /// See [NotificationBuilder::validate], also checks that the category is registered
+    async fn validate(&self, builder: &NotificationBuilder) -> Result<(), Error> {
+        let mut capabilities = self.capabilities().await?;
+        // without inline replies text inputs are shown as a plain button that opens the app
+        capabilities.inline_reply = true;
+        builder.validate(&capabilities)?;
+        if let Some(category_id) = &builder.category_id
+            && !self
+                .categories
+                .read()
+                .map_err(|_| Error::SettingHandler)?
+                .contains_key(category_id)
+        {
+            return Err(unknown_category(category_id));
+        }
+        Ok(())
+    }
+
+    async fn add_notification(&self, notification: NotificationHandleXdg) {
+        self.active_notifications.write().await.push(notification);
+    }
+
+    /// Removes the last matching notification from the active ones,
+    /// its dbus id is the `replaces_id` of the notification that replaces it
+    async fn take_replaced(
+        &self,
+        is_replaced: impl Fn(&NotificationHandleXdg) -> bool,
+    ) -> Option<u32> {
+        let replaced = {
+            let mut active_notifications = self.active_notifications.write().await;
+            active_notifications
+                .iter()
+                .rposition(is_replaced)
+                .map(|index| active_notifications.remove(index))
+        };
+        match replaced {
+            Some(replaced) => replaced.handle.read().await.as_ref().map(|h| h.id()),
+            None => None,
+        }
+    }
+
+    /// `replaces_id` is the dbus id of a notification that should be replaced by this one
+    async fn show_notification(
+        &self,
+        id: String,
+        builder: NotificationBuilder,
+        replaces_id: Option<u32>,
+    ) -> Result<NotificationHandleXdg, Error> {
+        if builder.progress.is_some() {
+            self.progress_notifications
+                .write()
+                .await
+                .insert(id.clone(), builder.clone());
+        }
+        let auto_dismiss_after = builder.auto_dismiss_after;
+        // the "value" hint is sent anyway, the text is only needed when the daemon does not draw the bar
+        let progress_value = builder
+            .progress
+            .as_ref()
+            .and_then(NotificationProgress::percent);
+        let progress_bar = progress_value.is_some()
+            && self
+                .capabilities()
+                .await
+                .is_ok_and(|capabilities| capabilities.progress);
+        let builder = if progress_bar {
+            builder.apply_progress_status()
+        } else {
+            builder.apply_progress()
+        };
+        let builder = builder
+            .apply_auto_dismiss(Utc::now())
+            .apply_sender()
+            .apply_interruption_level()
+            .apply_hero_image_and_attribution();
+        let content = NotificationContent::from(&builder);
+        let mut notification = notify_rust::Notification::new();
+
+        if let Some(replaces_id) = replaces_id {
+            notification.id(replaces_id);
+        }
+
+        // As said in the readme all notifications are persistent (TODO confirm it does what I expect on kde and gnome)
+        let urgency = match (builder.urgency, builder.interruption_level) {
+            (Some(NotificationUrgency::Low), _) => Urgency::Low,
+            (Some(NotificationUrgency::Normal), _) => Urgency::Normal,
+            (Some(NotificationUrgency::Critical), _) => Urgency::Critical,
+            (None, Some(InterruptionLevel::Passive)) => Urgency::Low,
+            (None, None | Some(InterruptionLevel::Active)) => Urgency::Normal,
+            (None, Some(InterruptionLevel::TimeSensitive | InterruptionLevel::Critical)) => {
+                Urgency::Critical
+            }
+        };
+        notification.hint(Hint::Urgency(urgency));
+        notification.hint(Hint::Resident(builder.xdg_resident.unwrap_or(true)));
+        if let Some(progress_value) = progress_value {
+            notification.hint(Hint::CustomInt("value".to_owned(), progress_value as i32));
+        }
+        // the daemon may ignore it for resident notifications, the expiration timer removes them anyway
+        if let Some(auto_dismiss_after) = auto_dismiss_after {
+            let milliseconds = u32::try_from(auto_dismiss_after.as_millis()).unwrap_or(u32::MAX);
+            notification.timeout(Timeout::Milliseconds(milliseconds));
+        }
+        if let Some(app_name) = builder.xdg_app_name.or(self.options.app_name.clone()) {
+            notification.appname(&app_name);
+        }
+        if let Some(desktop_entry) = self.options.get_desktop_entry() {
+            notification.hint(Hint::DesktopEntry(desktop_entry.to_owned()));
+        }
+
+        if let Some(body) = builder.body {
+            let body = if builder.body_markdown {
+                match self.capabilities().await {
+                    Ok(capabilities) if capabilities.body_markup => super::body_markup(
+                        &body,
+                        capabilities.raw.iter().any(|c| c == "body-hyperlinks"),
+                    ),
+                    _ => quick_xml::escape::escape(markdown::to_plain_text(&body)).into_owned(),
+                }
+            } else {
+                quick_xml::escape::escape(body).into_owned()
+            };
+            notification.body(&body);
+        }
+
+        if let Some(title) = builder.title {
+            notification.summary(&title);
+        }
+
+        // subtitles are not supported by xdg spec
+
+        match builder.sound {
+            // the daemon plays its usual sound
+            None | Some(Sound::Default) => {}
+            Some(Sound::None) => {
+                notification.hint(Hint::SuppressSound(true));
+            }
+            Some(Sound::System(sound)) => {
+                notification.hint(Hint::SoundName(sound_name(sound).to_owned()));
+            }
+            // played once
+            Some(Sound::Looping(sound)) => {
+                notification.hint(Hint::SoundName(sound_name(sound.system_sound()).to_owned()));
+            }
+            Some(Sound::Named(name)) => {
+                notification.hint(Hint::SoundName(name));
+            }
+            Some(Sound::File(path)) => {
+                if !path.is_file() {
+                    return Err(Error::UnsupportedSoundFile(path));
+                }
+                notification.hint(Hint::SoundFile(path.display().to_string()));
+            }
+        }
+
+        if let Some(path) = builder.image {
+            match load_image_data(&path) {
+                Ok(img) => {
+                    notification.hint(Hint::ImageData(img));
+                }
+                Err(error) => {
+                    // let the notification server try to load it itself
+                    log::warn!("failed to load image, falling back to image-path: {error}");
+                    notification.hint(Hint::ImagePath(format!("file://{}", path.display())));
+                }
+            }
+        }
+
+        if let Some(path) = builder.icon.or(self.options.icon.clone()) {
+            // untested
+            notification.icon(&format!("file://{}", path.display()));
+        } else {
+            notification.auto_icon();
+        }
+
+        if let Some(_thread_id) = &builder.thread_id {
+            // not specified yet (as of first half of 2025, but it is planned)
+            // does not exist in xdg spec yet: https://github.com/flatpak/xdg-desktop-portal/discussions/1495
+        }
+
+        let mut actions = Vec::new();
+        if let Some(category_id) = builder.category_id {
+            let categories = self.categories.read().map_err(|_| Error::SettingHandler)?;
+            if let Some(category) = categories.get(&category_id) {
+                actions.extend(category.actions.iter().cloned());
+            } else {
+                log::warn!("Category '{category_id}' not found in registered categories");
+            }
+        }
+        actions.extend(builder.actions);
+        let supports_inline_reply = actions
+            .iter()
+            .any(|action| matches!(action, NotificationCategoryAction::TextInputAction { .. }))
+            && self
+                .capabilities()
+                .await
+                .is_ok_and(|capabilities| capabilities.inline_reply);
+        // the identifier of the text input action that is shown as inline reply, there can only be one
+        let mut inline_reply = None;
+        for action in actions
+            .iter()
+            .flat_map(NotificationCategoryAction::expand_selection)
+        {
+            match action {
+                NotificationCategoryAction::TextInputAction {
+                    identifier,
+                    title,
+                    input_button_title,
+                    input_placeholder,
+                    ..
+                } if supports_inline_reply && inline_reply.is_none() => {
+                    // an extension of KDE Plasma, announced with the "inline-reply" capability
+                    notification.action(INLINE_REPLY_ACTION, &title);
+                    notification.hint(Hint::Custom(
+                        "x-kde-reply-placeholder-text".to_owned(),
+                        input_placeholder,
+                    ));
+                    notification.hint(Hint::Custom(
+                        "x-kde-reply-submit-button-text".to_owned(),
+                        input_button_title,
+                    ));
+                    inline_reply = Some(identifier);
+                }
+                // other text inputs are plain buttons, the app can ask for the text after it was opened
+                NotificationCategoryAction::Action { identifier, title }
+                | NotificationCategoryAction::TextInputAction {
+                    identifier, title, ..
+                }
+                | NotificationCategoryAction::SelectionInputAction {
+                    identifier, title, ..
+                } => {
+                    notification.action(&identifier, &title);
+                }
+            }
+        }
+        if inline_reply.is_some() {
+            self.listen_for_replies();
+        }
+
+        if let Some(xdg_category) = builder.xdg_category {
+            notification.hint(Hint::Category(xdg_category.to_string()));
+        }
+
+        // if let Some(payload) = &builder.user_info {
+        //     // seems to not exist yet - TODO investigate
+        // }
+
+        notification
+            .urgency(urgency)
+            .hint(Hint::Transient(builder.xdg_transient.unwrap_or(false)))
+            // default ation is needed otherwise the notification is not clickable
+            .action("default", "default");
+        //.action("open", "Open");
+
+        let notification_handle = notification.show_async().await?;
+        self.events
+            .set_response_callback(&id, &builder.response_callback);
+        self.events.emit(&id, NotificationEventKind::Presented);
+        if let Some(identifier) = inline_reply
+            && let Ok(mut inline_replies) = self.inline_replies.lock()
+        {
+            inline_replies.insert(notification_handle.id(), (id.clone(), identifier));
+        }
+
+        let user_info = builder.user_info.unwrap_or_default();
+
+        let dbus_id = notification_handle.id();
+        // the listener of the replaced notification keeps waiting for the same dbus id,
+        // unless it already stopped because that notification was closed
+        let listening = self
+            .notification_ids
+            .lock()
+            .is_ok_and(|mut notification_ids| {
+                let previous = notification_ids.get(&dbus_id).map(|(_, shown)| *shown);
+                notification_ids
+                    .insert(dbus_id, (id.clone(), previous.map_or(0, |shown| shown + 1)));
+                replaces_id.is_some() && previous.is_some()
+            });
+        if !listening {
+            // the listener also runs without handler, it removes the notification from the active ones when it is closed.
+            // The current handler is looked up for each response, it can be replaced in the meantime
+            let handler = self.handler.clone();
+            let first_notification_id = id.clone();
+            let cloned_user_info = user_info.clone();
+            let shown_at = content.delivered_at;
+            let active_notifications = self.active_notifications.clone();
+            let progress_notifications = self.progress_notifications.clone();
+            let expired_notifications = self.expired_notifications.clone();
+            let inline_replies = self.inline_replies.clone();
+            let notification_ids = self.notification_ids.clone();
+            let resident = builder.xdg_resident.unwrap_or(true);
+            // on_close and wait_for_action both consume notification_handle so we need to rely on this deprecated feature.
+            // It blocks until the signal arrives, so it gets its own thread.
+            let ids = notification_ids.clone();
+            let current_notification = move || {
+                ids.lock()
+                    .ok()
+                    .and_then(|notification_ids| notification_ids.get(&dbus_id).cloned())
+                    .unwrap_or_else(|| (first_notification_id.clone(), 0))
+            };
+            std::thread::spawn(move || {
+                loop {
+                    // stays true if the listener stopped without a signal
+                    let mut closed = true;
+                    // whether the daemon reported that the notification is gone
+                    let mut close_signal = false;
+                    let mut response = None;
+                    handle_action(dbus_id, |action| {
+                        closed = matches!(action, ActionResponse::Closed(_));
+                        close_signal = closed;
+                        response = response_action(action);
+                    });
+                    let (notification_id, shown) = current_notification();
+                    if let Some((action, mut dismiss_reason)) = response {
+                        // the notification was closed by our expiration timer
+                        if dismiss_reason == Some(NotificationDismissReason::ClosedByApp)
+                            && expired_notifications
+                                .lock()
+                                .is_ok_and(|mut expired| expired.remove(&notification_id))
+                        {
+                            dismiss_reason = Some(NotificationDismissReason::Expired);
+                        }
+                        // look up the user info again, because the notification might have been updated
+                        let (user_info, delivered_at) = active_notifications
+                            .blocking_read()
+                            .iter()
+                            .find(|n| n.id == notification_id)
+                            .map(|n| (n.user_info.clone(), n.content.delivered_at))
+                            .unwrap_or((cloned_user_info.clone(), shown_at));
+                        if let Some(handler) = handler.get() {
+                            handler(
+                                NotificationResponse {
+                                    notification_id: notification_id.clone(),
+                                    action,
+                                    user_text: None,
+                                    user_info,
+                                    internal_info: HashMap::new(),
+                                    dismiss_reason,
+                                    delivered_at,
+                                }
+                                .split_selection(),
+                            );
+                        }
+                    }
+                    // resident notifications stay on screen, so more actions can follow
+                    if !closed && resident {
+                        continue;
+                    }
+                    let Ok(mut ids) = notification_ids.lock() else {
+                        break;
+                    };
+                    // shown again with this dbus id after the signal, the listener waits for the new notification
+                    if ids
+                        .get(&dbus_id)
+                        .is_some_and(|(_, current)| *current != shown)
+                    {
+                        continue;
+                    }
+                    // without it a dedupe key would match the closed notification.
+                    // Done while holding the lock, so a notification shown again with this dbus id
+                    // either starts its own listener or is not removed here
+                    if close_signal {
+                        active_notifications
+                            .blocking_write()
+                            .retain(|n| n.id != notification_id);
+                        progress_notifications
+                            .blocking_write()
+                            .remove(&notification_id);
+                    }
+                    ids.remove(&dbus_id);
+                    break;
+                }
+                if let Ok(mut inline_replies) = inline_replies.lock() {
+                    inline_replies.remove(&dbus_id);
+                }
+            });
+        }
+
+        let handle = NotificationHandleXdg {
+            id,
+            thread_id: builder.thread_id,
+            user_info,
+            content,
+            handle: Arc::new(RwLock::new(Some(notification_handle))),
+        };
+
+        let manager = self.clone();
+        let expired_id = handle.id.clone();
+        self.scheduler
+            .schedule_expiration(&handle.id, builder.expiration, move || {
+                if let Ok(mut expired_notifications) = manager.expired_notifications.lock() {
+                    expired_notifications.insert(expired_id.clone());
+                }
+                if let Err(err) = manager.remove_delivered_notifications(vec![&expired_id]) {
+                    log::error!("failed to remove expired notification: {err:?}");
+                }
+            });
+
+        self.add_notification(handle.clone()).await;
+        Ok(handle)
+    }
+
+    /// Without native scheduling the notification is shown by a timer in this process
+    async fn schedule(
+        &self,
+        builder: NotificationBuilder,
+        schedule: Schedule,
+    ) -> Result<String, crate::Error> {
+        self.validate(&builder).await?;
+        let manager = self.clone();
+        self.scheduler.schedule_notification(
+            uuid::Uuid::new_v4().to_string(),
+            builder,
+            schedule,
+            move |id, builder| {
+                let manager = manager.clone();
+                async move {
+                    // repeating notifications replace the previous repetition in place,
+                    // so it is not reported as closed
+                    let replaces_id = manager.take_replaced(|n| n.id == id).await;
+                    manager
+                        .show_notification(id, builder, replaces_id)
+                        .await
+                        .map(drop)
+                }
+            },
+        )
+    }
+
+    /// Starts the listener for the `NotificationReplied` signal of KDE Plasma, once
+    fn listen_for_replies(&self) {
+        self.inline_reply_listener.get_or_init(|| {
+            let manager = self.clone();
+            std::thread::spawn(move || {
+                if let Err(err) = zbus::block_on(manager.forward_replies()) {
+                    log::error!("failed to listen for inline replies: {err:?}");
+                }
+            });
+        });
+    }
+
+    /// Passes the text of inline replies to the handler, as response to their text input action
+    async fn forward_replies(&self) -> Result<(), Error> {
+        let connection = zbus::Connection::session().await?;
+        let proxy = notifications_proxy(&connection).await?;
+        let mut signals = proxy.receive_signal("NotificationReplied").await?;
+        while let Some(message) = signals.next().await {
+            let (dbus_id, text): (u32, String) = match message.body().deserialize() {
+                Ok(args) => args,
+                Err(err) => {
+                    log::error!("failed to parse NotificationReplied signal: {err:?}");
+                    continue;
+                }
+            };
+            let Some((notification_id, identifier)) = self
+                .inline_replies
+                .lock()
+                .ok()
+                .and_then(|inline_replies| inline_replies.get(&dbus_id).cloned())
+            else {
+                // a reply to a notification of another app
+                continue;
+            };
+            let (user_info, delivered_at) = self
+                .active_notifications
+                .read()
+                .await
+                .iter()
+                .find(|n| n.id == notification_id)
+                .map(|n| (n.user_info.clone(), n.content.delivered_at))
+                .unwrap_or_default();
+            if let Some(handler) = self.handler.get() {
+                handler(NotificationResponse {
+                    notification_id,
+                    action: NotificationResponseAction::Other(identifier),
+                    user_text: Some(text),
+                    user_info,
+                    internal_info: HashMap::new(),
+                    dismiss_reason: None,
+                    delivered_at,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl NotificationManager for NotificationManagerXdg {
+    async fn get_notification_permission_state(&self) -> Result<bool, crate::Error> {
+        log::info!(
+            "NotificationManagerXdg::get_notification_permission_state: not implemented yet"
+        );
+
+        Ok(true)
+    }
+
+    async fn first_time_ask_for_notification_permission(&self) -> Result<bool, crate::Error> {
+        log::info!(
+            "NotificationManagerXdg::first_time_ask_for_notification_permission: not implemented yet"
+        );
+        Ok(true)
+    }
+
+    async fn capabilities(&self) -> Result<NotificationCapabilities, crate::Error> {
+        self.capabilities
+            .get_or_try_init(query_capabilities)
+            .await
+            .cloned()
+    }
+
+    /// The `Inhibited` property is not in the spec, KDE Plasma and some other daemons have it
+    async fn is_do_not_disturb_active(&self) -> Result<bool, crate::Error> {
+        let connection = zbus::Connection::session().await?;
+        let proxy = notifications_proxy(&connection).await?;
+        proxy
+            .get_property::<bool>("Inhibited")
+            .await
+            .map_err(inhibited_error)
+    }
+
+    fn subscribe_do_not_disturb(&self) -> Result<broadcast::Receiver<bool>, crate::Error> {
+        let sender = self.do_not_disturb.get_or_init(|| {
+            let (sender, _) = broadcast::channel(DO_NOT_DISTURB_CAPACITY);
+            let listener_sender = sender.clone();
+            std::thread::spawn(move || {
+                if let Err(err) = zbus::block_on(listen_for_inhibited(listener_sender)) {
+                    log::error!("failed to listen for do not disturb changes: {err:?}");
+                }
+            });
+            sender
+        });
+        Ok(sender.subscribe())
+    }
+
+    fn register(
+        &self,
+        handler_callback: Box<dyn Fn(crate::NotificationResponse) + Send + Sync + 'static>,
+        categories: Vec<crate::NotificationCategory>,
+    ) -> Result<(), crate::Error> {
+        log::info!("NotificationManagerXdg::register {categories:?}");
+
+        let _ = self
+            .handler
+            .register(self.events.wrap_handler(handler_callback));
+
+        let mut stored_categories = self.categories.write().map_err(|_| Error::SettingHandler)?;
+        stored_categories.clear();
+        for category in categories {
+            stored_categories.insert(category.identifier.clone(), category);
+        }
+
+        Ok(())
+    }
+
+    fn set_response_handler(
+        &self,
+        handler_callback: Box<dyn Fn(crate::NotificationResponse) + Send + Sync + 'static>,
+    ) -> Result<(), crate::Error> {
+        self.handler
+            .replace(self.events.wrap_handler(handler_callback))
+    }
+
+    fn subscribe_events(&self) -> tokio::sync::broadcast::Receiver<NotificationEvent> {
+        self.events.subscribe()
+    }
+
+    fn add_categories(
+        &self,
+        categories: Vec<crate::NotificationCategory>,
+    ) -> Result<(), crate::Error> {
+        log::info!("NotificationManagerXdg::add_categories {categories:?}");
+        let mut stored_categories = self.categories.write().map_err(|_| Error::SettingHandler)?;
+        for category in categories {
+            stored_categories.insert(category.identifier.clone(), category);
+        }
+        Ok(())
+    }
+
+    fn set_categories(
+        &self,
+        categories: Vec<crate::NotificationCategory>,
+    ) -> Result<(), crate::Error> {
+        log::info!("NotificationManagerXdg::set_categories {categories:?}");
+        let mut stored_categories = self.categories.write().map_err(|_| Error::SettingHandler)?;
+        stored_categories.clear();
+        for category in categories {
+            stored_categories.insert(category.identifier.clone(), category);
+        }
+        Ok(())
+    }
+
+    fn remove_category(&self, identifier: &str) -> Result<(), crate::Error> {
+        log::info!("NotificationManagerXdg::remove_category {identifier}");
+        self.categories
+            .write()
+            .map_err(|_| Error::SettingHandler)?
+            .remove(identifier);
+        Ok(())
+    }
+
+    fn remove_all_delivered_notifications(&self) -> Result<(), crate::Error> {
+        self.events.remove_response_callbacks(None);
+        let mut active_notifications = self.active_notifications.try_write()?;
+        let removed_notifications = active_notifications.drain(..);
+
+        for notification in removed_notifications {
+            if let Some(handle) = notification.handle.try_write()?.take() {
+                handle.close();
+            } else {
+                log::error!("handle is not there anymore");
+            }
+        }
+
+        Ok(())
+    }
+
+    fn remove_delivered_notifications(&self, ids: Vec<&str>) -> Result<(), crate::Error> {
+        self.events.remove_response_callbacks(Some(&ids));
+        let mut active_notifications = self.active_notifications.try_write()?;
+        let all_notifications = active_notifications.drain(..);
+        let mut kept = Vec::new();
+        let mut removed = Vec::new();
+        for n in all_notifications {
+            if ids.contains(&n.id.as_str()) {
+                removed.push(n);
+            } else {
+                kept.push(n);
+            }
+        }
+        active_notifications.append(&mut kept);
+
+        for notification in removed {
+            if let Some(handle) = notification.handle.try_write()?.take() {
+                handle.close();
+            } else {
+                log::error!("handle is not there anymore");
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn get_active_notifications(
+        &self,
+    ) -> Result<Vec<Box<dyn NotificationHandle>>, crate::Error> {
+        // can only get notification from active session for now
+        let active_notifications = self.active_notifications.read().await;
+        Ok(active_notifications
+            .clone()
+            .into_iter()
+            .map(|n| Box::new(n) as Box<dyn NotificationHandle>)
+            .collect())
+    }
+
+    async fn send_notification(
+        &self,
+        builder: NotificationBuilder,
+    ) -> Result<Box<dyn NotificationHandle>, crate::Error> {
+        self.validate(&builder).await?;
+        if let Some(duplicate) = find_duplicate(self, &builder).await? {
+            return Ok(duplicate);
+        }
+        log::info!("show notification {self:?}");
+        let id = builder.notification_id(|| uuid::Uuid::new_v4().to_string());
+        let replaces_id = match &builder.thread_id {
+            Some(thread_id) if builder.xdg_replace_thread == Some(true) => {
+                self.take_replaced(|n| n.thread_id.as_ref() == Some(thread_id))
+                    .await
+            }
+            _ => None,
+        };
+        let handle = self.show_notification(id, builder, replaces_id).await?;
+        Ok(Box::new(handle) as Box<dyn NotificationHandle>)
+    }
+
+    async fn update_notification(
+        &self,
+        id: &str,
+        builder: NotificationBuilder,
+    ) -> Result<Box<dyn NotificationHandle>, crate::Error> {
+        self.validate(&builder).await?;
+        log::info!("update notification {id} {self:?}");
+        let replaces_id = self.take_replaced(|n| n.id == id).await;
+        let handle = self
+            .show_notification(id.to_owned(), builder, replaces_id)
+            .await?;
+        Ok(Box::new(handle) as Box<dyn NotificationHandle>)
+    }
+
+    async fn update_progress(
+        &self,
+        id: &str,
+        state: ProgressState,
+        status: &str,
+    ) -> Result<(), crate::Error> {
+        log::info!("NotificationManagerXdg::update_progress {id} {state:?} {status}");
+        let mut builder = self
+            .progress_notifications
+            .read()
+            .await
+            .get(id)
+            .cloned()
+            .ok_or_else(|| Error::NotificationNotFound(id.to_owned()))?;
+        if let Some(progress) = builder.progress.as_mut() {
+            progress.state = state;
+            progress.status = status.to_owned();
+        }
+        self.update_notification(id, builder).await?;
+        Ok(())
+    }
+
+    async fn set_badge_count(&self, count: u32) -> Result<(), crate::Error> {
+        log::info!(
+            "NotificationManagerXdg::set_badge_count {count}: not supported, only remembered"
+        );
+        self.badge_count.store(count, Ordering::Relaxed);
+        Ok(())
+    }
+
+    async fn get_badge_count(&self) -> Result<u32, crate::Error> {
+        Ok(self.badge_count.load(Ordering::Relaxed))
+    }
+
+    async fn schedule_notification(
+        &self,
+        builder: NotificationBuilder,
+        at: DateTime<Utc>,
+    ) -> Result<String, crate::Error> {
+        self.schedule(builder, Schedule::At(at)).await
+    }
+
+    async fn schedule_repeating_notification(
+        &self,
+        builder: NotificationBuilder,
+        interval: RepeatInterval,
+    ) -> Result<String, crate::Error> {
+        self.schedule(builder, Schedule::Every(interval)).await
+    }
+
+    async fn schedule_calendar_notification(
+        &self,
+        builder: NotificationBuilder,
+        trigger: CalendarTrigger,
+    ) -> Result<String, crate::Error> {
+        self.schedule(builder, Schedule::Calendar(trigger)).await
+    }
+
+    fn cancel_scheduled_notification(&self, id: &str) -> Result<(), crate::Error> {
+        if !self.scheduler.cancel(id) {
+            return Err(Error::NotificationNotFound(id.to_owned()));
+        }
+        Ok(())
+    }
+}
+
+/// Changes that were not received yet by a subscriber are dropped after this many newer ones
+const DO_NOT_DISTURB_CAPACITY: usize = 8;
+
+/// The action that KDE Plasma shows as text field, its text arrives with the `NotificationReplied` signal
+const INLINE_REPLY_ACTION: &str = "inline-reply";
+
+/// The notification daemon, for the calls that notify-rust does not have
+async fn notifications_proxy(connection: &zbus::Connection) -> Result<zbus::Proxy<'static>, Error> {
+    Ok(zbus::Proxy::new(
+        connection,
+        "org.freedesktop.Notifications",
+        "/org/freedesktop/Notifications",
+        "org.freedesktop.Notifications",
+    )
+    .await?)
+}
+
+/// See [NotificationManager::capabilities], the daemon does not change while the app runs
+async fn query_capabilities() -> Result<NotificationCapabilities, Error> {
+    let connection = zbus::Connection::session().await?;
+    let proxy = notifications_proxy(&connection).await?;
+    let raw: Vec<String> = proxy.call("GetCapabilities", &()).await?;
+    let server = match proxy
+        .call::<_, _, (String, String, String, String)>("GetServerInformation", &())
+        .await
+    {
+        Ok((name, vendor, version, spec_version)) => Some(NotificationServerInformation {
+            name,
+            vendor,
+            version,
+            spec_version,
+        }),
+        Err(err) => {
+            log::warn!("failed to get notification server information: {err:?}");
+            None
+        }
+    };
+    Ok(NotificationCapabilities::from_xdg(raw, server))
+}
+
+/// Daemons without the `Inhibited` property answer with one of these errors
+fn inhibited_error(err: zbus::Error) -> Error {
+    match &err {
+        zbus::Error::FDO(fdo)
+            if matches!(
+                **fdo,
+                zbus::fdo::Error::UnknownProperty(_)
+                    | zbus::fdo::Error::UnknownInterface(_)
+                    | zbus::fdo::Error::InvalidArgs(_)
+            ) =>
+        {
+            Error::Unsupported("do not disturb detection with this notification daemon".to_owned())
+        }
+        _ => err.into(),
+    }
+}
+
+async fn listen_for_inhibited(sender: broadcast::Sender<bool>) -> Result<(), Error> {
+    let connection = zbus::Connection::session().await?;
+    let proxy = notifications_proxy(&connection).await?;
+    let mut changes = proxy.receive_property_changed::<bool>("Inhibited").await;
+    while let Some(change) = changes.next().await {
+        match change.get().await {
+            // fails when there is no subscriber, which is fine
+            Ok(inhibited) => {
+                let _ = sender.send(inhibited);
+            }
+            Err(err) => log::warn!("failed to read the Inhibited property: {err:?}"),
+        }
+    }
+    Ok(())
+}
+
+/// Name from the [sound naming specification](https://specifications.freedesktop.org/sound-naming-spec/latest/),
+/// see the table of [SystemSound]
+fn sound_name(sound: SystemSound) -> &'static str {
+    match sound {
+        SystemSound::Message => "message-new-instant",
+        SystemSound::Mail => "message-new-email",
+        SystemSound::Reminder => "dialog-information",
+        SystemSound::Alarm => "alarm-clock-elapsed",
+        SystemSound::Call => "phone-incoming-call",
+    }
+}
+
+/// Reads the image and scales it down, so that it can be sent as "image-data" over dbus
+fn load_image_data(path: &std::path::Path) -> Result<notify_rust::Image, String> {
+    let img = ImageReader::open(path)
+        .map_err(|error| format!("failed to open image: {error:?}"))?
+        .with_guessed_format()
+        .map_err(|error| format!("failed to guess image format: {error:?}"))?;
+    let img_data = img
+        .decode()
+        .map_err(|error| format!("failed to decode image: {error:?}"))?;
+    img_data
+        .thumbnail(512, 512)
+        .try_into()
+        .map_err(|error| format!("failed to convert image: {error:?}"))
+}
+
+/// Maps the dbus signal to the response action, `None` for signals that are not reported to the handler
+fn response_action(
+    action: &ActionResponse,
+) -> Option<(
+    NotificationResponseAction,
+    Option<NotificationDismissReason>,
+)> {
+    match action {
+        ActionResponse::Closed(reason) => {
+            let dismiss_reason = match reason {
+                CloseReason::Other(_) => {
+                    log::warn!("unhandles close reason {reason:?}");
+                    return None;
+                }
+                CloseReason::Expired => NotificationDismissReason::Expired,
+                CloseReason::Dismissed => NotificationDismissReason::DismissedByUser,
+                CloseReason::CloseAction => NotificationDismissReason::ClosedByApp,
+            };
+            Some((NotificationResponseAction::Dismiss, Some(dismiss_reason)))
+        }
+        ActionResponse::Custom("default") => Some((NotificationResponseAction::Default, None)),
+        // the text arrives with its own signal
+        ActionResponse::Custom(INLINE_REPLY_ACTION) => None,
+        ActionResponse::Custom(action) => {
+            Some((NotificationResponseAction::Other(action.to_string()), None))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inline_reply_action_is_not_a_response() {
+        assert_eq!(
+            response_action(&ActionResponse::Custom(INLINE_REPLY_ACTION)),
+            None
+        );
+        assert_eq!(
+            response_action(&ActionResponse::Custom("reply")),
+            Some((NotificationResponseAction::Other("reply".to_owned()), None))
+        );
+    }
+}
@@ -0,0 +1,642 @@
+//! Notifications over the [XDG Desktop Portal](https://flatpak.github.io/xdg-desktop-portal/docs/doc-org.freedesktop.portal.Notification.html),
+//! for sandboxed apps (flatpak) that are not allowed to talk to the notification daemon directly.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc, OnceLock,
+        atomic::{AtomicU32, Ordering},
+    },
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures_lite::StreamExt;
+use tokio::sync::RwLock;
+use zbus::zvariant::Value;
+
+use crate::{
+    CalendarTrigger, Error, InterruptionLevel, NotificationBuilder, NotificationCapabilities,
+    NotificationCategory, NotificationCategoryAction, NotificationContent,
+    NotificationDismissReason, NotificationEvent, NotificationEventKind, NotificationHandle,
+    NotificationManager, NotificationManagerOptions, NotificationResponse,
+    NotificationResponseAction, NotificationUrgency, ProgressState, RepeatInterval, Sound,
+    events::NotificationEvents,
+    find_duplicate, markdown,
+    response_handler::ResponseHandler,
+    scheduler::{Schedule, Scheduler},
+    unknown_category,
+};
+
+const PORTAL_DESTINATION: &str = "org.freedesktop.portal.Desktop";
+const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+const PORTAL_INTERFACE: &str = "org.freedesktop.portal.Notification";
+
+/// Returns true when the app runs inside of a flatpak sandbox,
+/// where the portal should be used instead of the notification daemon.
+pub fn is_sandboxed() -> bool {
+    std::path::Path::new("/.flatpak-info").exists()
+}
+
+async fn portal_proxy(connection: &zbus::Connection) -> Result<zbus::Proxy<'static>, Error> {
+    Ok(zbus::Proxy::new(
+        connection,
+        PORTAL_DESTINATION,
+        PORTAL_PATH,
+        PORTAL_INTERFACE,
+    )
+    .await?)
+}
+
+/// The version of the portal interface, 1 when it can not be read
+async fn portal_version(proxy: &zbus::Proxy<'_>) -> u32 {
+    proxy
+        .get_property::<u32>("version")
+        .await
+        .unwrap_or_else(|err| {
+            log::warn!("failed to read the version of the notification portal: {err:?}");
+            1
+        })
+}
+
+/// See [NotificationManager::capabilities]
+async fn query_capabilities() -> Result<NotificationCapabilities, Error> {
+    let connection = zbus::Connection::session().await?;
+    let proxy = portal_proxy(&connection).await?;
+    Ok(NotificationCapabilities {
+        actions: true,
+        // `markup-body` since version 2
+        body_markup: portal_version(&proxy).await >= 2,
+        persistence: true,
+        inline_reply: false,
+        attachments: true,
+        media_attachments: false,
+        // converted to their first frame
+        animated_images: false,
+        progress: false,
+        scheduling: true,
+        badges: false,
+        critical_alerts: false,
+        ..Default::default()
+    })
+}
+
+fn remove_notification(id: &str) -> Result<(), Error> {
+    zbus::block_on(async {
+        let connection = zbus::Connection::session().await?;
+        portal_proxy(&connection)
+            .await?
+            .call_method("RemoveNotification", &(id,))
+            .await?;
+        Ok(())
+    })
+}
+
+#[derive(Debug, Clone)]
+pub struct NotificationHandlePortal {
+    id: String,
+    thread_id: Option<String>,
+    user_info: HashMap<String, String>,
+    content: NotificationContent,
+}
+
+impl NotificationHandle for NotificationHandlePortal {
+    fn close(&self) -> Result<(), crate::Error> {
+        log::info!("called close notification handle {self:?}");
+        remove_notification(&self.id)
+    }
+
+    fn get_id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn get_stored_user_info(&self) -> &HashMap<String, String> {
+        &self.user_info
+    }
+
+    fn get_thread_id(&self) -> Option<&str> {
+        self.thread_id.as_deref()
+    }
+
+    fn get_content(&self) -> &NotificationContent {
+        &self.content
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct NotificationManagerPortal {
+    active_notifications: Arc<RwLock<Vec<NotificationHandlePortal>>>,
+    handler: ResponseHandler,
+    categories: Arc<std::sync::RwLock<HashMap<String, NotificationCategory>>>,
+    listener_loop: Arc<OnceLock<std::thread::JoinHandle<()>>>,
+    scheduler: Scheduler,
+    badge_count: Arc<AtomicU32>,
+    /// notifications with progress, to show them again with the new progress
+    progress_notifications: Arc<RwLock<HashMap<String, NotificationBuilder>>>,
+    events: NotificationEvents,
+    /// the name, desktop entry and icon of the app
+    options: Arc<NotificationManagerOptions>,
+    /// the capabilities depend on the version of the portal, queried once
+    capabilities: Arc<tokio::sync::OnceCell<NotificationCapabilities>>,
+}
+
+impl std::fmt::Debug for NotificationManagerPortal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NotificationManagerPortal")
+            .field("active_notifications", &self.active_notifications)
+            .field("handler", &self.handler.is_set().to_string())
+            .field("categories", &self.categories)
+            .finish()
+    }
+}
+
+impl NotificationManagerPortal {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// See [NotificationManagerOptions]
+    pub fn with_options(options: NotificationManagerOptions) -> Self {
+        Self {
+            options: Arc::new(options),
+            ..Self::default()
+        }
+    }
+
+    /// See [NotificationBuilder::validate], also checks that the category is registered
+    async fn validate(&self, builder: &NotificationBuilder) -> Result<(), Error> {
+        builder.validate(&self.capabilities().await?)?;
+        if let Some(category_id) = &builder.category_id
+            && !self
+                .categories
+                .read()
+                .map_err(|_| Error::SettingHandler)?
+                .contains_key(category_id)
+        {
+            return Err(unknown_category(category_id));
+        }
+        Ok(())
+    }
+
+    async fn add_notification(&self, notification: NotificationHandlePortal) {
+        self.active_notifications.write().await.push(notification);
+    }
+
+    async fn show_notification(
+        &self,
+        id: String,
+        builder: NotificationBuilder,
+    ) -> Result<NotificationHandlePortal, Error> {
+        if builder.progress.is_some() {
+            self.progress_notifications
+                .write()
+                .await
+                .insert(id.clone(), builder.clone());
+        }
+        let builder = builder
+            .apply_auto_dismiss(Utc::now())
+            .apply_sender()
+            .apply_progress()
+            .apply_interruption_level()
+            .apply_hero_image_and_attribution()
+            .apply_still_images();
+        let content = NotificationContent::from(&builder);
+        let connection = zbus::Connection::session().await?;
+        let proxy = portal_proxy(&connection).await?;
+        let mut notification: HashMap<&str, Value> = HashMap::new();
+
+        if let Some(title) = builder.title {
+            notification.insert("title", Value::from(title));
+        }
+        if let Some(body) = builder.body {
+            if !builder.body_markdown {
+                notification.insert("body", Value::from(body));
+            } else if portal_version(&proxy).await >= 2 {
+                notification.insert("markup-body", Value::from(super::body_markup(&body, true)));
+            } else {
+                notification.insert("body", Value::from(markdown::to_plain_text(&body)));
+            }
+        }
+
+        // the portal has no image field, so the image is used as icon when there is no explicit icon,
+        // without both the default icon of the options
+        if let Some(path) = builder.icon.or(builder.image).or(self.options.icon.clone()) {
+            match std::fs::read(&path) {
+                Ok(bytes) => {
+                    notification.insert("icon", Value::from(("bytes", Value::from(bytes))));
+                }
+                Err(err) => log::error!("failed to read icon {path:?}: {err:?}"),
+            }
+        }
+
+        match builder.sound {
+            None | Some(Sound::Default) => {}
+            // since version 2 of the portal, older versions ignore it
+            Some(Sound::None) => {
+                notification.insert("sound", Value::from("silent"));
+            }
+            Some(sound) => {
+                log::warn!(
+                    "sounds are not supported by the notification portal, ignoring {sound:?}"
+                )
+            }
+        }
+
+        let priority = match (builder.urgency, builder.interruption_level) {
+            (Some(NotificationUrgency::Low), _) => Some("low"),
+            (Some(NotificationUrgency::Normal), _) => Some("normal"),
+            (Some(NotificationUrgency::Critical), _) => Some("urgent"),
+            (None, Some(InterruptionLevel::Passive)) => Some("low"),
+            (None, Some(InterruptionLevel::Active)) => Some("normal"),
+            (None, Some(InterruptionLevel::TimeSensitive)) => Some("high"),
+            (None, Some(InterruptionLevel::Critical)) => Some("urgent"),
+            (None, None) => None,
+        };
+        if let Some(priority) = priority {
+            notification.insert("priority", Value::from(priority));
+        }
+
+        let mut default_action = None;
+        let mut actions = Vec::new();
+        if let Some(category_id) = builder.category_id {
+            let categories = self.categories.read().map_err(|_| Error::SettingHandler)?;
+            if let Some(category) = categories.get(&category_id) {
+                default_action = category.default_action.clone();
+                actions.extend(category.actions.iter().cloned());
+            } else {
+                log::warn!("Category '{category_id}' not found in registered categories");
+            }
+        }
+        actions.extend(builder.actions);
+        // the click on the notification triggers the default action of the category, it is not a button
+        let default_action = default_action.filter(|default_action| {
+            actions.iter().any(|action| {
+                matches!(
+                    action,
+                    NotificationCategoryAction::Action { identifier, .. } if identifier == default_action
+                )
+            })
+        });
+        notification.insert(
+            "default-action",
+            Value::from(default_action.clone().unwrap_or("default".to_owned())),
+        );
+        if let Some(default_action) = &default_action {
+            actions.retain(|action| {
+                !matches!(
+                    action,
+                    NotificationCategoryAction::Action { identifier, .. } if identifier == default_action
+                )
+            });
+        }
+        if !actions.is_empty() {
+            let buttons: Vec<HashMap<&str, Value>> = actions
+                .iter()
+                .flat_map(NotificationCategoryAction::expand_selection)
+                .map(|action| match action {
+                    NotificationCategoryAction::Action { identifier, title }
+                    | NotificationCategoryAction::TextInputAction {
+                        identifier, title, ..
+                    }
+                    | NotificationCategoryAction::SelectionInputAction {
+                        identifier, title, ..
+                    } => HashMap::from([
+                        ("label", Value::from(title)),
+                        ("action", Value::from(identifier)),
+                    ]),
+                })
+                .collect();
+            notification.insert("buttons", Value::from(buttons));
+        }
+
+        proxy
+            .call_method("AddNotification", &(id.as_str(), notification))
+            .await?;
+        self.events
+            .set_response_callback(&id, &builder.response_callback);
+        self.events.emit(&id, NotificationEventKind::Presented);
+
+        let handle = NotificationHandlePortal {
+            id,
+            thread_id: builder.thread_id,
+            user_info: builder.user_info.unwrap_or_default(),
+            content,
+        };
+
+        let manager = self.clone();
+        let expired_id = handle.id.clone();
+        self.scheduler
+            .schedule_expiration(&handle.id, builder.expiration, move || {
+                if let Err(err) = manager.remove_delivered_notifications(vec![&expired_id]) {
+                    log::error!("failed to remove expired notification: {err:?}");
+                }
+                // the portal has no signal for closed notifications
+                manager.events.emit(
+                    &expired_id,
+                    NotificationEventKind::Dismissed(NotificationDismissReason::Expired),
+                );
+            });
+
+        self.add_notification(handle.clone()).await;
+        Ok(handle)
+    }
+
+    /// Without native scheduling the notification is shown by a timer in this process
+    async fn schedule(
+        &self,
+        builder: NotificationBuilder,
+        schedule: Schedule,
+    ) -> Result<String, crate::Error> {
+        self.validate(&builder).await?;
+        let manager = self.clone();
+        self.scheduler.schedule_notification(
+            uuid::Uuid::new_v4().to_string(),
+            builder,
+            schedule,
+            move |id, builder| {
+                let manager = manager.clone();
+                // the portal replaces notifications with the same id
+                async move { manager.show_notification(id, builder).await.map(drop) }
+            },
+        )
+    }
+
+    /// listens for the `ActionInvoked` signal of the portal and forwards it to the handler
+    async fn listen_for_actions(
+        active_notifications: Arc<RwLock<Vec<NotificationHandlePortal>>>,
+        handler: ResponseHandler,
+    ) -> Result<(), Error> {
+        let connection = zbus::Connection::session().await?;
+        let proxy = portal_proxy(&connection).await?;
+        let mut signals = proxy.receive_signal("ActionInvoked").await?;
+
+        while let Some(message) = signals.next().await {
+            let (notification_id, action, _parameter): (String, String, Vec<Value>) =
+                match message.body().deserialize() {
+                    Ok(args) => args,
+                    Err(err) => {
+                        log::error!("failed to parse ActionInvoked signal: {err:?}");
+                        continue;
+                    }
+                };
+
+            let (user_info, delivered_at) = active_notifications
+                .read()
+                .await
+                .iter()
+                .find(|n| n.id == notification_id)
+                .map(|n| (n.user_info.clone(), n.content.delivered_at))
+                .unwrap_or_default();
+
+            let action = match action.as_str() {
+                "default" => NotificationResponseAction::Default,
+                _ => NotificationResponseAction::Other(action),
+            };
+
+            if let Some(handler) = handler.get() {
+                handler(
+                    NotificationResponse {
+                        notification_id,
+                        action,
+                        user_text: None,
+                        user_info,
+                        internal_info: HashMap::new(),
+                        dismiss_reason: None,
+                        delivered_at,
+                    }
+                    .split_selection(),
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl NotificationManager for NotificationManagerPortal {
+    async fn get_notification_permission_state(&self) -> Result<bool, crate::Error> {
+        // the portal does not expose whether the user allowed notifications
+        Ok(true)
+    }
+
+    async fn first_time_ask_for_notification_permission(&self) -> Result<bool, crate::Error> {
+        Ok(true)
+    }
+
+    /// The portal forwards to the notification server of the desktop, but does not tell which one
+    async fn capabilities(&self) -> Result<NotificationCapabilities, crate::Error> {
+        self.capabilities
+            .get_or_try_init(query_capabilities)
+            .await
+            .cloned()
+    }
+
+    fn register(
+        &self,
+        handler_callback: Box<dyn Fn(crate::NotificationResponse) + Send + Sync + 'static>,
+        categories: Vec<crate::NotificationCategory>,
+    ) -> Result<(), crate::Error> {
+        log::info!("NotificationManagerPortal::register {categories:?}");
+
+        self.handler
+            .register(self.events.wrap_handler(handler_callback))?;
+
+        {
+            let mut stored_categories =
+                self.categories.write().map_err(|_| Error::SettingHandler)?;
+            stored_categories.clear();
+            for category in categories {
+                stored_categories.insert(category.identifier.clone(), category);
+            }
+        }
+
+        let active_notifications = self.active_notifications.clone();
+        let handler = self.handler.clone();
+        let listener_loop = std::thread::spawn(move || {
+            if let Err(err) =
+                zbus::block_on(Self::listen_for_actions(active_notifications, handler))
+            {
+                log::error!("failed to listen for portal notification actions: {err:?}");
+            }
+        });
+        self.listener_loop
+            .set(listener_loop)
+            .map_err(|_| Error::SettingHandler)?;
+
+        Ok(())
+    }
+
+    fn set_response_handler(
+        &self,
+        handler_callback: Box<dyn Fn(crate::NotificationResponse) + Send + Sync + 'static>,
+    ) -> Result<(), crate::Error> {
+        self.handler
+            .replace(self.events.wrap_handler(handler_callback))
+    }
+
+    fn subscribe_events(&self) -> tokio::sync::broadcast::Receiver<NotificationEvent> {
+        self.events.subscribe()
+    }
+
+    fn add_categories(
+        &self,
+        categories: Vec<crate::NotificationCategory>,
+    ) -> Result<(), crate::Error> {
+        log::info!("NotificationManagerPortal::add_categories {categories:?}");
+        let mut stored_categories = self.categories.write().map_err(|_| Error::SettingHandler)?;
+        for category in categories {
+            stored_categories.insert(category.identifier.clone(), category);
+        }
+        Ok(())
+    }
+
+    fn set_categories(
+        &self,
+        categories: Vec<crate::NotificationCategory>,
+    ) -> Result<(), crate::Error> {
+        log::info!("NotificationManagerPortal::set_categories {categories:?}");
+        let mut stored_categories = self.categories.write().map_err(|_| Error::SettingHandler)?;
+        stored_categories.clear();
+        for category in categories {
+            stored_categories.insert(category.identifier.clone(), category);
+        }
+        Ok(())
+    }
+
+    fn remove_category(&self, identifier: &str) -> Result<(), crate::Error> {
+        log::info!("NotificationManagerPortal::remove_category {identifier}");
+        self.categories
+            .write()
+            .map_err(|_| Error::SettingHandler)?
+            .remove(identifier);
+        Ok(())
+    }
+
+    fn remove_all_delivered_notifications(&self) -> Result<(), crate::Error> {
+        self.events.remove_response_callbacks(None);
+        // waits for the lock, so a removal does not fail while a notification is sent
+        let removed: Vec<_> = zbus::block_on(self.active_notifications.write())
+            .drain(..)
+            .collect();
+        for notification in removed {
+            remove_notification(&notification.id)?;
+        }
+        Ok(())
+    }
+
+    fn remove_delivered_notifications(&self, ids: Vec<&str>) -> Result<(), crate::Error> {
+        self.events.remove_response_callbacks(Some(&ids));
+        zbus::block_on(self.active_notifications.write()).retain(|n| !ids.contains(&n.id.as_str()));
+        for id in ids {
+            remove_notification(id)?;
+        }
+        Ok(())
+    }
+
+    async fn get_active_notifications(
+        &self,
+    ) -> Result<Vec<Box<dyn NotificationHandle>>, crate::Error> {
+        // the portal has no way to list notifications, so we only know about the current session
+        let active_notifications = self.active_notifications.read().await;
+        Ok(active_notifications
+            .clone()
+            .into_iter()
+            .map(|n| Box::new(n) as Box<dyn NotificationHandle>)
+            .collect())
+    }
+
+    async fn send_notification(
+        &self,
+        builder: NotificationBuilder,
+    ) -> Result<Box<dyn NotificationHandle>, crate::Error> {
+        self.validate(&builder).await?;
+        if let Some(duplicate) = find_duplicate(self, &builder).await? {
+            return Ok(duplicate);
+        }
+        log::info!("show notification {self:?}");
+        let id = builder.notification_id(|| uuid::Uuid::new_v4().to_string());
+        let handle = self.show_notification(id, builder).await?;
+        Ok(Box::new(handle) as Box<dyn NotificationHandle>)
+    }
+
+    async fn update_notification(
+        &self,
+        id: &str,
+        builder: NotificationBuilder,
+    ) -> Result<Box<dyn NotificationHandle>, crate::Error> {
+        self.validate(&builder).await?;
+        log::info!("update notification {id} {self:?}");
+        // the portal replaces notifications with the same id
+        self.active_notifications
+            .write()
+            .await
+            .retain(|n| n.id != id);
+        let handle = self.show_notification(id.to_owned(), builder).await?;
+        Ok(Box::new(handle) as Box<dyn NotificationHandle>)
+    }
+
+    async fn update_progress(
+        &self,
+        id: &str,
+        state: ProgressState,
+        status: &str,
+    ) -> Result<(), crate::Error> {
+        log::info!("NotificationManagerPortal::update_progress {id} {state:?} {status}");
+        let mut builder = self
+            .progress_notifications
+            .read()
+            .await
+            .get(id)
+            .cloned()
+            .ok_or_else(|| Error::NotificationNotFound(id.to_owned()))?;
+        if let Some(progress) = builder.progress.as_mut() {
+            progress.state = state;
+            progress.status = status.to_owned();
+        }
+        self.update_notification(id, builder).await?;
+        Ok(())
+    }
+
+    async fn set_badge_count(&self, count: u32) -> Result<(), crate::Error> {
+        log::info!(
+            "NotificationManagerPortal::set_badge_count {count}: not supported, only remembered"
+        );
+        self.badge_count.store(count, Ordering::Relaxed);
+        Ok(())
+    }
+
+    async fn get_badge_count(&self) -> Result<u32, crate::Error> {
+        Ok(self.badge_count.load(Ordering::Relaxed))
+    }
+
+    async fn schedule_notification(
+        &self,
+        builder: NotificationBuilder,
+        at: DateTime<Utc>,
+    ) -> Result<String, crate::Error> {
+        self.schedule(builder, Schedule::At(at)).await
+    }
+
+    async fn schedule_repeating_notification(
+        &self,
+        builder: NotificationBuilder,
+        interval: RepeatInterval,
+    ) -> Result<String, crate::Error> {
+        self.schedule(builder, Schedule::Every(interval)).await
+    }
+
+    async fn schedule_calendar_notification(
+        &self,
+        builder: NotificationBuilder,
+        trigger: CalendarTrigger,
+    ) -> Result<String, crate::Error> {
+        self.schedule(builder, Schedule::Calendar(trigger)).await
+    }
+
+    fn cancel_scheduled_notification(&self, id: &str) -> Result<(), crate::Error> {
+        if !self.scheduler.cancel(id) {
+            return Err(Error::NotificationNotFound(id.to_owned()));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,656 @@
+//! Limits how many notifications reach the user, so a bug in the app can not flood them with notifications
+//!
+//! ```no_run
+//! use std::{sync::Arc, time::Duration};
+//! use user_notify::rate_limit::{RateLimit, RateLimitPolicy, RateLimitedNotificationManager};
+//!
+//! let manager = RateLimitedNotificationManager::new(user_notify::get_notification_manager(
+//!     "com.example.app".to_owned(),
+//!     None,
+//! ))
+//! // at most 5 notifications per minute, at most 2 of them at once
+//! .set_global_limit(RateLimit::new(5, Duration::from_secs(60)).set_burst(2))
+//! .set_thread_limit(RateLimit::new(1, Duration::from_secs(10)))
+//! .set_policy(RateLimitPolicy::Coalesce);
+//! let manager: Arc<dyn user_notify::NotificationManager> = Arc::new(manager);
+//! ```
+//!
+//! Only [NotificationManager::send_notification] is limited,
+//! updates of delivered notifications and scheduled notifications are passed through.
+//!
+//! Notifications that wait are sent by their priority, see [crate::NotificationBuilder::set_priority],
+//! so critical alerts jump ahead of informational ones.
+//! [RateLimitedNotificationManager::queue_metrics] tells how many wait and how many were dropped.
+
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use crate::{
+    Error, NotificationBuilder, NotificationContent, NotificationHandle, NotificationManager,
+    NotificationPriority, scheduler::Scheduler,
+};
+
+/// At most `max` notifications `per` duration, with at most `burst` of them in quick succession
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    max: u32,
+    per: Duration,
+    burst: u32,
+}
+
+impl RateLimit {
+    /// The burst defaults to `max`
+    pub fn new(max: u32, per: Duration) -> Self {
+        let max = max.max(1);
+        Self {
+            max,
+            per,
+            burst: max,
+        }
+    }
+
+    /// How many notifications can be sent in quick succession before the rate applies, at least 1
+    pub fn set_burst(mut self, burst: u32) -> Self {
+        self.burst = burst.max(1);
+        self
+    }
+
+    /// Time between two notifications once the burst is used up
+    fn interval(&self) -> chrono::Duration {
+        chrono::Duration::from_std(self.per / self.max).unwrap_or(chrono::Duration::MAX)
+    }
+
+    /// The earliest time at which a notification is allowed,
+    /// `tat` is the theoretical arrival time of the generic cell rate algorithm
+    fn allowed_at(&self, tat: Option<DateTime<Utc>>, now: DateTime<Utc>) -> DateTime<Utc> {
+        let tolerance = self
+            .interval()
+            .checked_mul((self.burst - 1) as i32)
+            .unwrap_or(chrono::Duration::MAX);
+        tat.map_or(now, |tat| {
+            now.max(tat.checked_sub_signed(tolerance).unwrap_or(now))
+        })
+    }
+
+    /// The new theoretical arrival time after a notification was sent at `at`
+    fn reserve(&self, tat: Option<DateTime<Utc>>, at: DateTime<Utc>) -> DateTime<Utc> {
+        tat.map_or(at, |tat| tat.max(at))
+            .checked_add_signed(self.interval())
+            .unwrap_or(DateTime::<Utc>::MAX_UTC)
+    }
+}
+
+/// What happens to notifications that exceed the limits
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitPolicy {
+    /// They are not shown, [NotificationManager::send_notification] fails with [Error::RateLimited]
+    #[default]
+    Drop,
+    /// They are shown once the limits allow it
+    Queue,
+    /// Like [RateLimitPolicy::Queue], but a newer notification of the same thread
+    /// replaces the one that is waiting, so only the latest one is shown
+    Coalesce,
+}
+
+/// Delay before the waiting notifications are tried again after a delivery failed with a retryable error
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+/// Id of the timer that sends the waiting notifications
+const DRAIN_TIMER: &str = "rate-limit-drain";
+
+/// See [RateLimitedNotificationManager::queue_metrics]
+#[non_exhaustive]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct QueueMetrics {
+    /// Notifications that wait for the limits
+    pub queued: usize,
+    /// The waiting notifications per priority
+    pub queued_by_priority: BTreeMap<NotificationPriority, usize>,
+    /// When the notification that waits the longest was queued
+    pub oldest_queued_at: Option<DateTime<Utc>>,
+    /// Notifications that were passed to the wrapped manager, right away or after waiting
+    pub sent: u64,
+    /// Notifications that were dropped with [RateLimitPolicy::Drop]
+    pub dropped: u64,
+    /// Waiting notifications that were replaced with [RateLimitPolicy::Coalesce]
+    pub coalesced: u64,
+    /// Deliveries that failed with a retryable error and were queued again
+    pub retried: u64,
+}
+
+#[derive(Debug)]
+struct Queued {
+    id: String,
+    builder: NotificationBuilder,
+    /// keeps the order of the notifications with the same priority
+    sequence: u64,
+    queued_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    global: Option<DateTime<Utc>>,
+    /// theoretical arrival times per thread id, `None` for notifications without thread
+    threads: HashMap<Option<String>, DateTime<Utc>>,
+    queue: Vec<Queued>,
+    next_sequence: u64,
+    /// the platform failed with a retryable error, nothing is sent until then
+    backoff_until: Option<DateTime<Utc>>,
+    /// when the drain timer fires, if it is scheduled
+    timer_at: Option<DateTime<Utc>>,
+    /// only the counters, the rest is filled in by [RateLimitedNotificationManager::queue_metrics]
+    metrics: QueueMetrics,
+}
+
+impl State {
+    fn take_sequence(&mut self) -> u64 {
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+        sequence
+    }
+
+    fn enqueue(&mut self, id: String, builder: NotificationBuilder, now: DateTime<Utc>) {
+        let sequence = self.take_sequence();
+        self.queue.push(Queued {
+            id,
+            builder,
+            sequence,
+            queued_at: now,
+        });
+    }
+}
+
+enum Admission {
+    Now,
+    Dropped,
+    Queued { id: String },
+    Coalesced { id: String },
+}
+
+/// Wraps a [NotificationManager] and limits the notifications it sends,
+/// see the [module documentation](self)
+#[derive(Debug, Clone)]
+pub struct RateLimitedNotificationManager {
+    inner: Arc<dyn NotificationManager>,
+    global_limit: Option<RateLimit>,
+    thread_limit: Option<RateLimit>,
+    policy: RateLimitPolicy,
+    state: Arc<Mutex<State>>,
+    scheduler: Scheduler,
+}
+
+impl RateLimitedNotificationManager {
+    /// Without limits until they are set
+    pub fn new(inner: Arc<dyn NotificationManager>) -> Self {
+        Self {
+            inner,
+            global_limit: None,
+            thread_limit: None,
+            policy: RateLimitPolicy::default(),
+            state: Arc::default(),
+            scheduler: Scheduler::default(),
+        }
+    }
+
+    /// Limit for all notifications together
+    pub fn set_global_limit(mut self, limit: RateLimit) -> Self {
+        self.global_limit = Some(limit);
+        self
+    }
+
+    /// Limit for each thread id, notifications without thread id share one limit
+    pub fn set_thread_limit(mut self, limit: RateLimit) -> Self {
+        self.thread_limit = Some(limit);
+        self
+    }
+
+    pub fn set_policy(mut self, policy: RateLimitPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// The wrapped manager
+    pub fn as_inner(&self) -> &Arc<dyn NotificationManager> {
+        &self.inner
+    }
+
+    /// How many notifications wait and what happened to the notifications so far
+    pub fn queue_metrics(&self) -> QueueMetrics {
+        let state = self.lock_state();
+        let mut metrics = state.metrics.clone();
+        metrics.queued = state.queue.len();
+        for queued in &state.queue {
+            *metrics
+                .queued_by_priority
+                .entry(queued.builder.get_priority())
+                .or_default() += 1;
+        }
+        metrics.oldest_queued_at = state.queue.iter().map(|queued| queued.queued_at).min();
+        metrics
+    }
+
+    /// The earliest time at which the limits allow a notification of this thread
+    fn allowed_at(
+        &self,
+        state: &State,
+        thread: &Option<String>,
+        now: DateTime<Utc>,
+    ) -> DateTime<Utc> {
+        let mut at = now;
+        if let Some(limit) = &self.global_limit {
+            at = at.max(limit.allowed_at(state.global, now));
+        }
+        if let Some(limit) = &self.thread_limit {
+            at = at.max(limit.allowed_at(state.threads.get(thread).copied(), now));
+        }
+        at
+    }
+
+    fn reserve(&self, state: &mut State, thread: &Option<String>, at: DateTime<Utc>) {
+        if let Some(limit) = &self.global_limit {
+            state.global = Some(limit.reserve(state.global, at));
+        }
+        if let Some(limit) = &self.thread_limit {
+            let tat = limit.reserve(state.threads.get(thread).copied(), at);
+            state.threads.insert(thread.clone(), tat);
+        }
+    }
+
+    /// The state stays consistent when a thread panics while holding the lock,
+    /// so the guard of a poisoned lock is used as is
+    fn lock_state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn admit(&self, builder: &NotificationBuilder) -> Admission {
+        let mut state = self.lock_state();
+        let now = Utc::now();
+        let thread = &builder.thread_id;
+        // forget the threads that are within their limit again
+        state.threads.retain(|_, tat| *tat > now);
+
+        let backoff = state.backoff_until.is_some_and(|until| until > now);
+        if !backoff && self.allowed_at(&state, thread, now) <= now {
+            self.reserve(&mut state, thread, now);
+            return Admission::Now;
+        }
+        if self.policy == RateLimitPolicy::Drop {
+            state.metrics.dropped += 1;
+            return Admission::Dropped;
+        }
+        if self.policy == RateLimitPolicy::Coalesce
+            && let Some(queued) = state
+                .queue
+                .iter_mut()
+                .find(|queued| &queued.builder.thread_id == thread)
+        {
+            // the newer notification keeps the place of the waiting one, unless it is more important
+            let priority = queued.builder.get_priority().max(builder.get_priority());
+            queued.builder = builder.clone().set_priority(priority);
+            let id = queued.id.clone();
+            state.metrics.coalesced += 1;
+            return Admission::Coalesced { id };
+        }
+        let id = uuid::Uuid::new_v4().to_string();
+        state.enqueue(id.clone(), builder.clone(), now);
+        self.schedule_drain(&mut state, now);
+        Admission::Queued { id }
+    }
+
+    /// Schedules the timer for the earliest time at which a waiting notification can be sent
+    fn schedule_drain(&self, state: &mut State, now: DateTime<Utc>) {
+        let wake = {
+            let state: &State = state;
+            state
+                .queue
+                .iter()
+                .map(|queued| self.allowed_at(state, &queued.builder.thread_id, now))
+                .min()
+        };
+        let Some(mut wake) = wake else {
+            return;
+        };
+        if let Some(until) = state.backoff_until {
+            wake = wake.max(until);
+        }
+        if state.timer_at.is_some_and(|timer_at| timer_at <= wake) {
+            return;
+        }
+        self.scheduler.cancel(DRAIN_TIMER);
+        state.timer_at = Some(wake);
+        let manager = self.clone();
+        self.scheduler
+            .schedule(DRAIN_TIMER.to_owned(), wake, async move {
+                manager.drain().await;
+            });
+    }
+
+    /// Sends the waiting notifications that the limits allow now, the most important first
+    async fn drain(&self) {
+        let due = {
+            let mut state = self.lock_state();
+            state.timer_at = None;
+            let now = Utc::now();
+            let mut due = Vec::new();
+            if state.backoff_until.is_none_or(|until| until <= now) {
+                state.backoff_until = None;
+                loop {
+                    let next = state
+                        .queue
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, queued)| {
+                            self.allowed_at(&state, &queued.builder.thread_id, now) <= now
+                        })
+                        .max_by_key(|(_, queued)| {
+                            (queued.builder.get_priority(), Reverse(queued.sequence))
+                        })
+                        .map(|(index, _)| index);
+                    let Some(index) = next else {
+                        break;
+                    };
+                    let queued = state.queue.remove(index);
+                    self.reserve(&mut state, &queued.builder.thread_id, now);
+                    due.push(queued);
+                }
+            }
+            self.schedule_drain(&mut state, now);
+            due
+        };
+
+        let mut due = due.into_iter();
+        while let Some(queued) = due.next() {
+            // shows it with the id of the returned handle
+            let builder = queued.builder.clone().with_preset_id(queued.id.clone());
+            match self.inner.send_notification(builder).await {
+                Ok(_) => self.lock_state().metrics.sent += 1,
+                Err(err) if err.is_retryable() => {
+                    log::warn!(
+                        "failed to show queued notification {}, retrying: {err}",
+                        queued.id
+                    );
+                    self.retry_later(std::iter::once(queued).chain(due).collect());
+                    return;
+                }
+                Err(err) => {
+                    log::error!("failed to show queued notification {}: {err:?}", queued.id);
+                }
+            }
+        }
+    }
+
+    /// Puts the notifications back into the queue after the platform failed with a retryable error
+    fn retry_later(&self, notifications: Vec<Queued>) {
+        let mut state = self.lock_state();
+        let now = Utc::now();
+        state.backoff_until = chrono::Duration::from_std(RETRY_DELAY)
+            .ok()
+            .and_then(|delay| now.checked_add_signed(delay));
+        state.metrics.retried += notifications.len() as u64;
+        state.queue.extend(notifications);
+        self.schedule_drain(&mut state, now);
+    }
+
+    /// Removes the queued notification, returns its builder if it was still waiting
+    fn take_queued(&self, id: &str) -> Option<NotificationBuilder> {
+        let mut state = self.lock_state();
+        let index = state.queue.iter().position(|queued| queued.id == id)?;
+        Some(state.queue.remove(index).builder)
+    }
+
+    fn queued_handle(
+        &self,
+        id: String,
+        builder: &NotificationBuilder,
+    ) -> Box<dyn NotificationHandle> {
+        Box::new(QueuedNotificationHandle {
+            id,
+            thread_id: builder.thread_id.clone(),
+            user_info: builder.user_info.clone().unwrap_or_default(),
+            content: NotificationContent::from(builder),
+            manager: self.clone(),
+        })
+    }
+}
+
+/// Handle of a notification that waits for the rate limit,
+/// it keeps its id when it is shown
+#[derive(Debug, Clone)]
+struct QueuedNotificationHandle {
+    id: String,
+    thread_id: Option<String>,
+    user_info: HashMap<String, String>,
+    content: NotificationContent,
+    manager: RateLimitedNotificationManager,
+}
+
+impl NotificationHandle for QueuedNotificationHandle {
+    fn close(&self) -> Result<(), Error> {
+        if self.manager.take_queued(&self.id).is_some() {
+            return Ok(());
+        }
+        self.manager
+            .inner
+            .remove_delivered_notifications(vec![&self.id])
+    }
+
+    fn get_id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn get_stored_user_info(&self) -> &HashMap<String, String> {
+        &self.user_info
+    }
+
+    fn get_thread_id(&self) -> Option<&str> {
+        self.thread_id.as_deref()
+    }
+
+    fn get_content(&self) -> &NotificationContent {
+        &self.content
+    }
+}
+
+#[async_trait]
+impl crate::delegate::DelegatingManager for RateLimitedNotificationManager {
+    fn inner(&self) -> &Arc<dyn NotificationManager> {
+        &self.inner
+    }
+
+    /// Depending on the [RateLimitPolicy], notifications over the limit fail with [Error::RateLimited]
+    /// or are shown later, then the returned handle has the id the notification will be shown with.
+    ///
+    /// Waiting notifications are sent by their [NotificationPriority], the same priority in order.
+    /// When the platform fails with a retryable error, the notification waits too, except with [RateLimitPolicy::Drop].
+    async fn send_notification(
+        &self,
+        builder: NotificationBuilder,
+    ) -> Result<Box<dyn NotificationHandle>, Error> {
+        // a notification that waits is shown later, so it is checked before
+        builder.validate(&self.inner.capabilities().await?)?;
+        match self.admit(&builder) {
+            Admission::Now => match self.inner.send_notification(builder.clone()).await {
+                Ok(handle) => {
+                    self.lock_state().metrics.sent += 1;
+                    Ok(handle)
+                }
+                Err(err) if err.is_retryable() && self.policy != RateLimitPolicy::Drop => {
+                    log::warn!("failed to show notification, queueing it: {err}");
+                    let id = uuid::Uuid::new_v4().to_string();
+                    let handle = self.queued_handle(id.clone(), &builder);
+                    let queued = Queued {
+                        id,
+                        builder,
+                        // takes its place among the notifications queued so far
+                        sequence: self.lock_state().take_sequence(),
+                        queued_at: Utc::now(),
+                    };
+                    self.retry_later(vec![queued]);
+                    Ok(handle)
+                }
+                Err(err) => Err(err),
+            },
+            Admission::Dropped => {
+                log::warn!(
+                    "dropping notification over the rate limit: {:?}",
+                    NotificationContent::from(&builder)
+                );
+                Err(Error::RateLimited)
+            }
+            Admission::Coalesced { id } => {
+                log::info!("notification {id} over the rate limit was replaced by a newer one");
+                Ok(self.queued_handle(id, &builder))
+            }
+            Admission::Queued { id } => {
+                log::info!("queueing notification {id} over the rate limit");
+                Ok(self.queued_handle(id, &builder))
+            }
+        }
+    }
+
+    async fn update_notification(
+        &self,
+        id: &str,
+        builder: NotificationBuilder,
+    ) -> Result<Box<dyn NotificationHandle>, Error> {
+        if let Some(queued) = self
+            .lock_state()
+            .queue
+            .iter_mut()
+            .find(|queued| queued.id == id)
+        {
+            queued.builder = builder.clone();
+            return Ok(self.queued_handle(id.to_owned(), &builder));
+        }
+        self.inner.update_notification(id, builder).await
+    }
+
+    /// Also cancels notifications that wait for the rate limit
+    fn cancel_scheduled_notification(&self, id: &str) -> Result<(), Error> {
+        if self.take_queued(id).is_some() {
+            return Ok(());
+        }
+        self.inner.cancel_scheduled_notification(id)
+    }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use super::*;
+    use crate::mock::NotificationManagerMock;
+
+    fn limited(
+        mock: &NotificationManagerMock,
+        policy: RateLimitPolicy,
+    ) -> RateLimitedNotificationManager {
+        RateLimitedNotificationManager::new(Arc::new(mock.clone()))
+            .set_global_limit(RateLimit::new(1, Duration::from_millis(100)))
+            .set_policy(policy)
+    }
+
+    fn notification(title: &str) -> NotificationBuilder {
+        NotificationBuilder::new()
+            .title(title)
+            .set_thread_id("thread")
+    }
+
+    #[tokio::test]
+    async fn drop_over_limit() {
+        let mock = NotificationManagerMock::new();
+        let manager = RateLimitedNotificationManager::new(Arc::new(mock.clone()))
+            .set_thread_limit(RateLimit::new(1, Duration::from_secs(60)).set_burst(2));
+        manager.send_notification(notification("1")).await.unwrap();
+        manager.send_notification(notification("2")).await.unwrap();
+        let err = manager
+            .send_notification(notification("3"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::RateLimited));
+        // other threads have their own limit
+        manager
+            .send_notification(NotificationBuilder::new().title("other"))
+            .await
+            .unwrap();
+        assert_eq!(mock.sent_notifications().await.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn queue_and_coalesce() {
+        let mock = NotificationManagerMock::new();
+        let manager = limited(&mock, RateLimitPolicy::Queue);
+        for title in ["1", "2", "3"] {
+            manager
+                .send_notification(notification(title))
+                .await
+                .unwrap();
+        }
+        assert_eq!(mock.sent_notifications().await.len(), 1);
+        tokio::time::sleep(Duration::from_millis(350)).await;
+        assert_eq!(mock.sent_notifications().await.len(), 3);
+
+        let mock = NotificationManagerMock::new();
+        let manager = limited(&mock, RateLimitPolicy::Coalesce);
+        let mut handles = Vec::new();
+        for title in ["1", "2", "3"] {
+            handles.push(
+                manager
+                    .send_notification(notification(title))
+                    .await
+                    .unwrap(),
+            );
+        }
+        assert_eq!(handles[1].get_id(), handles[2].get_id());
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        let sent = mock.sent_notifications().await;
+        assert_eq!(sent.len(), 2);
+        assert_eq!(sent[1].get_title(), Some("3"));
+        let active = mock.get_active_notifications().await.unwrap();
+        assert!(active.iter().any(|n| n.get_id() == handles[2].get_id()));
+    }
+
+    #[tokio::test]
+    async fn priority() {
+        let mock = NotificationManagerMock::new();
+        let manager = limited(&mock, RateLimitPolicy::Queue);
+        manager
+            .send_notification(notification("first"))
+            .await
+            .unwrap();
+        for (title, priority) in [
+            ("low", NotificationPriority::Low),
+            ("normal", NotificationPriority::Normal),
+            ("high", NotificationPriority::High),
+        ] {
+            manager
+                .send_notification(notification(title).set_priority(priority))
+                .await
+                .unwrap();
+        }
+        let metrics = manager.queue_metrics();
+        assert_eq!(metrics.queued, 3);
+        assert_eq!(
+            metrics.queued_by_priority.get(&NotificationPriority::High),
+            Some(&1)
+        );
+        assert!(metrics.oldest_queued_at.is_some());
+
+        tokio::time::sleep(Duration::from_millis(350)).await;
+        let titles: Vec<_> = mock
+            .sent_notifications()
+            .await
+            .iter()
+            .map(|n| n.get_title().unwrap_or_default().to_owned())
+            .collect();
+        assert_eq!(titles, ["first", "high", "normal", "low"]);
+        let metrics = manager.queue_metrics();
+        assert_eq!(metrics.queued, 0);
+        assert_eq!(metrics.sent, 4);
+    }
+}
@@ -0,0 +1,405 @@
+//! Images from `https://` urls, like chat avatars and link previews
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), user_notify::Error> {
+//! use async_trait::async_trait;
+//! use futures_lite::AsyncRead;
+//! use user_notify::{
+//!     Error, NotificationBuilder, NotificationManager, NotificationSender,
+//!     remote_image::{ImageDownloader, RemoteImageManager},
+//! };
+//!
+//! /// Use the HTTP client of the app here
+//! struct Downloader;
+//!
+//! #[async_trait]
+//! impl ImageDownloader for Downloader {
+//!     async fn open(&self, url: &url::Url) -> Result<Box<dyn AsyncRead + Send + Unpin>, Error> {
+//!         Err(Error::ImageDownload {
+//!             url: url.to_string(),
+//!             reason: "offline".to_owned(),
+//!         })
+//!     }
+//! }
+//!
+//! let manager = RemoteImageManager::new(
+//!     user_notify::get_notification_manager("com.example.app".to_owned(), None),
+//!     Downloader,
+//! )
+//! .set_max_size(2_000_000);
+//! manager
+//!     .send_notification(
+//!         NotificationBuilder::new()
+//!             .body("Look at this")
+//!             .set_sender(
+//!                 NotificationSender::new("Alice")
+//!                     .set_avatar_url("https://example.com/avatars/alice.png"),
+//!             )
+//!             .set_image_url("https://example.com/previews/cat.jpg"),
+//!     )
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The crate brings no HTTP client of its own, the [ImageDownloader] opens the response with the one of the app,
+//! [RemoteImageManager] enforces the timeout and the size limit while reading it.
+//! The images are stored by the hash of their content in the [RemoteImageManager::set_cache_dir],
+//! a url that was downloaded before is not downloaded again while the manager lives.
+//!
+//! An image that fails to download is left out and the error is logged,
+//! the notification is still sent, so a slow server does not hold back the message.
+//!
+//! When [NotificationCapabilities::remote_images] is set, like on the web, the urls are passed through.
+
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures_lite::{AsyncRead, AsyncReadExt};
+use sha2::{Digest, Sha256};
+use url::Url;
+
+use crate::{
+    CalendarTrigger, Error, LocationTrigger, NotificationBuilder, NotificationCapabilities,
+    NotificationHandle, NotificationManager, RepeatInterval, scheduler,
+};
+
+/// Opens the body of the response to a `GET` request, implemented with the HTTP client of the app
+#[async_trait]
+pub trait ImageDownloader: Send + Sync {
+    /// Fails with [Error::ImageDownload] when the server does not answer with a success status
+    async fn open(&self, url: &Url) -> Result<Box<dyn AsyncRead + Send + Unpin>, Error>;
+}
+
+/// Wraps a [NotificationManager] and downloads the images that are set by url,
+/// see the [module documentation](self)
+#[derive(Clone)]
+pub struct RemoteImageManager {
+    inner: Arc<dyn NotificationManager>,
+    downloader: Arc<dyn ImageDownloader>,
+    cache_dir: PathBuf,
+    timeout: Duration,
+    max_size: u64,
+    /// url to the downloaded file
+    downloaded: Arc<Mutex<HashMap<String, PathBuf>>>,
+}
+
+impl Debug for RemoteImageManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RemoteImageManager")
+            .field("inner", &self.inner)
+            .field("cache_dir", &self.cache_dir)
+            .field("timeout", &self.timeout)
+            .field("max_size", &self.max_size)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RemoteImageManager {
+    pub fn new(
+        inner: Arc<dyn NotificationManager>,
+        downloader: impl ImageDownloader + 'static,
+    ) -> Self {
+        Self {
+            inner,
+            downloader: Arc::new(downloader),
+            cache_dir: std::env::temp_dir().join("user-notify-images"),
+            timeout: Duration::from_secs(10),
+            max_size: 10_000_000,
+            downloaded: Arc::default(),
+        }
+    }
+
+    /// Where the images are stored, defaults to `user-notify-images` in the temp dir.
+    /// The files are not deleted, since the platforms may still show them.
+    pub fn set_cache_dir(mut self, cache_dir: impl Into<PathBuf>) -> Self {
+        self.cache_dir = cache_dir.into();
+        self
+    }
+
+    /// How long a download may take, defaults to 10 seconds
+    pub fn set_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// The largest image in bytes, defaults to 10 MB, bigger downloads are aborted
+    pub fn set_max_size(mut self, max_size: u64) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// The wrapped manager
+    pub fn as_inner(&self) -> &Arc<dyn NotificationManager> {
+        &self.inner
+    }
+
+    /// Downloads the image, or returns the file of an earlier download, for example to load an avatar ahead of time
+    pub async fn fetch(&self, url: &str) -> Result<PathBuf, Error> {
+        let download_error = |reason: String| Error::ImageDownload {
+            url: url.to_owned(),
+            reason,
+        };
+        if let Some(path) = self
+            .downloaded
+            .lock()
+            .map_err(|_| download_error("the cache lock is poisoned".to_owned()))?
+            .get(url)
+            .filter(|path| path.is_file())
+        {
+            return Ok(path.clone());
+        }
+        let parsed = Url::parse(url).map_err(|error| download_error(error.to_string()))?;
+        if parsed.scheme() != "https" {
+            return Err(download_error("only https urls are supported".to_owned()));
+        }
+
+        let download = async {
+            let mut body = Vec::new();
+            self.downloader
+                .open(&parsed)
+                .await?
+                .take(self.max_size + 1)
+                .read_to_end(&mut body)
+                .await
+                .map_err(|error| download_error(error.to_string()))?;
+            Ok(body)
+        };
+        let timeout = async {
+            scheduler::sleep(self.timeout).await;
+            Err(download_error(format!(
+                "timed out after {:?}",
+                self.timeout
+            )))
+        };
+        let body = futures_lite::future::or(download, timeout).await?;
+        if body.len() as u64 > self.max_size {
+            return Err(download_error(format!(
+                "the image is bigger than {} bytes",
+                self.max_size
+            )));
+        }
+        let extension = image_extension(&body).ok_or_else(|| {
+            download_error("the response is not a png, jpeg, gif or webp image".to_owned())
+        })?;
+
+        let path = self
+            .cache_dir
+            .join(format!("{:x}.{extension}", Sha256::digest(&body)));
+        if !path.is_file() {
+            std::fs::create_dir_all(&self.cache_dir)?;
+            // renamed when complete, so a notification never shows half an image
+            let partial = path.with_extension(format!("{}.part", uuid::Uuid::new_v4()));
+            std::fs::write(&partial, &body)?;
+            std::fs::rename(&partial, &path)?;
+        }
+        if let Ok(mut downloaded) = self.downloaded.lock() {
+            downloaded.insert(url.to_owned(), path.clone());
+        }
+        Ok(path)
+    }
+
+    /// Replaces the urls with the downloaded files, unless the inner manager loads urls itself
+    async fn resolve(&self, mut builder: NotificationBuilder) -> NotificationBuilder {
+        if !builder.has_image_urls()
+            || self
+                .inner
+                .capabilities()
+                .await
+                .is_ok_and(|capabilities| capabilities.remote_images)
+        {
+            return builder;
+        }
+        let fetch = |url: String| async move {
+            self.fetch(&url)
+                .await
+                .inspect_err(|error| log::warn!("leaving out the image: {error}"))
+                .ok()
+        };
+        if let Some(url) = builder.image_url.take() {
+            builder.image = fetch(url).await;
+        }
+        if let Some(url) = builder.hero_image_url.take() {
+            builder.hero_image = fetch(url).await;
+        }
+        if let Some(url) = builder
+            .sender
+            .as_mut()
+            .and_then(|sender| sender.avatar_url.take())
+        {
+            let avatar = fetch(url).await;
+            if let Some(sender) = &mut builder.sender {
+                sender.avatar = avatar;
+            }
+        }
+        builder
+    }
+}
+
+/// Guessed from the signature, the platforms need the right extension to show the file
+fn image_extension(body: &[u8]) -> Option<&'static str> {
+    if body.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("png")
+    } else if body.starts_with(b"\xff\xd8\xff") {
+        Some("jpg")
+    } else if body.starts_with(b"GIF87a") || body.starts_with(b"GIF89a") {
+        Some("gif")
+    } else if body.starts_with(b"RIFF") && body.get(8..12) == Some(b"WEBP") {
+        Some("webp")
+    } else {
+        None
+    }
+}
+
+#[async_trait]
+impl crate::delegate::DelegatingManager for RemoteImageManager {
+    fn inner(&self) -> &Arc<dyn NotificationManager> {
+        &self.inner
+    }
+
+    async fn capabilities(&self) -> Result<NotificationCapabilities, Error> {
+        Ok(NotificationCapabilities {
+            remote_images: true,
+            ..self.inner.capabilities().await?
+        })
+    }
+
+    async fn send_notification(
+        &self,
+        builder: NotificationBuilder,
+    ) -> Result<Box<dyn NotificationHandle>, Error> {
+        let builder = self.resolve(builder).await;
+        self.inner.send_notification(builder).await
+    }
+
+    async fn update_notification(
+        &self,
+        id: &str,
+        builder: NotificationBuilder,
+    ) -> Result<Box<dyn NotificationHandle>, Error> {
+        let builder = self.resolve(builder).await;
+        self.inner.update_notification(id, builder).await
+    }
+
+    /// The images are downloaded when it is scheduled
+    async fn schedule_notification(
+        &self,
+        builder: NotificationBuilder,
+        at: DateTime<Utc>,
+    ) -> Result<String, Error> {
+        let builder = self.resolve(builder).await;
+        self.inner.schedule_notification(builder, at).await
+    }
+
+    async fn schedule_repeating_notification(
+        &self,
+        builder: NotificationBuilder,
+        interval: RepeatInterval,
+    ) -> Result<String, Error> {
+        let builder = self.resolve(builder).await;
+        self.inner
+            .schedule_repeating_notification(builder, interval)
+            .await
+    }
+
+    async fn schedule_calendar_notification(
+        &self,
+        builder: NotificationBuilder,
+        trigger: CalendarTrigger,
+    ) -> Result<String, Error> {
+        let builder = self.resolve(builder).await;
+        self.inner
+            .schedule_calendar_notification(builder, trigger)
+            .await
+    }
+
+    async fn schedule_location_notification(
+        &self,
+        builder: NotificationBuilder,
+        trigger: LocationTrigger,
+    ) -> Result<String, Error> {
+        let builder = self.resolve(builder).await;
+        self.inner
+            .schedule_location_notification(builder, trigger)
+            .await
+    }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use super::*;
+    use crate::{NotificationSender, mock::NotificationManagerMock};
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\nnot really a png";
+
+    struct Downloader;
+
+    #[async_trait]
+    impl ImageDownloader for Downloader {
+        async fn open(&self, url: &Url) -> Result<Box<dyn AsyncRead + Send + Unpin>, Error> {
+            match url.path() {
+                "/avatar.png" => Ok(Box::new(futures_lite::io::Cursor::new(PNG))),
+                "/big.png" => Ok(Box::new(futures_lite::io::Cursor::new(
+                    [PNG, &[0; 1024]].concat(),
+                ))),
+                _ => Err(Error::ImageDownload {
+                    url: url.to_string(),
+                    reason: "404".to_owned(),
+                }),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn download_images() {
+        let cache_dir = std::env::temp_dir().join(format!("user-notify-{}", uuid::Uuid::new_v4()));
+        let manager = RemoteImageManager::new(Arc::new(NotificationManagerMock::new()), Downloader)
+            .set_cache_dir(&cache_dir)
+            .set_max_size(512);
+
+        let first = manager
+            .fetch("https://example.com/avatar.png")
+            .await
+            .unwrap();
+        assert_eq!(first.parent(), Some(cache_dir.as_path()));
+        assert_eq!(first.extension().unwrap(), "png");
+        assert_eq!(std::fs::read(&first).unwrap(), PNG);
+        // same content, same file
+        assert_eq!(
+            manager
+                .fetch("https://example.com/avatar.png?size=64")
+                .await
+                .unwrap(),
+            first
+        );
+        for url in [
+            "http://example.com/avatar.png",
+            "https://example.com/big.png",
+            "https://example.com/missing.png",
+        ] {
+            let err = manager.fetch(url).await.unwrap_err();
+            assert!(matches!(err, Error::ImageDownload { .. }), "{err}");
+        }
+
+        // failed images are left out instead of failing the notification
+        manager
+            .send_notification(
+                NotificationBuilder::new()
+                    .set_sender(
+                        NotificationSender::new("Alice")
+                            .set_avatar_url("https://example.com/avatar.png"),
+                    )
+                    .set_image_url("https://example.com/missing.png"),
+            )
+            .await
+            .unwrap();
+        std::fs::remove_dir_all(cache_dir).unwrap();
+    }
+}
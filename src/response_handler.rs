@@ -0,0 +1,70 @@
+//! The handler from [NotificationManager::register], shared with the threads and callbacks of the platforms,
+//! so it can be replaced with [NotificationManager::set_response_handler].
+//!
+//! [NotificationManager::register]: crate::NotificationManager::register
+//! [NotificationManager::set_response_handler]: crate::NotificationManager::set_response_handler
+
+use std::sync::{Arc, RwLock};
+
+use crate::{Error, NotificationResponse};
+
+type Handler = Box<dyn Fn(NotificationResponse) + Send + Sync + 'static>;
+
+#[derive(Default, Clone)]
+pub(crate) struct ResponseHandler {
+    handler: Arc<RwLock<Option<Arc<Handler>>>>,
+}
+
+impl ResponseHandler {
+    /// Fails if a handler was already registered
+    pub(crate) fn register(&self, handler: Handler) -> Result<(), Error> {
+        let mut current = self.handler.write().map_err(|_| Error::SettingHandler)?;
+        if current.is_some() {
+            return Err(Error::SettingHandler);
+        }
+        *current = Some(Arc::new(handler));
+        Ok(())
+    }
+
+    /// Fails if no handler was registered yet, the platform is only set up by the registration
+    pub(crate) fn replace(&self, handler: Handler) -> Result<(), Error> {
+        let mut current = self.handler.write().map_err(|_| Error::SettingHandler)?;
+        if current.is_none() {
+            return Err(Error::SettingHandler);
+        }
+        *current = Some(Arc::new(handler));
+        Ok(())
+    }
+
+    /// The current handler, it is cloned so it is not called while the lock is held
+    pub(crate) fn get(&self) -> Option<Arc<Handler>> {
+        self.handler.read().ok()?.clone()
+    }
+
+    pub(crate) fn is_set(&self) -> bool {
+        self.handler
+            .read()
+            .map(|handler| handler.is_some())
+            .unwrap_or_default()
+    }
+}
+
+/// The handler of [NotificationManager::responses](crate::NotificationManager::responses),
+/// it passes the responses to the returned receiver
+pub(crate) fn channel() -> (
+    Handler,
+    tokio::sync::mpsc::UnboundedReceiver<NotificationResponse>,
+) {
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    let handler: Handler = Box::new(move |response| {
+        if tx.send(response).is_err() {
+            log::warn!("notification response receiver was dropped");
+        }
+    });
+    (handler, rx)
+}
+
+/// The handler of [NotificationManager::clear_response_handler](crate::NotificationManager::clear_response_handler)
+pub(crate) fn dropping() -> Handler {
+    Box::new(|response| log::debug!("no response handler, dropping {response:?}"))
+}
@@ -0,0 +1,598 @@
+//! Timer based delivery of scheduled notifications,
+//! used by the backends where the platform has no native scheduling.
+//!
+//! With the `tokio` feature the deliveries are tokio tasks when scheduled from within a tokio runtime,
+//! otherwise each pending delivery waits on its own thread, so no async runtime is needed.
+//! On wasm32 with the `web` feature they are browser timers.
+
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{CalendarTrigger, Error, NotificationBuilder, RepeatInterval};
+
+/// Shortest interval for repeating notifications, same as the minimum on macOS
+pub(crate) const MIN_REPEAT_INTERVAL: Duration = Duration::from_secs(60);
+
+// used by the platform backends without the windows one
+#[cfg_attr(
+    not(any(
+        all(target_os = "macos", feature = "macos"),
+        all(target_arch = "wasm32", feature = "web"),
+        all(
+            any(
+                target_os = "linux",
+                target_os = "dragonfly",
+                target_os = "freebsd",
+                target_os = "openbsd",
+                target_os = "netbsd"
+            ),
+            any(feature = "linux-dbus", feature = "linux-portal")
+        )
+    )),
+    allow(dead_code)
+)]
+/// Appended to the notification id, so the expiration does not replace a scheduled delivery of the same notification
+const EXPIRATION_SUFFIX: &str = ".expiration";
+
+/// When a notification of [Scheduler::schedule_notification] is shown
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Schedule {
+    At(DateTime<Utc>),
+    Every(RepeatInterval),
+    Calendar(CalendarTrigger),
+}
+
+#[derive(Debug)]
+struct Pending {
+    /// Tells the tasks of a schedule apart from the tasks of an earlier schedule with the same id
+    generation: u64,
+    task: timer::Task,
+}
+
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Scheduler {
+    pending: Arc<Mutex<HashMap<String, Pending>>>,
+    generations: Arc<AtomicU64>,
+}
+
+impl Scheduler {
+    /// Runs `deliver` at `at`, or right away if `at` is in the past.
+    pub(crate) fn schedule<F>(&self, id: String, at: DateTime<Utc>, deliver: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let delay = (at - Utc::now()).to_std().unwrap_or_default();
+        let generation = self.next_generation();
+        let scheduler = self.clone();
+        let task_id = id.clone();
+        self.track(id, generation, false, || {
+            timer::after(delay, async move {
+                scheduler.untrack(&task_id, generation);
+                deliver.await;
+            })
+        });
+    }
+
+    /// Runs `deliver` every `interval`, starting one `interval` from now, until it is cancelled.
+    pub(crate) fn schedule_repeating<F, Fut>(&self, id: String, interval: Duration, deliver: F)
+    where
+        F: Fn() -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let generation = self.next_generation();
+        self.track(id, generation, false, || timer::every(interval, deliver));
+    }
+
+    /// Runs `deliver` at the next time the trigger matches, and at every later match if it repeats,
+    /// returns false if the trigger never matches.
+    pub(crate) fn schedule_calendar<F, Fut>(
+        &self,
+        id: String,
+        trigger: CalendarTrigger,
+        deliver: F,
+    ) -> bool
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let Some(at) = trigger.next_fire_time(Utc::now()) else {
+            return false;
+        };
+        let next_time = move |after| trigger.next_fire_time(after).filter(|_| trigger.repeats);
+        let generation = self.next_generation();
+        self.schedule_chain(
+            id,
+            generation,
+            at,
+            Arc::new(next_time),
+            Arc::new(deliver),
+            false,
+        );
+        true
+    }
+
+    /// Runs `deliver` at `at` and then at each time from `next_time`, until it returns None.
+    /// The next delivery is only armed if the schedule was not cancelled or replaced during the delivery.
+    fn schedule_chain<N, F, Fut>(
+        &self,
+        id: String,
+        generation: u64,
+        at: DateTime<Utc>,
+        next_time: Arc<N>,
+        deliver: Arc<F>,
+        continues: bool,
+    ) where
+        N: Fn(DateTime<Utc>) -> Option<DateTime<Utc>> + Send + Sync + 'static,
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let delay = (at - Utc::now()).to_std().unwrap_or_default();
+        let scheduler = self.clone();
+        let task_id = id.clone();
+        self.track(id, generation, continues, || {
+            timer::after(delay, async move {
+                // not before `at`, in case the timer fired early
+                let next = next_time(Utc::now().max(at));
+                if next.is_none() {
+                    scheduler.untrack(&task_id, generation);
+                }
+                deliver().await;
+                if let Some(next) = next {
+                    scheduler.schedule_chain(task_id, generation, next, next_time, deliver, true);
+                }
+            })
+        });
+    }
+
+    /// Calls `show` with the id and a copy of the builder at every time of the schedule and logs its errors,
+    /// repeating notifications are at most every [MIN_REPEAT_INTERVAL].
+    /// Fails if the calendar trigger never matches, returns the id otherwise.
+    pub(crate) fn schedule_notification<F, Fut>(
+        &self,
+        id: String,
+        builder: NotificationBuilder,
+        schedule: Schedule,
+        show: F,
+    ) -> Result<String, Error>
+    where
+        F: Fn(String, NotificationBuilder) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), Error>> + Send + 'static,
+    {
+        let notification_id = id.clone();
+        let deliver = move || {
+            let id = notification_id.clone();
+            let shown = show(id.clone(), builder.clone());
+            async move {
+                if let Err(err) = shown.await {
+                    log::error!("failed to show scheduled notification {id}: {err:?}");
+                }
+            }
+        };
+        match schedule {
+            Schedule::At(at) => self.schedule(id.clone(), at, deliver()),
+            Schedule::Every(interval) => {
+                let interval = interval.as_duration().max(MIN_REPEAT_INTERVAL);
+                self.schedule_repeating(id.clone(), interval, deliver);
+            }
+            Schedule::Calendar(trigger) => {
+                trigger.first_fire_time()?;
+                self.schedule_calendar(id.clone(), trigger, deliver);
+            }
+        }
+        Ok(id)
+    }
+
+    // used by the platform backends without the windows one
+    #[cfg_attr(
+        not(any(
+            all(target_os = "macos", feature = "macos"),
+            all(target_arch = "wasm32", feature = "web"),
+            all(
+                any(
+                    target_os = "linux",
+                    target_os = "dragonfly",
+                    target_os = "freebsd",
+                    target_os = "openbsd",
+                    target_os = "netbsd"
+                ),
+                any(feature = "linux-dbus", feature = "linux-portal")
+            )
+        )),
+        allow(dead_code)
+    )]
+    /// Runs `remove` at `expiration`, replacing the pending expiration of the notification.
+    pub(crate) fn schedule_expiration<F>(
+        &self,
+        id: &str,
+        expiration: Option<DateTime<Utc>>,
+        remove: F,
+    ) where
+        F: FnOnce() + Send + 'static,
+    {
+        let key = format!("{id}{EXPIRATION_SUFFIX}");
+        self.cancel(&key);
+        if let Some(expiration) = expiration {
+            self.schedule(key, expiration, async move { remove() });
+        }
+    }
+
+    fn next_generation(&self) -> u64 {
+        self.generations.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Starts the task of a new schedule, replacing the pending delivery with the same id.
+    /// When it `continues` a running schedule, it is only started if that schedule is still pending.
+    fn track(
+        &self,
+        id: String,
+        generation: u64,
+        continues: bool,
+        spawn: impl FnOnce() -> timer::Task,
+    ) {
+        // hold the lock while spawning, so the task can not finish before it is tracked
+        let Ok(mut pending) = self.pending.lock() else {
+            log::error!("scheduler lock is poisoned, dropping scheduled notification {id}");
+            return;
+        };
+        if continues && pending.get(&id).map(|pending| pending.generation) != Some(generation) {
+            log::debug!("not continuing schedule {id}, it was cancelled or replaced");
+            return;
+        }
+        let task = spawn();
+        // the task of the same schedule is the one that continues it, it is about to finish
+        if let Some(old) = pending.insert(id, Pending { generation, task })
+            && old.generation != generation
+        {
+            old.task.abort();
+        }
+    }
+
+    /// Forgets the delivery that is due, unless the id was scheduled again in the meantime
+    fn untrack(&self, id: &str, generation: u64) {
+        if let Ok(mut pending) = self.pending.lock()
+            && pending
+                .get(id)
+                .is_some_and(|pending| pending.generation == generation)
+        {
+            pending.remove(id);
+        }
+    }
+
+    /// Cancels the pending delivery, returns false if there was no pending delivery with this id
+    pub(crate) fn cancel(&self, id: &str) -> bool {
+        let Ok(mut pending) = self.pending.lock() else {
+            log::error!("scheduler lock is poisoned");
+            return false;
+        };
+        match pending.remove(id) {
+            Some(pending) => {
+                pending.task.abort();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Waits with the timers of the scheduler, so it also works without the `tokio` feature
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) async fn sleep(delay: Duration) {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    // dropping the task would abort the timer thread
+    let _task = timer::after(delay, async move {
+        let _ = tx.send(());
+    });
+    let _ = rx.await;
+}
+
+#[cfg(all(feature = "tokio", not(all(target_arch = "wasm32", feature = "web"))))]
+mod timer {
+    use std::{future::Future, time::Duration};
+
+    use super::thread_timer;
+
+    #[derive(Debug)]
+    pub(super) enum Task {
+        Tokio(tokio::task::AbortHandle),
+        Thread(thread_timer::Task),
+    }
+
+    impl Task {
+        pub(super) fn abort(self) {
+            match self {
+                Task::Tokio(task) => task.abort(),
+                Task::Thread(task) => task.abort(),
+            }
+        }
+    }
+
+    pub(super) fn after<F>(delay: Duration, deliver: F) -> Task
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return Task::Thread(thread_timer::after(delay, deliver));
+        };
+        let task = runtime.spawn(async move {
+            tokio::time::sleep(delay).await;
+            deliver.await;
+        });
+        Task::Tokio(task.abort_handle())
+    }
+
+    pub(super) fn every<F, Fut>(interval: Duration, deliver: F) -> Task
+    where
+        F: Fn() -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return Task::Thread(thread_timer::every(interval, deliver));
+        };
+        let task = runtime.spawn(async move {
+            let mut ticker =
+                tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+            loop {
+                ticker.tick().await;
+                deliver().await;
+            }
+        });
+        Task::Tokio(task.abort_handle())
+    }
+}
+
+#[cfg(not(any(feature = "tokio", all(target_arch = "wasm32", feature = "web"))))]
+use thread_timer as timer;
+
+#[cfg(not(all(target_arch = "wasm32", feature = "web")))]
+mod thread_timer {
+    use std::{
+        future::Future,
+        sync::mpsc::{self, RecvTimeoutError},
+        time::Duration,
+    };
+
+    /// Dropping the sender wakes the waiting thread, which then stops
+    #[derive(Debug)]
+    pub(super) struct Task(mpsc::Sender<()>);
+
+    impl Task {
+        pub(super) fn abort(self) {
+            drop(self.0);
+        }
+    }
+
+    pub(super) fn after<F>(delay: Duration, deliver: F) -> Task
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let (abort, aborted) = mpsc::channel();
+        std::thread::spawn(move || {
+            if let Err(RecvTimeoutError::Timeout) = aborted.recv_timeout(delay) {
+                futures_lite::future::block_on(deliver);
+            }
+        });
+        Task(abort)
+    }
+
+    pub(super) fn every<F, Fut>(interval: Duration, deliver: F) -> Task
+    where
+        F: Fn() -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let (abort, aborted) = mpsc::channel();
+        std::thread::spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = aborted.recv_timeout(interval) {
+                futures_lite::future::block_on(deliver());
+            }
+        });
+        Task(abort)
+    }
+}
+
+#[cfg(all(target_arch = "wasm32", feature = "web"))]
+mod timer {
+    use std::{future::Future, time::Duration};
+
+    use wasm_bindgen::{JsCast, closure::Closure};
+
+    /// `setTimeout` and `setInterval` share their ids, so both are cleared with `clearTimeout`
+    #[derive(Debug)]
+    pub(super) struct Task(Option<i32>);
+
+    impl Task {
+        pub(super) fn abort(self) {
+            if let (Some(handle), Some(window)) = (self.0, web_sys::window()) {
+                window.clear_timeout_with_handle(handle);
+            }
+        }
+    }
+
+    fn millis(duration: Duration) -> i32 {
+        duration.as_millis().try_into().unwrap_or(i32::MAX)
+    }
+
+    pub(super) fn after<F>(delay: Duration, deliver: F) -> Task
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let callback = Closure::once_into_js(move || wasm_bindgen_futures::spawn_local(deliver));
+        let handle = web_sys::window().and_then(|window| {
+            window
+                .set_timeout_with_callback_and_timeout_and_arguments_0(
+                    callback.unchecked_ref(),
+                    millis(delay),
+                )
+                .inspect_err(|err| log::error!("failed to set timeout: {err:?}"))
+                .ok()
+        });
+        Task(handle)
+    }
+
+    pub(super) fn every<F, Fut>(interval: Duration, deliver: F) -> Task
+    where
+        F: Fn() -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let callback =
+            Closure::<dyn Fn()>::new(move || wasm_bindgen_futures::spawn_local(deliver()))
+                .into_js_value();
+        let handle = web_sys::window().and_then(|window| {
+            window
+                .set_interval_with_callback_and_timeout_and_arguments_0(
+                    callback.unchecked_ref(),
+                    millis(interval),
+                )
+                .inspect_err(|err| log::error!("failed to set interval: {err:?}"))
+                .ok()
+        });
+        Task(handle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn deliver_and_cancel() {
+        let scheduler = Scheduler::default();
+        let delivered = Arc::new(AtomicBool::new(false));
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let at = Utc::now() + chrono::Duration::milliseconds(50);
+
+        let flag = delivered.clone();
+        scheduler.schedule("delivered".to_owned(), at, async move {
+            flag.store(true, Ordering::SeqCst);
+        });
+        let flag = cancelled.clone();
+        scheduler.schedule("cancelled".to_owned(), at, async move {
+            flag.store(true, Ordering::SeqCst);
+        });
+
+        assert!(scheduler.cancel("cancelled"));
+        assert!(!scheduler.cancel("unknown"));
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(delivered.load(Ordering::SeqCst));
+        assert!(!cancelled.load(Ordering::SeqCst));
+        // delivered notifications are no longer pending
+        assert!(!scheduler.cancel("delivered"));
+    }
+
+    #[test]
+    fn deliver_without_runtime() {
+        let scheduler = Scheduler::default();
+        let (tx, rx) = std::sync::mpsc::channel();
+        scheduler.schedule("id".to_owned(), Utc::now(), async move {
+            let _ = tx.send(());
+        });
+        assert!(rx.recv_timeout(Duration::from_secs(5)).is_ok());
+    }
+
+    #[tokio::test]
+    async fn cancel_during_delivery() {
+        let scheduler = Scheduler::default();
+        let count = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let next_time = |after| Some(after + chrono::Duration::milliseconds(50));
+
+        let (counter, flag, cancelling) = (count.clone(), cancelled.clone(), scheduler.clone());
+        let deliver = move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            flag.store(cancelling.cancel("id"), Ordering::SeqCst);
+            async {}
+        };
+        let generation = scheduler.next_generation();
+        scheduler.schedule_chain(
+            "id".to_owned(),
+            generation,
+            Utc::now(),
+            Arc::new(next_time),
+            Arc::new(deliver),
+            false,
+        );
+
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+        assert!(cancelled.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn schedule_again_replaces() {
+        let scheduler = Scheduler::default();
+        let count = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let at = Utc::now() + chrono::Duration::milliseconds(50);
+
+        for _ in 0..2 {
+            let counter = count.clone();
+            scheduler.schedule("id".to_owned(), at, async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+            });
+        }
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn expiration_is_replaced() {
+        let scheduler = Scheduler::default();
+        let count = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let at = Utc::now() + chrono::Duration::milliseconds(50);
+
+        for _ in 0..2 {
+            let counter = count.clone();
+            scheduler.schedule_expiration("id", Some(at), move || {
+                counter.fetch_add(1, Ordering::SeqCst);
+            });
+        }
+        // does not replace the expiration
+        scheduler.schedule("id".to_owned(), at, async {});
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+
+        let counter = count.clone();
+        scheduler.schedule_expiration("id", Some(at), move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+        scheduler.schedule_expiration("id", None, || {});
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn repeat_until_cancelled() {
+        let scheduler = Scheduler::default();
+        let count = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+
+        let counter = count.clone();
+        scheduler.schedule_repeating("repeat".to_owned(), Duration::from_millis(30), move || {
+            let counter = counter.clone();
+            async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(scheduler.cancel("repeat"));
+        let delivered = count.load(Ordering::SeqCst);
+        assert!(delivered >= 2, "delivered {delivered} times");
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(count.load(Ordering::SeqCst), delivered);
+    }
+}
@@ -0,0 +1,435 @@
+//! Snooze button that shows the notification again later, for reminders
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), user_notify::Error> {
+//! use std::time::Duration;
+//! use user_notify::{NotificationBuilder, NotificationManager, snooze::SnoozeNotificationManager};
+//!
+//! let manager = SnoozeNotificationManager::new(user_notify::get_notification_manager(
+//!     "com.example.app".to_owned(),
+//!     None,
+//! ))
+//! .set_action_title("Remind me later");
+//! manager.register(Box::new(|response| println!("{response:?}")), vec![])?;
+//! manager
+//!     .send_notification(
+//!         NotificationBuilder::new()
+//!             .title("Stand up")
+//!             .set_snooze(Duration::from_secs(10 * 60)),
+//!     )
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Notifications with [NotificationBuilder::set_snooze] get a button with the identifier [SNOOZE_ACTION].
+//! A click on it removes the notification and shows it again with the same id after the delay,
+//! the handler from [NotificationManager::register] does not see these clicks.
+//!
+//! The snoozed notifications wait in this process, so they are lost when the app quits.
+//! Snooze clicks on notifications of a previous run of the app are passed to the handler.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use crate::{
+    CalendarTrigger, Error, LocationTrigger, NotificationBuilder, NotificationCategory,
+    NotificationCategoryAction, NotificationHandle, NotificationManager, NotificationResponse,
+    RepeatInterval, scheduler::Scheduler,
+};
+
+/// Identifier of the snooze button
+pub const SNOOZE_ACTION: &str = "user_notify.snooze";
+
+#[derive(Debug, Default)]
+struct State {
+    /// the notifications with snooze button, without the button
+    notifications: HashMap<String, NotificationBuilder>,
+    /// ids of the notifications that wait to be shown again
+    snoozed: HashSet<String>,
+}
+
+/// Wraps a [NotificationManager] and handles the snooze button,
+/// see the [module documentation](self)
+#[derive(Debug, Clone)]
+pub struct SnoozeNotificationManager {
+    inner: Arc<dyn NotificationManager>,
+    action_title: String,
+    state: Arc<Mutex<State>>,
+    scheduler: Scheduler,
+}
+
+impl SnoozeNotificationManager {
+    pub fn new(inner: Arc<dyn NotificationManager>) -> Self {
+        Self {
+            inner,
+            action_title: "Snooze".to_owned(),
+            state: Arc::default(),
+            scheduler: Scheduler::default(),
+        }
+    }
+
+    /// Title of the snooze button, defaults to "Snooze"
+    pub fn set_action_title(mut self, title: &str) -> Self {
+        self.action_title = title.to_owned();
+        self
+    }
+
+    /// The wrapped manager
+    pub fn as_inner(&self) -> &Arc<dyn NotificationManager> {
+        &self.inner
+    }
+
+    /// The builder with the snooze button, if it has a snooze delay
+    fn with_snooze_action(&self, builder: &NotificationBuilder) -> Option<NotificationBuilder> {
+        builder.get_snooze()?;
+        Some(
+            builder
+                .clone()
+                .add_action(NotificationCategoryAction::Action {
+                    identifier: SNOOZE_ACTION.to_owned(),
+                    title: self.action_title.clone(),
+                }),
+        )
+    }
+
+    /// Keeps the builder for the snooze button, or forgets the previous one if it has no snooze delay
+    fn remember(&self, id: String, builder: NotificationBuilder) {
+        let Ok(mut state) = self.state.lock() else {
+            log::error!("snooze lock is poisoned");
+            return;
+        };
+        if builder.get_snooze().is_some() {
+            state.notifications.insert(id, builder);
+        } else {
+            state.notifications.remove(&id);
+        }
+    }
+
+    /// Forgets the notification and cancels its snooze, true when it was snoozed
+    fn forget(&self, id: &str) -> bool {
+        let Ok(mut state) = self.state.lock() else {
+            return false;
+        };
+        state.notifications.remove(id);
+        state.snoozed.remove(id) && self.scheduler.cancel(id)
+    }
+
+    /// Shows the notification with the snooze button under this id, replacing a notification with the same id
+    async fn show(
+        &self,
+        id: &str,
+        builder: NotificationBuilder,
+    ) -> Result<Box<dyn NotificationHandle>, Error> {
+        let handle = match self.with_snooze_action(&builder) {
+            Some(with_action) => self.inner.update_notification(id, with_action).await?,
+            None => self.inner.update_notification(id, builder.clone()).await?,
+        };
+        self.remember(handle.get_id(), builder);
+        Ok(handle)
+    }
+
+    /// Snoozes the notification of the response, returns false if the response is not for the snooze button
+    /// or the notification is not known, then it goes to the handler.
+    fn handle_response(&self, response: &NotificationResponse) -> bool {
+        let id = &response.notification_id;
+        // the platform reports the removal of the snoozed notification as closed
+        if response.is_dismissed()
+            && self
+                .state
+                .lock()
+                .is_ok_and(|state| state.snoozed.contains(id))
+        {
+            return true;
+        }
+        if response.action_id() != Some(SNOOZE_ACTION) {
+            // the snooze timer re-adds the notification, so a dismissal by the removal does not lose it
+            if let Ok(mut state) = self.state.lock() {
+                state.notifications.remove(id);
+            }
+            return false;
+        }
+        let Some(builder) = self
+            .state
+            .lock()
+            .ok()
+            .and_then(|state| state.notifications.get(id).cloned())
+        else {
+            log::warn!("can not snooze notification {id}, it was sent before the app started");
+            return false;
+        };
+        let Some(at) = builder
+            .get_snooze()
+            .and_then(|delay| chrono::Duration::from_std(delay).ok())
+            .and_then(|delay| Utc::now().checked_add_signed(delay))
+        else {
+            return false;
+        };
+
+        log::info!("snoozing notification {id} until {at}");
+        if let Ok(mut state) = self.state.lock() {
+            state.snoozed.insert(id.clone());
+        }
+        if let Err(err) = self.inner.remove_delivered_notifications(vec![id]) {
+            log::error!("failed to remove snoozed notification {id}: {err:?}");
+        }
+        let manager = self.clone();
+        let snoozed_id = id.clone();
+        self.scheduler.schedule(id.clone(), at, async move {
+            if let Ok(mut state) = manager.state.lock() {
+                state.snoozed.remove(&snoozed_id);
+            }
+            if let Err(err) = manager.show(&snoozed_id, builder).await {
+                log::error!("failed to show snoozed notification {snoozed_id}: {err:?}");
+            }
+        });
+        true
+    }
+
+    /// Wraps the handler, so it only gets the responses that are not handled by [Self::handle_response]
+    fn intercept_snooze(
+        &self,
+        handler_callback: Box<dyn Fn(NotificationResponse) + Send + Sync + 'static>,
+    ) -> Box<dyn Fn(NotificationResponse) + Send + Sync + 'static> {
+        // the platforms call the handler on their own threads, the snooze timer needs the runtime of the app
+        #[cfg(all(feature = "tokio", not(all(target_arch = "wasm32", feature = "web"))))]
+        let runtime = tokio::runtime::Handle::try_current().ok();
+        let manager = self.clone();
+        Box::new(move |response| {
+            #[cfg(all(feature = "tokio", not(all(target_arch = "wasm32", feature = "web"))))]
+            let _guard = runtime.as_ref().map(tokio::runtime::Handle::enter);
+            if !manager.handle_response(&response) {
+                handler_callback(response);
+            }
+        })
+    }
+}
+
+#[async_trait]
+impl crate::delegate::DelegatingManager for SnoozeNotificationManager {
+    fn inner(&self) -> &Arc<dyn NotificationManager> {
+        &self.inner
+    }
+
+    /// Clicks on the snooze button are handled here and not passed to the handler
+    fn register(
+        &self,
+        handler_callback: Box<dyn Fn(NotificationResponse) + Send + Sync + 'static>,
+        categories: Vec<NotificationCategory>,
+    ) -> Result<(), Error> {
+        self.inner
+            .register(self.intercept_snooze(handler_callback), categories)
+    }
+
+    /// Clicks on the snooze button are still handled here after the handler was replaced or cleared
+    fn set_response_handler(
+        &self,
+        handler_callback: Box<dyn Fn(NotificationResponse) + Send + Sync + 'static>,
+    ) -> Result<(), Error> {
+        self.inner
+            .set_response_handler(self.intercept_snooze(handler_callback))
+    }
+
+    /// Clicks on the snooze button are still handled here
+    fn clear_response_handler(&self) -> Result<(), Error> {
+        self.inner
+            .set_response_handler(self.intercept_snooze(crate::response_handler::dropping()))
+    }
+
+    /// Clicks on the snooze button are handled here and not sent to the receiver
+    fn responses(
+        &self,
+        categories: Vec<NotificationCategory>,
+    ) -> Result<tokio::sync::mpsc::UnboundedReceiver<NotificationResponse>, Error> {
+        let (handler, rx) = crate::response_handler::channel();
+        self.inner
+            .register(self.intercept_snooze(handler), categories)?;
+        Ok(rx)
+    }
+
+    /// Also cancels the snoozed notifications
+    fn remove_all_delivered_notifications(&self) -> Result<(), Error> {
+        if let Ok(mut state) = self.state.lock() {
+            for id in state.snoozed.drain() {
+                self.scheduler.cancel(&id);
+            }
+        }
+        self.inner.remove_all_delivered_notifications()
+    }
+
+    /// Also cancels the snoozed notifications with these ids
+    fn remove_delivered_notifications(&self, ids: Vec<&str>) -> Result<(), Error> {
+        for id in &ids {
+            self.forget(id);
+        }
+        self.inner.remove_delivered_notifications(ids)
+    }
+
+    async fn send_notification(
+        &self,
+        builder: NotificationBuilder,
+    ) -> Result<Box<dyn NotificationHandle>, Error> {
+        let Some(with_action) = self.with_snooze_action(&builder) else {
+            return self.inner.send_notification(builder).await;
+        };
+        let handle = self.inner.send_notification(with_action).await?;
+        self.remember(handle.get_id(), builder);
+        Ok(handle)
+    }
+
+    /// A snoozed notification is shown right away
+    async fn update_notification(
+        &self,
+        id: &str,
+        builder: NotificationBuilder,
+    ) -> Result<Box<dyn NotificationHandle>, Error> {
+        if let Ok(mut state) = self.state.lock()
+            && state.snoozed.remove(id)
+        {
+            self.scheduler.cancel(id);
+        }
+        self.show(id, builder).await
+    }
+
+    async fn schedule_notification(
+        &self,
+        builder: NotificationBuilder,
+        at: DateTime<Utc>,
+    ) -> Result<String, Error> {
+        let Some(with_action) = self.with_snooze_action(&builder) else {
+            return self.inner.schedule_notification(builder, at).await;
+        };
+        let id = self.inner.schedule_notification(with_action, at).await?;
+        self.remember(id.clone(), builder);
+        Ok(id)
+    }
+
+    async fn schedule_repeating_notification(
+        &self,
+        builder: NotificationBuilder,
+        interval: RepeatInterval,
+    ) -> Result<String, Error> {
+        let Some(with_action) = self.with_snooze_action(&builder) else {
+            return self
+                .inner
+                .schedule_repeating_notification(builder, interval)
+                .await;
+        };
+        let id = self
+            .inner
+            .schedule_repeating_notification(with_action, interval)
+            .await?;
+        self.remember(id.clone(), builder);
+        Ok(id)
+    }
+
+    async fn schedule_calendar_notification(
+        &self,
+        builder: NotificationBuilder,
+        trigger: CalendarTrigger,
+    ) -> Result<String, Error> {
+        let Some(with_action) = self.with_snooze_action(&builder) else {
+            return self
+                .inner
+                .schedule_calendar_notification(builder, trigger)
+                .await;
+        };
+        let id = self
+            .inner
+            .schedule_calendar_notification(with_action, trigger)
+            .await?;
+        self.remember(id.clone(), builder);
+        Ok(id)
+    }
+
+    async fn schedule_location_notification(
+        &self,
+        builder: NotificationBuilder,
+        trigger: LocationTrigger,
+    ) -> Result<String, Error> {
+        let Some(with_action) = self.with_snooze_action(&builder) else {
+            return self
+                .inner
+                .schedule_location_notification(builder, trigger)
+                .await;
+        };
+        let id = self
+            .inner
+            .schedule_location_notification(with_action, trigger)
+            .await?;
+        self.remember(id.clone(), builder);
+        Ok(id)
+    }
+
+    /// Also cancels the snoozed notification with this id
+    fn cancel_scheduled_notification(&self, id: &str) -> Result<(), Error> {
+        if self.forget(id) {
+            return Ok(());
+        }
+        self.inner.cancel_scheduled_notification(id)
+    }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{NotificationResponseAction, mock::NotificationManagerMock};
+
+    #[tokio::test(start_paused = true)]
+    async fn snooze() {
+        let mock = NotificationManagerMock::new();
+        let manager = SnoozeNotificationManager::new(Arc::new(mock.clone()));
+        let mut responses = manager.responses(vec![]).unwrap();
+
+        let handle = manager
+            .send_notification(
+                NotificationBuilder::new()
+                    .title("reminder")
+                    .set_snooze(Duration::from_millis(100)),
+            )
+            .await
+            .unwrap();
+        let id = handle.get_id();
+        let sent = mock.sent_notifications().await;
+        assert!(matches!(
+            &sent[0].actions[..],
+            [NotificationCategoryAction::Action { identifier, .. }] if identifier == SNOOZE_ACTION
+        ));
+
+        mock.simulate_response(
+            &id,
+            NotificationResponseAction::Other(SNOOZE_ACTION.to_owned()),
+            None,
+        )
+        .await
+        .unwrap();
+        assert!(responses.try_recv().is_err());
+        assert!(mock.get_active_notifications().await.unwrap().is_empty());
+        // the platform reports the removal as closed
+        mock.simulate_response(&id, NotificationResponseAction::Dismiss, None)
+            .await
+            .unwrap();
+        assert!(responses.try_recv().is_err());
+
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        let sent = mock.sent_notifications().await;
+        assert_eq!(sent.len(), 2);
+        assert_eq!(sent[1].get_title(), Some("reminder"));
+        let active = mock.get_active_notifications().await.unwrap();
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].get_id(), id);
+
+        // other responses go to the handler
+        mock.simulate_response(&id, NotificationResponseAction::Default, None)
+            .await
+            .unwrap();
+        assert!(responses.try_recv().unwrap().is_clicked());
+    }
+}
@@ -0,0 +1,93 @@
+//! The first frame of GIF images as PNG, for the backends that can not play animations
+//! and would fail to show the GIF at all, see [NotificationCapabilities::animated_images]
+//!
+//! [NotificationCapabilities::animated_images]: crate::NotificationCapabilities::animated_images
+
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    path::{Path, PathBuf},
+};
+
+use crate::{NotificationBuilder, is_gif};
+
+/// The frames are scaled down to fit, the platforms show the images much smaller anyway
+const MAX_DIMENSION: u32 = 1024;
+
+impl NotificationBuilder {
+    /// Replaces GIF images with their first frame, images that can not be converted are kept
+    pub(crate) fn apply_still_images(mut self) -> Self {
+        self.image = self.image.map(still_image);
+        self.hero_image = self.hero_image.map(still_image);
+        self
+    }
+}
+
+fn still_image(path: PathBuf) -> PathBuf {
+    if !is_gif(&path) {
+        return path;
+    }
+    match first_frame_png(&path) {
+        Ok(png) => png,
+        Err(error) => {
+            log::warn!("failed to convert the gif {path:?} to png, using it as it is: {error}");
+            path
+        }
+    }
+}
+
+/// Writes the first frame to the temp dir, it is reused as long as the GIF is not modified
+fn first_frame_png(path: &Path) -> Result<PathBuf, String> {
+    let metadata = std::fs::metadata(path).map_err(|error| error.to_string())?;
+    let mut hasher = DefaultHasher::new();
+    path.hash(&mut hasher);
+    metadata.len().hash(&mut hasher);
+    metadata.modified().ok().hash(&mut hasher);
+    let directory = std::env::temp_dir().join("user-notify");
+    let png = directory.join(format!("{:016x}.png", hasher.finish()));
+    if png.is_file() {
+        return Ok(png);
+    }
+
+    // the extension may not be gif, the signature is
+    let frame = image::ImageReader::open(path)
+        .and_then(|reader| reader.with_guessed_format())
+        .map_err(|error| format!("failed to open: {error}"))?
+        .decode()
+        .map_err(|error| format!("failed to decode: {error}"))?;
+    std::fs::create_dir_all(&directory).map_err(|error| error.to_string())?;
+    frame
+        .thumbnail(MAX_DIMENSION, MAX_DIMENSION)
+        .save_with_format(&png, image::ImageFormat::Png)
+        .map_err(|error| format!("failed to write {png:?}: {error}"))?;
+    Ok(png)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gif_to_png() {
+        let gif = std::env::temp_dir().join(format!("user-notify-{}.gif", uuid::Uuid::new_v4()));
+        image::RgbaImage::from_pixel(2048, 16, image::Rgba([255, 0, 0, 255]))
+            .save_with_format(&gif, image::ImageFormat::Gif)
+            .unwrap();
+
+        let builder = NotificationBuilder::new()
+            .attach_image(&gif)
+            .apply_still_images();
+        let png = builder.get_image().unwrap();
+        assert_eq!(png.extension().unwrap(), "png");
+        assert_eq!(image::image_dimensions(png).unwrap(), (1024, 8));
+
+        let other = std::env::temp_dir().join("not-a-gif.png");
+        assert_eq!(
+            NotificationBuilder::new()
+                .attach_image(&other)
+                .apply_still_images()
+                .get_image(),
+            Some(other.as_path())
+        );
+        std::fs::remove_file(gif).unwrap();
+    }
+}
@@ -0,0 +1,303 @@
+//! Collapses the notifications of a busy thread into one summary notification,
+//! on platforms that do not group them by themselves
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), user_notify::Error> {
+//! use user_notify::{NotificationBuilder, NotificationManager, summary::ThreadSummaryManager};
+//!
+//! let manager = ThreadSummaryManager::new(user_notify::get_notification_manager(
+//!     "com.example.app".to_owned(),
+//!     None,
+//! ))
+//! .set_threshold(3);
+//! manager
+//!     .send_notification(
+//!         NotificationBuilder::new()
+//!             .title("Alice")
+//!             .body("Lunch?")
+//!             .set_thread_id("chat-42")
+//!             .set_summary_argument("Team chat"),
+//!     )
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Once a thread would have more than [ThreadSummaryManager::set_threshold] items,
+//! its delivered notifications are removed and one summary notification with the same thread id
+//! shows the number of items instead, later notifications of the thread update that summary.
+//! A notification counts as [NotificationBuilder::set_summary_argument_count] items,
+//! the summary names the thread by [NotificationBuilder::set_summary_argument] or else by its thread id.
+//!
+//! The platforms report the removed notifications as closed, the handler does not see these dismissals.
+//!
+//! When [crate::NotificationCapabilities::thread_summaries] is set, like on macOS, the notifications are passed through.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+
+use crate::{
+    Error, NotificationBuilder, NotificationCategory, NotificationHandle, NotificationManager,
+    NotificationResponse, NotificationTemplate, SUMMARY_COUNT_KEY, SUMMARY_KEY,
+};
+
+/// Wraps a [NotificationManager] and summarizes the threads with many notifications,
+/// see the [module documentation](self)
+#[derive(Debug, Clone)]
+pub struct ThreadSummaryManager {
+    inner: Arc<dyn NotificationManager>,
+    threshold: usize,
+    template: NotificationTemplate,
+    /// notifications are sent one after the other, so they do not count the thread at the same time
+    lock: Arc<tokio::sync::Mutex<()>>,
+    /// ids of the notifications that were removed for a summary, until their dismissal is reported
+    summarized: Arc<Mutex<HashSet<String>>>,
+}
+
+impl ThreadSummaryManager {
+    pub fn new(inner: Arc<dyn NotificationManager>) -> Self {
+        Self {
+            inner,
+            threshold: 5,
+            template: NotificationTemplate {
+                title: Some("{count} new messages in {thread}".to_owned()),
+                ..Default::default()
+            },
+            lock: Arc::default(),
+            summarized: Arc::default(),
+        }
+    }
+
+    /// The number of items a thread can have before it is summarized, defaults to 5, 0 is raised to 1
+    pub fn set_threshold(mut self, threshold: usize) -> Self {
+        self.threshold = threshold.max(1);
+        self
+    }
+
+    /// The summary notification, with the placeholders `{count}` and `{thread}`.
+    /// Defaults to the title "{count} new messages in {thread}".
+    pub fn set_template(mut self, template: NotificationTemplate) -> Self {
+        self.template = template;
+        self
+    }
+
+    /// The wrapped manager
+    pub fn as_inner(&self) -> &Arc<dyn NotificationManager> {
+        &self.inner
+    }
+
+    async fn send_in_thread(
+        &self,
+        thread_id: &str,
+        builder: NotificationBuilder,
+    ) -> Result<Box<dyn NotificationHandle>, Error> {
+        let _guard = self.lock.lock().await;
+        let count = builder.get_summary_argument_count().unwrap_or(1) as usize;
+        let (summaries, notifications): (Vec<_>, Vec<_>) = self
+            .inner
+            .get_active_notifications()
+            .await?
+            .into_iter()
+            .filter(|handle| handle.get_thread_id() == Some(thread_id))
+            .partition(|handle| handle.get_stored_user_info().contains_key(SUMMARY_KEY));
+        let total = count
+            + summaries
+                .iter()
+                .chain(&notifications)
+                .map(|handle| item_count(handle.as_ref()))
+                .sum::<usize>();
+
+        if total <= self.threshold {
+            return self
+                .inner
+                .send_notification(with_item_count(builder, count, false))
+                .await;
+        }
+
+        let thread = builder.get_summary_argument().unwrap_or(thread_id);
+        let summary = self
+            .template
+            .instantiate(&HashMap::from([
+                ("count".to_owned(), total.to_string()),
+                ("thread".to_owned(), thread.to_owned()),
+            ]))?
+            .set_thread_id(thread_id)
+            .set_summary_argument(thread);
+        let summary = with_item_count(summary, total, true);
+
+        if !notifications.is_empty() {
+            let ids: Vec<String> = notifications.iter().map(|handle| handle.get_id()).collect();
+            if let Ok(mut summarized) = self.summarized.lock() {
+                summarized.extend(ids.iter().cloned());
+            }
+            self.inner
+                .remove_delivered_notifications(ids.iter().map(String::as_str).collect())?;
+        }
+        match summaries.first() {
+            Some(previous) => {
+                self.inner
+                    .update_notification(&previous.get_id(), summary)
+                    .await
+            }
+            None => self.inner.send_notification(summary).await,
+        }
+    }
+
+    /// Wraps the handler, so it does not get the dismissals of the notifications removed for a summary
+    fn filter_summarized(
+        &self,
+        handler_callback: Box<dyn Fn(NotificationResponse) + Send + Sync + 'static>,
+    ) -> Box<dyn Fn(NotificationResponse) + Send + Sync + 'static> {
+        let summarized = self.summarized.clone();
+        Box::new(move |response| {
+            if response.is_dismissed()
+                && summarized
+                    .lock()
+                    .is_ok_and(|mut summarized| summarized.remove(&response.notification_id))
+            {
+                return;
+            }
+            handler_callback(response);
+        })
+    }
+}
+
+fn item_count(handle: &dyn NotificationHandle) -> usize {
+    handle
+        .get_stored_user_info()
+        .get(SUMMARY_COUNT_KEY)
+        .and_then(|count| count.parse().ok())
+        .unwrap_or(1)
+}
+
+fn with_item_count(
+    builder: NotificationBuilder,
+    count: usize,
+    summary: bool,
+) -> NotificationBuilder {
+    let mut user_info = builder.get_user_info().unwrap_or_default();
+    user_info.insert(SUMMARY_COUNT_KEY.to_owned(), count.to_string());
+    if summary {
+        user_info.insert(SUMMARY_KEY.to_owned(), "true".to_owned());
+    }
+    builder.set_user_info(user_info)
+}
+
+#[async_trait]
+impl crate::delegate::DelegatingManager for ThreadSummaryManager {
+    fn inner(&self) -> &Arc<dyn NotificationManager> {
+        &self.inner
+    }
+
+    /// The dismissals of the notifications removed for a summary are not passed to the handler
+    fn register(
+        &self,
+        handler_callback: Box<dyn Fn(NotificationResponse) + Send + Sync + 'static>,
+        categories: Vec<NotificationCategory>,
+    ) -> Result<(), Error> {
+        self.inner
+            .register(self.filter_summarized(handler_callback), categories)
+    }
+
+    /// The dismissals of the notifications removed for a summary are not passed to the handler
+    fn set_response_handler(
+        &self,
+        handler_callback: Box<dyn Fn(NotificationResponse) + Send + Sync + 'static>,
+    ) -> Result<(), Error> {
+        self.inner
+            .set_response_handler(self.filter_summarized(handler_callback))
+    }
+
+    /// The dismissals of the notifications removed for a summary are not sent to the receiver
+    fn responses(
+        &self,
+        categories: Vec<NotificationCategory>,
+    ) -> Result<tokio::sync::mpsc::UnboundedReceiver<NotificationResponse>, Error> {
+        let (handler, rx) = crate::response_handler::channel();
+        self.inner
+            .register(self.filter_summarized(handler), categories)?;
+        Ok(rx)
+    }
+
+    async fn send_notification(
+        &self,
+        builder: NotificationBuilder,
+    ) -> Result<Box<dyn NotificationHandle>, Error> {
+        let Some(thread_id) = builder.get_thread_id().map(str::to_owned) else {
+            return self.inner.send_notification(builder).await;
+        };
+        if self
+            .inner
+            .capabilities()
+            .await
+            .is_ok_and(|capabilities| capabilities.thread_summaries)
+        {
+            return self.inner.send_notification(builder).await;
+        }
+        self.send_in_thread(&thread_id, builder).await
+    }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use super::*;
+    use crate::{NotificationResponseAction, mock::NotificationManagerMock};
+
+    #[tokio::test]
+    async fn summarize_thread() {
+        let mock = NotificationManagerMock::new();
+        let manager = ThreadSummaryManager::new(Arc::new(mock.clone())).set_threshold(3);
+        let mut responses = manager.responses(vec![]).unwrap();
+        let message = || {
+            NotificationBuilder::new()
+                .title("Alice")
+                .set_thread_id("chat")
+                .set_summary_argument("Team")
+        };
+        let titles = || async {
+            let mut titles: Vec<_> = manager
+                .get_active_notifications()
+                .await
+                .unwrap()
+                .iter()
+                .map(|handle| handle.get_content().title.clone().unwrap_or_default())
+                .collect();
+            titles.sort();
+            titles
+        };
+
+        manager
+            .send_notification(NotificationBuilder::new().title("Other"))
+            .await
+            .unwrap();
+        let mut summarized = Vec::new();
+        for _ in 0..3 {
+            summarized.push(manager.send_notification(message()).await.unwrap().get_id());
+        }
+        assert_eq!(titles().await.len(), 4);
+
+        let summary = manager.send_notification(message()).await.unwrap();
+        assert_eq!(summary.get_thread_id(), Some("chat"));
+        assert_eq!(titles().await, ["4 new messages in Team", "Other"]);
+        // the platform reports the removed notifications as closed
+        mock.simulate_response(&summarized[0], NotificationResponseAction::Dismiss, None)
+            .await
+            .unwrap();
+        assert!(responses.try_recv().is_err());
+
+        manager
+            .send_notification(message().set_summary_argument_count(2))
+            .await
+            .unwrap();
+        assert_eq!(titles().await, ["6 new messages in Team", "Other"]);
+
+        mock.simulate_response(&summary.get_id(), NotificationResponseAction::Dismiss, None)
+            .await
+            .unwrap();
+        assert!(responses.try_recv().unwrap().is_dismissed());
+    }
+}
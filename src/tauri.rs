@@ -0,0 +1,177 @@
+//! Tauri plugin that sets up the notification manager for the app
+//!
+//! ```ignore
+//! use user_notify::tauri::UserNotifyExt;
+//!
+//! tauri::Builder::default()
+//!     .plugin(user_notify::tauri::Builder::new().notification_protocol("myapp").build())
+//!     .setup(|app| {
+//!         let manager = app.user_notify();
+//!         Ok(())
+//!     })
+//! ```
+//!
+//! The plugin
+//! - takes the app id from the `identifier` of the tauri config
+//! - emits every [NotificationResponse] as [RESPONSE_EVENT] to the frontend
+//! - on Windows registers the app id with the product name, so toasts of unpackaged apps show the app name,
+//!   and registers the COM activator when [Builder::windows_activator] is set
+//!
+//! In `tauri dev` there is no bundle on macOS, then the [crate::noop::NoopNotificationManager] is used.
+//!
+//! Responses that arrive before the frontend listens are not seen by it,
+//! the click that started the app is kept for [crate::NotificationManager::take_launch_response].
+
+use std::{path::PathBuf, sync::Arc};
+
+use ::tauri::{
+    AppHandle, Emitter, Manager, Runtime,
+    plugin::{self, TauriPlugin},
+};
+
+use crate::{NotificationCategory, NotificationManager, NotificationResponse};
+
+/// Name of the event that carries the [NotificationResponse]s, the payload looks like
+///
+/// ```json
+/// { "notificationId": "…", "action": "default", "userText": null, "userInfo": {} }
+/// ```
+///
+/// `action` is `"default"` for a click on the notification, `"dismiss"` when it was closed,
+/// otherwise the identifier of the [crate::NotificationCategoryAction]
+pub const RESPONSE_EVENT: &str = "user-notify://response";
+
+/// Managed state of the plugin, use [UserNotifyExt::user_notify] to get the manager
+struct UserNotify {
+    manager: Arc<dyn NotificationManager>,
+}
+
+/// Gives access to the notification manager of the plugin on the app, app handle, windows and webviews
+pub trait UserNotifyExt<R: Runtime> {
+    /// Panics when the plugin was not added to the app
+    fn user_notify(&self) -> Arc<dyn NotificationManager>;
+}
+
+impl<R: Runtime, T: Manager<R>> UserNotifyExt<R> for T {
+    fn user_notify(&self) -> Arc<dyn NotificationManager> {
+        self.state::<UserNotify>().manager.clone()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Builder {
+    categories: Vec<NotificationCategory>,
+    notification_protocol: Option<String>,
+    windows_activator: Option<u128>,
+    windows_icon: Option<PathBuf>,
+}
+
+impl Builder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Categories that are registered together with the handler
+    pub fn categories(mut self, categories: Vec<NotificationCategory>) -> Self {
+        self.categories = categories;
+        self
+    }
+
+    /// See [crate::get_notification_manager], only used on Windows
+    pub fn notification_protocol(mut self, notification_protocol: &str) -> Self {
+        self.notification_protocol = Some(notification_protocol.to_owned());
+        self
+    }
+
+    /// Registers the COM activator with this CLSID on Windows,
+    /// see [crate::windows::NotificationManagerWindows::register_com_activator]
+    ///
+    /// The CLSID has to be a fixed GUID unique to your app, like `0x1a2b3c4d_0000_4000_8000_00000000abcd`
+    pub fn windows_activator(mut self, clsid: u128) -> Self {
+        self.windows_activator = Some(clsid);
+        self
+    }
+
+    /// Icon that Windows shows on the toasts, absolute path to a png or ico file
+    pub fn windows_icon(mut self, icon: PathBuf) -> Self {
+        self.windows_icon = Some(icon);
+        self
+    }
+
+    pub fn build<R: Runtime>(self) -> TauriPlugin<R> {
+        plugin::Builder::new("user-notify")
+            .setup(move |app, _api| {
+                let manager = self.manager(app)?;
+                let emitter = app.clone();
+                manager.register(
+                    Box::new(move |response| {
+                        if let Err(err) = emitter.emit(RESPONSE_EVENT, response_payload(&response))
+                        {
+                            log::error!("failed to emit notification response: {err}");
+                        }
+                    }),
+                    self.categories,
+                )?;
+                app.manage(UserNotify { manager });
+                Ok(())
+            })
+            .build()
+    }
+
+    #[cfg(all(target_os = "windows", feature = "windows"))]
+    fn manager<R: Runtime>(
+        &self,
+        app: &AppHandle<R>,
+    ) -> Result<Arc<dyn NotificationManager>, crate::Error> {
+        use crate::platform_impl::windows;
+
+        let app_id = app.config().identifier.clone();
+        windows::register_app_id(&app_id, &display_name(app), self.windows_icon.as_deref())?;
+        let manager =
+            windows::NotificationManagerWindows::new(app_id, self.notification_protocol.clone());
+        if let Some(clsid) = self.windows_activator {
+            manager.register_com_activator(::windows::core::GUID::from_u128(clsid))?;
+        }
+        Ok(Arc::new(manager))
+    }
+
+    #[cfg(not(all(target_os = "windows", feature = "windows")))]
+    fn manager<R: Runtime>(
+        &self,
+        app: &AppHandle<R>,
+    ) -> Result<Arc<dyn NotificationManager>, crate::Error> {
+        let mut options = crate::NotificationManagerOptions::new(&app.config().identifier)
+            .set_app_name(&display_name(app));
+        if let Some(notification_protocol) = &self.notification_protocol {
+            options = options.set_notification_protocol(notification_protocol);
+        }
+        Ok(crate::get_notification_manager_with_options(options))
+    }
+}
+
+/// The product name from the tauri config, or else the package name
+fn display_name<R: Runtime>(app: &AppHandle<R>) -> String {
+    app.config()
+        .product_name
+        .clone()
+        .unwrap_or_else(|| app.package_info().name.clone())
+}
+
+/// The plugin with the default options
+pub fn init<R: Runtime>() -> TauriPlugin<R> {
+    Builder::new().build()
+}
+
+fn response_payload(response: &NotificationResponse) -> serde_json::Value {
+    let action = match &response.action {
+        crate::NotificationResponseAction::Default => "default",
+        crate::NotificationResponseAction::Dismiss => "dismiss",
+        crate::NotificationResponseAction::Other(identifier) => identifier,
+    };
+    serde_json::json!({
+        "notificationId": response.notification_id,
+        "action": action,
+        "userText": response.user_text,
+        "userInfo": response.user_info,
+    })
+}
@@ -0,0 +1,166 @@
+//! Notification texts that live in config files, so they can be changed without a recompile
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, NotificationBuilder};
+
+/// A notification with `{name}` placeholders, which are replaced by parameters in [NotificationTemplate::instantiate].
+/// Use `{{` and `}}` for literal braces.
+///
+/// Deserialize it with serde from any format, like `toml::from_str`, or from json with [NotificationTemplate::from_json]:
+///
+/// ```
+/// # use std::collections::HashMap;
+/// let template = user_notify::NotificationTemplate::from_json(
+///     r#"{ "title": "{sender} sent a message", "body": "{text}", "thread": "chat-{chat_id}" }"#,
+/// )?;
+/// let builder = template.instantiate(&HashMap::from([
+///     ("sender".to_owned(), "Alice".to_owned()),
+///     ("text".to_owned(), "Hi".to_owned()),
+///     ("chat_id".to_owned(), "42".to_owned()),
+/// ]))?;
+/// assert_eq!(builder.get_thread_id(), Some("chat-42"));
+/// # Ok::<(), user_notify::Error>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotificationTemplate {
+    pub title: Option<String>,
+    pub subtitle: Option<String>,
+    pub body: Option<String>,
+    /// See [NotificationBuilder::set_category_id], without placeholders
+    pub category: Option<String>,
+    /// See [NotificationBuilder::sound], without placeholders
+    pub sound: Option<String>,
+    /// Pattern for [NotificationBuilder::set_thread_id], like `chat-{chat_id}`
+    pub thread: Option<String>,
+}
+
+impl NotificationTemplate {
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        serde_json::from_str(json).map_err(|err| Error::InvalidTemplate(err.to_string()))
+    }
+
+    /// Fills in the placeholders,
+    /// fails with [Error::MissingTemplateParameter] when a placeholder has no parameter
+    pub fn instantiate(
+        &self,
+        parameters: &HashMap<String, String>,
+    ) -> Result<NotificationBuilder, Error> {
+        let mut builder = NotificationBuilder::new();
+        if let Some(title) = &self.title {
+            builder = builder.title(&render(title, parameters)?);
+        }
+        if let Some(subtitle) = &self.subtitle {
+            builder = builder.subtitle(&render(subtitle, parameters)?);
+        }
+        if let Some(body) = &self.body {
+            builder = builder.body(&render(body, parameters)?);
+        }
+        if let Some(category) = &self.category {
+            builder = builder.set_category_id(category);
+        }
+        if let Some(sound) = &self.sound {
+            builder = builder.sound(sound);
+        }
+        if let Some(thread) = &self.thread {
+            builder = builder.set_thread_id(&render(thread, parameters)?);
+        }
+        Ok(builder)
+    }
+}
+
+fn render(template: &str, parameters: &HashMap<String, String>) -> Result<String, Error> {
+    let mut rendered = String::with_capacity(template.len());
+    let mut chars = template.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.as_str().starts_with('{') => {
+                chars.next();
+                rendered.push('{');
+            }
+            '}' if chars.as_str().starts_with('}') => {
+                chars.next();
+                rendered.push('}');
+            }
+            '{' => {
+                let rest = chars.as_str();
+                let Some(end) = rest.find('}') else {
+                    return Err(Error::InvalidTemplate(format!(
+                        "unclosed placeholder in {template:?}"
+                    )));
+                };
+                let name = rest[..end].trim();
+                let value = parameters
+                    .get(name)
+                    .ok_or_else(|| Error::MissingTemplateParameter(name.to_owned()))?;
+                rendered.push_str(value);
+                chars = rest[end + 1..].chars();
+            }
+            '}' => {
+                return Err(Error::InvalidTemplate(format!(
+                    "unmatched }} in {template:?}"
+                )));
+            }
+            c => rendered.push(c),
+        }
+    }
+    Ok(rendered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parameters() -> HashMap<String, String> {
+        HashMap::from([
+            ("sender".to_owned(), "Alice".to_owned()),
+            ("count".to_owned(), "3".to_owned()),
+        ])
+    }
+
+    #[test]
+    fn from_toml() {
+        let template: NotificationTemplate = toml::from_str(
+            r#"
+            title = "{sender}"
+            body = "{count} new messages {{unread}}"
+            category = "message"
+            sound = "ping"
+            thread = "chat-{ sender }"
+            "#,
+        )
+        .unwrap();
+        let builder = template.instantiate(&parameters()).unwrap();
+        assert_eq!(builder.get_title(), Some("Alice"));
+        assert_eq!(builder.get_body(), Some("3 new messages {unread}"));
+        assert_eq!(builder.get_category_id(), Some("message"));
+        assert_eq!(
+            builder.get_sound(),
+            Some(&crate::Sound::Named("ping".to_owned()))
+        );
+        assert_eq!(builder.get_thread_id(), Some("chat-Alice"));
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(matches!(
+            render("{missing}", &parameters()),
+            Err(Error::MissingTemplateParameter(name)) if name == "missing"
+        ));
+        assert!(matches!(
+            render("{sender", &parameters()),
+            Err(Error::InvalidTemplate(_))
+        ));
+        assert!(matches!(
+            render("sender}", &parameters()),
+            Err(Error::InvalidTemplate(_))
+        ));
+        assert!(matches!(
+            NotificationTemplate::from_json(r#"{ "titel": "typo" }"#),
+            Err(Error::InvalidTemplate(_))
+        ));
+    }
+}
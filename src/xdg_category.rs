@@ -1,7 +1,9 @@
+use serde::{Deserialize, Serialize};
+
 /// Category for the notification
 ///
 /// https://specifications.freedesktop.org/notification-spec/latest/categories.html
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum XdgNotificationCategory {
     /// A generic audio or video call notification that doesn't fit into any other category.
     Call,
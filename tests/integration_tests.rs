@@ -1,7 +1,9 @@
 use std::{collections::HashMap, time::Duration};
 
 use tokio::time::sleep;
-use user_notify::{NotificationCategory, NotificationCategoryAction, get_notification_manager};
+use user_notify::{
+    NotificationCategory, NotificationCategoryAction, NotificationManager, get_notification_manager,
+};
 
 const DEFAULT_BUNDLE_ID: &str = "ai.gety";
 
@@ -37,6 +39,7 @@ fn create_test_categories() -> Vec<NotificationCategory> {
                     title: "Detail".to_string(),
                 },
             ],
+            ..Default::default()
         },
         NotificationCategory {
             identifier: TEXT_INPUT_CATEGORY_ID.to_string(),
@@ -45,7 +48,9 @@ fn create_test_categories() -> Vec<NotificationCategory> {
                 title: "Reply".to_string(),
                 input_button_title: "Send".to_string(),
                 input_placeholder: "type your message here".to_string(),
+                default_text: None,
             }],
+            ..Default::default()
         },
     ]
 }
@@ -56,7 +61,7 @@ async fn test_notification_manager_creation() -> anyhow::Result<()> {
     log::debug!("Testing notification manager creation");
 
     let bundle_id = get_test_bundle_id();
-    let manager = get_notification_manager(bundle_id.clone(), None);
+    let _manager = get_notification_manager(bundle_id.clone(), None);
 
     log::info!(
         "✅ Notification manager created successfully with bundle ID: {}",
@@ -231,6 +236,7 @@ async fn test_get_active_notifications() -> anyhow::Result<()> {
 }
 
 #[tokio::test]
+#[ignore = "needs a desktop session, in headless environments the noop manager has no active notifications"]
 async fn test_notification_verification() -> anyhow::Result<()> {
     init_logger();
     log::debug!("Testing notification verification");
@@ -288,6 +294,7 @@ async fn test_notification_verification() -> anyhow::Result<()> {
                 handle.get_user_info().get("verification_key"),
                 Some(&"verification_value".to_string())
             );
+            assert_eq!(handle.get_thread_id(), Some("test-thread-verify"));
         }
         None => {
             log::error!("❌ No notification containing 'verification_key' found!");
@@ -307,6 +314,7 @@ async fn test_notification_verification() -> anyhow::Result<()> {
 }
 
 #[tokio::test]
+#[ignore = "needs a desktop session, in headless environments the noop manager has no active notifications"]
 async fn test_integration_full_flow() -> anyhow::Result<()> {
     init_logger();
     log::debug!("Testing full integration flow");
@@ -397,6 +405,13 @@ async fn test_long_text_notification() -> anyhow::Result<()> {
 
     let bundle_id = get_test_bundle_id();
     let manager = get_notification_manager(bundle_id, None);
+    manager.register(
+        Box::new(|response| {
+            log::info!("📳 Received notification response: {response:?}");
+        }),
+        create_test_categories(),
+    )?;
+
     let long_text_notification = user_notify::NotificationBuilder::new()
         .title("📄 Long Text Test - This is a very long title that might get truncated or wrapped depending on the system notification display limits")
         .body("这是一个超长文本测试通知。This is a very long text notification test to see how the notification system handles extremely long content. We want to test if the text gets truncated, wrapped, or displayed in some other way. The notification system should handle this gracefully without breaking or causing issues. 这个通知包含了中英文混合的超长文本内容，用来测试通知系统对于长文本的处理能力。We're testing various scenarios: very long titles, very long body text, mixed languages (Chinese and English), special characters, emoji 🎉🔥💯, and other edge cases that might occur in real-world usage. This helps ensure our notification library is robust and can handle different types of content gracefully.")
@@ -410,3 +425,37 @@ async fn test_long_text_notification() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_noop_notification_manager() -> anyhow::Result<()> {
+    init_logger();
+    log::debug!("Testing the noop manager of headless environments");
+
+    let manager = user_notify::noop::NoopNotificationManager::new();
+    manager.register(
+        Box::new(|response| {
+            log::info!("📳 Received notification response: {response:?}");
+        }),
+        create_test_categories(),
+    )?;
+
+    let mut user_info = HashMap::new();
+    user_info.insert("noop_test".to_owned(), "headless".to_owned());
+    let handle = manager
+        .send_notification(
+            user_notify::NotificationBuilder::new()
+                .title("Noop Test")
+                .body("Not shown in headless environments")
+                .set_thread_id("noop-thread")
+                .set_user_info(user_info.clone())
+                .set_category_id(TEXT_INPUT_CATEGORY_ID),
+        )
+        .await?;
+    assert_eq!(handle.get_user_info(), user_info);
+    assert_eq!(handle.get_thread_id(), Some("noop-thread"));
+
+    // nothing is shown, so nothing is active
+    assert!(manager.get_active_notifications().await?.is_empty());
+    manager.remove_delivered_notifications(vec![&handle.get_id()])?;
+    Ok(())
+}